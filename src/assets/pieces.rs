@@ -0,0 +1,180 @@
+//! Embedded piece set. Pieces are small SVGs drawn on a 45×45 canvas with
+//! theme colors filled in, rasterized at runtime for egui and embedded as
+//! vector groups in exported diagrams, so no view depends on a font having
+//! chess glyphs.
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Colors the piece set is drawn in.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum PieceTheme {
+    #[default]
+    Classic,
+    /// Thicker outlines and pure black/white, for small boards.
+    Contrast,
+    Ocean,
+}
+
+struct Palette {
+    light: &'static str,
+    dark: &'static str,
+    outline: &'static str,
+    /// Marks drawn on dark pieces (bishop slit, knight eye).
+    dark_detail: &'static str,
+    stroke_width: f32,
+}
+
+impl PieceTheme {
+    pub const ALL: [PieceTheme; 3] = [PieceTheme::Classic, PieceTheme::Contrast, PieceTheme::Ocean];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PieceTheme::Classic => "Classic",
+            PieceTheme::Contrast => "High contrast",
+            PieceTheme::Ocean => "Ocean",
+        }
+    }
+
+    fn palette(&self) -> Palette {
+        match self {
+            PieceTheme::Classic => Palette {
+                light: "#ffffff",
+                dark: "#2b2b2b",
+                outline: "#000000",
+                dark_detail: "#e8e8e8",
+                stroke_width: 1.5,
+            },
+            PieceTheme::Contrast => Palette {
+                light: "#ffffff",
+                dark: "#000000",
+                outline: "#000000",
+                dark_detail: "#ffffff",
+                stroke_width: 2.5,
+            },
+            PieceTheme::Ocean => Palette {
+                light: "#e6f1ff",
+                dark: "#1d3557",
+                outline: "#0b1a2e",
+                dark_detail: "#a8dadc",
+                stroke_width: 1.5,
+            },
+        }
+    }
+}
+
+// Shapes use FILL for the body and DETAIL for marks inside it.
+const PAWN: &str = r#"<circle cx="22.5" cy="13" r="4.5"/><path d="M18.5 19.5h8l-1 2.5c2.5 2 3.5 5 3.5 8h-13c0-3 1-6 3.5-8z"/><path d="M12 37c0-4 3-7 6-7h9c3 0 6 3 6 7z"/>"#;
+const ROOK: &str = r#"<path d="M11 37h23v-3.5H11z"/><path d="M14.5 33.5l1.5-14h13l1.5 14z"/><path d="M12 19.5v-8h4.2v3h4.2v-3h4.2v3h4.2v-3H33v8z"/>"#;
+const KNIGHT: &str = r#"<path d="M13 37h20c0-9-1.5-17-5-21.5-1.2-1.6-3-3-3-5.5l-2.2 3c-4.3.3-7.8 2.6-10.3 6.8l-2.8 4.8c-.9 1.7.8 3.6 2.6 2.8l3-1.6c1.2 1 3 1 4.2 0-2.4 3.6-6.5 6.4-6.5 11.2z"/><circle cx="21" cy="17" r="1.2" fill="DETAIL" stroke="none"/>"#;
+const BISHOP: &str = r#"<path d="M14 37h17l-1.5-3.5h-14z"/><path d="M16.8 33.5c-2.3-6.5-.6-13.5 5.7-19.5 6.3 6 8 13 5.7 19.5z"/><circle cx="22.5" cy="10.5" r="2.6"/><path d="M25.5 18.5l-4.5 6" fill="none" stroke="DETAIL"/>"#;
+const QUEEN: &str = r#"<path d="M10 27l-2.5-14 7.5 9.5 2.5-12.5 5 12 5-12 2.5 12.5 7.5-9.5-2.5 14c-7.5-2.5-17.5-2.5-25 0z"/><path d="M10 27c7.5-2.5 17.5-2.5 25 0l-2.5 10h-20z"/><circle cx="7.5" cy="12" r="2"/><circle cx="17.5" cy="9" r="2"/><circle cx="27.5" cy="9" r="2"/><circle cx="37.5" cy="12" r="2"/>"#;
+const KING: &str = r#"<path d="M22.5 5.5v8M18.5 9h8" fill="none"/><path d="M12 37h21l-1.5-7c4-3 6.5-8 3.5-12-3-3.5-9-2.5-12.5 3.5C19 15.5 13 14.5 10 18c-3 4-.5 9 3.5 12z"/><path d="M13.5 30c6-2 12-2 18 0" fill="none" stroke="DETAIL"/>"#;
+
+fn shapes(piece: char) -> Option<&'static str> {
+    Some(match piece.to_ascii_lowercase() {
+        'p' => PAWN,
+        'r' => ROOK,
+        'n' => KNIGHT,
+        'b' => BISHOP,
+        'q' => QUEEN,
+        'k' => KING,
+        _ => return None,
+    })
+}
+
+/// The piece for a FEN letter as a `<g>` in the 45×45 piece space, ready to
+/// be placed inside a nested `<svg>`.
+pub fn piece_group(piece: char, theme: PieceTheme) -> Option<String> {
+    let p = theme.palette();
+    let white = piece.is_ascii_uppercase();
+    let (fill, detail) = if white {
+        (p.light, p.outline)
+    } else {
+        (p.dark, p.dark_detail)
+    };
+    Some(format!(
+        r#"<g fill="{fill}" stroke="{}" stroke-width="{}" stroke-linejoin="round" stroke-linecap="round">{}</g>"#,
+        p.outline,
+        p.stroke_width,
+        shapes(piece)?.replace("DETAIL", detail)
+    ))
+}
+
+/// Standalone SVG document for one piece.
+pub fn piece_svg(piece: char, theme: PieceTheme) -> Option<String> {
+    Some(format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="45" height="45" viewBox="0 0 45 45">{}</svg>"#,
+        piece_group(piece, theme)?
+    ))
+}
+
+/// Rasterizes a piece to a `size`×`size` image.
+pub fn rasterize(piece: char, theme: PieceTheme, size: u32) -> Option<egui::ColorImage> {
+    let svg = piece_svg(piece, theme)?;
+    let tree = resvg::usvg::Tree::from_str(&svg, &resvg::usvg::Options::default()).ok()?;
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(size, size)?;
+    let scale = size as f32 / tree.size().width();
+    resvg::render(
+        &tree,
+        resvg::tiny_skia::Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+    Some(egui::ColorImage::from_rgba_premultiplied(
+        [size as usize, size as usize],
+        pixmap.data(),
+    ))
+}
+
+type SpriteCache = HashMap<(PieceTheme, char, u32), egui::TextureHandle>;
+
+/// Sprites are rendered in steps of this many pixels so resizing a window
+/// doesn't rasterize on every frame.
+const SIZE_STEP: u32 = 16;
+
+/// Texture for a piece at least `px` pixels wide, rasterized on first use
+/// and kept in the context's memory.
+fn sprite(
+    ctx: &egui::Context,
+    piece: char,
+    theme: PieceTheme,
+    px: f32,
+) -> Option<egui::TextureHandle> {
+    let size = ((px.max(1.0) as u32).div_ceil(SIZE_STEP) * SIZE_STEP).min(512);
+    let id = egui::Id::new("piece_sprites");
+    let key = (theme, piece, size);
+    if let Some(texture) = ctx.data(|d| {
+        d.get_temp::<SpriteCache>(id)
+            .and_then(|c| c.get(&key).cloned())
+    }) {
+        return Some(texture);
+    }
+    let image = rasterize(piece, theme, size)?;
+    let texture = ctx.load_texture(
+        format!("piece-{:?}-{}-{}", theme, piece, size),
+        image,
+        egui::TextureOptions::LINEAR,
+    );
+    ctx.data_mut(|d| {
+        d.get_temp_mut_or_default::<SpriteCache>(id)
+            .insert(key, texture.clone())
+    });
+    Some(texture)
+}
+
+/// Draws the piece for a FEN letter into `rect`, multiplied by `tint`
+/// (white for normal pieces, translucent for ghosts).
+pub fn draw_piece(
+    painter: &egui::Painter,
+    rect: egui::Rect,
+    piece: char,
+    theme: PieceTheme,
+    tint: egui::Color32,
+) {
+    let px = rect.width() * painter.ctx().pixels_per_point();
+    if let Some(texture) = sprite(painter.ctx(), piece, theme, px) {
+        let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+        painter.image(texture.id(), rect, uv, tint);
+    }
+}
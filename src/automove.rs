@@ -0,0 +1,147 @@
+use crate::config::AppConfig;
+use crate::errors::{AppError, SharedErrors};
+use crate::overlay::screen_map::ScreenMapping;
+use crate::snapshot::OverlaySnapshot;
+use eframe::egui;
+use rdev::{simulate, Button, EventType};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Pause between simulated events; some systems drop events sent faster.
+const EVENT_GAP: Duration = Duration::from_millis(30);
+
+/// A move is being clicked; further requests are ignored until it is done.
+static PLAYING: AtomicBool = AtomicBool::new(false);
+
+/// Playing the first board's best move with simulated mouse clicks when
+/// the "Play best move" hotkey is pressed. Off until enabled.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct AutoMove {
+    pub enabled: bool,
+    /// Random wait before the first click, between these bounds.
+    pub min_delay_ms: u32,
+    pub max_delay_ms: u32,
+    /// Refuse to move when the position has the other side to move.
+    pub own_turn_only: bool,
+    /// Put the cursor back where it was after clicking.
+    pub restore_cursor: bool,
+}
+
+impl Default for AutoMove {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_delay_ms: 400,
+            max_delay_ms: 1200,
+            own_turn_only: true,
+            restore_cursor: true,
+        }
+    }
+}
+
+impl AutoMove {
+    fn delay(&self) -> Duration {
+        let lo = self.min_delay_ms.min(self.max_delay_ms) as u64;
+        let hi = self.min_delay_ms.max(self.max_delay_ms) as u64;
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.subsec_nanos() as u64);
+        Duration::from_millis(lo + nanos % (hi - lo + 1))
+    }
+}
+
+fn click(x: f64, y: f64) -> Result<(), rdev::SimulateError> {
+    for event in [
+        EventType::MouseMove { x, y },
+        EventType::ButtonPress(Button::Left),
+        EventType::ButtonRelease(Button::Left),
+    ] {
+        simulate(&event)?;
+        thread::sleep(EVENT_GAP);
+    }
+    Ok(())
+}
+
+/// Clicks the origin and then the destination square of the first board's
+/// best move, after the configured random delay, on a background thread.
+/// Why nothing was played ends up in `auto_move_status`.
+pub fn play_best_move(
+    config: Arc<Mutex<AppConfig>>,
+    snapshot: &OverlaySnapshot,
+    mapping: &ScreenMapping,
+    errors: SharedErrors,
+) {
+    let mut c = config.lock().unwrap();
+    let settings = c.auto_move.clone();
+    if !settings.enabled {
+        c.auto_move_status = Some("Click-to-play is off".to_string());
+        return;
+    }
+    let (Some(region), Some(Some(board))) = (
+        c.board_region
+            .as_ref()
+            .and_then(|r| r.sub_regions(c.split_region).first().cloned()),
+        snapshot.boards.first(),
+    ) else {
+        c.auto_move_status = Some("No analyzed board".to_string());
+        return;
+    };
+    let Some(best) = board.moves.first() else {
+        c.auto_move_status = Some("No suggested move".to_string());
+        return;
+    };
+    let white_to_move = board.fen.split_whitespace().nth(1) == Some("w");
+    if settings.own_turn_only && white_to_move != c.shows_white(0) {
+        c.auto_move_status = Some("Not your turn".to_string());
+        return;
+    }
+    // The refined grid leaves out coordinates and margins around the
+    // squares, as on the overlay
+    let squares = match board.grid {
+        Some([x, y, w, h]) => egui::Rect::from_min_size(egui::pos2(x, y), egui::vec2(w, h)),
+        None => egui::Rect::from_min_size(
+            egui::pos2(region.x as f32, region.y as f32),
+            egui::vec2(region.width as f32, region.height as f32),
+        ),
+    };
+    let (Some(from), Some(to)) = (
+        best.get(0..2)
+            .and_then(|sq| mapping.square_on_desktop(squares, sq)),
+        best.get(2..4)
+            .and_then(|sq| mapping.square_on_desktop(squares, sq)),
+    ) else {
+        c.auto_move_status = Some(format!("Can't place {} on the screen", best));
+        return;
+    };
+    if PLAYING.swap(true, Ordering::SeqCst) {
+        c.auto_move_status = Some("Still playing the previous move".to_string());
+        return;
+    }
+    c.auto_move_status = Some(if best.len() > 4 {
+        format!("Playing {}; choose the promotion piece", best)
+    } else {
+        format!("Playing {}", best)
+    });
+    drop(c);
+
+    let restore = settings
+        .restore_cursor
+        .then(crate::hotkeys::cursor_position)
+        .flatten();
+    let delay = settings.delay();
+    thread::spawn(move || {
+        thread::sleep(delay);
+        let result = click(from.0, from.1).and_then(|_| click(to.0, to.1));
+        if let Some((x, y)) = restore {
+            let _ = simulate(&EventType::MouseMove { x, y });
+        }
+        if let Err(e) = result {
+            errors.report(AppError::System(format!("Simulated click failed: {:?}", e)));
+        }
+        PLAYING.store(false, Ordering::SeqCst);
+    });
+}
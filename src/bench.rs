@@ -0,0 +1,177 @@
+//! `--bench <folder> [--model <path>]`: runs the vision pipeline over
+//! labeled board screenshots and reports how close the recognized
+//! positions are to the expected ones, to compare models and catch
+//! recognition regressions.
+//!
+//! Every image (`.png`, `.jpg`) needs a sidecar with the same name and a
+//! `.fen` extension holding the expected FEN; only its piece placement is
+//! compared. Images without one are skipped.
+
+use crate::config::AppConfig;
+use crate::vision::board::{attempted_fen, min_board_fraction, select_board};
+use crate::vision::color::correct_colors;
+use crate::vision::inference::Detector;
+use crate::vision::site::board_grid;
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Piece letters in report order.
+const PIECES: &str = "KQRBNPkqrbnp";
+
+/// Squares of a FEN placement, a8 first, `None` for empty.
+fn squares(fen: &str) -> Option<Vec<Option<char>>> {
+    let placement = fen.split_whitespace().next()?;
+    let mut out = Vec::with_capacity(64);
+    for rank in placement.split('/') {
+        let start = out.len();
+        for c in rank.chars() {
+            match c.to_digit(10) {
+                Some(n) => out.extend(std::iter::repeat_n(None, n as usize)),
+                None if PIECES.contains(c) => out.push(Some(c)),
+                None => return None,
+            }
+        }
+        if out.len() - start != 8 {
+            return None;
+        }
+    }
+    (out.len() == 64).then_some(out)
+}
+
+/// Hits and totals per expected square content.
+#[derive(Default)]
+struct Tally {
+    /// Indexed like `PIECES`, then empty squares last.
+    correct: [usize; 13],
+    total: [usize; 13],
+}
+
+impl Tally {
+    fn add(&mut self, expected: &[Option<char>], got: &[Option<char>]) {
+        for (e, g) in expected.iter().zip(got) {
+            let i = e.and_then(|c| PIECES.find(c)).unwrap_or(12);
+            self.total[i] += 1;
+            if e == g {
+                self.correct[i] += 1;
+            }
+        }
+    }
+}
+
+fn percent(correct: usize, total: usize) -> String {
+    if total == 0 {
+        return "-".to_string();
+    }
+    format!("{:.1}%", correct as f32 * 100.0 / total as f32)
+}
+
+fn labeled_images(dir: &Path) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let mut images: Vec<(PathBuf, PathBuf)> = fs::read_dir(dir)
+        .with_context(|| format!("cannot read {}", dir.display()))?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| matches!(e.to_ascii_lowercase().as_str(), "png" | "jpg" | "jpeg"))
+        })
+        .filter_map(|p| {
+            let sidecar = p.with_extension("fen");
+            sidecar.exists().then_some((p, sidecar))
+        })
+        .collect();
+    images.sort();
+    Ok(images)
+}
+
+/// Recognizes every labeled image in `dir` with the configured confidence
+/// threshold, board size and inference backend, and prints per-piece
+/// accuracy, square accuracy, fully correct positions and average
+/// detection latency. `model` overrides the configured model.
+pub fn bench(dir: &Path, model: Option<&Path>) -> Result<()> {
+    let config = AppConfig::load();
+    let images = labeled_images(dir)?;
+    if images.is_empty() {
+        bail!("no images with a .fen sidecar in {}", dir.display());
+    }
+    let model_path = match model {
+        Some(path) => path.to_path_buf(),
+        None => crate::paths::model_path(&config.model_path),
+    };
+    let mut detector = Detector::new(&model_path.to_string_lossy(), config.inference_backend)?;
+
+    let mut tally = Tally::default();
+    let mut exact = 0;
+    let mut latency = Duration::ZERO;
+    for (image, sidecar) in &images {
+        let name = image.file_name().unwrap_or_default().to_string_lossy();
+        let expected_fen = fs::read_to_string(sidecar)?;
+        let Some(expected) = squares(&expected_fen) else {
+            bail!("{}: not a FEN", sidecar.display());
+        };
+        let img = image::open(image).with_context(|| format!("failed to load {}", name))?;
+
+        let started = Instant::now();
+        let mut detections =
+            detector.detect(&img, config.confidence_threshold, config.iou_threshold)?;
+        if config.color_check {
+            correct_colors(&img, &mut detections, config.color_check_below);
+        }
+        latency += started.elapsed();
+
+        let min_fraction = min_board_fraction(config.min_board_px, img.width().min(img.height()));
+        let candidates = select_board(&detections, min_fraction, 0);
+        let grid = candidates
+            .selected
+            .and_then(|b| board_grid(&img, b, config.coordinate_strip, config.refine_board_grid));
+        let board_box = grid.as_ref().or(candidates.selected);
+        let reassign = config.reassign_cell_conflicts;
+        let fen = attempted_fen(&detections, board_box, true, reassign);
+        let got = squares(&fen).unwrap_or_else(|| vec![None; 64]);
+        tally.add(&expected, &got);
+        if got == expected {
+            exact += 1;
+        } else {
+            let wrong = expected.iter().zip(&got).filter(|(e, g)| e != g).count();
+            println!("{}: {} wrong squares", name, wrong);
+            println!(
+                "  expected {}",
+                expected_fen.split_whitespace().next().unwrap_or("")
+            );
+            println!("  got      {}", fen.split_whitespace().next().unwrap_or(""));
+        }
+    }
+
+    let n = images.len();
+    println!();
+    println!("Model:      {}", model_path.display());
+    println!("Backend:    {}", config.inference_backend.label());
+    println!("Images:     {}", n);
+    println!("Positions:  {} ({}/{})", percent(exact, n), exact, n);
+    println!(
+        "Squares:    {}",
+        percent(tally.correct.iter().sum(), tally.total.iter().sum())
+    );
+    println!(
+        "Latency:    {:.1} ms per image",
+        latency.as_secs_f64() * 1000.0 / n as f64
+    );
+    println!("Per piece:");
+    for (i, c) in PIECES.chars().chain(['.']).enumerate() {
+        let name = if c == '.' {
+            "empty".to_string()
+        } else {
+            c.to_string()
+        };
+        println!(
+            "  {:<6}{:>7}  ({}/{})",
+            name,
+            percent(tally.correct[i], tally.total[i]),
+            tally.correct[i],
+            tally.total[i]
+        );
+    }
+    Ok(())
+}
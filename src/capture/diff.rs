@@ -0,0 +1,99 @@
+use image::{imageops::FilterType, DynamicImage};
+use std::time::{Duration, Instant};
+
+/// Side length of the downsampled grayscale thumbnail used for diffing.
+const SIGNATURE_SIZE: u32 = 32;
+
+/// Mean absolute luma difference (0.0..1.0) above which two frames are
+/// considered different positions.
+const CHANGE_THRESHOLD: f32 = 0.02;
+
+/// Cheap fingerprint of a captured frame, used to detect position changes
+/// without running the full vision model.
+#[derive(Clone)]
+pub struct FrameSignature {
+    pixels: Vec<u8>,
+}
+
+impl FrameSignature {
+    pub fn from_image(img: &DynamicImage) -> Self {
+        let thumb = img
+            .resize_exact(SIGNATURE_SIZE, SIGNATURE_SIZE, FilterType::Triangle)
+            .to_luma8();
+        Self {
+            pixels: thumb.into_raw(),
+        }
+    }
+
+    pub fn difference(&self, other: &FrameSignature) -> f32 {
+        if self.pixels.len() != other.pixels.len() || self.pixels.is_empty() {
+            return 1.0;
+        }
+        let total: u32 = self
+            .pixels
+            .iter()
+            .zip(&other.pixels)
+            .map(|(a, b)| a.abs_diff(*b) as u32)
+            .sum();
+        total as f32 / (self.pixels.len() as f32 * 255.0)
+    }
+}
+
+/// Tracks the last analyzed frame and confirms a change once the new frame
+/// has been seen on two consecutive captures (so mid-animation frames are
+/// not analyzed) and a cooldown after the first differing capture has
+/// passed (sites briefly show both the old and new squares while a piece
+/// slides).
+#[derive(Default)]
+pub struct ChangeDetector {
+    analyzed: Option<FrameSignature>,
+    previous: Option<FrameSignature>,
+    /// First capture that differed from the analyzed frame.
+    changed_at: Option<Instant>,
+}
+
+impl ChangeDetector {
+    /// Feeds a new capture, returning true if it differs from the last
+    /// analyzed frame, is stable against the previous capture, and the
+    /// change is at least `cooldown` old. There is no cooldown before the
+    /// first analysis or after a reset.
+    pub fn observe(&mut self, sig: FrameSignature, cooldown: Duration) -> bool {
+        let changed = match &self.analyzed {
+            Some(analyzed) => analyzed.difference(&sig) > CHANGE_THRESHOLD,
+            None => true,
+        };
+        let stable = match &self.previous {
+            Some(prev) => prev.difference(&sig) <= CHANGE_THRESHOLD,
+            None => false,
+        };
+        self.previous = Some(sig);
+        if !changed {
+            self.changed_at = None;
+            return false;
+        }
+        if self.analyzed.is_none() {
+            // First frame or forced re-scan: nothing is moving
+            return stable;
+        }
+        let since = *self.changed_at.get_or_insert_with(Instant::now);
+        stable && since.elapsed() >= cooldown
+    }
+
+    /// Whether the last capture differed from the analyzed frame but is not
+    /// confirmed yet (still moving or within the cooldown).
+    pub fn is_pending(&self) -> bool {
+        self.changed_at.is_some()
+    }
+
+    /// Marks the most recently observed frame as analyzed.
+    pub fn mark_analyzed(&mut self) {
+        self.analyzed = self.previous.clone();
+        self.changed_at = None;
+    }
+
+    /// Forgets the analyzed frame so the next stable capture is analyzed.
+    pub fn reset(&mut self) {
+        self.analyzed = None;
+        self.changed_at = None;
+    }
+}
@@ -1,12 +1,232 @@
+use crate::capture::monitor::screen_for;
 use anyhow::Result;
 use image::DynamicImage;
 use screenshots::Screen;
+use std::thread;
+use std::time::{Duration, Instant};
 
-pub fn capture_region(x: u32, y: u32, w: u32, h: u32) -> Result<DynamicImage> {
-    let screens = Screen::all()?;
-    let screen = screens
-        .first()
-        .ok_or_else(|| anyhow::anyhow!("No screen found"))?;
+/// Attempts per `grab` call before the failure is counted.
+const RETRIES: u32 = 3;
+/// Delay before the first retry; doubled for each further attempt.
+const BACKOFF: Duration = Duration::from_millis(50);
+/// Consecutive failed grabs before switching to the next backend.
+const SWITCH_AFTER: u32 = 5;
+/// While on the full-screen fallback, how often area capture is tried
+/// again, so a passing failure doesn't leave us grabbing the whole monitor
+/// for good.
+const PROBE_AREA_EVERY: Duration = Duration::from_secs(30);
+
+/// Coordinates are relative to `screen`.
+pub fn capture_region(screen: &Screen, x: u32, y: u32, w: u32, h: u32) -> Result<DynamicImage> {
     let image = screen.capture_area(x as i32, y as i32, w, h)?;
     Ok(DynamicImage::ImageRgba8(image))
 }
+
+/// Grabs the whole screen and crops, for drivers where area capture fails.
+fn capture_full_and_crop(screen: &Screen, x: u32, y: u32, w: u32, h: u32) -> Result<DynamicImage> {
+    let image = DynamicImage::ImageRgba8(screen.capture()?);
+    if x + w > image.width() || y + h > image.height() {
+        return Err(anyhow::anyhow!("Region is outside the screen"));
+    }
+    Ok(image.crop_imm(x, y, w, h))
+}
+
+#[cfg(windows)]
+fn capture_gdi(screen: &Screen, x: u32, y: u32, w: u32, h: u32) -> Result<DynamicImage> {
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::Graphics::Gdi::{
+        BitBlt, CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject, GetDC,
+        GetDIBits, ReleaseDC, SelectObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS,
+        SRCCOPY,
+    };
+
+    // The screen DC spans the whole virtual desktop
+    let left = screen.display_info.x + x as i32;
+    let top = screen.display_info.y + y as i32;
+    let mut pixels = vec![0u8; (w * h * 4) as usize];
+    unsafe {
+        let screen_dc = GetDC(HWND::default());
+        let mem_dc = CreateCompatibleDC(screen_dc);
+        let bitmap = CreateCompatibleBitmap(screen_dc, w as i32, h as i32);
+        let previous = SelectObject(mem_dc, bitmap);
+        let blit = BitBlt(
+            mem_dc, 0, 0, w as i32, h as i32, screen_dc, left, top, SRCCOPY,
+        );
+
+        let mut info = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: w as i32,
+                // Negative height requests top-down rows
+                biHeight: -(h as i32),
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let lines = GetDIBits(
+            mem_dc,
+            bitmap,
+            0,
+            h,
+            Some(pixels.as_mut_ptr() as *mut _),
+            &mut info,
+            DIB_RGB_COLORS,
+        );
+
+        SelectObject(mem_dc, previous);
+        let _ = DeleteObject(bitmap);
+        let _ = DeleteDC(mem_dc);
+        ReleaseDC(HWND::default(), screen_dc);
+
+        blit?;
+        if lines == 0 {
+            return Err(anyhow::anyhow!("GetDIBits failed"));
+        }
+    }
+
+    // BGRA -> RGBA
+    for px in pixels.chunks_exact_mut(4) {
+        px.swap(0, 2);
+        px[3] = 255;
+    }
+    let image = image::RgbaImage::from_raw(w, h, pixels)
+        .ok_or_else(|| anyhow::anyhow!("Invalid GDI buffer"))?;
+    Ok(DynamicImage::ImageRgba8(image))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaptureBackend {
+    ScreenArea,
+    ScreenCrop,
+    #[cfg(windows)]
+    Gdi,
+}
+
+impl CaptureBackend {
+    pub fn label(&self) -> &'static str {
+        match self {
+            CaptureBackend::ScreenArea => "screenshots (area)",
+            CaptureBackend::ScreenCrop => "screenshots (full + crop)",
+            #[cfg(windows)]
+            CaptureBackend::Gdi => "GDI",
+        }
+    }
+
+    fn capture(&self, screen: &Screen, x: u32, y: u32, w: u32, h: u32) -> Result<DynamicImage> {
+        match self {
+            CaptureBackend::ScreenArea => capture_region(screen, x, y, w, h),
+            CaptureBackend::ScreenCrop => capture_full_and_crop(screen, x, y, w, h),
+            #[cfg(windows)]
+            CaptureBackend::Gdi => capture_gdi(screen, x, y, w, h),
+        }
+    }
+}
+
+/// Region capture with retries and automatic failover between backends.
+pub struct Grabber {
+    backends: Vec<CaptureBackend>,
+    active: usize,
+    consecutive_failures: u32,
+    /// Backends that failed `SWITCH_AFTER` times in a row since the last success.
+    exhausted: usize,
+    /// Display id regions are relative to; `None` for the primary display.
+    monitor: Option<u32>,
+    /// `monitor` resolved once instead of enumerating displays every grab;
+    /// dropped when a grab fails, in case the display layout changed.
+    screen: Option<Screen>,
+    last_area_probe: Instant,
+}
+
+impl Default for Grabber {
+    fn default() -> Self {
+        Self {
+            backends: vec![
+                CaptureBackend::ScreenArea,
+                CaptureBackend::ScreenCrop,
+                #[cfg(windows)]
+                CaptureBackend::Gdi,
+            ],
+            active: 0,
+            consecutive_failures: 0,
+            exhausted: 0,
+            monitor: None,
+            screen: None,
+            last_area_probe: Instant::now(),
+        }
+    }
+}
+
+impl Grabber {
+    pub fn backend(&self) -> CaptureBackend {
+        self.backends[self.active]
+    }
+
+    /// True once every backend has failed repeatedly without a success.
+    pub fn is_failing(&self) -> bool {
+        self.exhausted >= self.backends.len()
+    }
+
+    pub fn set_monitor(&mut self, monitor: Option<u32>) {
+        if monitor != self.monitor {
+            self.screen = None;
+        }
+        self.monitor = monitor;
+    }
+
+    pub fn grab(&mut self, x: u32, y: u32, w: u32, h: u32) -> Result<DynamicImage> {
+        let screen = match self.screen {
+            Some(screen) => screen,
+            None => *self.screen.insert(screen_for(self.monitor)?),
+        };
+        if self.backend() == CaptureBackend::ScreenCrop
+            && self.last_area_probe.elapsed() >= PROBE_AREA_EVERY
+        {
+            self.last_area_probe = Instant::now();
+            if let Ok(img) = capture_region(&screen, x, y, w, h) {
+                println!(
+                    "Area capture works again, leaving {}",
+                    self.backend().label()
+                );
+                self.active = 0;
+                self.consecutive_failures = 0;
+                self.exhausted = 0;
+                return Ok(img);
+            }
+        }
+        let mut last_err = None;
+        for attempt in 0..RETRIES {
+            match self.backend().capture(&screen, x, y, w, h) {
+                Ok(img) => {
+                    self.consecutive_failures = 0;
+                    self.exhausted = 0;
+                    return Ok(img);
+                }
+                Err(e) => {
+                    last_err = Some(e);
+                    if attempt + 1 < RETRIES {
+                        thread::sleep(BACKOFF * 2u32.pow(attempt));
+                    }
+                }
+            }
+        }
+
+        self.screen = None;
+        self.consecutive_failures += 1;
+        if self.consecutive_failures >= SWITCH_AFTER {
+            let failed = self.backend();
+            self.active = (self.active + 1) % self.backends.len();
+            self.consecutive_failures = 0;
+            self.exhausted = (self.exhausted + 1).min(self.backends.len());
+            self.last_area_probe = Instant::now();
+            println!(
+                "Capture backend {} keeps failing, switching to {}",
+                failed.label(),
+                self.backend().label()
+            );
+        }
+        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("Capture failed")))
+    }
+}
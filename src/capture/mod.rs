@@ -1 +1,6 @@
+pub mod diff;
 pub mod grabber;
+pub mod monitor;
+pub mod source;
+pub mod stream;
+pub mod target_window;
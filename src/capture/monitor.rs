@@ -0,0 +1,58 @@
+use anyhow::Result;
+use screenshots::Screen;
+
+/// A connected display, as offered in the monitor picker.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MonitorInfo {
+    pub id: u32,
+    pub label: String,
+    /// Top-left corner in virtual desktop coordinates.
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub is_primary: bool,
+}
+
+pub fn list_monitors() -> Vec<MonitorInfo> {
+    let screens = match Screen::all() {
+        Ok(s) => s,
+        Err(e) => {
+            println!("Failed to list monitors: {:?}", e);
+            return Vec::new();
+        }
+    };
+    screens
+        .iter()
+        .enumerate()
+        .map(|(i, s)| {
+            let d = s.display_info;
+            MonitorInfo {
+                id: d.id,
+                label: format!(
+                    "Display {} ({}x{}){}",
+                    i + 1,
+                    d.width,
+                    d.height,
+                    if d.is_primary { " primary" } else { "" }
+                ),
+                x: d.x,
+                y: d.y,
+                width: d.width,
+                height: d.height,
+                is_primary: d.is_primary,
+            }
+        })
+        .collect()
+}
+
+/// The screen with the given id, or the primary screen when `id` is `None`
+/// or that display is no longer connected.
+pub fn screen_for(id: Option<u32>) -> Result<Screen> {
+    let screens = Screen::all()?;
+    id.and_then(|id| screens.iter().find(|s| s.display_info.id == id))
+        .or_else(|| screens.iter().find(|s| s.display_info.is_primary))
+        .or_else(|| screens.first())
+        .copied()
+        .ok_or_else(|| anyhow::anyhow!("No screen found"))
+}
@@ -0,0 +1,176 @@
+use crate::capture::grabber::Grabber;
+use crate::config::BoardRegion;
+use anyhow::{anyhow, bail, Result};
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// Where board images come from. The capture thread only talks to this
+/// trait, so a new kind of source needs an implementation and a
+/// `CaptureSourceKind` variant, not changes to the worker. Sources are
+/// opened on the capture thread and never leave it.
+pub trait CaptureSource {
+    /// Shown with capture errors, e.g. the screen backend in use.
+    fn label(&self) -> String;
+
+    /// Called once before the first frame; an error keeps the source idle
+    /// until its settings change.
+    fn start(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// One image per region, taken together. Regions are in the source's
+    /// own pixel coordinates.
+    fn next_frame(&mut self, regions: &[BoardRegion]) -> Result<Vec<DynamicImage>>;
+
+    /// True once errors are persistent rather than a passing glitch.
+    fn is_failing(&self) -> bool {
+        true
+    }
+
+    /// Called when capturing stops or the source is replaced.
+    fn stop(&mut self) {}
+}
+
+/// Which `CaptureSource` the worker captures from.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum CaptureSourceKind {
+    /// The board region on the selected monitor.
+    #[default]
+    Monitor,
+    /// Still images from a folder, played in file name order and looped,
+    /// for testing without a live game.
+    ImageSequence,
+}
+
+impl CaptureSourceKind {
+    pub const ALL: [CaptureSourceKind; 2] =
+        [CaptureSourceKind::Monitor, CaptureSourceKind::ImageSequence];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CaptureSourceKind::Monitor => "Screen",
+            CaptureSourceKind::ImageSequence => "Image folder",
+        }
+    }
+}
+
+/// A source and the settings it was opened with; a different spec replaces
+/// the running source.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SourceSpec {
+    pub kind: CaptureSourceKind,
+    /// Display id for `Monitor`; `None` for the primary display.
+    pub monitor: Option<u32>,
+    /// Folder for `ImageSequence`.
+    pub folder: String,
+}
+
+impl SourceSpec {
+    pub fn open(&self) -> Box<dyn CaptureSource> {
+        match self.kind {
+            CaptureSourceKind::Monitor => Box::new(MonitorSource::new(self.monitor)),
+            CaptureSourceKind::ImageSequence => {
+                Box::new(ImageSequenceSource::new(PathBuf::from(self.folder.trim())))
+            }
+        }
+    }
+}
+
+/// Screen regions grabbed through `Grabber`, with its backend failover.
+pub struct MonitorSource {
+    grabber: Grabber,
+}
+
+impl MonitorSource {
+    pub fn new(monitor: Option<u32>) -> Self {
+        let mut grabber = Grabber::default();
+        grabber.set_monitor(monitor);
+        Self { grabber }
+    }
+}
+
+impl CaptureSource for MonitorSource {
+    fn label(&self) -> String {
+        self.grabber.backend().label().to_string()
+    }
+
+    fn next_frame(&mut self, regions: &[BoardRegion]) -> Result<Vec<DynamicImage>> {
+        regions
+            .iter()
+            .map(|r| self.grabber.grab(r.x, r.y, r.width, r.height))
+            .collect()
+    }
+
+    fn is_failing(&self) -> bool {
+        self.grabber.is_failing()
+    }
+}
+
+/// Image files from a folder, one per frame, cropped to the regions.
+pub struct ImageSequenceSource {
+    folder: PathBuf,
+    files: Vec<PathBuf>,
+    next: usize,
+}
+
+impl ImageSequenceSource {
+    pub fn new(folder: PathBuf) -> Self {
+        Self {
+            folder,
+            files: Vec::new(),
+            next: 0,
+        }
+    }
+}
+
+impl CaptureSource for ImageSequenceSource {
+    fn label(&self) -> String {
+        format!("image folder {}", self.folder.display())
+    }
+
+    fn start(&mut self) -> Result<()> {
+        let mut files: Vec<PathBuf> = fs::read_dir(&self.folder)?
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| {
+                p.extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| {
+                        ["png", "jpg", "jpeg", "bmp"].contains(&ext.to_lowercase().as_str())
+                    })
+            })
+            .collect();
+        if files.is_empty() {
+            bail!("No images in {}", self.folder.display());
+        }
+        files.sort();
+        self.files = files;
+        self.next = 0;
+        Ok(())
+    }
+
+    fn next_frame(&mut self, regions: &[BoardRegion]) -> Result<Vec<DynamicImage>> {
+        let path = self
+            .files
+            .get(self.next)
+            .cloned()
+            .ok_or_else(|| anyhow!("Image folder not started"))?;
+        let image = image::open(&path)?;
+        self.next = (self.next + 1) % self.files.len();
+        regions
+            .iter()
+            .map(|r| {
+                if r.x + r.width > image.width() || r.y + r.height > image.height() {
+                    bail!("Region is outside {}", path.display());
+                }
+                Ok(image.crop_imm(r.x, r.y, r.width, r.height))
+            })
+            .collect()
+    }
+
+    fn stop(&mut self) {
+        self.files.clear();
+    }
+}
@@ -0,0 +1,208 @@
+use crate::capture::source::{CaptureSource, SourceSpec};
+use crate::config::BoardRegion;
+use image::DynamicImage;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Frames kept for the analysis worker; older ones are dropped unread.
+const RING_SIZE: usize = 4;
+/// How often an idle stream checks for a new target.
+const IDLE_POLL: Duration = Duration::from_millis(50);
+
+/// One capture of every board region, taken together.
+pub struct CapturedFrame {
+    pub seq: u64,
+    pub regions: Vec<BoardRegion>,
+    pub images: Vec<DynamicImage>,
+}
+
+/// Capture rate and frames the worker never got to, for the overlay.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CaptureStats {
+    /// Smoothed frames per second actually captured.
+    pub fps: f32,
+    /// Frames replaced by a newer one before the worker read them.
+    pub dropped: u64,
+}
+
+#[derive(Default)]
+struct Shared {
+    ring: VecDeque<Arc<CapturedFrame>>,
+    /// Regions and the source to capture them from; `None` idles the
+    /// thread and stops the source.
+    target: Option<(Vec<BoardRegion>, SourceSpec)>,
+    interval: Duration,
+    /// Set while every capture backend keeps failing.
+    error: Option<String>,
+    stats: CaptureStats,
+    /// Newest frame handed to the worker.
+    taken: u64,
+    stop: bool,
+}
+
+/// Grabs the board regions on its own thread at a steady rate, so capture
+/// timing doesn't depend on how long inference and search take. The worker
+/// reads the newest frame whenever it is ready for one.
+pub struct CaptureStream {
+    shared: Arc<Mutex<Shared>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl CaptureStream {
+    pub fn spawn() -> Self {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let thread_shared = shared.clone();
+        let handle = thread::spawn(move || run(thread_shared));
+        Self {
+            shared,
+            handle: Some(handle),
+        }
+    }
+
+    /// What to capture, from which source, and how long to wait between
+    /// captures; `None` stops capturing.
+    pub fn set_target(
+        &self,
+        regions: Option<&[BoardRegion]>,
+        source: &SourceSpec,
+        interval: Duration,
+    ) {
+        let mut shared = self.shared.lock().unwrap();
+        shared.interval = interval;
+        let target = regions.map(|r| (r.to_vec(), source.clone()));
+        if shared.target != target {
+            // Frames of the old layout must not be analyzed
+            shared.ring.clear();
+            shared.target = target;
+        }
+    }
+
+    /// The newest frame not handed out yet; frames it replaces count as
+    /// dropped.
+    pub fn latest(&self) -> Option<Arc<CapturedFrame>> {
+        let mut shared = self.shared.lock().unwrap();
+        let newest = shared.ring.back()?.clone();
+        if newest.seq <= shared.taken {
+            return None;
+        }
+        let taken = shared.taken;
+        let skipped = shared
+            .ring
+            .iter()
+            .filter(|f| f.seq > taken && f.seq < newest.seq)
+            .count();
+        shared.stats.dropped += skipped as u64;
+        shared.taken = newest.seq;
+        Some(newest)
+    }
+
+    pub fn error(&self) -> Option<String> {
+        self.shared.lock().unwrap().error.clone()
+    }
+
+    pub fn stats(&self) -> CaptureStats {
+        self.shared.lock().unwrap().stats
+    }
+}
+
+impl Drop for CaptureStream {
+    fn drop(&mut self) {
+        self.shared.lock().unwrap().stop = true;
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn run(shared: Arc<Mutex<Shared>>) {
+    // The open source, `None` inside when it failed to start
+    let mut source: Option<(SourceSpec, Option<Box<dyn CaptureSource>>)> = None;
+    let mut seq = 0;
+    let mut last_frame: Option<Instant> = None;
+    loop {
+        let (target, interval) = {
+            let shared = shared.lock().unwrap();
+            if shared.stop {
+                return;
+            }
+            (shared.target.clone(), shared.interval)
+        };
+        let Some((regions, spec)) = target else {
+            if let Some((_, Some(mut old))) = source.take() {
+                old.stop();
+            }
+            last_frame = None;
+            thread::sleep(IDLE_POLL);
+            continue;
+        };
+
+        if source.as_ref().map(|(s, _)| s) != Some(&spec) {
+            if let Some((_, Some(mut old))) = source.take() {
+                old.stop();
+            }
+            let mut opened = spec.open();
+            let result = opened.start();
+            if let Err(e) = &result {
+                shared.lock().unwrap().error = Some(format!("{} ({})", e, opened.label()));
+            }
+            source = Some((spec, result.is_ok().then_some(opened)));
+        }
+        let Some(active) = source.as_mut().and_then(|(_, s)| s.as_mut()) else {
+            // Stays idle until the source settings change
+            thread::sleep(IDLE_POLL);
+            continue;
+        };
+
+        let started = Instant::now();
+        let (images, error) = match active.next_frame(&regions) {
+            Ok(images) => (images, None),
+            Err(e) => {
+                let error = active
+                    .is_failing()
+                    .then(|| format!("{} ({})", e, active.label()));
+                (Vec::new(), error)
+            }
+        };
+
+        {
+            let mut shared = shared.lock().unwrap();
+            let complete = images.len() == regions.len();
+            if complete {
+                shared.error = None;
+            } else if error.is_some() {
+                shared.error = error;
+            }
+            // The target may have changed while grabbing
+            if complete && shared.target.as_ref().map(|t| &t.0) == Some(&regions) {
+                seq += 1;
+                let now = Instant::now();
+                if let Some(last) = last_frame {
+                    let rate = 1.0 / now.duration_since(last).as_secs_f32().max(1e-3);
+                    let fps = &mut shared.stats.fps;
+                    *fps = if *fps == 0.0 {
+                        rate
+                    } else {
+                        *fps * 0.8 + rate * 0.2
+                    };
+                }
+                last_frame = Some(now);
+                shared.ring.push_back(Arc::new(CapturedFrame {
+                    seq,
+                    regions,
+                    images,
+                }));
+                while shared.ring.len() > RING_SIZE {
+                    if let Some(old) = shared.ring.pop_front() {
+                        if old.seq > shared.taken {
+                            shared.stats.dropped += 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        thread::sleep(interval.saturating_sub(started.elapsed()));
+    }
+}
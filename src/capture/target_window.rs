@@ -0,0 +1,133 @@
+/// Why the window showing the board can't be seen right now.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(not(windows), allow(dead_code))]
+pub enum Hidden {
+    Minimized,
+    /// Cloaked by the shell, which is how windows on another virtual
+    /// desktop appear.
+    OtherDesktop,
+    Unfocused,
+}
+
+impl Hidden {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Hidden::Minimized => "game window minimized",
+            Hidden::OtherDesktop => "game window on another desktop",
+            Hidden::Unfocused => "game window not focused",
+        }
+    }
+}
+
+/// The top-level window under the board region, found once per region and
+/// kept so it can still be checked after it is minimized.
+#[derive(Default)]
+pub struct TargetWindow {
+    /// Screen point the window was looked up at.
+    point: Option<(i32, i32)>,
+    /// Raw window handle; `None` when nothing (or only one of our own
+    /// windows) was under the region.
+    handle: Option<isize>,
+}
+
+impl TargetWindow {
+    /// Looks up the window at `point` (virtual desktop coordinates) when
+    /// the point changed, the window was closed or none was found yet.
+    pub fn track(&mut self, point: (i32, i32)) {
+        if self.point != Some(point) || !self.handle.is_some_and(alive) {
+            self.point = Some(point);
+            self.handle = window_at(point);
+        }
+    }
+
+    pub fn forget(&mut self) {
+        self.point = None;
+        self.handle = None;
+    }
+
+    /// Raw handle of the window found under the region.
+    pub fn handle(&self) -> Option<isize> {
+        self.handle
+    }
+
+    /// `None` while the window is visible (or unknown, which never pauses).
+    pub fn hidden(&self, require_focus: bool) -> Option<Hidden> {
+        state(self.handle?, require_focus)
+    }
+}
+
+#[cfg(windows)]
+fn window_at((x, y): (i32, i32)) -> Option<isize> {
+    use windows::Win32::Foundation::POINT;
+    use windows::Win32::UI::WindowsAndMessaging::{GetAncestor, WindowFromPoint, GA_ROOT};
+
+    let window = unsafe { GetAncestor(WindowFromPoint(POINT { x, y }), GA_ROOT) };
+    // Our overlay covers the region; the settings window may too
+    (!window.is_invalid() && !is_ours(window)).then_some(window.0 as isize)
+}
+
+#[cfg(windows)]
+fn is_ours(window: windows::Win32::Foundation::HWND) -> bool {
+    use windows::Win32::UI::WindowsAndMessaging::GetWindowThreadProcessId;
+
+    let mut pid = 0;
+    unsafe { GetWindowThreadProcessId(window, Some(&mut pid as *mut u32)) };
+    pid == std::process::id()
+}
+
+#[cfg(windows)]
+fn alive(handle: isize) -> bool {
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::WindowsAndMessaging::IsWindow;
+
+    unsafe { IsWindow(HWND(handle as _)).as_bool() }
+}
+
+#[cfg(windows)]
+fn state(handle: isize, require_focus: bool) -> Option<Hidden> {
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::Graphics::Dwm::{DwmGetWindowAttribute, DWMWA_CLOAKED};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetAncestor, GetForegroundWindow, IsIconic, IsWindowVisible, GA_ROOT,
+    };
+
+    let window = HWND(handle as _);
+    unsafe {
+        if IsIconic(window).as_bool() || !IsWindowVisible(window).as_bool() {
+            return Some(Hidden::Minimized);
+        }
+        let mut cloaked = 0u32;
+        let queried = DwmGetWindowAttribute(
+            window,
+            DWMWA_CLOAKED,
+            &mut cloaked as *mut u32 as *mut _,
+            std::mem::size_of::<u32>() as u32,
+        );
+        if queried.is_ok() && cloaked != 0 {
+            return Some(Hidden::OtherDesktop);
+        }
+        if require_focus {
+            let foreground = GetAncestor(GetForegroundWindow(), GA_ROOT);
+            // Using our own settings window doesn't count as leaving the game
+            if foreground != window && !is_ours(foreground) {
+                return Some(Hidden::Unfocused);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(not(windows))]
+fn window_at(_point: (i32, i32)) -> Option<isize> {
+    None
+}
+
+#[cfg(not(windows))]
+fn alive(_handle: isize) -> bool {
+    true
+}
+
+#[cfg(not(windows))]
+fn state(_handle: isize, _require_focus: bool) -> Option<Hidden> {
+    None
+}
@@ -0,0 +1,503 @@
+use crate::engine::stockfish::Evaluation;
+use shakmaty::{
+    fen::Fen,
+    san::{San, SanPlus},
+    uci::UciMove,
+    CastlingMode, Chess, Color, EnPassantMode, Position, Role,
+};
+use std::time::{Duration, Instant};
+
+/// Parses a FEN into a playable position, rejecting illegal setups.
+pub fn position_from_fen(fen: &str) -> Option<Chess> {
+    Fen::from_ascii(fen.as_bytes())
+        .ok()?
+        .into_position(CastlingMode::Standard)
+        .ok()
+}
+
+/// Full FEN of a position, including castling and en passant fields.
+pub fn position_fen(pos: &Chess) -> String {
+    let setup = pos.clone().into_setup(EnPassantMode::Legal);
+    Fen::from_setup(setup).to_string()
+}
+
+/// Placement field of a FEN.
+fn placement(fen: &str) -> &str {
+    fen.split_whitespace().next().unwrap_or("")
+}
+
+/// Observations without a matching move before the tracker gives up and
+/// resyncs from the detected board (losing castling/en passant history).
+const LOST_GRACE: u32 = 3;
+
+/// Follows the game move by move so the full FEN (castling rights, en
+/// passant target, move counters) survives between captures. Moves are
+/// inferred by matching each detected placement against the legal moves of
+/// the tracked position, or entered by hand when vision misses one.
+#[derive(Default)]
+pub struct GameTracker {
+    position: Option<Chess>,
+    /// Moves played since tracking started, in SAN.
+    moves: Vec<String>,
+    /// Consecutive observations that matched no legal continuation.
+    unmatched: u32,
+    /// Side to move and move number where tracking started.
+    start: Option<(bool, u32)>,
+    /// Full FEN of the position tracking started from.
+    start_fen: Option<String>,
+    /// Engine score for White of the start position and of the position
+    /// after each move, as far as it was analyzed.
+    evals: Vec<Option<Evaluation>>,
+    /// When the side to move started thinking.
+    turn_started: Option<Instant>,
+    /// Thinking time measured between detected moves, White then Black.
+    used: [Duration; 2],
+}
+
+impl GameTracker {
+    pub fn moves(&self) -> &[String] {
+        &self.moves
+    }
+
+    /// Whether White was to move, and the full move number, where the
+    /// move list starts.
+    pub fn start(&self) -> Option<(bool, u32)> {
+        self.start
+    }
+
+    pub fn start_fen(&self) -> Option<&str> {
+        self.start_fen.as_deref()
+    }
+
+    /// Score for White per position: the start, then after each move.
+    pub fn evals(&self) -> &[Option<Evaluation>] {
+        &self.evals
+    }
+
+    /// Stores the engine's score for White of `fen` if it is the current
+    /// tracked position. Returns whether the stored score changed.
+    pub fn record_eval(&mut self, fen: &str, eval: Evaluation) -> bool {
+        let Some(pos) = &self.position else {
+            return false;
+        };
+        if position_fen(pos) != fen {
+            return false;
+        }
+        let ply = self.moves.len();
+        self.evals.resize(ply + 1, None);
+        let changed = self.evals[ply] != Some(eval);
+        self.evals[ply] = Some(eval);
+        changed
+    }
+
+    /// Time each side (White, Black) spent between moves seen so far.
+    pub fn time_used(&self) -> [Duration; 2] {
+        self.used
+    }
+
+    pub fn turn_started(&self) -> Option<Instant> {
+        self.turn_started
+    }
+
+    pub fn white_to_move(&self) -> Option<bool> {
+        self.position.as_ref().map(|p| p.turn() == Color::White)
+    }
+
+    pub fn is_lost(&self) -> bool {
+        self.unmatched > 0
+    }
+
+    /// Feeds a FEN built from vision. Returns the full FEN of the tracked
+    /// position when it agrees with the detected board.
+    pub fn observe(&mut self, detected_fen: &str) -> Option<String> {
+        let detected = placement(detected_fen);
+        let Some(pos) = self.position.clone() else {
+            return self.resync(detected_fen);
+        };
+        if placement(&position_fen(&pos)) == detected {
+            self.unmatched = 0;
+            return Some(position_fen(&pos));
+        }
+
+        // One move, or two if a frame in between was missed
+        for m in pos.legal_moves() {
+            let mut after = pos.clone();
+            let san = SanPlus::from_move_and_play_unchecked(&mut after, &m).to_string();
+            if placement(&position_fen(&after)) == detected {
+                return Some(self.advance(after, vec![san]));
+            }
+            for reply in after.legal_moves() {
+                let mut after2 = after.clone();
+                let san2 = SanPlus::from_move_and_play_unchecked(&mut after2, &reply).to_string();
+                if placement(&position_fen(&after2)) == detected {
+                    return Some(self.advance(after2, vec![san.clone(), san2]));
+                }
+            }
+        }
+
+        self.unmatched += 1;
+        if self.unmatched > LOST_GRACE {
+            println!("Game tracker lost sync, restarting from detected board");
+            return self.resync(detected_fen);
+        }
+        None
+    }
+
+    /// Plays a move typed by the user (SAN like "e5" or "Nxd4", or UCI like
+    /// "e7e5") on the tracked position.
+    pub fn play_manual(&mut self, text: &str) -> Result<String, String> {
+        let pos = self
+            .position
+            .clone()
+            .ok_or_else(|| "No position tracked yet".to_string())?;
+        let text = text.trim();
+        let m = San::from_ascii(text.as_bytes())
+            .ok()
+            .and_then(|san| san.to_move(&pos).ok())
+            .or_else(|| {
+                UciMove::from_ascii(text.as_bytes())
+                    .ok()
+                    .and_then(|uci| uci.to_move(&pos).ok())
+            })
+            .ok_or_else(|| format!("'{}' is not a legal move here", text))?;
+        let mut after = pos;
+        let san = SanPlus::from_move_and_play_unchecked(&mut after, &m).to_string();
+        Ok(self.advance(after, vec![san]))
+    }
+
+    fn advance(&mut self, pos: Chess, sans: Vec<String>) -> String {
+        // Time since the last move goes to whoever moved first
+        if let (Some(prev), Some(since)) = (&self.position, self.turn_started) {
+            let side = if prev.turn() == Color::White { 0 } else { 1 };
+            self.used[side] += since.elapsed();
+        }
+        self.turn_started = Some(Instant::now());
+        self.moves.extend(sans);
+        self.unmatched = 0;
+        let fen = position_fen(&pos);
+        self.position = Some(pos);
+        fen
+    }
+
+    fn resync(&mut self, detected_fen: &str) -> Option<String> {
+        self.moves.clear();
+        self.evals.clear();
+        self.unmatched = 0;
+        self.used = [Duration::ZERO; 2];
+        self.turn_started = Some(Instant::now());
+        self.position = position_from_fen(detected_fen);
+        self.start = self
+            .position
+            .as_ref()
+            .map(|p| (p.turn() == Color::White, p.fullmoves().get()));
+        self.start_fen = self.position.as_ref().map(position_fen);
+        self.start_fen.clone()
+    }
+}
+
+/// Infers whose turn it is by diffing consecutive detected placements: the
+/// color whose pieces arrived on new squares made the last move. Keeps the
+/// previous answer while the board is unchanged or the diff is ambiguous.
+#[derive(Default)]
+pub struct TurnDetector {
+    last: Option<[Option<char>; 64]>,
+    white_to_move: Option<bool>,
+    /// En passant target left by a double pawn push in the last diff.
+    en_passant: Option<String>,
+}
+
+impl TurnDetector {
+    /// Feeds a detected FEN; returns true when White is to move, or None
+    /// until a move has been seen.
+    pub fn observe(&mut self, fen: &str) -> Option<bool> {
+        let Some(squares) = parse_placement(placement(fen)) else {
+            return self.white_to_move;
+        };
+        if let Some(last) = self.last.filter(|last| *last != squares) {
+            if let Some(white_moved) = mover(&last, &squares) {
+                self.white_to_move = Some(!white_moved);
+            }
+            self.en_passant = double_push(&last, &squares);
+        }
+        self.last = Some(squares);
+        self.white_to_move
+    }
+
+    /// Square skipped by a double pawn push seen in the last change ("e3").
+    pub fn en_passant(&self) -> Option<&str> {
+        self.en_passant.as_deref()
+    }
+}
+
+/// En passant target if the only pawn move between two placements is a
+/// two-square advance from the starting rank.
+fn double_push(before: &[Option<char>; 64], after: &[Option<char>; 64]) -> Option<String> {
+    // (pawn, start row, skipped row, target row); row 0 is the 8th rank
+    for (pawn, start, skipped, target) in [('P', 6, 5, 4), ('p', 1, 2, 3)] {
+        for col in 0..8 {
+            let at = |row: usize| row * 8 + col;
+            if before[at(start)] == Some(pawn)
+                && after[at(start)].is_none()
+                && before[at(skipped)].is_none()
+                && after[at(skipped)].is_none()
+                && before[at(target)].is_none()
+                && after[at(target)] == Some(pawn)
+            {
+                let file = (b'a' + col as u8) as char;
+                return Some(format!("{}{}", file, 8 - skipped));
+            }
+        }
+    }
+    None
+}
+
+/// Castling rights plausible from piece placement alone: a king and rook
+/// still on their original squares. Moves that already lost the right
+/// can't be seen this way, which the game tracker corrects once it follows
+/// the game.
+fn castling_rights(squares: &[Option<char>; 64]) -> String {
+    let mut rights = String::new();
+    // (king, rook, king square, rook square, letter)
+    for (king, rook, king_at, rook_at, letter) in [
+        ('K', 'R', 60, 63, 'K'),
+        ('K', 'R', 60, 56, 'Q'),
+        ('k', 'r', 4, 7, 'k'),
+        ('k', 'r', 4, 0, 'q'),
+    ] {
+        if squares[king_at] == Some(king) && squares[rook_at] == Some(rook) {
+            rights.push(letter);
+        }
+    }
+    if rights.is_empty() {
+        rights.push('-');
+    }
+    rights
+}
+
+/// Fills the castling and en passant fields of a FEN built from vision
+/// (which leaves them empty). The en passant target is kept only if it is
+/// legal for the side to move; anything the position rejects is dropped.
+pub fn complete_fen(fen: &str, en_passant: Option<&str>) -> String {
+    let mut fields: Vec<&str> = fen.split_whitespace().collect();
+    let Some(squares) = fields.first().and_then(|p| parse_placement(p)) else {
+        return fen.to_string();
+    };
+    fields.resize(6, "-");
+    let castling = castling_rights(&squares);
+    fields[2] = &castling;
+    fields[3] = "-";
+    fields[4] = "0";
+    fields[5] = "1";
+    let without_ep = fields.join(" ");
+    if let Some(ep) = en_passant {
+        fields[3] = ep;
+        let with_ep = fields.join(" ");
+        if position_from_fen(&with_ep)
+            .is_some_and(|pos| pos.ep_square(EnPassantMode::Legal).is_some())
+        {
+            return with_ep;
+        }
+    }
+    if position_from_fen(&without_ep).is_some() {
+        without_ep
+    } else {
+        fen.to_string()
+    }
+}
+
+fn parse_placement(placement: &str) -> Option<[Option<char>; 64]> {
+    let mut squares = [None; 64];
+    let mut i = 0;
+    for c in placement.chars().filter(|c| *c != '/') {
+        match c.to_digit(10) {
+            Some(n) => i += n as usize,
+            None => {
+                *squares.get_mut(i)? = Some(c);
+                i += 1;
+            }
+        }
+    }
+    (i == 64).then_some(squares)
+}
+
+/// Color that moved between two placements (true = White): every square
+/// that gained a piece, or changed piece, must hold that color. Castling
+/// and captures fit; a flicker of both colors does not.
+fn mover(before: &[Option<char>; 64], after: &[Option<char>; 64]) -> Option<bool> {
+    let mut arrived = before.iter().zip(after).filter_map(|(b, a)| match a {
+        Some(piece) if b != a => Some(piece.is_ascii_uppercase()),
+        _ => None,
+    });
+    let first = arrived.next()?;
+    arrived.all(|white| white == first).then_some(first)
+}
+
+/// Replaces the side-to-move field of a FEN.
+pub fn with_turn(fen: &str, white_to_move: bool) -> String {
+    let turn = if white_to_move { "w" } else { "b" };
+    fen.split_whitespace()
+        .enumerate()
+        .map(|(i, field)| if i == 1 { turn } else { field })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// What kind of move a UCI move is in a given position.
+#[derive(Clone, Copy, Debug)]
+pub struct MoveFacts {
+    pub role: Role,
+    pub capture: bool,
+    pub check: bool,
+}
+
+pub fn move_facts(fen: &str, uci: &str) -> Option<MoveFacts> {
+    let mut pos = position_from_fen(fen)?;
+    let m = UciMove::from_ascii(uci.as_bytes())
+        .ok()?
+        .to_move(&pos)
+        .ok()?;
+    let role = m.role();
+    let capture = m.is_capture();
+    pos.play_unchecked(&m);
+    Some(MoveFacts {
+        role,
+        capture,
+        check: pos.is_check(),
+    })
+}
+
+/// SAN ("Nxe5+") of a UCI move in the position `fen`.
+pub fn uci_to_san(fen: &str, uci: &str) -> Option<String> {
+    let mut pos = position_from_fen(fen)?;
+    let m = UciMove::from_ascii(uci.as_bytes())
+        .ok()?
+        .to_move(&pos)
+        .ok()?;
+    Some(SanPlus::from_move_and_play_unchecked(&mut pos, &m).to_string())
+}
+
+/// SAN of a line of UCI moves played from `fen`, stopping at the first move
+/// that is not legal.
+pub fn line_to_san(fen: &str, line: &[String]) -> Vec<String> {
+    let Some(mut pos) = position_from_fen(fen) else {
+        return Vec::new();
+    };
+    let mut sans = Vec::new();
+    for uci in line {
+        let Some(m) = UciMove::from_ascii(uci.as_bytes())
+            .ok()
+            .and_then(|m| m.to_move(&pos).ok())
+        else {
+            break;
+        };
+        sans.push(SanPlus::from_move_and_play_unchecked(&mut pos, &m).to_string());
+    }
+    sans
+}
+
+/// Plays a UCI move on a FEN, returning the resulting FEN if it is legal.
+pub fn play_uci(fen: &str, uci: &str) -> Option<String> {
+    let mut pos = position_from_fen(fen)?;
+    let m = UciMove::from_ascii(uci.as_bytes())
+        .ok()?
+        .to_move(&pos)
+        .ok()?;
+    pos.play_unchecked(&m);
+    Some(position_fen(&pos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR";
+
+    #[test]
+    fn castling_rights_follow_kings_and_rooks_at_home() {
+        assert_eq!(
+            complete_fen(&format!("{} w", START), None),
+            format!("{} w KQkq - 0 1", START)
+        );
+        // White's h-rook has left h1
+        assert_eq!(
+            complete_fen("r3k2r/8/8/8/8/8/8/R3K1R1 w", None),
+            "r3k2r/8/8/8/8/8/8/R3K1R1 w Qkq - 0 1"
+        );
+        // Kings off their squares
+        assert_eq!(
+            complete_fen("r2k3r/8/8/8/8/8/8/R4K1R b", None),
+            "r2k3r/8/8/8/8/8/8/R4K1R b - - 0 1"
+        );
+    }
+
+    #[test]
+    fn double_push_sets_en_passant_target() {
+        let mut turn = TurnDetector::default();
+        turn.observe(START);
+        assert_eq!(
+            turn.observe("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR"),
+            Some(false)
+        );
+        assert_eq!(turn.en_passant(), Some("e3"));
+        assert_eq!(
+            turn.observe("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR"),
+            Some(true)
+        );
+        assert_eq!(turn.en_passant(), Some("d6"));
+        // A single step leaves no target
+        turn.observe("rnbqkbnr/ppp1pppp/8/3p4/4P3/5N2/PPPP1PPP/RNBQKB1R");
+        assert_eq!(turn.en_passant(), None);
+    }
+
+    #[test]
+    fn en_passant_kept_only_when_capturable() {
+        let placement = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR";
+        assert_eq!(
+            complete_fen(&format!("{} b", placement), Some("e3")),
+            format!("{} b KQkq - 0 1", placement)
+        );
+        let placement = "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR";
+        assert_eq!(
+            complete_fen(&format!("{} b", placement), Some("e3")),
+            format!("{} b KQkq e3 0 1", placement)
+        );
+    }
+
+    #[test]
+    fn san_disambiguates_by_file_then_rank() {
+        let rooks = "1k6/8/8/8/8/8/4K3/R6R w - - 0 1";
+        assert_eq!(uci_to_san(rooks, "a1d1").as_deref(), Some("Rad1"));
+        let knights = "k7/8/8/1N6/8/8/8/1N2K3 w - - 0 1";
+        assert_eq!(uci_to_san(knights, "b1c3").as_deref(), Some("N1c3"));
+    }
+
+    #[test]
+    fn san_marks_check_and_mate() {
+        assert_eq!(
+            uci_to_san("k7/8/8/8/8/8/8/4K2R w - - 0 1", "h1h8").as_deref(),
+            Some("Rh8+")
+        );
+        assert_eq!(
+            uci_to_san("k7/8/1K6/8/8/8/8/7R w - - 0 1", "h1h8").as_deref(),
+            Some("Rh8#")
+        );
+    }
+
+    #[test]
+    fn san_promotion_and_castling() {
+        let fen = "1r4k1/P7/8/8/8/8/8/4K2R w K - 0 1";
+        assert_eq!(uci_to_san(fen, "a7b8q").as_deref(), Some("axb8=Q+"));
+        assert_eq!(uci_to_san(fen, "a7a8n").as_deref(), Some("a8=N"));
+        assert_eq!(uci_to_san(fen, "e1g1").as_deref(), Some("O-O"));
+        assert_eq!(uci_to_san(fen, "e1e3"), None);
+    }
+
+    #[test]
+    fn line_to_san_stops_at_illegal_move() {
+        let line = ["e2e4", "e7e5", "e4e5", "g1f3"].map(String::from);
+        assert_eq!(
+            line_to_san(&format!("{} w KQkq - 0 1", START), &line),
+            ["e4", "e5"]
+        );
+    }
+}
@@ -1,8 +1,26 @@
+use crate::assets::pieces::PieceTheme;
+use crate::automove::AutoMove;
+use crate::capture::source::CaptureSourceKind;
+use crate::config_migrate::{self, Upgrade, CONFIG_VERSION};
+use crate::config_schema::{self, ConfigIssue};
+use crate::engine::options::EngineOptions;
+use crate::engine::process::EngineProcessSettings;
+use crate::engine::stockfish::{SearchLimit, SearchMode};
+use crate::hotkeys::{HotkeyAction, Hotkeys};
+use crate::notation::Notation;
+use crate::overlay::arrow::{ArrowStyle, MoveHighlight};
+use crate::overlay::arrow_filter::ArrowFilter;
+use crate::overlay::hot_zone::HotZone;
+use crate::overlay::theme::OverlayTheme;
+use crate::overlay::window::DesktopMode;
+use crate::vision::inference::{InferenceBackend, DEFAULT_IOU_THRESHOLD};
+use crate::vision::site::Site;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct BoardRegion {
     pub x: u32,
     pub y: u32,
@@ -10,56 +28,938 @@ pub struct BoardRegion {
     pub height: u32,
 }
 
+impl BoardRegion {
+    /// Splits the region into per-board sub-regions. With `split` enabled, a
+    /// region clearly wider (or taller) than square is cut into two halves
+    /// along its long side, one per board; otherwise the region is returned
+    /// unchanged.
+    pub fn sub_regions(&self, split: bool) -> Vec<BoardRegion> {
+        if split && self.width * 2 > self.height * 3 {
+            let half = self.width / 2;
+            vec![
+                BoardRegion {
+                    width: half,
+                    ..self.clone()
+                },
+                BoardRegion {
+                    x: self.x + half,
+                    width: self.width - half,
+                    ..self.clone()
+                },
+            ]
+        } else if split && self.height * 2 > self.width * 3 {
+            let half = self.height / 2;
+            vec![
+                BoardRegion {
+                    height: half,
+                    ..self.clone()
+                },
+                BoardRegion {
+                    y: self.y + half,
+                    height: self.height - half,
+                    ..self.clone()
+                },
+            ]
+        } else {
+            vec![self.clone()]
+        }
+    }
+
+    /// The region scaled by `factor` about its center and kept inside a
+    /// `max_w`×`max_h` screen.
+    pub fn scaled_about_center(&self, factor: f32, max_w: u32, max_h: u32) -> BoardRegion {
+        let width = ((self.width as f32 * factor).round() as u32).clamp(1, max_w.max(1));
+        let height = ((self.height as f32 * factor).round() as u32).clamp(1, max_h.max(1));
+        let cx = self.x as f32 + self.width as f32 / 2.0;
+        let cy = self.y as f32 + self.height as f32 / 2.0;
+        let x = (cx - width as f32 / 2.0).round().max(0.0) as u32;
+        let y = (cy - height as f32 / 2.0).round().max(0.0) as u32;
+        BoardRegion {
+            x: x.min(max_w - width),
+            y: y.min(max_h - height),
+            width,
+            height,
+        }
+    }
+}
+
+/// The display the board is on. Regions are relative to its top-left corner.
+/// The label is kept so a disconnected display can still be named.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MonitorChoice {
+    pub id: u32,
+    pub label: String,
+}
+
+/// The side the player has on one board, for boards that aren't played
+/// from the same side as the main one.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BoardSide {
+    /// Follow `show_white_moves`.
+    #[default]
+    Main,
+    White,
+    Black,
+}
+
+impl BoardSide {
+    pub const ALL: [BoardSide; 3] = [BoardSide::Main, BoardSide::White, BoardSide::Black];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BoardSide::Main => "Same as main",
+            BoardSide::White => "White",
+            BoardSide::Black => "Black",
+        }
+    }
+
+    /// Whether the player is White on this board, given `show_white_moves`.
+    pub fn shows_white(&self, main: bool) -> bool {
+        match self {
+            BoardSide::Main => main,
+            BoardSide::White => true,
+            BoardSide::Black => false,
+        }
+    }
+}
+
+/// Which overlay visuals are drawn, so a config can switch the whole layout
+/// from minimal to full at once.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct OverlayElements {
+    pub arrows: bool,
+    pub premoves: bool,
+    /// Trust dots on suggestion arrows (depth, stability, agreement).
+    pub quality_badges: bool,
+    /// Color arrows best/good/inaccuracy/mistake instead of plain green.
+    pub move_colors: bool,
+    pub legend: bool,
+    pub eval_bar: bool,
+    pub hud: bool,
+    /// Movable panel with eval, depth, engine speed and detection confidence.
+    pub info_panel: bool,
+    /// Recent errors as toasts in the overlay's corner.
+    pub error_toasts: bool,
+    /// Follow-up moves of the best line as numbered arrows.
+    pub variation: bool,
+    /// Text next to arrows: premove replies, threat and human-move tags,
+    /// variation numbers and the book list.
+    pub labels: bool,
+    /// The opponent's threat arrow.
+    pub threats: bool,
+    pub human_move: bool,
+    pub book: bool,
+    /// Red border and refutation arrow after a blunder.
+    pub blunder: bool,
+    /// Suggested moves' target squares shaded by rank.
+    pub heatmap: bool,
+    /// Board picture in the presentation window; the lines stay listed.
+    pub mini_board: bool,
+}
+
+impl Default for OverlayElements {
+    fn default() -> Self {
+        Self {
+            arrows: true,
+            premoves: true,
+            quality_badges: true,
+            move_colors: true,
+            legend: false,
+            eval_bar: true,
+            hud: true,
+            info_panel: true,
+            error_toasts: true,
+            variation: false,
+            labels: true,
+            threats: true,
+            human_move: true,
+            book: true,
+            blunder: true,
+            heatmap: false,
+            mini_board: true,
+        }
+    }
+}
+
+impl OverlayElements {
+    /// Whether the renderer registered under `name` should draw; `None`
+    /// for a name with no toggle here.
+    pub fn enabled(&self, name: &str) -> Option<bool> {
+        Some(match name {
+            "arrows" => self.arrows,
+            "premoves" => self.premoves,
+            "eval_bar" => self.eval_bar,
+            "quality_badges" => self.quality_badges,
+            "legend" => self.legend,
+            "status" => self.hud,
+            "variation" => self.variation,
+            "threats" => self.threats,
+            "human_move" => self.human_move,
+            "book" => self.book,
+            "blunder" => self.blunder,
+            "heatmap" => self.heatmap,
+            // Switched by their own settings: the theme's dimming, the
+            // detection view, F7 and the empty-board calibration
+            "board_dim" | "detections" | "explanation" | "empty_grid" => true,
+            _ => return None,
+        })
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
 pub struct AppConfig {
+    /// Layout version of the file, for `config_migrate`.
+    pub version: u32,
     pub board_region: Option<BoardRegion>,
+    /// `None` uses the primary display.
+    pub monitor: Option<MonitorChoice>,
+    /// Treat the region as two side-by-side boards (bughouse, broadcasts).
+    pub split_region: bool,
+    /// Further boards analyzed alongside the one in `board_region`, such as
+    /// a second game or the other boards of a broadcast.
+    pub extra_regions: Vec<BoardRegion>,
+    /// Player's side per board, indexed like `board_regions`; boards past
+    /// the end follow `show_white_moves`.
+    pub board_sides: Vec<BoardSide>,
+    /// The player's own clock on screen, read to fit searches to the time
+    /// left when `clock_time_management` is on.
+    pub clock_region: Option<BoardRegion>,
+    /// Shorten searches in a time scramble and deepen them when there is
+    /// plenty of time, from the clock read in `clock_region`.
+    pub clock_time_management: bool,
+    /// Which limit ends each search: depth, move time or node count.
+    pub search_mode: SearchMode,
     pub stockfish_depth: u32,
     pub stockfish_lines: u32,
     pub stockfish_time_ms: u32,
+    pub stockfish_nodes: u64,
+    /// Vary Skill Level and pick among near-equal lines for practice games.
+    pub sparring: bool,
+    /// On the opponent's turn, pre-compute answers to their likely replies.
+    pub premove_planning: bool,
+    /// Show opening book moves instead of searching positions in book.
+    pub use_opening_book: bool,
+    /// Polyglot `.bin` file; empty uses `book.bin` in the app folder.
+    pub opening_book_path: String,
+    /// Share of the search limit given to the side not to move: arrows for
+    /// a board analyzed on the opponent's turn, and premove planning.
+    pub off_turn_depth_ratio: f32,
+    /// Also ask the engine what a player of `human_elo` would play, and
+    /// mark that move next to the full-strength suggestion.
+    pub human_mode: bool,
+    pub human_elo: u32,
+    /// Search what the opponent would play if the player passed and draw
+    /// it as a threat arrow when it would cost them.
+    pub threat_arrows: bool,
+    /// Flash the board and show the refutation when a move loses more
+    /// than `blunder_threshold_cp`.
+    pub blunder_alert: bool,
+    pub blunder_threshold_cp: u32,
+    pub engine_process: EngineProcessSettings,
+    pub engine_options: EngineOptions,
+    /// ONNX piece model; empty uses `best.onnx` in the app folder.
+    pub model_path: String,
+    /// Execution provider for the piece model; falls back to the CPU.
+    pub inference_backend: InferenceBackend,
+    /// Site whose detection profile was last applied.
+    pub site: Site,
     pub confidence_threshold: f32,
+    /// Overlap (intersection over union) above which the weaker of two
+    /// detections is dropped as a duplicate.
+    pub iou_threshold: f32,
+    /// When two pieces are detected on one square, move the weaker one to
+    /// the neighboring empty square it leans toward instead of dropping it.
+    pub reassign_cell_conflicts: bool,
+    /// Locate the squares' grid inside the detected board from its edges,
+    /// and read the position and draw arrows on that grid.
+    pub refine_board_grid: bool,
+    /// Share of the detected board box taken by coordinates drawn outside
+    /// the squares, cut from its left and bottom edges.
+    pub coordinate_strip: f32,
+    /// Re-check the color of pieces detected with less confidence than
+    /// `color_check_below` from their brightness, for unusual themes.
+    pub color_check: bool,
+    pub color_check_below: f32,
+    /// Save the crop, detections and attempted FEN into `debug_dumps/`
+    /// whenever a detected position is rejected.
+    pub dump_failed_recognitions: bool,
+    /// Smallest board side, in screen pixels, accepted as a board detection.
+    pub min_board_px: u32,
+    /// Margin added around an auto-detected board, as a share of its side.
+    pub auto_region_padding: f32,
+    /// Re-run board auto-detection when the board drifts inside the region.
+    pub auto_track_board: bool,
+    /// Rescale a selected region when the board inside it grows or shrinks.
+    pub auto_rescale_region: bool,
+    /// Board side as a share of the region, measured after the region was
+    /// set; what `auto_rescale_region` compares against.
+    pub region_board_fraction: Option<f32>,
     pub show_white_moves: bool,
+    /// Infer the side to move from the last move seen; when off, the side
+    /// from `show_white_moves` is always to move.
+    pub auto_side_to_move: bool,
+    /// Stop capturing while the window under the region is minimized or on
+    /// another virtual desktop.
+    pub auto_pause_hidden: bool,
+    /// Also stop while that window isn't the focused one.
+    pub pause_when_unfocused: bool,
+    /// Where board images come from: the screen, or a folder of images.
+    pub capture_source: CaptureSourceKind,
+    /// Folder read by the image folder source.
+    pub image_sequence_dir: String,
+    /// Maximum full analyses (inference + engine) per second.
     pub fps: u32,
-    #[serde(default)]
+    /// Captures per second used to detect position changes.
+    pub capture_fps: u32,
+    /// Show the measured capture rate and dropped frames on the overlay.
+    pub show_capture_stats: bool,
+    /// Wait this long after a board change is first seen before trusting
+    /// the frame, so move animations are not analyzed half-way.
+    pub post_move_cooldown_ms: u32,
+    /// Seconds without a board change before capture slows down to
+    /// `idle_interval_ms`; 0 never throttles.
+    pub idle_after_secs: u32,
+    /// Time between captures while idle.
+    pub idle_interval_ms: u32,
     pub running: bool,
+    /// Which virtual desktops the overlay is shown on.
+    pub overlay_desktop: DesktopMode,
+    /// Mirror the overlay in a regular window (second monitor, streaming).
+    pub show_presentation_window: bool,
+    /// Side panel in the presentation window with the tracked move list.
+    pub show_move_list: bool,
+    /// Also show each side's thinking time in the move list panel.
+    pub show_move_clocks: bool,
+    pub overlay_elements: OverlayElements,
+    pub arrow_style: ArrowStyle,
+    /// Colors, opacities and sizes of the overlay's drawings.
+    pub overlay_theme: OverlayTheme,
+    /// Arrows, highlighted from/to squares, or both.
+    pub move_highlight: MoveHighlight,
+    /// Piece set colors for the mini-boards and exported diagrams.
+    pub piece_theme: PieceTheme,
+    /// Plies of the best line shown when the variation is on, counting the
+    /// suggested move itself.
+    pub pv_length: u32,
+    /// Which suggestions become arrows (captures only, one piece type, ...).
+    pub arrow_filter: ArrowFilter,
+    /// Most suggestion arrows drawn over all boards together.
+    pub max_arrows: u32,
+    /// Append analyzed positions to `history/` for later replay.
+    pub record_history: bool,
+    pub hotkeys: Hotkeys,
+    /// Piece letters used wherever SAN is shown.
+    pub notation: Notation,
+    pub hot_zone: HotZone,
+    /// Click-to-play: the best move is clicked on the board on a hotkey.
+    pub auto_move: AutoMove,
+    /// Memory growth since startup, in MB, that counts as a likely leak.
+    pub memory_warn_mb: u32,
+    /// Folder holding the shareable `config.json` (a synced drive, say);
+    /// empty keeps it next to `config.local.json` in the app folder.
+    pub shared_config_dir: String,
+    /// Rebuild the model session and restart the engine this often, in
+    /// hours; 0 turns scheduled reclaiming off.
+    pub reclaim_interval_hours: u32,
+    /// Reclaim as soon as memory growth passes `memory_warn_mb`.
+    pub reclaim_on_warning: bool,
+    /// Serve the latest analysis on 127.0.0.1:`api_port` (`/position` and
+    /// a `/ws` WebSocket) for streaming overlays and bots.
+    pub api_server: bool,
+    pub api_port: u16,
+    /// Web origins (like `https://example.com`) whose pages may read the
+    /// API. Requests from any other web page are refused; local tools and
+    /// files send no web origin and are always served.
+    pub api_allowed_origins: Vec<String>,
+    /// Keep the first board's evaluation, best line and FEN in text files
+    /// (and an HTML page with `stream_output_html`) for streaming software.
+    pub stream_output: bool,
+    /// Folder for the stream files; empty uses `stream/` in the app folder.
+    pub stream_output_dir: String,
+    pub stream_output_html: bool,
     #[serde(skip)]
     pub request_selection: bool,
+    #[serde(skip)]
+    pub request_extra_selection: bool,
+    /// Address served on, or why the port couldn't be opened.
+    #[serde(skip)]
+    pub api_status: Option<String>,
+    #[serde(skip)]
+    pub request_clock_selection: bool,
+    #[serde(skip)]
+    pub request_analysis_board: bool,
+    #[serde(skip)]
+    pub request_replay: bool,
+    /// The current region came from auto-detection, so drift tracking applies.
+    #[serde(skip)]
+    pub region_auto_detected: bool,
+    /// Which ranked board candidate to analyze; advanced by the F3 hotkey.
+    #[serde(skip)]
+    pub board_cycle: usize,
+    /// Text of the manual move box in the settings window.
+    #[serde(skip)]
+    pub manual_move_input: String,
+    /// Play the first board's best move with simulated clicks; handled by
+    /// the UI, which knows where the board is on the desktop.
+    #[serde(skip)]
+    pub request_auto_move: bool,
+    /// What the last click-to-play request did.
+    #[serde(skip)]
+    pub auto_move_status: Option<String>,
+    /// Line hovered in the presentation window: board index and entry of
+    /// that board's moves.
+    #[serde(skip)]
+    pub hovered_line: Option<(usize, usize)>,
+    /// Board whose game the move list shows.
+    #[serde(skip)]
+    pub move_list_board: usize,
+    /// Paused by hotkey or button: no capturing or searching, but the
+    /// session stays started and the last arrows stay up.
+    #[serde(skip)]
+    pub analysis_paused: bool,
+    /// Save board crops and labels into `samples/` for retraining.
+    #[serde(skip)]
+    pub collect_samples: bool,
+    /// Save every analyzed crop into `recordings/` for offline replay.
+    #[serde(skip)]
+    pub record_frames: bool,
+    /// Write each analyzed frame with its grid and detections into `debug/`.
+    #[serde(skip)]
+    pub debug_capture: bool,
+    /// Draw every detection's box, class and confidence on the overlay.
+    #[serde(skip)]
+    pub show_detections: bool,
+    /// Problems found in `config.json` at startup, already worked around.
+    #[serde(skip)]
+    pub config_issues: Vec<ConfigIssue>,
+    /// Where the shared file is copied before the next save overwrites it,
+    /// when it was upgraded, came from a newer version or didn't parse.
+    #[serde(skip)]
+    pub config_backup: Option<PathBuf>,
+    /// Where `config.local.json` is copied before the next save, when it
+    /// didn't parse. While set, saving leaves the shared file alone: the
+    /// local file named its folder, so it may not be the one that was read.
+    #[serde(skip)]
+    pub local_backup: Option<PathBuf>,
+    /// Named profile the settings came from and are saved to; `None` is
+    /// the default profile.
+    #[serde(skip)]
+    pub profile: Option<String>,
+    /// Overlay drawing hidden by hotkey; analysis keeps running.
+    #[serde(skip)]
+    pub overlay_hidden: bool,
+    /// Action waiting for its new key in the settings window.
+    #[serde(skip)]
+    pub capturing_hotkey: Option<HotkeyAction>,
+    /// The OS refused the global key hook; hotkeys only work while one of
+    /// our windows has focus.
+    #[serde(skip)]
+    pub global_hotkeys_failed: bool,
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
+            version: CONFIG_VERSION,
             board_region: None,
+            monitor: None,
+            split_region: false,
+            extra_regions: Vec::new(),
+            board_sides: Vec::new(),
+            clock_region: None,
+            clock_time_management: false,
+            search_mode: SearchMode::default(),
             stockfish_depth: 15,
             stockfish_lines: 3,
             stockfish_time_ms: 500,
+            stockfish_nodes: 1_000_000,
+            sparring: false,
+            premove_planning: false,
+            off_turn_depth_ratio: 0.6,
+            human_mode: false,
+            human_elo: 1500,
+            threat_arrows: false,
+            blunder_alert: true,
+            blunder_threshold_cp: 200,
+            use_opening_book: false,
+            opening_book_path: String::new(),
+            engine_process: EngineProcessSettings::default(),
+            engine_options: EngineOptions::default(),
+            model_path: String::new(),
+            inference_backend: InferenceBackend::default(),
+            site: Site::default(),
             confidence_threshold: 0.5,
+            iou_threshold: DEFAULT_IOU_THRESHOLD,
+            reassign_cell_conflicts: false,
+            refine_board_grid: false,
+            coordinate_strip: 0.0,
+            color_check: false,
+            color_check_below: 0.6,
+            dump_failed_recognitions: true,
+            min_board_px: 120,
+            auto_region_padding: 0.03,
+            auto_track_board: true,
+            auto_rescale_region: true,
+            region_board_fraction: None,
             show_white_moves: true,
+            auto_side_to_move: true,
+            auto_pause_hidden: true,
+            pause_when_unfocused: false,
+            capture_source: CaptureSourceKind::default(),
+            image_sequence_dir: String::new(),
             fps: 3,
+            capture_fps: 10,
+            show_capture_stats: false,
+            post_move_cooldown_ms: 200,
+            idle_after_secs: 20,
+            idle_interval_ms: 2000,
             running: false,
+            overlay_desktop: DesktopMode::default(),
+            show_presentation_window: false,
+            show_move_list: true,
+            show_move_clocks: true,
+            overlay_elements: OverlayElements::default(),
+            arrow_style: ArrowStyle::default(),
+            overlay_theme: OverlayTheme::default(),
+            move_highlight: MoveHighlight::default(),
+            piece_theme: PieceTheme::default(),
+            pv_length: 4,
+            arrow_filter: ArrowFilter::default(),
+            max_arrows: 6,
+            record_history: false,
+            hotkeys: Hotkeys::default(),
+            notation: Notation::default(),
+            hot_zone: HotZone::default(),
+            auto_move: AutoMove::default(),
+            memory_warn_mb: 500,
+            shared_config_dir: String::new(),
+            reclaim_interval_hours: 0,
+            reclaim_on_warning: false,
+            api_server: false,
+            api_port: 7878,
+            api_allowed_origins: Vec::new(),
+            stream_output: false,
+            stream_output_dir: String::new(),
+            stream_output_html: false,
             request_selection: false,
+            request_extra_selection: false,
+            api_status: None,
+            request_clock_selection: false,
+            request_analysis_board: false,
+            request_replay: false,
+            region_auto_detected: false,
+            board_cycle: 0,
+            manual_move_input: String::new(),
+            request_auto_move: false,
+            auto_move_status: None,
+            hovered_line: None,
+            move_list_board: 0,
+            analysis_paused: false,
+            collect_samples: false,
+            record_frames: false,
+            debug_capture: false,
+            show_detections: false,
+            config_issues: Vec::new(),
+            config_backup: None,
+            local_backup: None,
+            profile: None,
+            overlay_hidden: false,
+            capturing_hotkey: None,
+            global_hotkeys_failed: false,
         }
     }
 }
 
+/// Boards of a main region and its extra regions, in analysis order; see
+/// `AppConfig::board_regions`.
+pub fn board_regions(
+    region: Option<&BoardRegion>,
+    split: bool,
+    extra: &[BoardRegion],
+) -> Vec<BoardRegion> {
+    match region {
+        Some(r) => r
+            .sub_regions(split)
+            .into_iter()
+            .chain(extra.iter().cloned())
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
 impl AppConfig {
+    pub fn search_limit(&self) -> SearchLimit {
+        SearchLimit::new(
+            self.search_mode,
+            self.stockfish_depth,
+            self.stockfish_time_ms,
+            self.stockfish_nodes,
+        )
+    }
+
+    /// One region per analyzed board: the selected region, split in two
+    /// with `split_region`, then the extra regions. Nothing is analyzed
+    /// until a main region is selected.
+    pub fn board_regions(&self) -> Vec<BoardRegion> {
+        board_regions(
+            self.board_region.as_ref(),
+            self.split_region,
+            &self.extra_regions,
+        )
+    }
+
+    /// Whether the player is White on board `board` of `board_regions`.
+    pub fn shows_white(&self, board: usize) -> bool {
+        let side = self.board_sides.get(board).copied().unwrap_or_default();
+        side.shows_white(self.show_white_moves)
+    }
+
+    /// Loads the active profile.
     pub fn load() -> Self {
-        let path = Self::config_path();
-        if let Ok(content) = fs::read_to_string(path) {
-            if let Ok(config) = serde_json::from_str(&content) {
-                return config;
+        let profile = active_profile();
+        if profile.is_none() {
+            adopt_legacy_files();
+        }
+        Self::load_profile(profile)
+    }
+
+    /// Loads `profile` (the default one for `None`) and remembers it, so
+    /// saving writes back to it.
+    pub fn load_profile(profile: Option<String>) -> Self {
+        let mut config = Self::read(profile.as_deref());
+        config.profile = profile;
+        config
+    }
+
+    /// Reads `config.local.json` and the shared `config.json` it points to,
+    /// keeping every valid setting. Files from older versions are upgraded
+    /// first; unknown keys, bad types and out-of-range values are repaired
+    /// and listed in `config_issues` rather than discarding the whole file.
+    fn read(profile: Option<&str>) -> Self {
+        let config = Self::read_files(profile);
+        for issue in &config.config_issues {
+            println!(
+                "Config: {}: {} ({})",
+                issue.key, issue.problem, issue.suggestion
+            );
+        }
+        config
+    }
+
+    fn read_files(profile: Option<&str>) -> Self {
+        let mut issues = Vec::new();
+        // A broken local file only costs the machine's settings; the shared
+        // ones are still read from the profile folder
+        let local_path = Self::local_path(profile);
+        let (local, local_backup) = match read_json(&local_path) {
+            Ok(v) => (v.unwrap_or_else(|| json!({})), None),
+            Err(mut issue) => {
+                issue.suggestion = "using defaults for this machine's settings; \
+                                    the file is backed up before Save Settings rewrites it"
+                    .to_string();
+                issues.push(issue);
+                let backup = local_path.with_extension("invalid.json.bak");
+                (json!({}), Some(backup))
+            }
+        };
+        let shared_dir = local["shared_config_dir"].as_str().unwrap_or_default();
+        let shared_path = Self::shared_path(profile, shared_dir);
+        let (mut value, existed) = match read_json(&shared_path) {
+            Ok(Some(v)) => (v, true),
+            Ok(None) => (json!({}), false),
+            Err(issue) => {
+                issues.push(issue);
+                let backup = shared_path.with_extension("invalid.json.bak");
+                return Self::with_issues(issues, Some(backup), local_backup);
+            }
+        };
+        // Older installs kept everything in `config.json`; local values win
+        for key in LOCAL_KEYS {
+            if let Some(v) = lookup(&local, key) {
+                insert_path(&mut value, key, v.clone());
+            }
+        }
+        let mut backup = None;
+        match existed.then(|| config_migrate::migrate(&mut value)) {
+            Some(Upgrade::From(from)) => {
+                backup = Some(shared_path.with_extension(format!("v{}.json.bak", from)));
+            }
+            Some(Upgrade::Newer(version)) => {
+                backup = Some(shared_path.with_extension(format!("v{}.json.bak", version)));
+                issues.push(ConfigIssue {
+                    key: "version".to_string(),
+                    problem: format!(
+                        "written by a newer version ({}, this one reads {})",
+                        version, CONFIG_VERSION
+                    ),
+                    suggestion: "settings it doesn't know are dropped on save; \
+                                 the file is backed up first"
+                        .to_string(),
+                });
+                value["version"] = json!(CONFIG_VERSION);
+            }
+            Some(Upgrade::Current) | None => {}
+        }
+        issues.extend(config_schema::validate(&mut value));
+        let mut config: Self = match serde_json::from_value(value) {
+            Ok(c) => c,
+            Err(e) => {
+                let backup = shared_path.with_extension("invalid.json.bak");
+                issues.push(ConfigIssue {
+                    key: "config.json".to_string(),
+                    problem: e.to_string(),
+                    suggestion: "using defaults; Save Settings overwrites the file".to_string(),
+                });
+                return Self::with_issues(issues, Some(backup), local_backup);
             }
+        };
+        config.config_issues = issues;
+        config.config_backup = backup;
+        config.local_backup = local_backup;
+        config
+    }
+
+    fn with_issues(
+        issues: Vec<ConfigIssue>,
+        backup: Option<PathBuf>,
+        local_backup: Option<PathBuf>,
+    ) -> Self {
+        Self {
+            config_issues: issues,
+            config_backup: backup,
+            local_backup,
+            ..Self::default()
         }
-        Self::default()
     }
 
+    /// Writes machine-specific settings to `config.local.json` and the rest
+    /// to the shared `config.json`.
     pub fn save(&self) -> anyhow::Result<()> {
-        let path = Self::config_path();
-        let content = serde_json::to_string_pretty(self)?;
-        fs::write(path, content)?;
+        let mut shared = serde_json::to_value(self)?;
+        let mut local = json!({});
+        for key in LOCAL_KEYS {
+            if let Some(v) = take_path(&mut shared, key) {
+                insert_path(&mut local, key, v);
+            }
+        }
+        let profile = self.profile.as_deref();
+        fs::create_dir_all(crate::paths::profile_dir(profile))?;
+        let shared_path = Self::shared_path(profile, &self.shared_config_dir);
+        let local_path = Self::local_path(profile);
+        // The first backup of each kind is kept; later saves only rewrite
+        for (path, backup) in [
+            (&shared_path, &self.config_backup),
+            (&local_path, &self.local_backup),
+        ] {
+            if let Some(backup) = backup {
+                if path.exists() && !backup.exists() {
+                    fs::copy(path, backup)?;
+                    println!("Config: previous file backed up to {}", backup.display());
+                }
+            }
+        }
+        if self.local_backup.is_none() {
+            if let Some(dir) = shared_path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&shared_path, serde_json::to_string_pretty(&shared)?)?;
+        }
+        fs::write(local_path, serde_json::to_string_pretty(&local)?)?;
         Ok(())
     }
 
-    fn config_path() -> PathBuf {
-        PathBuf::from("config.json")
+    /// Saves the current settings, then loads `profile` in their place and
+    /// makes it the one used at startup. Session state (API status, hotkey
+    /// fallback) carries over.
+    pub fn switch_profile(&mut self, profile: Option<String>) -> anyhow::Result<()> {
+        self.save()?;
+        let mut loaded = Self::load_profile(profile);
+        loaded.api_status = self.api_status.take();
+        loaded.global_hotkeys_failed = self.global_hotkeys_failed;
+        *self = loaded;
+        set_active_profile(self.profile.as_deref())
+    }
+
+    /// Saves the current settings as a new profile named `name` and
+    /// switches to it.
+    pub fn save_as_profile(&mut self, name: &str) -> anyhow::Result<()> {
+        let name = profile_name(name).map_err(anyhow::Error::msg)?;
+        if profile_names().contains(&name) {
+            anyhow::bail!("a profile named \"{}\" already exists", name);
+        }
+        self.profile = Some(name);
+        self.save()?;
+        set_active_profile(self.profile.as_deref())
+    }
+
+    fn local_path(profile: Option<&str>) -> PathBuf {
+        crate::paths::profile_dir(profile).join("config.local.json")
+    }
+
+    /// `config.json` of `profile`: in its profile folder, or with a shared
+    /// settings folder in that folder, with named profiles under
+    /// `profiles/<name>/` as in the config folder so they don't overwrite
+    /// each other.
+    fn shared_path(profile: Option<&str>, dir: &str) -> PathBuf {
+        let dir = dir.trim();
+        if dir.is_empty() {
+            return crate::paths::profile_dir(profile).join("config.json");
+        }
+        let dir = PathBuf::from(dir);
+        match profile {
+            Some(name) => dir.join("profiles").join(name).join("config.json"),
+            None => dir.join("config.json"),
+        }
+    }
+}
+
+/// File in the config folder naming the profile loaded at startup;
+/// missing or empty for the default profile.
+const ACTIVE_PROFILE_FILE: &str = "profile.txt";
+
+/// Profile loaded at startup: `--profile <name>` when given, otherwise the
+/// one last switched to. A profile whose folder is gone falls back to the
+/// default one.
+pub fn active_profile() -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    let from_args = args
+        .iter()
+        .position(|a| a == "--profile")
+        .and_then(|i| args.get(i + 1).cloned());
+    let name = from_args.or_else(|| {
+        fs::read_to_string(crate::paths::config_dir().join(ACTIVE_PROFILE_FILE)).ok()
+    })?;
+    let name = profile_name(&name).ok()?;
+    if crate::paths::profile_dir(Some(&name)).is_dir() {
+        Some(name)
+    } else {
+        println!("Config: profile \"{}\" not found, using the default", name);
+        None
+    }
+}
+
+fn set_active_profile(profile: Option<&str>) -> anyhow::Result<()> {
+    let dir = crate::paths::config_dir();
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join(ACTIVE_PROFILE_FILE), profile.unwrap_or_default())?;
+    Ok(())
+}
+
+/// Named profiles, sorted; the default profile is not listed.
+pub fn profile_names() -> Vec<String> {
+    let Ok(entries) = fs::read_dir(crate::paths::config_dir().join("profiles")) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| e.file_name().into_string().ok())
+        .collect();
+    names.sort();
+    names
+}
+
+/// `name` trimmed, when it can be used as a profile folder's name.
+pub fn profile_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("a profile needs a name".to_string());
+    }
+    let forbidden = ['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+    if name.contains(forbidden) || name.starts_with('.') {
+        return Err(format!("\"{}\" can't be used as a folder name", name));
+    }
+    Ok(name.to_string())
+}
+
+/// Copies config files left in the app folder by versions that kept them
+/// there into the config folder, the first time it is used. The old files
+/// stay in place.
+fn adopt_legacy_files() {
+    let (old, new) = (crate::paths::app_dir(), crate::paths::config_dir());
+    let names = ["config.json", "config.local.json"];
+    if old == new || names.iter().any(|n| new.join(n).exists()) {
+        return;
+    }
+    for name in names.iter().filter(|n| old.join(n).exists()) {
+        let copied =
+            fs::create_dir_all(&new).and_then(|_| fs::copy(old.join(name), new.join(name)));
+        match copied {
+            Ok(_) => println!("Config: copied {} to {}", name, new.display()),
+            Err(e) => println!("Config: couldn't copy {} to {}: {}", name, new.display(), e),
+        }
+    }
+}
+
+/// Settings tied to this machine (screen layout, hardware, file paths),
+/// kept in `config.local.json` so the shared `config.json` can be synced
+/// between machines. Dotted paths reach into sections.
+const LOCAL_KEYS: &[&str] = &[
+    "board_region",
+    "monitor",
+    "split_region",
+    "extra_regions",
+    "board_sides",
+    "clock_region",
+    "region_board_fraction",
+    "engine_process",
+    "inference_backend",
+    "model_path",
+    "engine_options.threads",
+    "engine_options.hash_mb",
+    "engine_options.syzygy_path",
+    "opening_book_path",
+    "capture_source",
+    "image_sequence_dir",
+    "shared_config_dir",
+    "api_port",
+    "stream_output_dir",
+    "running",
+];
+
+/// Parses a config file; `Ok(None)` when it doesn't exist yet.
+fn read_json(path: &Path) -> Result<Option<Value>, ConfigIssue> {
+    let Ok(content) = fs::read_to_string(path) else {
+        return Ok(None);
+    };
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| ConfigIssue {
+            key: path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            problem: format!("not valid JSON: {}", e),
+            suggestion: "using defaults; Save Settings overwrites the file".to_string(),
+        })
+}
+
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |v, key| v.get(key))
+}
+
+fn take_path(value: &mut Value, path: &str) -> Option<Value> {
+    let (parent, key) = match path.rsplit_once('.') {
+        Some((parent, key)) => (parent.split('.').try_fold(value, |v, k| v.get_mut(k))?, key),
+        None => (value, path),
+    };
+    parent.as_object_mut()?.remove(key)
+}
+
+fn insert_path(value: &mut Value, path: &str, new: Value) {
+    let mut target = value;
+    let mut keys = path.split('.').peekable();
+    while let Some(key) = keys.next() {
+        let Some(fields) = target.as_object_mut() else {
+            return;
+        };
+        if keys.peek().is_none() {
+            fields.insert(key.to_string(), new);
+            return;
+        }
+        target = fields.entry(key).or_insert_with(|| json!({}));
     }
 }
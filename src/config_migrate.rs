@@ -0,0 +1,133 @@
+//! Upgrades of `config.json` files written by older versions, applied
+//! before validation so settings that were renamed or changed meaning
+//! keep their values instead of falling back to defaults.
+
+use serde_json::{json, Value};
+
+/// Version written to `config.json`; bump it with each new migration.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// One upgrade step, from version `to - 1` to `to`.
+struct Migration {
+    to: u32,
+    description: &'static str,
+    apply: fn(&mut Value),
+}
+
+const MIGRATIONS: &[Migration] = &[Migration {
+    to: 1,
+    description: "capture rate taken from the analysis rate",
+    apply: split_capture_rate,
+}];
+
+/// How a loaded file's version compared to this build.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Upgrade {
+    Current,
+    /// Migrated from the given older version.
+    From(u32),
+    /// Written by a newer version; loaded as is, so its newer settings
+    /// are dropped by validation.
+    Newer(u32),
+}
+
+/// Version a config object was written with; files from before
+/// versioning have none and count as 0.
+pub fn version_of(value: &Value) -> u32 {
+    value
+        .get("version")
+        .and_then(Value::as_u64)
+        .map_or(0, |v| v.min(u32::MAX as u64) as u32)
+}
+
+/// Applies every migration newer than the file's version, in order, and
+/// stamps it with `CONFIG_VERSION`.
+pub fn migrate(value: &mut Value) -> Upgrade {
+    let from = version_of(value);
+    if from > CONFIG_VERSION {
+        return Upgrade::Newer(from);
+    }
+    if !value.is_object() {
+        return Upgrade::Current;
+    }
+    for migration in MIGRATIONS.iter().filter(|m| m.to > from) {
+        (migration.apply)(value);
+        println!(
+            "Config: upgraded to version {}: {}",
+            migration.to, migration.description
+        );
+    }
+    value["version"] = json!(CONFIG_VERSION);
+    if from == CONFIG_VERSION {
+        Upgrade::Current
+    } else {
+        Upgrade::From(from)
+    }
+}
+
+/// Before `capture_fps` existed the screen was captured at `fps`; keep
+/// that rate rather than the faster default.
+fn split_capture_rate(value: &mut Value) {
+    let Some(fields) = value.as_object_mut() else {
+        return;
+    };
+    if fields.contains_key("capture_fps") {
+        return;
+    }
+    if let Some(fps) = fields.get("fps").cloned() {
+        fields.insert("capture_fps".to_string(), fps);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unversioned_file_is_upgraded() {
+        let mut value = json!({ "fps": 2 });
+        assert_eq!(migrate(&mut value), Upgrade::From(0));
+        assert_eq!(value["capture_fps"], json!(2));
+        assert_eq!(value["version"], json!(CONFIG_VERSION));
+    }
+
+    #[test]
+    fn existing_capture_rate_is_kept() {
+        let mut value = json!({ "fps": 2, "capture_fps": 10 });
+        migrate(&mut value);
+        assert_eq!(value["capture_fps"], json!(10));
+    }
+
+    #[test]
+    fn current_file_is_left_alone() {
+        let mut value = json!({ "version": CONFIG_VERSION, "fps": 2 });
+        let before = value.clone();
+        assert_eq!(migrate(&mut value), Upgrade::Current);
+        assert_eq!(value, before);
+    }
+
+    #[test]
+    fn newer_file_is_not_touched() {
+        let newer = CONFIG_VERSION + 1;
+        let mut value = json!({ "version": newer, "fps": 2 });
+        let before = value.clone();
+        assert_eq!(migrate(&mut value), Upgrade::Newer(newer));
+        assert_eq!(value, before);
+    }
+
+    #[test]
+    fn non_object_is_not_touched() {
+        for before in [json!([1, 2]), json!("config"), Value::Null] {
+            let mut value = before.clone();
+            assert_eq!(migrate(&mut value), Upgrade::Current);
+            assert_eq!(value, before);
+        }
+    }
+
+    #[test]
+    fn bad_version_counts_as_unversioned() {
+        assert_eq!(version_of(&json!({ "version": "1" })), 0);
+        assert_eq!(version_of(&json!({ "version": -1 })), 0);
+        assert_eq!(version_of(&json!({ "version": u64::MAX })), u32::MAX);
+    }
+}
@@ -0,0 +1,420 @@
+//! JSON schema for `config.json`, derived from `AppConfig`'s defaults, and
+//! validation of a loaded file against it.
+
+use crate::assets::pieces::PieceTheme;
+use crate::capture::source::CaptureSourceKind;
+use crate::config::{AppConfig, BoardRegion, BoardSide, MonitorChoice};
+use crate::engine::process::EnginePriority;
+use crate::engine::stockfish::SearchMode;
+use crate::notation::Notation;
+use crate::overlay::arrow::MoveHighlight;
+use crate::overlay::arrow_filter::PieceFilter;
+use crate::overlay::theme::{Palette, Rgb};
+use crate::overlay::window::DesktopMode;
+use crate::vision::inference::InferenceBackend;
+use crate::vision::site::Site;
+use serde_json::{json, Map, Value};
+
+/// Valid ranges for numeric settings, matching the settings window sliders.
+/// Keys are dotted paths into the config object.
+const RANGES: &[(&str, f64, f64)] = &[
+    ("stockfish_depth", 1.0, 30.0),
+    ("stockfish_lines", 1.0, 5.0),
+    ("stockfish_time_ms", 10.0, 60_000.0),
+    ("stockfish_nodes", 1_000.0, 1_000_000_000.0),
+    ("off_turn_depth_ratio", 0.2, 1.0),
+    ("human_elo", 1320.0, 3190.0),
+    ("blunder_threshold_cp", 50.0, 1000.0),
+    ("confidence_threshold", 0.1, 1.0),
+    ("iou_threshold", 0.1, 0.9),
+    ("color_check_below", 0.2, 1.0),
+    ("coordinate_strip", 0.0, 0.1),
+    ("min_board_px", 40.0, 800.0),
+    ("auto_region_padding", 0.0, 0.5),
+    ("api_port", 1024.0, 65535.0),
+    ("region_board_fraction", 0.05, 1.0),
+    ("fps", 1.0, 10.0),
+    ("capture_fps", 1.0, 30.0),
+    ("post_move_cooldown_ms", 0.0, 2000.0),
+    ("idle_after_secs", 0.0, 600.0),
+    ("idle_interval_ms", 100.0, 10_000.0),
+    ("arrow_style.thickness", 0.04, 0.4),
+    ("arrow_style.opacity", 0.1, 1.0),
+    ("overlay_theme.first_line_opacity", 0.05, 1.0),
+    ("overlay_theme.second_line_opacity", 0.05, 1.0),
+    ("overlay_theme.other_lines_opacity", 0.05, 1.0),
+    ("overlay_theme.highlight_opacity", 0.05, 1.0),
+    ("overlay_theme.hud_font_size", 8.0, 24.0),
+    ("overlay_theme.board_dim", 0.0, 0.8),
+    ("pv_length", 2.0, 12.0),
+    ("max_arrows", 1.0, 20.0),
+    ("engine_options.threads", 1.0, 1024.0),
+    ("engine_options.hash_mb", 1.0, 33_554_432.0),
+    ("engine_options.skill_level", 0.0, 20.0),
+    ("engine_options.elo", 1320.0, 3190.0),
+    ("engine_options.contempt", -100.0, 100.0),
+    ("auto_move.min_delay_ms", 0.0, 10_000.0),
+    ("auto_move.max_delay_ms", 0.0, 10_000.0),
+    ("memory_warn_mb", 50.0, 8192.0),
+    ("reclaim_interval_hours", 0.0, 48.0),
+];
+
+/// A problem found in the config file, with the fix applied when loading.
+#[derive(Clone, Debug)]
+pub struct ConfigIssue {
+    pub key: String,
+    pub problem: String,
+    pub suggestion: String,
+}
+
+/// The default config with sample values for optional sections, so every
+/// key has a concrete type to check against.
+fn template() -> Value {
+    let mut value = serde_json::to_value(AppConfig::default()).unwrap_or(Value::Null);
+    value["board_region"] = json!(BoardRegion {
+        x: 0,
+        y: 0,
+        width: 0,
+        height: 0,
+    });
+    value["clock_region"] = value["board_region"].clone();
+    value["monitor"] = json!(MonitorChoice {
+        id: 0,
+        label: String::new(),
+    });
+    value["region_board_fraction"] = json!(1.0);
+    value["overlay_theme"]["highlight"] = json!(Rgb(255, 255, 255));
+    value["overlay_theme"]["white_arrows"] = json!(Rgb(255, 255, 255));
+    value["overlay_theme"]["black_arrows"] = json!(Rgb(255, 255, 255));
+    value
+}
+
+/// Keys whose `null` value is valid (unset optional sections).
+fn nullable(path: &str) -> bool {
+    matches!(
+        path,
+        "board_region"
+            | "clock_region"
+            | "monitor"
+            | "region_board_fraction"
+            | "overlay_theme.highlight"
+            | "overlay_theme.white_arrows"
+            | "overlay_theme.black_arrows"
+    )
+}
+
+fn allowed_strings(path: &str) -> Option<Vec<Value>> {
+    match path {
+        "engine_process.priority" => Some(
+            EnginePriority::ALL
+                .iter()
+                .filter_map(|p| serde_json::to_value(p).ok())
+                .collect(),
+        ),
+        "search_mode" => Some(
+            SearchMode::ALL
+                .iter()
+                .filter_map(|m| serde_json::to_value(m).ok())
+                .collect(),
+        ),
+        "arrow_filter.piece" => Some(
+            PieceFilter::ALL
+                .iter()
+                .filter_map(|p| serde_json::to_value(p).ok())
+                .collect(),
+        ),
+        "piece_theme" => Some(
+            PieceTheme::ALL
+                .iter()
+                .filter_map(|t| serde_json::to_value(t).ok())
+                .collect(),
+        ),
+        "inference_backend" => Some(
+            InferenceBackend::ALL
+                .iter()
+                .filter_map(|b| serde_json::to_value(b).ok())
+                .collect(),
+        ),
+        "move_highlight" => Some(
+            MoveHighlight::ALL
+                .iter()
+                .filter_map(|m| serde_json::to_value(m).ok())
+                .collect(),
+        ),
+        "overlay_desktop" => Some(
+            DesktopMode::ALL
+                .iter()
+                .filter_map(|m| serde_json::to_value(m).ok())
+                .collect(),
+        ),
+        "capture_source" => Some(
+            CaptureSourceKind::ALL
+                .iter()
+                .filter_map(|k| serde_json::to_value(k).ok())
+                .collect(),
+        ),
+        "site" => Some(
+            Site::ALL
+                .iter()
+                .filter_map(|s| serde_json::to_value(s).ok())
+                .collect(),
+        ),
+        "notation" => Some(
+            Notation::ALL
+                .iter()
+                .filter_map(|n| serde_json::to_value(n).ok())
+                .collect(),
+        ),
+        "overlay_theme.palette" => Some(
+            Palette::ALL
+                .iter()
+                .filter_map(|p| serde_json::to_value(p).ok())
+                .collect(),
+        ),
+        // Elements of the array
+        "board_sides[]" => Some(
+            BoardSide::ALL
+                .iter()
+                .filter_map(|s| serde_json::to_value(s).ok())
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+fn range(path: &str) -> Option<(f64, f64)> {
+    RANGES
+        .iter()
+        .find(|(key, _, _)| *key == path)
+        .map(|(_, min, max)| (*min, *max))
+}
+
+/// Whether a template value is a color, which the file must then also
+/// give as "#rrggbb".
+fn is_color(template: &Value) -> bool {
+    template
+        .as_str()
+        .is_some_and(|s| s.starts_with('#') && Rgb::parse(s).is_some())
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn join(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
+
+/// JSON schema (draft 2020-12) describing `config.json`.
+pub fn schema() -> Value {
+    let mut root = schema_for(&template(), "");
+    root["$schema"] = json!("https://json-schema.org/draft/2020-12/schema");
+    root["title"] = json!("Chess Overlay config.json");
+    root
+}
+
+fn schema_for(value: &Value, path: &str) -> Value {
+    let mut schema = match value {
+        Value::Object(fields) => {
+            let properties: Map<String, Value> = fields
+                .iter()
+                .map(|(key, v)| (key.clone(), schema_for(v, &join(path, key))))
+                .collect();
+            json!({
+                "type": "object",
+                "properties": properties,
+                "additionalProperties": false,
+            })
+        }
+        Value::Array(_) => match allowed_strings(&format!("{}[]", path)) {
+            Some(values) => json!({ "type": "array", "items": { "enum": values } }),
+            None => json!({ "type": "array" }),
+        },
+        other => json!({ "type": type_name(other), "default": other }),
+    };
+    if value.is_u64() {
+        schema["minimum"] = json!(0);
+    }
+    if let Some((min, max)) = range(path) {
+        schema["minimum"] = json!(min);
+        schema["maximum"] = json!(max);
+    }
+    if let Some(values) = allowed_strings(path) {
+        schema["enum"] = Value::Array(values);
+    }
+    if is_color(value) {
+        schema["pattern"] = json!("^#?[0-9a-fA-F]{6}$");
+    }
+    if nullable(path) {
+        let inner = schema;
+        schema = json!({ "anyOf": [inner, { "type": "null" }], "default": null });
+    }
+    schema
+}
+
+/// Writes `config.schema.json` next to `config.json`.
+pub fn write_schema() -> anyhow::Result<std::path::PathBuf> {
+    let path = crate::paths::config_dir().join("config.schema.json");
+    std::fs::write(&path, serde_json::to_string_pretty(&schema())?)?;
+    Ok(path)
+}
+
+/// Checks `value` against the schema and repairs it in place: unknown keys
+/// and values of the wrong type are removed (so the default applies) and
+/// out-of-range numbers are clamped.
+pub fn validate(value: &mut Value) -> Vec<ConfigIssue> {
+    let mut issues = Vec::new();
+    check(&template(), value, "", &mut issues);
+    issues
+}
+
+fn check(template: &Value, value: &mut Value, path: &str, issues: &mut Vec<ConfigIssue>) {
+    let (Value::Object(expected), Value::Object(fields)) = (template, value) else {
+        return;
+    };
+    let keys: Vec<String> = fields.keys().cloned().collect();
+    for key in keys {
+        let key_path = join(path, &key);
+        let Some(expected_value) = expected.get(&key) else {
+            let suggestion = closest_key(&key, expected.keys())
+                .map(|k| format!("did you mean \"{}\"?", join(path, k)))
+                .unwrap_or_else(|| "remove it".to_string());
+            issues.push(ConfigIssue {
+                key: key_path,
+                problem: "unknown key, ignored".to_string(),
+                suggestion,
+            });
+            fields.remove(&key);
+            continue;
+        };
+
+        let actual = fields.get_mut(&key).unwrap();
+        if actual.is_null() && nullable(&key_path) {
+            continue;
+        }
+        let type_ok = match (expected_value, &*actual) {
+            (Value::Number(e), Value::Number(a)) => !e.is_u64() || a.is_u64(),
+            (e, a) => type_name(e) == type_name(a) || (e.is_number() && a.is_number()),
+        };
+        if !type_ok {
+            let default = default_at(&key_path);
+            issues.push(ConfigIssue {
+                key: key_path,
+                problem: format!(
+                    "expected {}, found {}",
+                    type_name(expected_value),
+                    type_name(actual)
+                ),
+                suggestion: format!("using default {}", default),
+            });
+            fields.remove(&key);
+            continue;
+        }
+
+        if let Some(allowed) = allowed_strings(&key_path) {
+            if !allowed.contains(actual) {
+                let default = default_at(&key_path);
+                issues.push(ConfigIssue {
+                    key: key_path,
+                    problem: format!("{} is not one of {}", actual, Value::Array(allowed)),
+                    suggestion: format!("using default {}", default),
+                });
+                fields.remove(&key);
+                continue;
+            }
+        }
+
+        if let (Some(allowed), Value::Array(items)) =
+            (allowed_strings(&format!("{}[]", key_path)), &*actual)
+        {
+            if let Some(bad) = items.iter().find(|v| !allowed.contains(v)) {
+                let default = default_at(&key_path);
+                issues.push(ConfigIssue {
+                    key: key_path,
+                    problem: format!("{} is not one of {}", bad, Value::Array(allowed)),
+                    suggestion: format!("using default {}", default),
+                });
+                fields.remove(&key);
+                continue;
+            }
+        }
+
+        if is_color(expected_value) && actual.as_str().and_then(Rgb::parse).is_none() {
+            let default = default_at(&key_path);
+            issues.push(ConfigIssue {
+                key: key_path,
+                problem: format!("{} is not a #rrggbb color", actual),
+                suggestion: format!("using default {}", default),
+            });
+            fields.remove(&key);
+            continue;
+        }
+
+        if let (Some((min, max)), Some(n)) = (range(&key_path), actual.as_f64()) {
+            if n < min || n > max {
+                let clamped = n.clamp(min, max);
+                *actual = if expected_value.is_u64() {
+                    json!(clamped as u64)
+                } else {
+                    json!(clamped)
+                };
+                issues.push(ConfigIssue {
+                    key: key_path,
+                    problem: format!("{} is outside {}..={}", n, min, max),
+                    suggestion: format!("clamped to {}", actual),
+                });
+                continue;
+            }
+        }
+
+        check(expected_value, actual, &key_path, issues);
+    }
+}
+
+fn default_at(path: &str) -> Value {
+    let defaults = serde_json::to_value(AppConfig::default()).unwrap_or(Value::Null);
+    path.split('.')
+        .try_fold(&defaults, |v, key| v.get(key))
+        .cloned()
+        .unwrap_or(Value::Null)
+}
+
+/// Known key within edit distance 2 of `key`, for typo suggestions.
+fn closest_key<'a>(key: &str, known: impl Iterator<Item = &'a String>) -> Option<&'a String> {
+    known
+        .map(|k| (edit_distance(key, k), k))
+        .filter(|(d, _)| *d <= 2)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, k)| k)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                prev
+            } else {
+                1 + prev.min(row[j]).min(row[j + 1])
+            };
+            prev = current;
+        }
+    }
+    row[b.len()]
+}
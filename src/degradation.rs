@@ -0,0 +1,217 @@
+use crate::engine::stockfish::SearchLimit;
+use std::time::{Duration, Instant};
+
+/// Consecutive over-budget cycles before stepping one level down the ladder.
+const ESCALATE_AFTER: u32 = 5;
+/// Consecutive comfortable cycles before stepping back up. Much larger than
+/// `ESCALATE_AFTER` so the level doesn't oscillate around the budget.
+const RECOVER_AFTER: u32 = 20;
+/// A cycle counts as comfortable when it used less than this share of the budget.
+const RECOVER_RATIO: f32 = 0.5;
+/// How long analysis stays paused before retrying at the previous level.
+const PAUSE_DURATION: Duration = Duration::from_secs(10);
+/// Share of the CPU busy with other programs above which a cycle counts as
+/// over budget however fast it was.
+const HIGH_LOAD: f32 = 0.9;
+/// Load below which a fast cycle counts as comfortable.
+const COMFORTABLE_LOAD: f32 = 0.6;
+/// Shortest gap between cycles worth reading the CPU load over.
+const MIN_LOAD_GAP: Duration = Duration::from_millis(200);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum DegradationLevel {
+    #[default]
+    Normal,
+    ReducedLines,
+    ReducedDepth,
+    ReducedCapture,
+    Paused,
+}
+
+impl DegradationLevel {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DegradationLevel::Normal => "Normal",
+            DegradationLevel::ReducedLines => "Reduced lines",
+            DegradationLevel::ReducedDepth => "Reduced search",
+            DegradationLevel::ReducedCapture => "Reduced capture rate",
+            DegradationLevel::Paused => "Paused (overloaded)",
+        }
+    }
+
+    fn worse(self) -> Self {
+        match self {
+            DegradationLevel::Normal => DegradationLevel::ReducedLines,
+            DegradationLevel::ReducedLines => DegradationLevel::ReducedDepth,
+            DegradationLevel::ReducedDepth => DegradationLevel::ReducedCapture,
+            _ => DegradationLevel::Paused,
+        }
+    }
+
+    fn better(self) -> Self {
+        match self {
+            DegradationLevel::Paused => DegradationLevel::ReducedCapture,
+            DegradationLevel::ReducedCapture => DegradationLevel::ReducedDepth,
+            DegradationLevel::ReducedDepth => DegradationLevel::ReducedLines,
+            _ => DegradationLevel::Normal,
+        }
+    }
+}
+
+/// Search and capture settings after the current degradation level is applied.
+pub struct Effective {
+    pub limit: SearchLimit,
+    pub lines: u32,
+    pub capture_fps: u32,
+}
+
+/// Steps through `DegradationLevel`s based on how analysis cycles compare to
+/// their time budget. Each level keeps the reductions of the levels before it.
+#[derive(Default)]
+pub struct DegradationLadder {
+    level: DegradationLevel,
+    over_budget: u32,
+    comfortable: u32,
+    paused_at: Option<Instant>,
+}
+
+impl DegradationLadder {
+    pub fn level(&self) -> DegradationLevel {
+        self.level
+    }
+
+    /// Records how long a whole analysis cycle (detection, tracking and the
+    /// engine search, which the first levels shorten) took against its
+    /// budget, and the system's CPU load from `CpuMeter::idle_load`.
+    pub fn record(&mut self, elapsed: Duration, budget: Duration, load: Option<f32>) {
+        if elapsed > budget || load.is_some_and(|l| l > HIGH_LOAD) {
+            self.over_budget += 1;
+            self.comfortable = 0;
+        } else if elapsed.as_secs_f32() < budget.as_secs_f32() * RECOVER_RATIO
+            && load.is_none_or(|l| l < COMFORTABLE_LOAD)
+        {
+            self.comfortable += 1;
+            self.over_budget = 0;
+        } else {
+            self.over_budget = 0;
+            self.comfortable = 0;
+        }
+
+        if self.over_budget >= ESCALATE_AFTER {
+            self.set_level(self.level.worse());
+        } else if self.comfortable >= RECOVER_AFTER {
+            self.set_level(self.level.better());
+        }
+    }
+
+    /// Returns true while analysis should be skipped entirely. Leaves the
+    /// paused level on its own once `PAUSE_DURATION` has passed.
+    pub fn is_paused(&mut self) -> bool {
+        if let Some(paused_at) = self.paused_at {
+            if paused_at.elapsed() >= PAUSE_DURATION {
+                self.set_level(DegradationLevel::ReducedCapture);
+            }
+        }
+        self.level == DegradationLevel::Paused
+    }
+
+    pub fn apply(&self, limit: SearchLimit, lines: u32, capture_fps: u32) -> Effective {
+        let level = self.level;
+        Effective {
+            lines: if level >= DegradationLevel::ReducedLines {
+                1
+            } else {
+                lines
+            },
+            limit: if level >= DegradationLevel::ReducedDepth {
+                limit.reduced()
+            } else {
+                limit
+            },
+            capture_fps: if level >= DegradationLevel::ReducedCapture {
+                (capture_fps / 2).max(1)
+            } else {
+                capture_fps
+            },
+        }
+    }
+
+    fn set_level(&mut self, level: DegradationLevel) {
+        if level != self.level {
+            println!(
+                "Performance level: {} -> {}",
+                self.level.label(),
+                level.label()
+            );
+        }
+        self.level = level;
+        self.over_budget = 0;
+        self.comfortable = 0;
+        self.paused_at = (level == DegradationLevel::Paused).then(Instant::now);
+    }
+}
+
+/// Busy and total CPU time of the whole system since boot, in the OS's
+/// units.
+#[cfg(windows)]
+fn cpu_times() -> Option<(u64, u64)> {
+    use windows::Win32::Foundation::FILETIME;
+    use windows::Win32::System::Threading::GetSystemTimes;
+
+    let (mut idle, mut kernel, mut user) = Default::default();
+    unsafe { GetSystemTimes(Some(&mut idle), Some(&mut kernel), Some(&mut user)) }.ok()?;
+    let ticks = |t: FILETIME| ((t.dwHighDateTime as u64) << 32) | t.dwLowDateTime as u64;
+    // Kernel time includes idle time
+    let total = ticks(kernel) + ticks(user);
+    Some((total - ticks(idle), total))
+}
+
+#[cfg(target_os = "linux")]
+fn cpu_times() -> Option<(u64, u64)> {
+    // First line: cpu user nice system idle iowait irq softirq steal ...
+    let stat = std::fs::read_to_string("/proc/stat").ok()?;
+    let fields: Vec<u64> = stat
+        .lines()
+        .next()?
+        .split_whitespace()
+        .skip(1)
+        .take(8)
+        .filter_map(|f| f.parse().ok())
+        .collect();
+    if fields.len() < 5 {
+        return None;
+    }
+    let total: u64 = fields.iter().sum();
+    Some((total - fields[3] - fields[4], total))
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn cpu_times() -> Option<(u64, u64)> {
+    None
+}
+
+/// System CPU load between analysis cycles, while neither the model nor
+/// the engine is working, so it shows what other programs are using.
+#[derive(Default)]
+pub struct CpuMeter {
+    mark: Option<((u64, u64), Instant)>,
+}
+
+impl CpuMeter {
+    /// Called when a cycle's work ends.
+    pub fn mark(&mut self) {
+        self.mark = cpu_times().map(|times| (times, Instant::now()));
+    }
+
+    /// Busy share of the CPU since `mark`, or `None` when it can't be read
+    /// or the gap was too short to say.
+    pub fn idle_load(&mut self) -> Option<f32> {
+        let ((busy, total), at) = self.mark.take()?;
+        if at.elapsed() < MIN_LOAD_GAP {
+            return None;
+        }
+        let (busy_now, total_now) = cpu_times()?;
+        let total = total_now.checked_sub(total)?;
+        (total > 0).then(|| busy_now.saturating_sub(busy) as f32 / total as f32)
+    }
+}
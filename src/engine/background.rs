@@ -0,0 +1,63 @@
+use crate::engine::options::EngineOptions;
+use crate::engine::process::EngineProcessSettings;
+use crate::engine::stockfish::{Evaluation, Stockfish};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::path::Path;
+use std::thread;
+
+/// A second engine process on its own thread, used for side analysis (e.g.
+/// the interactive board) so it never competes with the live overlay's
+/// engine. Requests are FENs; only the newest pending one is searched.
+pub struct BackgroundEngine {
+    request_tx: Sender<String>,
+    result_rx: Receiver<(String, Option<Evaluation>)>,
+}
+
+impl BackgroundEngine {
+    pub fn spawn(
+        engine_path: &Path,
+        depth: u32,
+        process: EngineProcessSettings,
+        options: EngineOptions,
+    ) -> Self {
+        let (request_tx, request_rx) = unbounded::<String>();
+        let (result_tx, result_rx) = unbounded();
+        let path = engine_path.to_string_lossy().to_string();
+
+        thread::spawn(move || {
+            let mut sf = match Stockfish::new(&path, &process, &options) {
+                Ok(s) => s,
+                Err(e) => {
+                    println!("Background engine failed to start: {:?}", e);
+                    return;
+                }
+            };
+            while let Ok(mut fen) = request_rx.recv() {
+                // Skip requests that were superseded while we were busy
+                while let Ok(newer) = request_rx.try_recv() {
+                    fen = newer;
+                }
+                let eval = sf.evaluate(&fen, depth).unwrap_or_else(|e| {
+                    println!("Background engine error: {:?}", e);
+                    None
+                });
+                if result_tx.send((fen, eval)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            request_tx,
+            result_rx,
+        }
+    }
+
+    pub fn request(&self, fen: String) {
+        let _ = self.request_tx.send(fen);
+    }
+
+    pub fn try_result(&self) -> Option<(String, Option<Evaluation>)> {
+        self.result_rx.try_iter().last()
+    }
+}
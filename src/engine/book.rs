@@ -0,0 +1,184 @@
+use crate::chess_logic::{play_uci, position_from_fen};
+use anyhow::{bail, Result};
+use shakmaty::zobrist::{Zobrist64, ZobristHash};
+use shakmaty::{Chess, EnPassantMode, Position, Role, Square};
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Size of one Polyglot entry: key, move, weight and learn data.
+const ENTRY_SIZE: usize = 16;
+
+/// Where the book is read from: the configured file, or `book.bin` in the
+/// app folder when none is set.
+pub fn resolve_path(configured: &str) -> PathBuf {
+    let configured = configured.trim();
+    if configured.is_empty() {
+        crate::paths::app_dir().join("book.bin")
+    } else {
+        PathBuf::from(configured)
+    }
+}
+
+/// A move the book knows for a position, with its weight.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BookMove {
+    pub uci: String,
+    pub weight: u16,
+    /// Share of the position's total weight, 0.0..=1.0.
+    pub share: f32,
+}
+
+struct Entry {
+    key: u64,
+    mv: u16,
+    weight: u16,
+}
+
+/// A Polyglot (`.bin`) opening book, loaded into memory. Entries are sorted
+/// by position key in the file, so lookups are a binary search.
+pub struct OpeningBook {
+    entries: Vec<Entry>,
+}
+
+impl OpeningBook {
+    pub fn open(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path)?;
+        if bytes.len() % ENTRY_SIZE != 0 {
+            bail!("{} is not a Polyglot book", path.display());
+        }
+        let entries = bytes
+            .chunks_exact(ENTRY_SIZE)
+            .map(|e| Entry {
+                key: u64::from_be_bytes(e[0..8].try_into().unwrap()),
+                mv: u16::from_be_bytes([e[8], e[9]]),
+                weight: u16::from_be_bytes([e[10], e[11]]),
+            })
+            .collect();
+        Ok(Self { entries })
+    }
+
+    pub fn entry_count(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Book moves for `fen`, heaviest first. Empty when the position is out
+    /// of book; moves that are illegal here (key collisions) are dropped.
+    pub fn moves(&self, fen: &str) -> Vec<BookMove> {
+        let Some(pos) = position_from_fen(fen) else {
+            return Vec::new();
+        };
+        // Polyglot keys count an en passant square only when a pawn could
+        // take there
+        let key = pos.zobrist_hash::<Zobrist64>(EnPassantMode::PseudoLegal).0;
+        let start = self.entries.partition_point(|e| e.key < key);
+        let mut moves: Vec<(String, u16)> = self.entries[start..]
+            .iter()
+            .take_while(|e| e.key == key)
+            .filter(|e| e.weight > 0)
+            .map(|e| (polyglot_uci(&pos, e.mv), e.weight))
+            .filter(|(uci, _)| play_uci(fen, uci).is_some())
+            .collect();
+        moves.sort_by_key(|(_, weight)| Reverse(*weight));
+        let total: u32 = moves.iter().map(|(_, w)| *w as u32).sum();
+        moves
+            .into_iter()
+            .map(|(uci, weight)| BookMove {
+                uci,
+                weight,
+                share: weight as f32 / total.max(1) as f32,
+            })
+            .collect()
+    }
+}
+
+/// Decodes a Polyglot move: target in bits 0-5, origin in bits 6-11 and
+/// the promotion piece in bits 12-14. Castling is stored as the king taking
+/// its own rook and becomes the usual two-square king move.
+fn polyglot_uci(pos: &Chess, raw: u16) -> String {
+    let square = |bits: u16| Square::new((bits & 63) as u32);
+    let from = square(raw >> 6);
+    let mut to = square(raw);
+    if pos.board().role_at(from) == Some(Role::King) {
+        to = match (from, to) {
+            (Square::E1, Square::H1) => Square::G1,
+            (Square::E1, Square::A1) => Square::C1,
+            (Square::E8, Square::H8) => Square::G8,
+            (Square::E8, Square::A8) => Square::C8,
+            _ => to,
+        };
+    }
+    let promotion = match (raw >> 12) & 7 {
+        1 => "n",
+        2 => "b",
+        3 => "r",
+        4 => "q",
+        _ => "",
+    };
+    format!("{}{}{}", from, to, promotion)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Polyglot key of the starting position.
+    const START_KEY: u64 = 0x463b96181691fc9c;
+    const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    /// Raw Polyglot move from square indices (a1 = 0, h8 = 63).
+    fn raw(from: u16, to: u16) -> u16 {
+        (from << 6) | to
+    }
+
+    fn book(mut entries: Vec<Entry>) -> OpeningBook {
+        entries.sort_by_key(|e| e.key);
+        OpeningBook { entries }
+    }
+
+    #[test]
+    fn start_position_moves_by_key() {
+        let book = book(vec![
+            Entry {
+                key: START_KEY,
+                mv: raw(11, 27),
+                weight: 1,
+            },
+            Entry {
+                key: START_KEY,
+                mv: raw(12, 28),
+                weight: 3,
+            },
+            Entry {
+                key: START_KEY + 1,
+                mv: raw(6, 21),
+                weight: 5,
+            },
+        ]);
+        let moves = book.moves(START);
+        let ucis: Vec<_> = moves.iter().map(|m| m.uci.as_str()).collect();
+        assert_eq!(ucis, ["e2e4", "d2d4"]);
+        assert_eq!(moves[0].share, 0.75);
+    }
+
+    #[test]
+    fn king_takes_rook_becomes_castling() {
+        let fen = "r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQkq - 0 1";
+        let pos = position_from_fen(fen).unwrap();
+        let key = pos.zobrist_hash::<Zobrist64>(EnPassantMode::PseudoLegal).0;
+        let book = book(vec![
+            Entry {
+                key,
+                mv: raw(4, 7),
+                weight: 2,
+            },
+            Entry {
+                key,
+                mv: raw(4, 0),
+                weight: 1,
+            },
+        ]);
+        let ucis: Vec<_> = book.moves(fen).into_iter().map(|m| m.uci).collect();
+        assert_eq!(ucis, ["e1g1", "e1c1"]);
+    }
+}
@@ -0,0 +1,96 @@
+use std::fmt;
+use std::io;
+use std::process::ExitStatus;
+
+/// Where to get a build that matches this machine.
+pub const DOWNLOAD_URL: &str = "https://stockfishchess.org/download/";
+
+/// Windows `STATUS_ILLEGAL_INSTRUCTION`, reported when a build uses CPU
+/// features (AVX2, BMI2, ...) this processor lacks.
+#[cfg(windows)]
+const STATUS_ILLEGAL_INSTRUCTION: u32 = 0xC000_001D;
+
+/// A recognizable reason the engine binary cannot be used, with advice
+/// more specific than the underlying I/O error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineProblem {
+    Missing(String),
+    /// Not an executable for this OS/architecture, or a corrupted file.
+    WrongArchitecture,
+    NotExecutable,
+    /// Built for newer CPU instructions than this processor has.
+    UnsupportedCpu,
+    /// Started but never identified itself as a UCI engine.
+    NotUci,
+    Exited(String),
+}
+
+impl EngineProblem {
+    /// Classifies an error from spawning the engine process.
+    pub fn from_spawn_error(err: &io::Error, path: &str) -> Option<Self> {
+        match err.kind() {
+            io::ErrorKind::NotFound => return Some(EngineProblem::Missing(path.to_string())),
+            io::ErrorKind::PermissionDenied => return Some(EngineProblem::NotExecutable),
+            _ => {}
+        }
+        // ERROR_BAD_EXE_FORMAT / ERROR_EXE_MACHINE_TYPE_MISMATCH on Windows,
+        // ENOEXEC elsewhere
+        let bad_format = if cfg!(windows) {
+            matches!(err.raw_os_error(), Some(193) | Some(216))
+        } else {
+            err.raw_os_error() == Some(8)
+        };
+        bad_format.then_some(EngineProblem::WrongArchitecture)
+    }
+
+    /// Classifies an engine that exited during the handshake.
+    pub fn from_exit(status: ExitStatus) -> Self {
+        #[cfg(windows)]
+        if status.code().map(|c| c as u32) == Some(STATUS_ILLEGAL_INSTRUCTION) {
+            return EngineProblem::UnsupportedCpu;
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+            // SIGILL
+            if status.signal() == Some(4) {
+                return EngineProblem::UnsupportedCpu;
+            }
+        }
+        EngineProblem::Exited(status.to_string())
+    }
+
+    /// What the user should do about it.
+    pub fn advice(&self) -> &'static str {
+        match self {
+            EngineProblem::Missing(_) if cfg!(windows) => "Download Stockfish and place it next to the app as stockfish.exe.",
+            EngineProblem::Missing(_) => {
+                "Install Stockfish with your package manager, or place it next to the app as stockfish."
+            }
+            EngineProblem::WrongArchitecture => {
+                "The file is not a program for this system or is damaged. Download the build for your OS (x86-64 for most PCs)."
+            }
+            EngineProblem::NotExecutable => "Allow the file to run (check permissions or antivirus quarantine).",
+            EngineProblem::UnsupportedCpu => {
+                "This build needs newer CPU instructions. Download a more compatible build (e.g. \"x86-64-sse41-popcnt\" or plain \"x86-64\")."
+            }
+            EngineProblem::NotUci => "The file does not behave like a UCI chess engine. Download Stockfish.",
+            EngineProblem::Exited(_) => "Try re-downloading the engine.",
+        }
+    }
+}
+
+impl fmt::Display for EngineProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineProblem::Missing(path) => write!(f, "Engine not found at {}", path),
+            EngineProblem::WrongArchitecture => write!(f, "Engine binary has the wrong format"),
+            EngineProblem::NotExecutable => write!(f, "Engine binary is not executable"),
+            EngineProblem::UnsupportedCpu => write!(f, "Engine build is not supported by this CPU"),
+            EngineProblem::NotUci => write!(f, "Engine did not identify itself (no \"id name\")"),
+            EngineProblem::Exited(status) => write!(f, "Engine exited during startup ({})", status),
+        }
+    }
+}
+
+impl std::error::Error for EngineProblem {}
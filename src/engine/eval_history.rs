@@ -0,0 +1,110 @@
+use crate::chess_logic::{play_uci, position_fen, position_from_fen};
+use crate::engine::stockfish::Evaluation;
+use shakmaty::Position;
+use std::collections::VecDeque;
+use std::time::Instant;
+
+/// Positions remembered per board; only the last two are compared, the
+/// rest smooth over a frame the engine skipped.
+const KEPT: usize = 8;
+/// Scores are capped here before comparing, so "winning by a lot" turning
+/// into "winning by a bit less" is not a blunder.
+const SWING_CAP_CP: i32 = 1000;
+
+/// A move that lost more than the configured threshold.
+#[derive(Clone, Debug)]
+pub struct BlunderAlert {
+    /// Position after the losing move.
+    pub fen: String,
+    /// Centipawns the move gave up, from the mover's point of view.
+    pub loss_cp: i32,
+    /// Whether White played it.
+    pub by_white: bool,
+    /// The engine's best answer, which punishes the move.
+    pub refutation: Option<String>,
+    pub raised_at: Instant,
+}
+
+/// Scores for White of the positions a board went through, oldest first,
+/// to notice the score swinging after a move.
+#[derive(Default)]
+pub struct EvalHistory {
+    entries: VecDeque<(String, Evaluation)>,
+    alert: Option<BlunderAlert>,
+}
+
+fn placement(fen: &str) -> &str {
+    fen.split_whitespace().next().unwrap_or("")
+}
+
+/// Whether one legal move in `before` gives the placement of `after`.
+fn one_move_apart(before: &str, after: &str) -> bool {
+    let Some(pos) = position_from_fen(before) else {
+        return false;
+    };
+    pos.legal_moves().iter().any(|m| {
+        let mut next = pos.clone();
+        next.play_unchecked(m);
+        placement(&position_fen(&next)) == placement(after)
+    })
+}
+
+impl EvalHistory {
+    /// Stores the score for White of `fen`; a deeper search of the same
+    /// position replaces the earlier score. When `fen` follows the previous
+    /// position by one move that lost more than `threshold_cp` for the side
+    /// that made it, the alert for `fen` is raised with `best` (the best
+    /// reply) as refutation.
+    pub fn record(&mut self, fen: &str, eval: Evaluation, best: Option<&str>, threshold_cp: i32) {
+        if self.entries.back().is_some_and(|(last, _)| last == fen) {
+            self.entries.pop_back();
+        }
+        self.entries.push_back((fen.to_string(), eval));
+        while self.entries.len() > KEPT {
+            self.entries.pop_front();
+        }
+        if self.alert.as_ref().is_some_and(|a| a.fen != fen) {
+            self.alert = None;
+        }
+
+        let mut recent = self.entries.iter().rev();
+        let (Some(_), Some((before, before_eval))) = (recent.next(), recent.next()) else {
+            return;
+        };
+        if !one_move_apart(before, fen) {
+            return;
+        }
+        let by_white = before.split_whitespace().nth(1) == Some("w");
+        let score = |e: &Evaluation| e.as_centipawns().clamp(-SWING_CAP_CP, SWING_CAP_CP);
+        let swing = score(before_eval) - score(&eval);
+        let loss_cp = if by_white { swing } else { -swing };
+        if loss_cp <= threshold_cp {
+            self.alert = None;
+            return;
+        }
+        let refutation = best
+            .filter(|m| play_uci(fen, m).is_some())
+            .map(str::to_string);
+        match &mut self.alert {
+            // Same blunder seen deeper: keep when it was first raised
+            Some(alert) => {
+                alert.loss_cp = loss_cp;
+                alert.refutation = refutation;
+            }
+            None => {
+                self.alert = Some(BlunderAlert {
+                    fen: fen.to_string(),
+                    loss_cp,
+                    by_white,
+                    refutation,
+                    raised_at: Instant::now(),
+                })
+            }
+        }
+    }
+
+    /// The alert for the last recorded position, if its move was a blunder.
+    pub fn alert(&self) -> Option<&BlunderAlert> {
+        self.alert.as_ref()
+    }
+}
@@ -0,0 +1,115 @@
+use crate::chess_logic::{line_to_san, play_uci, position_fen, position_from_fen, uci_to_san};
+use crate::engine::stockfish::{Evaluation, SearchLimit, Stockfish};
+use anyhow::Result;
+
+/// Plies of each line shown in the explanation.
+const SHOWN_PLIES: usize = 4;
+/// Centipawns passing must lose before the opponent's reply counts as a
+/// threat worth an arrow.
+const THREAT_MIN_CP: i32 = 80;
+
+/// Why the best move matters, as short lines in SAN: what the opponent
+/// would do if we passed, and how the runner-up move goes wrong.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Explanation {
+    /// Position the explanation belongs to; dropped once the board moves on.
+    pub fen: String,
+    pub best: String,
+    /// Opponent's best line after a null move, i.e. the threat the best
+    /// move deals with. `None` when passing is illegal (in check).
+    pub threat: Option<Vec<String>>,
+    /// Score for us after passing.
+    pub threat_eval: Option<Evaluation>,
+    /// The second suggestion and the opponent's best answer to it.
+    pub alternative: Option<(String, Vec<String>)>,
+    pub alternative_eval: Option<Evaluation>,
+}
+
+/// The position with the other side to move and no en passant target, if
+/// that is legal.
+pub fn null_move_fen(fen: &str) -> Option<String> {
+    let mut fields: Vec<&str> = fen.split_whitespace().collect();
+    if fields.len() < 4 {
+        return None;
+    }
+    fields[1] = if fields[1] == "w" { "b" } else { "w" };
+    fields[3] = "-";
+    position_from_fen(&fields.join(" ")).map(|pos| position_fen(&pos))
+}
+
+/// Searches `fen` for the side to move and returns its line in SAN with
+/// the score negated to our point of view.
+fn reply_line(
+    sf: &mut Stockfish,
+    fen: &str,
+    limit: SearchLimit,
+) -> Result<Option<(Vec<String>, Option<Evaluation>)>> {
+    let Some(line) = sf.analyze(fen, limit, 1)?.into_iter().next() else {
+        return Ok(None);
+    };
+    let mut san = line_to_san(fen, &line.pv);
+    san.truncate(SHOWN_PLIES);
+    // The reply's score is the opponent's, from their side
+    let eval = line.eval.map(|e| e.for_white(false));
+    Ok(Some((san, eval)))
+}
+
+/// The opponent's best line in UCI if the side to move in `fen` passed,
+/// for the threat arrows. `eval` is the position's score for the side to
+/// move; the line is only kept when passing would cost more than
+/// `THREAT_MIN_CP` against it. Empty when passing is illegal (in check)
+/// or harmless.
+pub fn threat_line(
+    sf: &mut Stockfish,
+    fen: &str,
+    eval: Option<Evaluation>,
+    limit: SearchLimit,
+) -> Result<Vec<String>> {
+    let Some(null) = null_move_fen(fen) else {
+        return Ok(Vec::new());
+    };
+    let Some(line) = sf.analyze(&null, limit, 1)?.into_iter().next() else {
+        return Ok(Vec::new());
+    };
+    // The reply's score is the opponent's; negated it is ours after passing
+    let after = line.eval.map(|e| e.for_white(false).as_centipawns());
+    let loss = match (eval, after) {
+        (Some(now), Some(after)) => now.as_centipawns().saturating_sub(after),
+        _ => 0,
+    };
+    if loss < THREAT_MIN_CP {
+        return Ok(Vec::new());
+    }
+    Ok(line.pv)
+}
+
+/// Explains `best` in `fen` with two short searches: one after a null
+/// move and one after `alternative` (the next suggestion), when given.
+pub fn explain(
+    sf: &mut Stockfish,
+    fen: &str,
+    best: &str,
+    alternative: Option<&str>,
+    limit: SearchLimit,
+) -> Result<Explanation> {
+    let (threat, threat_eval) = match null_move_fen(fen) {
+        Some(null) => reply_line(sf, &null, limit)?.unzip(),
+        None => (None, None),
+    };
+    let mut explanation = Explanation {
+        fen: fen.to_string(),
+        best: uci_to_san(fen, best).unwrap_or_else(|| best.to_string()),
+        threat,
+        threat_eval: threat_eval.flatten(),
+        alternative: None,
+        alternative_eval: None,
+    };
+    if let Some((alt, after)) = alternative.and_then(|alt| Some((alt, play_uci(fen, alt)?))) {
+        if let Some((line, eval)) = reply_line(sf, &after, limit)? {
+            let alt_san = uci_to_san(fen, alt).unwrap_or_else(|| alt.to_string());
+            explanation.alternative = Some((alt_san, line));
+            explanation.alternative_eval = eval;
+        }
+    }
+    Ok(explanation)
+}
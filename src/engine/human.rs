@@ -0,0 +1,25 @@
+use crate::engine::options::EngineOptions;
+use crate::engine::stockfish::{SearchLimit, Stockfish};
+use anyhow::Result;
+
+/// The move Stockfish plays in `fen` when limited to `elo`, as a
+/// human-plausible alternative to the full-strength suggestion. The engine
+/// only weakens its final choice, so the move is its `bestmove` rather than
+/// a MultiPV line. `options` are restored afterwards.
+pub fn human_move(
+    sf: &mut Stockfish,
+    fen: &str,
+    limit: SearchLimit,
+    elo: u32,
+    options: &EngineOptions,
+) -> Result<Option<String>> {
+    sf.set_option("UCI_LimitStrength", "true")?;
+    sf.set_option("UCI_Elo", &elo.to_string())?;
+    let searched = sf.analyze(fen, limit, 1);
+    sf.set_option("UCI_LimitStrength", &options.limit_strength.to_string())?;
+    if options.limit_strength {
+        sf.set_option("UCI_Elo", &options.elo.to_string())?;
+    }
+    searched?;
+    Ok(sf.analysis().best_move)
+}
@@ -1 +1,14 @@
+pub mod background;
+pub mod book;
+pub mod diagnose;
+pub mod eval_history;
+pub mod explain;
+pub mod human;
+pub mod options;
+pub mod premove;
+pub mod process;
+pub mod quality;
+pub mod sparring;
 pub mod stockfish;
+pub mod supervisor;
+pub mod time_control;
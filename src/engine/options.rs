@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+/// UCI options sent to the engine at start and whenever they are changed
+/// in the settings window.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct EngineOptions {
+    pub threads: u32,
+    pub hash_mb: u32,
+    /// 0..=20; sparring varies it below this when enabled.
+    pub skill_level: u32,
+    /// Play at `elo` instead of full strength (`UCI_LimitStrength`).
+    pub limit_strength: bool,
+    pub elo: u32,
+    /// Sent only when non-zero; current Stockfish has no such option.
+    pub contempt: i32,
+    /// Folder(s) with Syzygy tablebases; empty leaves them off.
+    pub syzygy_path: String,
+}
+
+impl Default for EngineOptions {
+    fn default() -> Self {
+        Self {
+            threads: 8,
+            hash_mb: 256,
+            skill_level: 20,
+            limit_strength: false,
+            elo: 1500,
+            contempt: 0,
+            syzygy_path: String::new(),
+        }
+    }
+}
+
+impl EngineOptions {
+    /// Name/value pairs to send, leaving out options that are off so
+    /// engines without them don't report errors for defaults.
+    pub fn uci(&self) -> Vec<(&'static str, String)> {
+        let mut options = vec![
+            ("Threads", self.threads.to_string()),
+            ("Hash", self.hash_mb.to_string()),
+            ("Skill Level", self.skill_level.to_string()),
+            ("UCI_LimitStrength", self.limit_strength.to_string()),
+        ];
+        if self.limit_strength {
+            options.push(("UCI_Elo", self.elo.to_string()));
+        }
+        if self.contempt != 0 {
+            options.push(("Contempt", self.contempt.to_string()));
+        }
+        if !self.syzygy_path.trim().is_empty() {
+            options.push(("SyzygyPath", self.syzygy_path.trim().to_string()));
+        }
+        options
+    }
+}
@@ -0,0 +1,55 @@
+use crate::chess_logic::{play_uci, uci_to_san};
+use crate::engine::stockfish::{SearchLimit, Stockfish};
+use anyhow::Result;
+
+/// A conditional suggestion: if the opponent plays `reply`, answer with
+/// `response`.
+#[derive(Debug, Clone)]
+pub struct Premove {
+    pub reply: String,
+    pub response: String,
+    /// Both moves in SAN, for the arrow labels.
+    pub reply_san: Option<String>,
+    pub response_san: Option<String>,
+    /// Position after `reply`, to recognize it when it is played.
+    pub after: String,
+}
+
+/// While the opponent is to move in `fen`, takes their `replies` most
+/// likely moves (the engine's MultiPV lines) and searches our best answer to
+/// each, for premove planning. Every search runs with `limit`, which the
+/// caller already reduces since this is the side-line of the analysis.
+pub fn plan_premoves(
+    sf: &mut Stockfish,
+    fen: &str,
+    limit: SearchLimit,
+    replies: u32,
+) -> Result<Vec<Premove>> {
+    let mut premoves = Vec::new();
+    for line in sf.analyze(fen, limit, replies)? {
+        let Some(after) = play_uci(fen, &line.mv) else {
+            continue;
+        };
+        if let Some(best) = sf.analyze(&after, limit, 1)?.into_iter().next() {
+            premoves.push(Premove {
+                reply_san: uci_to_san(fen, &line.mv),
+                response_san: uci_to_san(&after, &best.mv),
+                reply: line.mv,
+                response: best.mv,
+                after,
+            });
+        }
+    }
+    Ok(premoves)
+}
+
+/// The planned answer when `fen` is the position after one of the
+/// `premoves` replies, so it can be shown before the new search finishes.
+/// Only the piece placement and side to move are compared, since castling
+/// and move counters may be read differently from the screen.
+pub fn ponder_hit<'a>(premoves: &'a [Premove], fen: &str) -> Option<&'a Premove> {
+    fn key(fen: &str) -> Vec<&str> {
+        fen.split_whitespace().take(2).collect()
+    }
+    premoves.iter().find(|p| key(&p.after) == key(fen))
+}
@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+#[cfg(unix)]
+use std::ffi::c_int;
+use std::process::Child;
+
+/// Scheduling priority for the engine child process.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum EnginePriority {
+    Normal,
+    #[default]
+    BelowNormal,
+    Idle,
+}
+
+impl EnginePriority {
+    pub const ALL: [EnginePriority; 3] = [
+        EnginePriority::Normal,
+        EnginePriority::BelowNormal,
+        EnginePriority::Idle,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EnginePriority::Normal => "Normal",
+            EnginePriority::BelowNormal => "Below normal",
+            EnginePriority::Idle => "Idle",
+        }
+    }
+}
+
+/// Process-level settings applied to the engine right after it is spawned,
+/// so long searches don't starve the capture and GUI threads.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct EngineProcessSettings {
+    pub priority: EnginePriority,
+    /// Bit N allows the engine to run on logical CPU N; 0 leaves it unrestricted.
+    pub affinity_mask: u64,
+}
+
+impl Default for EngineProcessSettings {
+    fn default() -> Self {
+        Self {
+            priority: EnginePriority::BelowNormal,
+            affinity_mask: 0,
+        }
+    }
+}
+
+#[cfg(windows)]
+pub fn apply(child: &Child, settings: &EngineProcessSettings) {
+    use std::os::windows::io::AsRawHandle;
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::System::Threading::{
+        SetPriorityClass, SetProcessAffinityMask, BELOW_NORMAL_PRIORITY_CLASS, IDLE_PRIORITY_CLASS,
+        NORMAL_PRIORITY_CLASS,
+    };
+
+    let handle = HANDLE(child.as_raw_handle());
+    let class = match settings.priority {
+        EnginePriority::Normal => NORMAL_PRIORITY_CLASS,
+        EnginePriority::BelowNormal => BELOW_NORMAL_PRIORITY_CLASS,
+        EnginePriority::Idle => IDLE_PRIORITY_CLASS,
+    };
+    unsafe {
+        if let Err(e) = SetPriorityClass(handle, class) {
+            println!("Failed to set engine priority: {:?}", e);
+        }
+        if settings.affinity_mask != 0 {
+            if let Err(e) = SetProcessAffinityMask(handle, settings.affinity_mask as usize) {
+                println!("Failed to set engine affinity: {:?}", e);
+            }
+        }
+    }
+}
+
+/// `PRIO_PROCESS`, the same on Linux and macOS.
+#[cfg(unix)]
+const PRIO_PROCESS: c_int = 0;
+
+#[cfg(unix)]
+extern "C" {
+    fn setpriority(which: c_int, who: u32, prio: c_int) -> c_int;
+}
+
+#[cfg(target_os = "linux")]
+extern "C" {
+    fn sched_setaffinity(pid: i32, size: usize, mask: *const u64) -> c_int;
+}
+
+/// Runs before the engine starts its search threads, which inherit the
+/// nice value and CPU set.
+#[cfg(unix)]
+pub fn apply(child: &Child, settings: &EngineProcessSettings) {
+    let nice = match settings.priority {
+        EnginePriority::Normal => 0,
+        EnginePriority::BelowNormal => 5,
+        EnginePriority::Idle => 19,
+    };
+    if nice != 0 && unsafe { setpriority(PRIO_PROCESS, child.id(), nice) } != 0 {
+        let e = std::io::Error::last_os_error();
+        println!("Failed to set engine priority: {}", e);
+    }
+    if settings.affinity_mask != 0 {
+        set_affinity(child.id(), settings.affinity_mask);
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_affinity(pid: u32, mask: u64) {
+    // A cpu_set_t of 1024 CPUs; the mask covers the first 64
+    let mut set = [0u64; 16];
+    set[0] = mask;
+    let size = std::mem::size_of_val(&set);
+    if unsafe { sched_setaffinity(pid as i32, size, set.as_ptr()) } != 0 {
+        let e = std::io::Error::last_os_error();
+        println!("Failed to set engine affinity: {}", e);
+    }
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn set_affinity(_pid: u32, _mask: u64) {
+    println!("Engine CPU affinity isn't supported on this OS");
+}
+
+#[cfg(not(any(windows, unix)))]
+pub fn apply(_child: &Child, settings: &EngineProcessSettings) {
+    if settings.priority != EnginePriority::Normal || settings.affinity_mask != 0 {
+        println!("Engine priority/affinity settings aren't supported on this OS");
+    }
+}
@@ -0,0 +1,123 @@
+use crate::engine::stockfish::{Evaluation, PvLine};
+use eframe::egui;
+
+/// Depth at which a line counts as fully searched for badge purposes.
+const TRUSTED_DEPTH: u32 = 18;
+
+/// How much to trust a suggestion, shown as a dot on its arrow.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Quality {
+    Low,
+    Medium,
+    High,
+}
+
+impl Quality {
+    pub fn color(&self) -> egui::Color32 {
+        match self {
+            Quality::Low => egui::Color32::from_rgb(220, 60, 60),
+            Quality::Medium => egui::Color32::from_rgb(230, 190, 40),
+            Quality::High => egui::Color32::from_rgb(60, 200, 90),
+        }
+    }
+
+    /// Combines depth reached, how long the line's move has been stable
+    /// during the search, and whether the previous analysis of the same
+    /// position suggested it too (`None` when there was none).
+    pub fn assess(line: &PvLine, agreed: Option<bool>) -> Self {
+        let depth = (line.depth as f32 / TRUSTED_DEPTH as f32).min(1.0);
+        let stability = if line.depth == 0 {
+            0.0
+        } else {
+            (line.depth - line.stable_since.min(line.depth)) as f32 / line.depth as f32
+        };
+        let agreement = match agreed {
+            Some(true) => 1.0,
+            Some(false) => 0.0,
+            None => 0.5,
+        };
+        let score = 0.4 * depth + 0.3 * stability + 0.3 * agreement;
+        if score >= 0.75 {
+            Quality::High
+        } else if score >= 0.45 {
+            Quality::Medium
+        } else {
+            Quality::Low
+        }
+    }
+}
+
+/// How a suggested move compares to the best line, by centipawn loss.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveClass {
+    Best,
+    Good,
+    Inaccuracy,
+    Mistake,
+}
+
+impl MoveClass {
+    pub const ALL: [MoveClass; 4] = [
+        MoveClass::Best,
+        MoveClass::Good,
+        MoveClass::Inaccuracy,
+        MoveClass::Mistake,
+    ];
+
+    /// Classifies each line against the best score among them. Lines
+    /// without a score count as best so they are not flagged wrongly.
+    pub fn classify(evals: &[Option<Evaluation>]) -> Vec<Self> {
+        let best = evals
+            .iter()
+            .flatten()
+            .map(|e| e.as_centipawns())
+            .max()
+            .unwrap_or(0);
+        evals
+            .iter()
+            .map(|e| match e {
+                Some(e) => MoveClass::from_loss(best.saturating_sub(e.as_centipawns())),
+                None => MoveClass::Best,
+            })
+            .collect()
+    }
+
+    /// Class of a move that gave up `loss` centipawns against the best.
+    pub fn from_loss(loss: i32) -> Self {
+        match loss {
+            ..=20 => MoveClass::Best,
+            21..=80 => MoveClass::Good,
+            81..=200 => MoveClass::Inaccuracy,
+            _ => MoveClass::Mistake,
+        }
+    }
+
+    /// PGN annotation glyph: `$2` (?) for an inaccuracy and `$4` (??) for a
+    /// mistake, since losing more than two pawns is a blunder by any
+    /// reader's standard.
+    pub fn nag(&self) -> Option<u8> {
+        match self {
+            MoveClass::Best | MoveClass::Good => None,
+            MoveClass::Inaccuracy => Some(2),
+            MoveClass::Mistake => Some(4),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            MoveClass::Best => "Best",
+            MoveClass::Good => "Good (≤0.8)",
+            MoveClass::Inaccuracy => "Inaccuracy (≤2.0)",
+            MoveClass::Mistake => "Mistake",
+        }
+    }
+
+    pub fn color(&self) -> egui::Color32 {
+        match self {
+            MoveClass::Best => egui::Color32::from_rgb(0, 255, 0),
+            MoveClass::Good => egui::Color32::from_rgb(150, 230, 60),
+            MoveClass::Inaccuracy => egui::Color32::from_rgb(255, 190, 0),
+            MoveClass::Mistake => egui::Color32::from_rgb(255, 70, 50),
+        }
+    }
+}
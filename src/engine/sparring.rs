@@ -0,0 +1,53 @@
+use crate::engine::stockfish::PvLine;
+
+/// Skill Level range used while sparring (Stockfish accepts 0..=20).
+const SKILL_RANGE: (u32, u32) = (8, 16);
+/// Lines within this many centipawns of the best one count as near-equal.
+const NEAR_EQUAL_CP: i32 = 30;
+
+/// "Sparring" preset: varies the engine's Skill Level between searches and
+/// picks randomly among near-equal lines, so practice games against
+/// yourself don't always follow the same top move. Seeded per frame (see
+/// `recording::frame_seed`) so a recorded session replays the same choices.
+pub struct Sparring {
+    state: u64,
+}
+
+impl Sparring {
+    /// Sparring with a fixed seed, so its choices can be reproduced.
+    pub fn with_seed(seed: u64) -> Self {
+        Self { state: seed | 1 }
+    }
+
+    fn next(&mut self) -> u64 {
+        // xorshift64
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// Skill Level to use for the next search.
+    pub fn skill_level(&mut self) -> u32 {
+        let (lo, hi) = SKILL_RANGE;
+        lo + (self.next() % (hi - lo + 1) as u64) as u32
+    }
+
+    /// Moves a randomly chosen near-equal line to the front.
+    pub fn pick(&mut self, lines: &mut [PvLine]) {
+        let Some(best) = lines.first().and_then(|l| l.eval) else {
+            return;
+        };
+        let candidates = lines
+            .iter()
+            .take_while(|l| {
+                l.eval
+                    .is_some_and(|e| best.as_centipawns() - e.as_centipawns() <= NEAR_EQUAL_CP)
+            })
+            .count();
+        if candidates > 1 {
+            let chosen = (self.next() % candidates as u64) as usize;
+            lines[..=chosen].rotate_right(1);
+        }
+    }
+}
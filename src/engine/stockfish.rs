@@ -1,33 +1,447 @@
+use crate::engine::diagnose::EngineProblem;
+use crate::engine::options::EngineOptions;
+use crate::engine::process::{self, EngineProcessSettings};
 use anyhow::{anyhow, Result};
+use crossbeam_channel::{unbounded, Receiver, RecvTimeoutError};
+use serde::{Deserialize, Serialize};
 use std::io::{BufRead, BufReader, Write};
-use std::process::{Child, Command, Stdio};
+use std::process::{Child, ChildStdin, Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
 use std::time::{Duration, Instant};
 
+/// Engine score, from the point of view of the side to move unless
+/// converted with `for_white`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Evaluation {
+    Centipawns(i32),
+    /// Moves to mate; negative when the side is getting mated.
+    Mate(i32),
+}
+
+impl Evaluation {
+    /// Parses the `score cp N` / `score mate N` part of a UCI info line.
+    pub fn from_info_line(line: &str) -> Option<Self> {
+        let mut tokens = line.split_whitespace();
+        tokens.find(|t| *t == "score")?;
+        let kind = tokens.next()?;
+        let value = tokens.next()?.parse().ok()?;
+        match kind {
+            "cp" => Some(Evaluation::Centipawns(value)),
+            "mate" => Some(Evaluation::Mate(value)),
+            _ => None,
+        }
+    }
+
+    pub fn for_white(self, white_to_move: bool) -> Self {
+        if white_to_move {
+            return self;
+        }
+        match self {
+            Evaluation::Centipawns(cp) => Evaluation::Centipawns(-cp),
+            Evaluation::Mate(n) => Evaluation::Mate(-n),
+        }
+    }
+
+    /// Comparable score where mates rank beyond any material advantage.
+    pub fn as_centipawns(&self) -> i32 {
+        match *self {
+            Evaluation::Centipawns(cp) => cp,
+            Evaluation::Mate(n) if n >= 0 => 100_000 - n,
+            Evaluation::Mate(n) => -100_000 - n,
+        }
+    }
+
+    pub fn display(&self) -> String {
+        match self {
+            Evaluation::Centipawns(cp) => format!("{:+.2}", *cp as f32 / 100.0),
+            Evaluation::Mate(n) => format!("M{}", n),
+        }
+    }
+}
+
+/// What ends a search: the `go depth`, `go movetime` or `go nodes` limit.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum SearchMode {
+    #[default]
+    Depth,
+    MoveTime,
+    Nodes,
+    /// `go infinite`, with the latest lines shown as they improve.
+    Infinite,
+}
+
+impl SearchMode {
+    pub const ALL: [SearchMode; 4] = [
+        SearchMode::Depth,
+        SearchMode::MoveTime,
+        SearchMode::Nodes,
+        SearchMode::Infinite,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SearchMode::Depth => "Depth",
+            SearchMode::MoveTime => "Move time",
+            SearchMode::Nodes => "Nodes",
+            SearchMode::Infinite => "Infinite",
+        }
+    }
+}
+
+/// A concrete search limit for one `go` command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchLimit {
+    Depth(u32),
+    MoveTime {
+        ms: u32,
+    },
+    Nodes(u64),
+    /// Runs until stopped; see `Stockfish::start_infinite`.
+    Infinite,
+}
+
+impl SearchLimit {
+    pub fn new(mode: SearchMode, depth: u32, time_ms: u32, nodes: u64) -> Self {
+        match mode {
+            SearchMode::Depth => SearchLimit::Depth(depth.max(1)),
+            SearchMode::MoveTime => SearchLimit::MoveTime {
+                ms: time_ms.max(10),
+            },
+            SearchMode::Nodes => SearchLimit::Nodes(nodes.max(1)),
+            SearchMode::Infinite => SearchLimit::Infinite,
+        }
+    }
+
+    /// Two thirds of the limit, for cheaper side searches and degraded
+    /// performance levels. An infinite search becomes a one second one.
+    pub fn reduced(self) -> Self {
+        match self {
+            SearchLimit::Depth(d) => SearchLimit::Depth((d * 2 / 3).max(1)),
+            SearchLimit::MoveTime { ms } => SearchLimit::MoveTime {
+                ms: (ms * 2 / 3).max(10),
+            },
+            SearchLimit::Nodes(n) => SearchLimit::Nodes((n * 2 / 3).max(1)),
+            SearchLimit::Infinite => SearchLimit::MoveTime { ms: 1000 },
+        }
+    }
+
+    /// The limit times `ratio` (0..=1), for searches that need less
+    /// precision than the main one. An infinite search becomes a one
+    /// second one first.
+    pub fn scaled(self, ratio: f32) -> Self {
+        let ratio = ratio.clamp(0.0, 1.0);
+        match self {
+            SearchLimit::Depth(d) => SearchLimit::Depth(((d as f32 * ratio).round() as u32).max(1)),
+            SearchLimit::MoveTime { ms } => SearchLimit::MoveTime {
+                ms: ((ms as f32 * ratio) as u32).max(10),
+            },
+            SearchLimit::Nodes(n) => SearchLimit::Nodes(((n as f64 * ratio as f64) as u64).max(1)),
+            SearchLimit::Infinite => SearchLimit::MoveTime { ms: 1000 }.scaled(ratio),
+        }
+    }
+
+    fn go_command(&self) -> String {
+        match self {
+            SearchLimit::Depth(d) => format!("go depth {}", d),
+            SearchLimit::MoveTime { ms } => format!("go movetime {}", ms),
+            SearchLimit::Nodes(n) => format!("go nodes {}", n),
+            SearchLimit::Infinite => "go infinite".to_string(),
+        }
+    }
+
+    /// How long to wait for `bestmove` before stopping the search. Depth
+    /// and node searches have no known duration, so they get a generous
+    /// cap; a timed search ends on its own shortly after `ms`. A blocking
+    /// `analyze` with an infinite limit is stopped after the depth cap.
+    fn timeout(&self) -> Duration {
+        match self {
+            SearchLimit::Depth(_) | SearchLimit::Infinite => Duration::from_secs(5),
+            SearchLimit::MoveTime { ms } => Duration::from_millis(*ms as u64 + 2000),
+            SearchLimit::Nodes(n) => Duration::from_secs(5 + n / 1_000_000),
+        }
+    }
+}
+
+/// One MultiPV line: its first move and score for the side to move.
+#[derive(Debug, Clone)]
+pub struct PvLine {
+    pub mv: String,
+    /// The whole principal variation, starting with `mv`.
+    pub pv: Vec<String>,
+    pub eval: Option<Evaluation>,
+    /// Search depth of the last info line for this index.
+    pub depth: u32,
+    /// Depth from which `mv` has been this line's first move.
+    pub stable_since: u32,
+}
+
+/// Latest output of the running (or last) search, kept up to date by the
+/// reader thread as info lines arrive.
+#[derive(Debug, Clone, Default)]
+pub struct AnalysisState {
+    /// Position being searched.
+    pub fen: String,
+    /// Latest line per MultiPV index (1-based in UCI).
+    pv: Vec<Option<PvLine>>,
+    /// Deepest depth reported so far.
+    pub depth: u32,
+    /// Latest search speed reported, in nodes per second.
+    pub nps: Option<u64>,
+    /// Cleared when `bestmove` arrives.
+    pub searching: bool,
+    /// The engine's final choice, once `bestmove` arrived. Differs from the
+    /// first line when strength is limited.
+    pub best_move: Option<String>,
+    /// Info lines with a pv were seen, and whether any carried `multipv`.
+    pv_seen: bool,
+    multipv_seen: bool,
+}
+
+impl AnalysisState {
+    fn begin(fen: &str, lines: u32) -> Self {
+        Self {
+            fen: fen.to_string(),
+            pv: vec![None; lines.max(1) as usize],
+            depth: 0,
+            nps: None,
+            searching: true,
+            best_move: None,
+            pv_seen: false,
+            multipv_seen: false,
+        }
+    }
+
+    /// Whether this search showed MultiPV support, once it has asked for
+    /// several lines and produced some. Engines that ignore the option
+    /// print plain `info .. pv` lines without a `multipv` index.
+    fn multipv_support(&self) -> Option<bool> {
+        (self.pv.len() > 1 && self.pv_seen).then_some(self.multipv_seen)
+    }
+
+    /// Final (or so far) first move and score of each line, best first.
+    pub fn lines(&self) -> Vec<PvLine> {
+        self.pv.iter().flatten().cloned().collect()
+    }
+
+    /// Folds an `info depth .. multipv .. score .. pv ..` line into the state.
+    fn update(&mut self, line: &str) {
+        if !line.starts_with("info") {
+            return;
+        }
+        // Speed also comes on info lines without a pv
+        if let Some(nps) = line
+            .split_whitespace()
+            .skip_while(|t| *t != "nps")
+            .nth(1)
+            .and_then(|n| n.parse().ok())
+        {
+            self.nps = Some(nps);
+        }
+        if !line.contains(" depth ") || !line.contains(" pv ") {
+            return;
+        }
+        let value_after = |key: &str| {
+            line.split_whitespace()
+                .skip_while(|t| *t != key)
+                .nth(1)
+                .and_then(|n| n.parse::<u32>().ok())
+        };
+        let index = value_after("multipv").unwrap_or(1) as usize;
+        let depth = value_after("depth").unwrap_or(0);
+        let pv: Vec<String> = line
+            .split(" pv ")
+            .nth(1)
+            .map(|pv| pv.split_whitespace().map(str::to_string).collect())
+            .unwrap_or_default();
+        let Some(best_move) = pv.first().cloned() else {
+            return;
+        };
+        self.pv_seen = true;
+        self.multipv_seen |= line.split_whitespace().any(|t| t == "multipv");
+        if index < 1 || index > self.pv.len() {
+            return;
+        }
+        let stable_since = match &self.pv[index - 1] {
+            Some(prev) if prev.mv == best_move => prev.stable_since,
+            _ => depth,
+        };
+        self.pv[index - 1] = Some(PvLine {
+            mv: best_move,
+            pv,
+            eval: Evaluation::from_info_line(line),
+            depth,
+            stable_since,
+        });
+        self.depth = self.depth.max(depth);
+    }
+}
+
+/// An option advertised by the engine in its `uci` handshake.
+#[derive(Debug, Clone)]
+pub struct UciOption {
+    pub name: String,
+    pub kind: String,
+    pub min: Option<i64>,
+    pub max: Option<i64>,
+}
+
+impl UciOption {
+    /// Parses `option name <name> type <kind> [default ..] [min ..] [max ..]`.
+    fn parse(line: &str) -> Option<Self> {
+        let rest = line.trim().strip_prefix("option name ")?;
+        let (name, rest) = rest.split_once(" type ")?;
+        let mut tokens = rest.split_whitespace();
+        let kind = tokens.next()?.to_string();
+        let mut option = UciOption {
+            name: name.trim().to_string(),
+            kind,
+            min: None,
+            max: None,
+        };
+        while let Some(token) = tokens.next() {
+            match token {
+                "min" => option.min = tokens.next().and_then(|v| v.parse().ok()),
+                "max" => option.max = tokens.next().and_then(|v| v.parse().ok()),
+                _ => {}
+            }
+        }
+        Some(option)
+    }
+
+    /// Checks `value` against the advertised type and range.
+    fn validate(&self, value: &str) -> Result<(), String> {
+        if self.kind == "spin" {
+            let v: i64 = value
+                .parse()
+                .map_err(|_| format!("{}: '{}' is not a number", self.name, value))?;
+            if self.min.is_some_and(|min| v < min) || self.max.is_some_and(|max| v > max) {
+                return Err(format!(
+                    "{}: {} is outside {}..{}",
+                    self.name,
+                    v,
+                    self.min.unwrap_or(i64::MIN),
+                    self.max.unwrap_or(i64::MAX)
+                ));
+            }
+        } else if self.kind == "check" && value != "true" && value != "false" {
+            return Err(format!("{}: expected true or false", self.name));
+        }
+        Ok(())
+    }
+}
+
+/// A UCI engine process. A reader thread owns its stdout: search info goes
+/// straight into the shared `AnalysisState`, every other line is passed on
+/// over `lines` for the command/response exchanges, so reads never block
+/// past their timeout and a long search can be watched while it runs.
 pub struct Stockfish {
     child: Child,
-    path: String,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+    state: Arc<Mutex<AnalysisState>>,
+    options: Vec<UciOption>,
+    option_errors: Vec<String>,
+    /// `None` until known from the handshake or a multi-line search.
+    multipv: Option<bool>,
 }
 
 impl Stockfish {
-    pub fn new(path: &str) -> Result<Self> {
-        let child = Command::new(path)
+    /// Starts the engine and runs the UCI handshake. Recognizable failures
+    /// (wrong architecture, unsupported CPU, not an engine) are returned as
+    /// an `EngineProblem` inside the error.
+    pub fn new(
+        path: &str,
+        process: &EngineProcessSettings,
+        options: &EngineOptions,
+    ) -> Result<Self> {
+        let mut child = Command::new(path)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
-            .spawn()?;
+            .spawn()
+            .map_err(|e| match EngineProblem::from_spawn_error(&e, path) {
+                Some(problem) => anyhow::Error::new(problem),
+                None => e.into(),
+            })?;
+        process::apply(&child, process);
+
+        let stdin = child.stdin.take().ok_or(anyhow!("Failed to open stdin"))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or(anyhow!("Failed to open stdout"))?;
+        let state = Arc::new(Mutex::new(AnalysisState::default()));
+        let (lines_tx, lines) = unbounded();
+        let reader_state = state.clone();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(stdout);
+            loop {
+                let mut line = String::new();
+                match reader.read_line(&mut line) {
+                    Ok(0) | Err(_) => break,
+                    Ok(_) => {}
+                }
+                let line = line.trim_end().to_string();
+                if line.starts_with("info") && !line.starts_with("info string") {
+                    reader_state.lock().unwrap().update(&line);
+                    continue;
+                }
+                if line.starts_with("bestmove") {
+                    let mut state = reader_state.lock().unwrap();
+                    state.searching = false;
+                    state.best_move = line
+                        .split_whitespace()
+                        .nth(1)
+                        .filter(|m| *m != "(none)")
+                        .map(str::to_string);
+                }
+                if lines_tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
 
         let mut sf = Self {
             child,
-            path: path.to_string(),
+            stdin,
+            lines,
+            state,
+            options: Vec::new(),
+            option_errors: Vec::new(),
+            multipv: None,
         };
 
         // Initial handshake
-        sf.send("uci")?;
-        sf.wait_for("uciok", Duration::from_secs(5))?;
+        let handshake = sf
+            .send("uci")
+            .and_then(|_| sf.read_until("uciok", Duration::from_secs(5)));
+        let handshake = match handshake {
+            Ok(lines) => lines,
+            Err(e) => {
+                // Give a crashing binary a moment to report its exit status
+                std::thread::sleep(Duration::from_millis(100));
+                return Err(match sf.child.try_wait() {
+                    Ok(Some(status)) => EngineProblem::from_exit(status).into(),
+                    _ => e,
+                });
+            }
+        };
+        if !handshake.iter().any(|l| l.starts_with("id name")) {
+            return Err(EngineProblem::NotUci.into());
+        }
+        sf.options = handshake
+            .iter()
+            .filter_map(|l| UciOption::parse(l))
+            .collect();
+        if !sf.options.is_empty() && !sf.has_option("MultiPV") {
+            sf.multipv = Some(false);
+        }
 
-        // Optimize for your 12-core i5-12500H CPU
-        sf.set_option("Threads", "8")?;
-        sf.set_option("Hash", "256")?;
+        sf.option_errors = sf.apply_engine_options(options)?;
+        for err in &sf.option_errors {
+            println!("Engine option error: {}", err);
+        }
 
         println!("Stockfish initialized successfully");
         Ok(sf)
@@ -37,107 +451,169 @@ impl Stockfish {
         self.send(&format!("setoption name {} value {}", name, value))
     }
 
-    pub fn stop(&mut self) -> Result<()> {
-        self.send("stop")?;
-        // Clear any pending output
-        let mut reader = BufReader::new(self.child.stdout.as_mut().ok_or(anyhow!("No stdout"))?);
-        let mut line = String::new();
-        // We don't want to block here, so we just do a quick read if possible
-        // Actually, "isready" is better to sync
-        self.send("isready")?;
-        self.wait_for("readyok", Duration::from_secs(2))?;
-        Ok(())
+    fn has_option(&self, name: &str) -> bool {
+        self.options
+            .iter()
+            .any(|o| o.name.eq_ignore_ascii_case(name))
     }
 
-    pub fn analyze(&mut self, fen: &str, depth: u32, lines: u32) -> Result<Vec<String>> {
-        // Sync engine
-        self.send("isready")?;
-        self.wait_for("readyok", Duration::from_secs(2))?;
+    /// Whether the engine can search several lines at once; `None` until a
+    /// search has shown it. Without it every search is a single line.
+    pub fn supports_multipv(&self) -> Option<bool> {
+        self.multipv
+    }
 
-        self.set_option("MultiPV", &lines.to_string())?;
-        self.send(&format!("position fen {}", fen))?;
-        self.send(&format!("go depth {}", depth))?;
+    /// Records what the last search showed about MultiPV support.
+    fn learn_multipv(&mut self) {
+        if self.multipv.is_none() {
+            self.multipv = self.state.lock().unwrap().multipv_support();
+            if self.multipv == Some(false) {
+                println!("Engine ignores MultiPV; showing a single line");
+            }
+        }
+    }
 
-        let mut moves = Vec::new();
-        let start_time = Instant::now();
-        let timeout = Duration::from_secs(5); // Maximum 5 seconds for any scan
+    /// Options that failed validation when the engine was started.
+    pub fn option_errors(&self) -> &[String] {
+        &self.option_errors
+    }
 
-        // Get a mutable reference to stdout
-        let stdout = self
-            .child
-            .stdout
-            .as_mut()
-            .ok_or(anyhow!("Failed to open stdout"))?;
-        let mut reader = BufReader::new(stdout);
+    /// Sets options after checking them against what the engine advertised,
+    /// then syncs with `isready` and collects any complaints the engine
+    /// printed. Returns one message per option that did not apply.
+    pub fn apply_options(&mut self, options: &[(&str, &str)]) -> Result<Vec<String>> {
+        let mut errors = Vec::new();
+        for (name, value) in options {
+            let known = self
+                .options
+                .iter()
+                .find(|o| o.name.eq_ignore_ascii_case(name));
+            match known {
+                None if !self.options.is_empty() => {
+                    errors.push(format!("{}: not supported by this engine", name));
+                    continue;
+                }
+                Some(option) => {
+                    if let Err(e) = option.validate(value) {
+                        errors.push(e);
+                        continue;
+                    }
+                }
+                None => {}
+            }
+            self.set_option(name, value)?;
+        }
 
-        loop {
-            if start_time.elapsed() > timeout {
-                println!("WARNING: Stockfish analysis timed out!");
-                // Force stop if it hangs
-                let _ = self.send("stop");
-                break;
+        self.send("isready")?;
+        for line in self.read_until("readyok", Duration::from_secs(2))? {
+            if line.contains("No such option") || line.starts_with("info string ERROR") {
+                errors.push(line.trim().to_string());
             }
+        }
+        Ok(errors)
+    }
 
-            let mut line = String::new();
-            // Note: read_line is blocking. In a perfect world we'd use async or non-blocking
-            // but for UCI depth-based search it usually responds fast.
-            reader.read_line(&mut line)?;
+    /// Sends the settings window's engine options; see `apply_options`.
+    pub fn apply_engine_options(&mut self, options: &EngineOptions) -> Result<Vec<String>> {
+        let uci = options.uci();
+        let pairs: Vec<(&str, &str)> = uci.iter().map(|(n, v)| (*n, v.as_str())).collect();
+        self.apply_options(&pairs)
+    }
 
-            if line.is_empty() {
-                break;
-            }
+    /// Stops a running search and waits for its `bestmove`.
+    pub fn stop(&mut self) -> Result<()> {
+        if !self.state.lock().unwrap().searching {
+            return Ok(());
+        }
+        self.send("stop")?;
+        self.wait_for("bestmove", Duration::from_secs(2))
+    }
 
-            if line.contains("bestmove") {
-                break;
-            }
+    /// Starts the search for `fen` and returns right away; with
+    /// `SearchLimit::Infinite` it runs until `stop` or the next search.
+    /// Poll `analysis` for the lines found so far.
+    pub fn start(&mut self, fen: &str, limit: SearchLimit, lines: u32) -> Result<()> {
+        self.stop()?;
+        self.learn_multipv();
+        // Drop leftovers of earlier exchanges, then sync
+        while self.lines.try_recv().is_ok() {}
+        self.send("isready")?;
+        self.wait_for("readyok", Duration::from_secs(2))?;
 
-            if line.contains("info depth") && line.contains(" pv ") {
-                // Parse the move
-                if let Some(pv_part) = line.split(" pv ").nth(1) {
-                    let best_move = pv_part.split_whitespace().next().unwrap_or("").to_string();
-                    if !best_move.is_empty() && !moves.contains(&best_move) {
-                        moves.push(best_move);
-                    }
-                }
-            }
+        let lines = if self.multipv == Some(false) {
+            1
+        } else {
+            self.set_option("MultiPV", &lines.to_string())?;
+            lines
+        };
+        *self.state.lock().unwrap() = AnalysisState::begin(fen, lines);
+        self.send(&format!("position fen {}", fen))?;
+        self.send(&limit.go_command())
+    }
+
+    /// Shorthand for an infinite `start`.
+    pub fn start_infinite(&mut self, fen: &str, lines: u32) -> Result<()> {
+        self.start(fen, SearchLimit::Infinite, lines)
+    }
+
+    /// Snapshot of the current or last search.
+    pub fn analysis(&self) -> AnalysisState {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// Searches until `limit` with `lines` MultiPV lines and returns the
+    /// final first move and score of each line, best first.
+    pub fn analyze(&mut self, fen: &str, limit: SearchLimit, lines: u32) -> Result<Vec<PvLine>> {
+        self.start(fen, limit, lines)?;
+        if self.wait_for("bestmove", limit.timeout()).is_err() {
+            println!("WARNING: Stockfish analysis timed out!");
+            self.stop()?;
         }
+        self.learn_multipv();
+        Ok(self.analysis().lines())
+    }
 
-        // Keep only the most recent N moves from the info lines (MultiPV)
-        // PV lines come in order of PV 1, PV 2, etc. in the final depth
-        Ok(moves.into_iter().rev().take(lines as usize).collect())
+    /// Searches a single line to `depth` and returns the final score from
+    /// the side to move's point of view.
+    pub fn evaluate(&mut self, fen: &str, depth: u32) -> Result<Option<Evaluation>> {
+        let lines = self.analyze(fen, SearchLimit::Depth(depth), 1)?;
+        Ok(lines.first().and_then(|l| l.eval))
+    }
+
+    /// How the process ended, once it has.
+    pub fn exit_status(&mut self) -> Option<ExitStatus> {
+        self.child.try_wait().ok().flatten()
     }
 
     fn send(&mut self, msg: &str) -> Result<()> {
-        let stdin = self
-            .child
-            .stdin
-            .as_mut()
-            .ok_or(anyhow!("Failed to open stdin"))?;
-        writeln!(stdin, "{}", msg)?;
-        stdin.flush()?;
+        writeln!(self.stdin, "{}", msg)?;
+        self.stdin.flush()?;
         Ok(())
     }
 
     fn wait_for(&mut self, expected: &str, timeout: Duration) -> Result<()> {
-        let start = Instant::now();
-        let stdout = self.child.stdout.as_mut().ok_or(anyhow!("No stdout"))?;
-        let mut reader = BufReader::new(stdout);
+        self.read_until(expected, timeout).map(|_| ())
+    }
 
+    /// Reads lines until one contains `expected`, returning the lines before
+    /// it. Search info lines are not included; see `analysis`.
+    fn read_until(&mut self, expected: &str, timeout: Duration) -> Result<Vec<String>> {
+        let deadline = Instant::now() + timeout;
+        let mut lines = Vec::new();
         loop {
-            if start.elapsed() > timeout {
-                return Err(anyhow!("Timed out waiting for {}", expected));
-            }
-            let mut line = String::new();
-            reader.read_line(&mut line)?;
-            if line.contains(expected) {
-                return Ok(());
-            }
-            if line.is_empty() && start.elapsed() > Duration::from_millis(100) {
-                // Process might have died
-                return Err(anyhow!(
-                    "Engine stream closed while waiting for {}",
-                    expected
-                ));
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.lines.recv_timeout(remaining) {
+                Ok(line) if line.contains(expected) => return Ok(lines),
+                Ok(line) => lines.push(line),
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(anyhow!("Timed out waiting for {}", expected))
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(anyhow!(
+                        "Engine stream closed while waiting for {}",
+                        expected
+                    ))
+                }
             }
         }
     }
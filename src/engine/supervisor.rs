@@ -0,0 +1,239 @@
+use crate::engine::diagnose::EngineProblem;
+use crate::engine::options::EngineOptions;
+use crate::engine::process::EngineProcessSettings;
+use crate::engine::stockfish::Stockfish;
+use anyhow::Result;
+use std::time::{Duration, Instant};
+
+/// First restart delay; doubled after each failure in a row.
+const BACKOFF_START: Duration = Duration::from_secs(1);
+const BACKOFF_MAX: Duration = Duration::from_secs(30);
+/// Failures in a row before giving up until a manual restart.
+const MAX_FAILURES: u32 = 6;
+/// An engine that ran this long before failing starts a new backoff series.
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+
+/// What the overlay shows about the engine process.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum EngineHealth {
+    #[default]
+    Starting,
+    Running,
+    /// Crashed or stopped answering; the next start is at `retry_at`.
+    Restarting {
+        attempt: u32,
+        retry_at: Instant,
+    },
+    /// Not retried until restarted from the settings window.
+    Failed(String),
+}
+
+impl EngineHealth {
+    pub fn label(&self) -> String {
+        match self {
+            EngineHealth::Starting => "Engine starting".to_string(),
+            EngineHealth::Running => "Engine running".to_string(),
+            EngineHealth::Restarting { attempt, retry_at } => format!(
+                "Engine crashed, restart #{} in {}s",
+                attempt,
+                retry_at
+                    .saturating_duration_since(Instant::now())
+                    .as_secs_f32()
+                    .ceil()
+            ),
+            EngineHealth::Failed(reason) => format!("Engine failed: {}", reason),
+        }
+    }
+}
+
+/// Owns the engine process: starts it with the configured options, notices
+/// when it exits or a command fails, and restarts it with exponential
+/// backoff. Callers get the engine only while it is running.
+pub struct EngineSupervisor {
+    path: String,
+    process: EngineProcessSettings,
+    options: EngineOptions,
+    engine: Option<Stockfish>,
+    health: EngineHealth,
+    failures: u32,
+    started_at: Instant,
+    /// Successful starts so far, to notice a fresh process.
+    starts: u64,
+    /// Skill Level currently set; sparring lowers it between searches.
+    skill: u32,
+    option_errors: Vec<String>,
+    problem: Option<EngineProblem>,
+}
+
+impl EngineSupervisor {
+    pub fn new(path: &str, process: EngineProcessSettings, options: EngineOptions) -> Self {
+        let mut supervisor = Self {
+            path: path.to_string(),
+            process,
+            skill: options.skill_level,
+            options,
+            engine: None,
+            health: EngineHealth::Starting,
+            failures: 0,
+            started_at: Instant::now(),
+            starts: 0,
+            option_errors: Vec::new(),
+            problem: None,
+        };
+        supervisor.launch();
+        supervisor
+    }
+
+    fn launch(&mut self) {
+        self.health = EngineHealth::Starting;
+        self.started_at = Instant::now();
+        match Stockfish::new(&self.path, &self.process, &self.options) {
+            Ok(sf) => {
+                self.option_errors = sf.option_errors().to_vec();
+                self.skill = self.options.skill_level;
+                self.engine = Some(sf);
+                self.health = EngineHealth::Running;
+                self.problem = None;
+                self.starts += 1;
+            }
+            Err(e) => {
+                self.problem = e.downcast_ref::<EngineProblem>().cloned();
+                let reason = match &self.problem {
+                    Some(problem) => problem.to_string(),
+                    None => format!("{:#}", e),
+                };
+                self.fail(reason);
+            }
+        }
+    }
+
+    /// Drops the engine and schedules the next start, or gives up when the
+    /// binary itself is unusable or it kept failing.
+    fn fail(&mut self, reason: String) {
+        self.engine = None;
+        if self.started_at.elapsed() >= STABLE_AFTER {
+            self.failures = 0;
+        }
+        self.failures += 1;
+        // A missing or incompatible binary won't start on a retry
+        let permanent = self
+            .problem
+            .as_ref()
+            .is_some_and(|p| !matches!(p, EngineProblem::Exited(_)));
+        if permanent || self.failures > MAX_FAILURES {
+            println!("Engine failed, not restarting: {}", reason);
+            self.health = EngineHealth::Failed(reason);
+            return;
+        }
+        let delay = (BACKOFF_START * 2u32.pow(self.failures - 1)).min(BACKOFF_MAX);
+        println!(
+            "Engine failed ({}), restarting in {:?} (attempt {})",
+            reason, delay, self.failures
+        );
+        self.health = EngineHealth::Restarting {
+            attempt: self.failures,
+            retry_at: Instant::now() + delay,
+        };
+    }
+
+    /// Checks the process and restarts it once its backoff has passed. Call
+    /// once per worker loop.
+    pub fn poll(&mut self) {
+        if let Some(sf) = &mut self.engine {
+            if let Some(status) = sf.exit_status() {
+                self.fail(format!("process exited ({})", status));
+            }
+        }
+        if let EngineHealth::Restarting { retry_at, .. } = self.health {
+            if Instant::now() >= retry_at {
+                self.launch();
+            }
+        }
+    }
+
+    /// The engine, while it is running.
+    pub fn engine(&mut self) -> Option<&mut Stockfish> {
+        self.engine.as_mut()
+    }
+
+    /// A command to the engine failed; treat the process as dead.
+    pub fn report_error(&mut self, e: &anyhow::Error) {
+        self.fail(format!("{:#}", e));
+    }
+
+    /// Starts a fresh process right away with a clean failure count (memory
+    /// reclaim, or the user retrying a failed engine).
+    pub fn restart(&mut self) {
+        self.engine = None;
+        self.failures = 0;
+        self.launch();
+    }
+
+    pub fn health(&self) -> &EngineHealth {
+        &self.health
+    }
+
+    pub fn starts(&self) -> u64 {
+        self.starts
+    }
+
+    /// Options that failed validation at the last start or change.
+    pub fn option_errors(&self) -> &[String] {
+        &self.option_errors
+    }
+
+    /// Why the binary could not be started, when recognized.
+    pub fn problem(&self) -> Option<&EngineProblem> {
+        self.problem.as_ref()
+    }
+
+    /// Process settings used from the next start on.
+    pub fn set_process(&mut self, process: EngineProcessSettings) {
+        self.process = process;
+    }
+
+    pub fn options(&self) -> &EngineOptions {
+        &self.options
+    }
+
+    /// Keeps `options` for later starts and applies them to the running
+    /// engine.
+    pub fn apply_options(&mut self, options: EngineOptions) -> Result<()> {
+        self.options = options;
+        self.skill = self.options.skill_level;
+        let Some(sf) = &mut self.engine else {
+            return Ok(());
+        };
+        // Options can't change under a running search
+        let result = sf
+            .stop()
+            .and_then(|_| sf.apply_engine_options(&self.options));
+        match result {
+            Ok(errors) => {
+                self.option_errors = errors;
+                Ok(())
+            }
+            Err(e) => {
+                self.report_error(&e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Sets the Skill Level for the next search if it differs.
+    pub fn set_skill(&mut self, skill: u32) {
+        if skill == self.skill {
+            return;
+        }
+        let Some(sf) = &mut self.engine else {
+            return;
+        };
+        let result = sf
+            .stop()
+            .and_then(|_| sf.set_option("Skill Level", &skill.to_string()));
+        match result {
+            Ok(()) => self.skill = skill,
+            Err(e) => self.report_error(&e),
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use crate::engine::stockfish::SearchLimit;
+use std::time::Duration;
+
+/// Below this much time left, searches are cut down to a share of it.
+const SCRAMBLE: Duration = Duration::from_secs(60);
+/// Above this much time left, searches are allowed to go deeper.
+const PLENTY: Duration = Duration::from_secs(300);
+/// In a scramble each search gets this share of the remaining time, as if
+/// that many moves were still to play.
+const SCRAMBLE_MOVES: u32 = 30;
+const SCRAMBLE_MIN_MS: u32 = 50;
+const SCRAMBLE_MAX_MS: u32 = 2000;
+/// With plenty of time, a search never takes more than this share of it.
+const PLENTY_SHARE: u32 = 40;
+
+/// Fits the configured search to the clock: in a time scramble it becomes
+/// a short timed search, with plenty of time left it goes deeper. An
+/// infinite search is left alone since the overlay stops it anyway.
+pub fn budget(limit: SearchLimit, remaining: Duration) -> SearchLimit {
+    let remaining_ms = remaining.as_millis().min(u32::MAX as u128) as u32;
+    if remaining < SCRAMBLE {
+        let ms = (remaining_ms / SCRAMBLE_MOVES).clamp(SCRAMBLE_MIN_MS, SCRAMBLE_MAX_MS);
+        return match limit {
+            SearchLimit::Infinite => limit,
+            SearchLimit::MoveTime { ms: configured } => SearchLimit::MoveTime {
+                ms: configured.min(ms),
+            },
+            _ => SearchLimit::MoveTime { ms },
+        };
+    }
+    if remaining > PLENTY {
+        return match limit {
+            SearchLimit::Depth(d) => SearchLimit::Depth(d + 4),
+            SearchLimit::MoveTime { ms } => SearchLimit::MoveTime {
+                ms: (ms * 2).min((remaining_ms / PLENTY_SHARE).max(ms)),
+            },
+            SearchLimit::Nodes(n) => SearchLimit::Nodes(n * 2),
+            SearchLimit::Infinite => limit,
+        };
+    }
+    limit
+}
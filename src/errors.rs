@@ -0,0 +1,192 @@
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// A repeated error is printed again at most this often, with its count.
+const REPRINT_AFTER: Duration = Duration::from_secs(30);
+/// Oldest entries are dropped beyond this many distinct messages.
+const MAX_ENTRIES: usize = 20;
+
+/// A failure, by the part of the pipeline it came from. Plain strings
+/// convert to `Other`, so any message can still be reported.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AppError {
+    /// Screen capture or the capture source.
+    Capture(String),
+    /// Loading or running the piece detection model.
+    Inference(String),
+    /// A detected position that is not a legal FEN.
+    Fen(String),
+    /// The engine process, its options or a search.
+    Engine(String),
+    /// The OS: files written to disk, hotkeys, input simulation and memory.
+    System(String),
+    Other(String),
+}
+
+impl AppError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AppError::Capture(_) => "Capture",
+            AppError::Inference(_) => "Inference",
+            AppError::Fen(_) => "Position",
+            AppError::Engine(_) => "Engine",
+            AppError::System(_) => "System",
+            AppError::Other(_) => "Error",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::Capture(m)
+            | AppError::Inference(m)
+            | AppError::Fen(m)
+            | AppError::Engine(m)
+            | AppError::System(m)
+            | AppError::Other(m) => m,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.kind(), self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Other(message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::Other(message.to_string())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ErrorEntry {
+    pub error: AppError,
+    pub count: u32,
+    pub first_seen: Instant,
+    pub last_seen: Instant,
+    last_printed: Instant,
+    printed_count: u32,
+    /// Closed in the overlay; shown again if it happens again.
+    dismissed: bool,
+}
+
+impl ErrorEntry {
+    /// Text copied by "Copy details".
+    pub fn details(&self) -> String {
+        format!(
+            "{}\nOccurrences: {}\nFirst seen: {} ago\nLast seen: {} ago",
+            self.error,
+            self.count,
+            format_age(self.first_seen),
+            format_age(self.last_seen)
+        )
+    }
+}
+
+/// Coalesces identical errors so a persistent failure (missing engine, bad
+/// region) is logged once with a counter instead of every cycle.
+#[derive(Clone, Debug, Default)]
+pub struct ErrorLog {
+    entries: Vec<ErrorEntry>,
+}
+
+impl ErrorLog {
+    pub fn report(&mut self, error: impl Into<AppError>) {
+        let error = error.into();
+        let now = Instant::now();
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.error == error) {
+            entry.count += 1;
+            entry.last_seen = now;
+            entry.dismissed = false;
+            if now.duration_since(entry.last_printed) >= REPRINT_AFTER {
+                println!(
+                    "{} (repeated {} times, first seen {}s ago)",
+                    entry.error,
+                    entry.count - entry.printed_count,
+                    entry.first_seen.elapsed().as_secs()
+                );
+                entry.last_printed = now;
+                entry.printed_count = entry.count;
+            }
+            return;
+        }
+
+        println!("{}", error);
+        if self.entries.len() >= MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+        self.entries.push(ErrorEntry {
+            error,
+            count: 1,
+            first_seen: now,
+            last_seen: now,
+            last_printed: now,
+            printed_count: 1,
+            dismissed: false,
+        });
+    }
+
+    /// Most recently seen first.
+    pub fn entries(&self) -> Vec<&ErrorEntry> {
+        let mut entries: Vec<&ErrorEntry> = self.entries.iter().collect();
+        entries.sort_by_key(|e| std::cmp::Reverse(e.last_seen));
+        entries
+    }
+
+    /// Entries seen within `window` and not dismissed, most recent first.
+    pub fn recent(&self, window: Duration) -> Vec<&ErrorEntry> {
+        let mut recent = self.entries();
+        recent.retain(|e| !e.dismissed && e.last_seen.elapsed() < window);
+        recent
+    }
+
+    /// Hides `error` from `recent` until it is reported again.
+    pub fn dismiss(&mut self, error: &AppError) {
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.error == *error) {
+            entry.dismissed = true;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// The error log, shared by the worker, the UI and the helper threads.
+/// Kept out of the config so reporting never waits on the settings lock.
+#[derive(Clone, Default)]
+pub struct SharedErrors(Arc<Mutex<ErrorLog>>);
+
+impl SharedErrors {
+    pub fn report(&self, error: impl Into<AppError>) {
+        self.0.lock().unwrap().report(error);
+    }
+
+    pub fn lock(&self) -> MutexGuard<'_, ErrorLog> {
+        self.0.lock().unwrap()
+    }
+}
+
+/// Short "12s" / "3m" / "2h" age for display.
+pub fn format_age(since: Instant) -> String {
+    let secs = since.elapsed().as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        _ => format!("{}h", secs / 3600),
+    }
+}
@@ -0,0 +1,144 @@
+use crate::vision::board::{board_area, cell_of, piece_for_class, INPUT_SIZE};
+use crate::vision::inference::Detection;
+use anyhow::Result;
+use image::{DynamicImage, ImageOutputFormat};
+use std::fmt::Write as _;
+use std::fs;
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const GRID_COLOR: &str = "#00e5ff";
+const LABEL_COLOR: &str = "#ffeb3b";
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | ((*b as u32) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[((n >> (18 - 6 * i)) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Renders the captured crop with the 8×8 grid of the selected board and,
+/// in each cell, the class letter and confidence of every piece detection
+/// whose center falls there. Coordinates are the model's 640×640 input
+/// space stretched over the crop. The crop is embedded, so the SVG is a
+/// single self-contained file.
+pub fn render_detection_svg(
+    img: &DynamicImage,
+    detections: &[Detection],
+    board_box: Option<&Detection>,
+) -> Result<String> {
+    let mut png = Vec::new();
+    img.write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)?;
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {INPUT_SIZE} {INPUT_SIZE}">"#,
+        img.width(),
+        img.height()
+    );
+    let _ = writeln!(
+        svg,
+        r#"<image width="{INPUT_SIZE}" height="{INPUT_SIZE}" preserveAspectRatio="none" href="data:image/png;base64,{}"/>"#,
+        base64(&png)
+    );
+
+    let area = board_area(board_box);
+    let (bx, by, bw, bh) = area;
+    for i in 0..=8 {
+        let x = bx + bw * i as f32 / 8.0;
+        let y = by + bh * i as f32 / 8.0;
+        let _ = writeln!(
+            svg,
+            r#"<line x1="{x}" y1="{by}" x2="{x}" y2="{}" stroke="{GRID_COLOR}" stroke-width="1"/>"#,
+            by + bh
+        );
+        let _ = writeln!(
+            svg,
+            r#"<line x1="{bx}" y1="{y}" x2="{}" y2="{y}" stroke="{GRID_COLOR}" stroke-width="1"/>"#,
+            bx + bw
+        );
+    }
+
+    let mut cells: Vec<Vec<&Detection>> = vec![Vec::new(); 64];
+    for d in detections.iter().filter(|d| d.class_id != 0) {
+        if let Some((col, row)) = cell_of(d, area) {
+            cells[(row * 8 + col) as usize].push(d);
+        }
+    }
+    let font = (bh / 8.0 / 4.0).max(6.0);
+    for (i, cell) in cells.iter().enumerate() {
+        let x = bx + bw * (i % 8) as f32 / 8.0 + 2.0;
+        let y = by + bh * (i / 8) as f32 / 8.0;
+        for (line, d) in cell.iter().enumerate() {
+            let label = piece_for_class(d.class_id).map(|p| p.char()).unwrap_or('?');
+            let _ = writeln!(
+                svg,
+                r#"<text x="{x}" y="{}" font-size="{font}" font-family="monospace" fill="{LABEL_COLOR}" stroke="black" stroke-width="0.3">{label} {:.2}</text>"#,
+                y + font * (line + 1) as f32,
+                d.confidence
+            );
+        }
+    }
+
+    if let Some(b) = board_box {
+        let _ = writeln!(
+            svg,
+            r#"<text x="{bx}" y="{}" font-size="{font}" font-family="monospace" fill="{GRID_COLOR}">board {:.2}</text>"#,
+            (by - 2.0).max(font),
+            b.confidence
+        );
+    }
+    svg.push_str("</svg>\n");
+    Ok(svg)
+}
+
+/// Writes one detection SVG per analyzed frame into
+/// `debug/run-<timestamp>/` while debug capture is on.
+pub struct DetectionExporter {
+    dir: PathBuf,
+    written: usize,
+}
+
+impl DetectionExporter {
+    pub fn new() -> Result<Self> {
+        let stamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let dir = crate::paths::app_dir()
+            .join("debug")
+            .join(format!("run-{}", stamp));
+        fs::create_dir_all(&dir)?;
+        println!("Writing detection images to {}", dir.display());
+        Ok(Self { dir, written: 0 })
+    }
+
+    pub fn written(&self) -> usize {
+        self.written
+    }
+
+    pub fn export(
+        &mut self,
+        frame: u64,
+        board: usize,
+        img: &DynamicImage,
+        detections: &[Detection],
+        board_box: Option<&Detection>,
+    ) -> Result<PathBuf> {
+        let path = self.dir.join(format!("frame-{:06}-b{}.svg", frame, board));
+        fs::write(&path, render_detection_svg(img, detections, board_box)?)?;
+        self.written += 1;
+        Ok(path)
+    }
+}
@@ -0,0 +1,178 @@
+use crate::assets::pieces::{piece_group, PieceTheme};
+use crate::overlay::arrow::promotion;
+use anyhow::{Context, Result};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SQUARE: u32 = 60;
+const LIGHT_SQUARE: &str = "#f0d9b5";
+const DARK_SQUARE: &str = "#b58863";
+const ARROW_COLOR: &str = "#15781b";
+
+/// File format of an exported diagram.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiagramFormat {
+    Svg,
+    Png,
+}
+
+impl DiagramFormat {
+    pub const ALL: [DiagramFormat; 2] = [DiagramFormat::Svg, DiagramFormat::Png];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DiagramFormat::Svg => "SVG",
+            DiagramFormat::Png => "PNG",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            DiagramFormat::Svg => "svg",
+            DiagramFormat::Png => "png",
+        }
+    }
+}
+
+fn square_center(sq: &str) -> Option<(u32, u32)> {
+    let mut chars = sq.chars();
+    let file = chars.next()? as u32;
+    let rank = chars.next()?.to_digit(10)?;
+    if !('a' as u32..='h' as u32).contains(&file) || !(1..=8).contains(&rank) {
+        return None;
+    }
+    let col = file - 'a' as u32;
+    let row = 8 - rank;
+    Some((col * SQUARE + SQUARE / 2, row * SQUARE + SQUARE / 2))
+}
+
+/// Renders the position of `fen` with the suggested `moves` as arrows into a
+/// standalone SVG diagram.
+pub fn render_svg(fen: &str, moves: &[String], theme: PieceTheme) -> String {
+    let size = SQUARE * 8;
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{size}" height="{size}" viewBox="0 0 {size} {size}">"#
+    );
+    // A literal fill: `context-stroke` is SVG 2, which many viewers and
+    // the PNG rasterizer don't support
+    let _ = writeln!(
+        svg,
+        r#"<defs><marker id="head" markerWidth="4" markerHeight="4" refX="2" refY="2" orient="auto"><path d="M0,0 L4,2 L0,4 z" fill="{ARROW_COLOR}"/></marker></defs>"#
+    );
+
+    for row in 0..8 {
+        for col in 0..8 {
+            let fill = if (row + col) % 2 == 0 {
+                LIGHT_SQUARE
+            } else {
+                DARK_SQUARE
+            };
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{}" y="{}" width="{SQUARE}" height="{SQUARE}" fill="{fill}"/>"#,
+                col * SQUARE,
+                row * SQUARE
+            );
+        }
+    }
+
+    let placement = fen.split_whitespace().next().unwrap_or("");
+    for (row, rank) in placement.split('/').take(8).enumerate() {
+        let mut col = 0;
+        for c in rank.chars() {
+            if let Some(empty) = c.to_digit(10) {
+                col += empty;
+                continue;
+            }
+            if let Some(piece) = piece_group(c, theme) {
+                let _ = writeln!(
+                    svg,
+                    r#"<svg x="{}" y="{}" width="{SQUARE}" height="{SQUARE}" viewBox="0 0 45 45">{piece}</svg>"#,
+                    col * SQUARE,
+                    row as u32 * SQUARE
+                );
+            }
+            col += 1;
+        }
+    }
+
+    for (i, m) in moves.iter().enumerate() {
+        if m.len() < 4 {
+            continue;
+        }
+        let (Some(from), Some(to)) = (square_center(&m[0..2]), square_center(&m[2..4])) else {
+            continue;
+        };
+        let opacity = match i {
+            0 => 0.9,
+            1 => 0.6,
+            _ => 0.35,
+        };
+        let _ = writeln!(
+            svg,
+            r##"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{ARROW_COLOR}" stroke-width="10" stroke-linecap="round" opacity="{opacity}" marker-end="url(#head)"/>"##,
+            from.0, from.1, to.0, to.1
+        );
+        // The promotion piece in the destination's corner; drawn as a piece
+        // rather than a letter, since the PNG rasterizer has no fonts
+        if let Some(piece) = promotion(m) {
+            let piece = if m.get(3..4) == Some("8") {
+                piece.to_ascii_uppercase()
+            } else {
+                piece
+            };
+            let glyph = piece_group(piece, theme).unwrap_or_default();
+            let (x, y) = (to.0 + SQUARE / 4, to.1 - SQUARE / 4);
+            let _ = writeln!(
+                svg,
+                r#"<circle cx="{x}" cy="{y}" r="13" fill="{ARROW_COLOR}" opacity="{opacity}"/><svg x="{}" y="{}" width="22" height="22" viewBox="0 0 45 45">{glyph}</svg>"#,
+                x - 11,
+                y - 11
+            );
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Rasterizes an SVG diagram at its own size. The squares cover the whole
+/// image, so every pixel is opaque and the premultiplied pixels can be
+/// used as they are.
+fn render_png(svg: &str) -> Result<image::RgbaImage> {
+    let tree = resvg::usvg::Tree::from_str(svg, &resvg::usvg::Options::default())?;
+    let size = SQUARE * 8;
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(size, size).context("empty diagram")?;
+    resvg::render(
+        &tree,
+        resvg::tiny_skia::Transform::identity(),
+        &mut pixmap.as_mut(),
+    );
+    image::RgbaImage::from_raw(size, size, pixmap.take()).context("diagram size mismatch")
+}
+
+/// Writes the diagram of board `board` (0-based) into `diagrams/` under
+/// the app directory and returns the file path.
+pub fn export_diagram(
+    fen: &str,
+    moves: &[String],
+    theme: PieceTheme,
+    board: usize,
+    format: DiagramFormat,
+) -> Result<PathBuf> {
+    let dir = crate::paths::app_dir().join("diagrams");
+    fs::create_dir_all(&dir)?;
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let name = format!("diagram-{}-{}.{}", stamp, board + 1, format.extension());
+    let path = dir.join(name);
+    let svg = render_svg(fen, moves, theme);
+    match format {
+        DiagramFormat::Svg => fs::write(&path, svg)?,
+        DiagramFormat::Png => render_png(&svg)?.save(&path)?,
+    }
+    Ok(path)
+}
@@ -0,0 +1,117 @@
+use crate::vision::inference::Detection;
+use anyhow::Result;
+use image::DynamicImage;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Minimum time between two dumps; a bad position usually stays on screen
+/// for many frames.
+const DUMP_INTERVAL: Duration = Duration::from_secs(5);
+/// Oldest dumps are deleted beyond this many...
+const MAX_DUMPS: usize = 100;
+/// ...or this many bytes in total.
+const MAX_BYTES: u64 = 100 * 1024 * 1024;
+
+#[derive(Serialize)]
+struct DumpInfo<'a> {
+    board: usize,
+    fen: &'a str,
+    error: &'a str,
+    detections: &'a [Detection],
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|e| e.metadata().ok())
+                .map(|m| m.len())
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// Saves recognitions whose position was rejected into
+/// `debug_dumps/<timestamp>-b<board>/`: the captured crop as `crop.png`
+/// and the attempted FEN, error and raw detections as `detections.json`,
+/// so a model issue can be reported with the data to reproduce it.
+#[derive(Default)]
+pub struct FailureDumper {
+    last_dump: Option<Instant>,
+    last_fen: Option<String>,
+    written: usize,
+}
+
+impl FailureDumper {
+    pub fn written(&self) -> usize {
+        self.written
+    }
+
+    /// Dumps one failed recognition, unless one was written less than
+    /// `DUMP_INTERVAL` ago or it is the same position as the last dump.
+    /// Returns the folder written to.
+    pub fn dump(
+        &mut self,
+        board: usize,
+        img: &DynamicImage,
+        detections: &[Detection],
+        fen: &str,
+        error: &str,
+    ) -> Result<Option<PathBuf>> {
+        if self.last_dump.is_some_and(|t| t.elapsed() < DUMP_INTERVAL)
+            || self.last_fen.as_deref() == Some(fen)
+        {
+            return Ok(None);
+        }
+        self.last_dump = Some(Instant::now());
+        self.last_fen = Some(fen.to_string());
+
+        let root = crate::paths::app_dir().join("debug_dumps");
+        let stamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+        let dir = root.join(format!("{}-b{}", stamp, board));
+        fs::create_dir_all(&dir)?;
+        img.save(dir.join("crop.png"))?;
+        let info = DumpInfo {
+            board,
+            fen,
+            error,
+            detections,
+        };
+        fs::write(
+            dir.join("detections.json"),
+            serde_json::to_string_pretty(&info)?,
+        )?;
+        self.written += 1;
+        prune(&root)?;
+        Ok(Some(dir))
+    }
+}
+
+/// Deletes the oldest dumps until both limits hold. Folder names start
+/// with the timestamp, so name order is age order.
+fn prune(root: &Path) -> Result<()> {
+    let mut dumps: Vec<(PathBuf, u64)> = fs::read_dir(root)?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .map(|p| {
+            let size = dir_size(&p);
+            (p, size)
+        })
+        .collect();
+    dumps.sort();
+    let mut total: u64 = dumps.iter().map(|(_, size)| size).sum();
+    let mut count = dumps.len();
+    for (path, size) in dumps {
+        if count <= MAX_DUMPS && total <= MAX_BYTES {
+            break;
+        }
+        fs::remove_dir_all(&path)?;
+        count -= 1;
+        total -= size;
+    }
+    Ok(())
+}
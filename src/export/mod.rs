@@ -0,0 +1,5 @@
+pub mod detections;
+pub mod diagram;
+pub mod failures;
+pub mod pgn;
+pub mod stream;
@@ -0,0 +1,162 @@
+use crate::engine::quality::MoveClass;
+use crate::engine::stockfish::Evaluation;
+use crate::snapshot::GameRecord;
+use anyhow::{anyhow, Result};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+const LINE_WIDTH: usize = 79;
+
+/// Score in the `[%eval ...]` form lichess and ChessBase read: pawns for
+/// White, or `#N` for a mate.
+fn eval_comment(eval: Evaluation) -> String {
+    match eval {
+        Evaluation::Centipawns(cp) => format!("{{ [%eval {:.2}] }}", cp as f32 / 100.0),
+        Evaluation::Mate(n) => format!("{{ [%eval #{}] }}", n),
+    }
+}
+
+/// Class of the move between two scores for White, from the mover's side.
+fn played_class(before: Evaluation, after: Evaluation, white_moved: bool) -> MoveClass {
+    let before = before.for_white(white_moved).as_centipawns();
+    let after = after.for_white(white_moved).as_centipawns();
+    MoveClass::from_loss(before.saturating_sub(after))
+}
+
+/// Renders the tracked game as PGN. Each analyzed position gets an
+/// `[%eval]` comment after the move that reached it, and moves that lost
+/// enough against the previous score get a `$2`/`$4` glyph.
+pub fn render_pgn(game: &GameRecord) -> String {
+    let (white_first, first_number) = game.start.unwrap_or((true, 1));
+    let start_fen = game.start_fen.as_deref().unwrap_or(START_FEN);
+
+    let mut pgn = String::new();
+    for (tag, value) in [
+        ("Event", "Chess overlay session"),
+        ("Site", "?"),
+        ("Date", "????.??.??"),
+        ("Round", "-"),
+        ("White", "?"),
+        ("Black", "?"),
+        ("Result", "*"),
+    ] {
+        pgn.push_str(&format!("[{} \"{}\"]\n", tag, value));
+    }
+    if start_fen != START_FEN {
+        pgn.push_str("[SetUp \"1\"]\n");
+        pgn.push_str(&format!("[FEN \"{}\"]\n", start_fen));
+    }
+    pgn.push('\n');
+
+    let eval_at = |i: usize| game.evals.get(i).copied().flatten();
+    let mut tokens: Vec<String> = Vec::new();
+    // Black's move needs its number after a comment or at the start
+    let mut need_number = true;
+    for (i, san) in game.moves.iter().enumerate() {
+        let white_moved = (i % 2 == 0) == white_first;
+        let number = first_number as usize + (i + usize::from(!white_first)) / 2;
+        if white_moved {
+            tokens.push(format!("{}.", number));
+        } else if need_number {
+            tokens.push(format!("{}...", number));
+        }
+        tokens.push(san.clone());
+        need_number = false;
+        if let (Some(before), Some(after)) = (eval_at(i), eval_at(i + 1)) {
+            if let Some(nag) = played_class(before, after, white_moved).nag() {
+                tokens.push(format!("${}", nag));
+            }
+        }
+        if let Some(after) = eval_at(i + 1) {
+            tokens.push(eval_comment(after));
+            need_number = true;
+        }
+    }
+    tokens.push("*".to_string());
+
+    let mut line_len = 0;
+    for token in tokens {
+        if line_len > 0 && line_len + 1 + token.len() > LINE_WIDTH {
+            pgn.push('\n');
+            line_len = 0;
+        } else if line_len > 0 {
+            pgn.push(' ');
+            line_len += 1;
+        }
+        line_len += token.len();
+        pgn.push_str(&token);
+    }
+    pgn.push('\n');
+    pgn
+}
+
+/// Writes the game of board `board` (0-based) into `games/` under the app
+/// directory and returns the file path.
+pub fn export_pgn(game: &GameRecord, board: usize) -> Result<PathBuf> {
+    if game.start.is_none() {
+        return Err(anyhow!("No game tracked yet"));
+    }
+    let dir = crate::paths::app_dir().join("games");
+    fs::create_dir_all(&dir)?;
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let path = dir.join(format!("game-{}-{}.pgn", stamp, board + 1));
+    fs::write(&path, render_pgn(game))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::stockfish::Evaluation::Centipawns;
+
+    fn movetext(pgn: &str) -> String {
+        let (_, moves) = pgn.split_once("\n\n").unwrap();
+        moves.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    #[test]
+    fn evals_and_nags_follow_their_moves() {
+        let game = GameRecord {
+            moves: ["e4", "e5", "Qh5", "Nc6"].map(String::from).to_vec(),
+            start: Some((true, 1)),
+            evals: vec![
+                Some(Centipawns(20)),
+                Some(Centipawns(30)),
+                // Black gave up 1.2 pawns, then White 3.5
+                Some(Centipawns(150)),
+                Some(Centipawns(-200)),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            movetext(&render_pgn(&game)),
+            "1. e4 { [%eval 0.30] } 1... e5 $2 { [%eval 1.50] } \
+             2. Qh5 $4 { [%eval -2.00] } 2... Nc6 *"
+        );
+    }
+
+    #[test]
+    fn mate_scores_and_set_up_positions() {
+        let fen = "k7/8/1K6/8/8/8/8/7R w - - 0 40";
+        let game = GameRecord {
+            moves: vec!["Rh8#".to_string()],
+            start: Some((true, 40)),
+            start_fen: Some(fen.to_string()),
+            evals: vec![Some(Evaluation::Mate(1))],
+            ..Default::default()
+        };
+        let pgn = render_pgn(&game);
+        assert!(pgn.contains(&format!("[SetUp \"1\"]\n[FEN \"{}\"]\n", fen)));
+        assert_eq!(movetext(&pgn), "40. Rh8# *");
+
+        let game = GameRecord {
+            moves: vec!["Rh8#".to_string()],
+            start: Some((true, 40)),
+            evals: vec![Some(Evaluation::Mate(1)), Some(Evaluation::Mate(0))],
+            ..Default::default()
+        };
+        assert_eq!(movetext(&render_pgn(&game)), "40. Rh8# { [%eval #0] } *");
+    }
+}
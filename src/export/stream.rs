@@ -0,0 +1,130 @@
+use crate::config::AppConfig;
+use crate::engine::stockfish::Evaluation;
+use crate::errors::{AppError, SharedErrors};
+use crate::notation::Notation;
+use crate::server::{position_report, BoardReport};
+use crate::snapshot::SharedSnapshot;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// How often a new analysis is looked for.
+const POLL: Duration = Duration::from_millis(250);
+/// Seconds between reloads of the HTML page.
+const HTML_REFRESH_SECS: u32 = 1;
+
+/// Folder the stream files go to: `configured`, or `stream/` in the app
+/// folder when empty.
+pub fn output_dir(configured: &str) -> PathBuf {
+    if configured.trim().is_empty() {
+        crate::paths::app_dir().join("stream")
+    } else {
+        PathBuf::from(configured)
+    }
+}
+
+/// File name and contents of every stream file for the first analyzed
+/// board; all empty when there is none.
+fn files(board: Option<&BoardReport>, notation: Notation, html: bool) -> Vec<(String, String)> {
+    let eval = match board.map(|b| (b.cp, b.mate)) {
+        Some((Some(cp), _)) => Evaluation::Centipawns(cp).display(),
+        Some((_, Some(n))) => Evaluation::Mate(n).display(),
+        _ => String::new(),
+    };
+    let line = board
+        .and_then(|b| Some(notation.format_line(&b.fen, &b.lines.first()?.pv)))
+        .unwrap_or_default();
+    let fen = board.map(|b| b.fen.clone()).unwrap_or_default();
+
+    let mut files = Vec::new();
+    if html {
+        // Scores, SAN and FEN hold no characters that need escaping
+        let page = format!(
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\">\
+             <meta http-equiv=\"refresh\" content=\"{}\">\n<style>\
+             body{{margin:0;background:transparent;color:#fff;font:bold 28px sans-serif;\
+             text-shadow:0 0 4px #000}}.line{{font-size:20px}}\
+             .fen{{font-size:12px;opacity:.7}}</style></head>\n<body>\
+             <div class=\"eval\">{}</div><div class=\"line\">{}</div>\
+             <div class=\"fen\">{}</div></body></html>\n",
+            HTML_REFRESH_SECS, eval, line, fen
+        );
+        files.push(("analysis.html".to_string(), page));
+    }
+    files.push(("eval.txt".to_string(), eval));
+    files.push(("best_line.txt".to_string(), line));
+    files.push(("fen.txt".to_string(), fen));
+    files
+}
+
+/// Replaces `path` in one step, so a source reading it never sees half
+/// a file.
+fn write_atomic(path: &Path, contents: &str) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Writes the files whose contents differ from what `written` last
+/// recorded for them.
+fn write_changed(
+    dir: &Path,
+    files: Vec<(String, String)>,
+    written: &mut HashMap<PathBuf, String>,
+) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    for (name, contents) in files {
+        let path = dir.join(name);
+        if written.get(&path) != Some(&contents) {
+            write_atomic(&path, &contents)?;
+            written.insert(path, contents);
+        }
+    }
+    Ok(())
+}
+
+/// Keeps the stream files up to date while `stream_output` is on: the
+/// first board's evaluation, best line in SAN and FEN as text files, and
+/// optionally an HTML page that reloads itself, for OBS text and browser
+/// sources that can't capture the transparent overlay.
+pub fn spawn(
+    config: Arc<Mutex<AppConfig>>,
+    snapshot: SharedSnapshot,
+    errors: SharedErrors,
+) -> JoinHandle<()> {
+    thread::spawn(move || {
+        // Contents last written per file, to skip unchanged writes
+        let mut written: HashMap<PathBuf, String> = HashMap::new();
+        loop {
+            thread::sleep(POLL);
+            let (enabled, dir, html, notation) = {
+                let c = config.lock().unwrap();
+                (
+                    c.stream_output,
+                    output_dir(&c.stream_output_dir),
+                    c.stream_output_html,
+                    c.notation,
+                )
+            };
+            if !enabled {
+                written.clear();
+                continue;
+            }
+            let report = position_report(&snapshot.load());
+            let files = files(report.boards.first(), notation, html);
+            if let Err(e) = write_changed(&dir, files, &mut written) {
+                errors.report(AppError::System(format!(
+                    "Stream output to {} failed: {:#}",
+                    dir.display(),
+                    e
+                )));
+                config.lock().unwrap().stream_output = false;
+            }
+        }
+    })
+}
@@ -0,0 +1,50 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// How often the file's modification time is read.
+const CHECK_INTERVAL: Duration = Duration::from_secs(2);
+/// A new version must sit unchanged this long before it is reported, so a
+/// download or copy still in progress isn't picked up half-written.
+const SETTLE: Duration = Duration::from_secs(3);
+
+/// Notices when a file the app has loaded (engine binary, model) is
+/// replaced on disk.
+pub struct FileWatch {
+    path: PathBuf,
+    /// Modification time of the version loaded or last reported.
+    seen: Option<SystemTime>,
+    last_check: Instant,
+}
+
+impl FileWatch {
+    pub fn new(path: PathBuf) -> Self {
+        let seen = modified(&path);
+        Self {
+            path,
+            seen,
+            last_check: Instant::now(),
+        }
+    }
+
+    /// True once for each new version of the file, after it has settled.
+    pub fn changed(&mut self) -> bool {
+        if self.last_check.elapsed() < CHECK_INTERVAL {
+            return false;
+        }
+        self.last_check = Instant::now();
+        let Some(current) = modified(&self.path) else {
+            return false;
+        };
+        let settled = current.elapsed().map_or(true, |age| age >= SETTLE);
+        if Some(current) == self.seen || !settled {
+            return false;
+        }
+        self.seen = Some(current);
+        true
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
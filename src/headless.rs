@@ -0,0 +1,177 @@
+//! `--analyze <image> [--depth N] [--lines N] [--black] [--json]`: runs
+//! the detector and engine on one screenshot or board image without
+//! opening the overlay, and prints the position and best lines. Model,
+//! engine and thresholds come from `config.json` like in the app.
+
+use crate::chess_logic::{line_to_san, uci_to_san, GameTracker, TurnDetector};
+use crate::config::AppConfig;
+use crate::engine::stockfish::{Evaluation, SearchLimit, SearchMode, Stockfish};
+use crate::paths;
+use crate::vision::board::{min_board_fraction, select_board};
+use crate::vision::color::correct_colors;
+use crate::vision::inference::Detector;
+use crate::vision::site::board_grid;
+use crate::worker::board_position;
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// What to analyze, parsed from the command line.
+pub struct AnalyzeArgs {
+    pub image: PathBuf,
+    /// Overrides the configured search with a fixed depth.
+    pub depth: Option<u32>,
+    pub lines: Option<u32>,
+    /// Black to move instead of the configured side.
+    pub black: bool,
+    pub json: bool,
+}
+
+impl AnalyzeArgs {
+    /// Reads the options following `--analyze`; `None` when the image path
+    /// is missing or a value doesn't parse.
+    pub fn parse(args: &[String]) -> Option<Self> {
+        let i = args.iter().position(|a| a == "--analyze")?;
+        let image = args.get(i + 1).filter(|a| !a.starts_with("--"))?;
+        let value = |flag: &str| -> Option<Option<u32>> {
+            match args.iter().position(|a| a == flag) {
+                Some(j) => args.get(j + 1)?.parse().ok().map(Some),
+                None => Some(None),
+            }
+        };
+        Some(Self {
+            image: PathBuf::from(image),
+            depth: value("--depth")?,
+            lines: value("--lines")?,
+            black: args.iter().any(|a| a == "--black"),
+            json: args.iter().any(|a| a == "--json"),
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct LineReport {
+    /// UCI move.
+    #[serde(rename = "move")]
+    mv: String,
+    san: Option<String>,
+    /// Score from White's point of view: centipawns or moves to mate.
+    cp: Option<i32>,
+    mate: Option<i32>,
+    depth: u32,
+    pv: Vec<String>,
+    pv_san: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct AnalyzeReport {
+    image: String,
+    detections: usize,
+    fen: String,
+    lines: Vec<LineReport>,
+}
+
+/// Detects the position in `args.image`, searches it and prints the
+/// result as text or, with `--json`, as one JSON object.
+pub fn analyze(args: &AnalyzeArgs) -> Result<()> {
+    let config = AppConfig::load();
+    let img = image::open(&args.image)
+        .with_context(|| format!("failed to load {}", args.image.display()))?;
+
+    let model_path = paths::model_path(&config.model_path);
+    let mut detector = Detector::new(&model_path.to_string_lossy(), config.inference_backend)?;
+    let mut detections =
+        detector.detect(&img, config.confidence_threshold, config.iou_threshold)?;
+    if config.color_check {
+        correct_colors(&img, &mut detections, config.color_check_below);
+    }
+    let min_fraction = min_board_fraction(config.min_board_px, img.width().min(img.height()));
+    let candidates = select_board(&detections, min_fraction, 0);
+    let grid = candidates
+        .selected
+        .and_then(|b| board_grid(&img, b, config.coordinate_strip, config.refine_board_grid));
+    let show_white = if args.black {
+        false
+    } else {
+        config.show_white_moves
+    };
+    let (fen, _) = board_position(
+        &detections,
+        grid.as_ref().or(candidates.selected),
+        config.reassign_cell_conflicts,
+        show_white,
+        config.auto_side_to_move && !args.black,
+        &mut GameTracker::default(),
+        &mut TurnDetector::default(),
+    )
+    .map_err(|e| anyhow!("No legal position recognized: {}", e))?;
+
+    let engine_path = paths::engine_path();
+    let mut sf = Stockfish::new(
+        &engine_path.to_string_lossy(),
+        &config.engine_process,
+        &config.engine_options,
+    )?;
+    let limit = match args.depth {
+        Some(depth) => SearchLimit::Depth(depth.max(1)),
+        // An infinite search has no end without the overlay to stop it
+        None if config.search_mode == SearchMode::Infinite => SearchLimit::Depth(20),
+        None => SearchLimit::new(
+            config.search_mode,
+            config.stockfish_depth,
+            config.stockfish_time_ms,
+            config.stockfish_nodes,
+        ),
+    };
+    let lines = args.lines.unwrap_or(config.stockfish_lines).max(1);
+    let white_to_move = fen.split_whitespace().nth(1) == Some("w");
+    let lines = sf
+        .analyze(&fen, limit, lines)?
+        .into_iter()
+        .map(|l| {
+            let eval = l.eval.map(|e| e.for_white(white_to_move));
+            LineReport {
+                san: uci_to_san(&fen, &l.mv),
+                pv_san: line_to_san(&fen, &l.pv),
+                cp: match eval {
+                    Some(Evaluation::Centipawns(cp)) => Some(cp),
+                    _ => None,
+                },
+                mate: match eval {
+                    Some(Evaluation::Mate(n)) => Some(n),
+                    _ => None,
+                },
+                mv: l.mv,
+                depth: l.depth,
+                pv: l.pv,
+            }
+        })
+        .collect();
+
+    let report = AnalyzeReport {
+        image: args.image.display().to_string(),
+        detections: detections.len(),
+        fen,
+        lines,
+    };
+    // One line, after the model and engine startup messages
+    if args.json {
+        println!("{}", serde_json::to_string(&report)?);
+        return Ok(());
+    }
+    println!("FEN: {}", report.fen);
+    for (i, line) in report.lines.iter().enumerate() {
+        let eval = match (line.cp, line.mate) {
+            (Some(cp), _) => Evaluation::Centipawns(cp).display(),
+            (_, Some(n)) => Evaluation::Mate(n).display(),
+            _ => "?".to_string(),
+        };
+        let pv = if line.pv_san.is_empty() {
+            line.pv.join(" ")
+        } else {
+            line.pv_san.join(" ")
+        };
+        println!("{}. {:>6}  (depth {})  {}", i + 1, eval, line.depth, pv);
+    }
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Session files kept in `history/`; the oldest are deleted when a new
+/// session starts.
+const MAX_SESSIONS: usize = 20;
+
+/// One analyzed position as seen by the worker.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HistoryEntry {
+    /// Milliseconds since the Unix epoch.
+    pub timestamp_ms: u64,
+    pub board: usize,
+    pub fen: String,
+    pub moves: Vec<String>,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+pub fn history_dir() -> PathBuf {
+    crate::paths::app_dir().join("history")
+}
+
+/// Appends analyzed positions to `history/session-<timestamp>.jsonl`, one
+/// JSON object per line, so a session can be replayed without inference.
+/// Only the last `MAX_SESSIONS` sessions are kept.
+pub struct SessionRecorder {
+    file: Option<File>,
+}
+
+impl SessionRecorder {
+    pub fn new() -> Self {
+        Self { file: None }
+    }
+
+    fn open(&mut self) -> Result<&mut File> {
+        if self.file.is_none() {
+            let dir = history_dir();
+            fs::create_dir_all(&dir)?;
+            for old in list_sessions().iter().skip(MAX_SESSIONS - 1) {
+                if let Err(e) = fs::remove_file(old) {
+                    println!("Failed to delete old session {}: {}", old.display(), e);
+                }
+            }
+            let path = dir.join(format!("session-{}.jsonl", now_ms() / 1000));
+            self.file = Some(OpenOptions::new().create(true).append(true).open(path)?);
+        }
+        Ok(self.file.as_mut().unwrap())
+    }
+
+    pub fn record(&mut self, board: usize, fen: &str, moves: &[String]) -> Result<()> {
+        let entry = HistoryEntry {
+            timestamp_ms: now_ms(),
+            board,
+            fen: fen.to_string(),
+            moves: moves.to_vec(),
+        };
+        let line = serde_json::to_string(&entry)?;
+        let file = self.open()?;
+        writeln!(file, "{}", line)?;
+        Ok(())
+    }
+}
+
+/// Recorded session files, newest first.
+pub fn list_sessions() -> Vec<PathBuf> {
+    let mut sessions: Vec<PathBuf> = fs::read_dir(history_dir())
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|ext| ext == "jsonl"))
+                .collect()
+        })
+        .unwrap_or_default();
+    sessions.sort();
+    sessions.reverse();
+    sessions
+}
+
+/// Reads a session file, skipping lines that fail to parse.
+pub fn load_session(path: &PathBuf) -> Result<Vec<HistoryEntry>> {
+    let reader = BufReader::new(File::open(path)?);
+    Ok(reader
+        .lines()
+        .map_while(|l| l.ok())
+        .filter_map(|l| serde_json::from_str(&l).ok())
+        .collect())
+}
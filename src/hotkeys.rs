@@ -0,0 +1,268 @@
+use crate::config::AppConfig;
+use crate::errors::{AppError, SharedErrors};
+use crate::platform::{display_server, DisplayServer};
+use crate::worker::WorkerCommand;
+use crossbeam_channel::Sender;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Last global cursor position in screen pixels, tracked by the listener
+/// because the overlay gets no mouse events while it is click-through.
+static CURSOR: Mutex<Option<(f64, f64)>> = Mutex::new(None);
+
+pub fn cursor_position() -> Option<(f64, f64)> {
+    *CURSOR.lock().unwrap()
+}
+
+/// Something a global hotkey can trigger.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HotkeyAction {
+    ToggleOverlay,
+    PauseAnalysis,
+    ForceRescan,
+    SwitchSide,
+    AnalysisBoard,
+    NextBoard,
+    ExplainMove,
+    PlayMove,
+    ToggleDetections,
+}
+
+impl HotkeyAction {
+    pub const ALL: [HotkeyAction; 9] = [
+        HotkeyAction::ToggleOverlay,
+        HotkeyAction::PauseAnalysis,
+        HotkeyAction::ForceRescan,
+        HotkeyAction::SwitchSide,
+        HotkeyAction::AnalysisBoard,
+        HotkeyAction::NextBoard,
+        HotkeyAction::ExplainMove,
+        HotkeyAction::PlayMove,
+        HotkeyAction::ToggleDetections,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            HotkeyAction::ToggleOverlay => "Show/hide overlay",
+            HotkeyAction::PauseAnalysis => "Pause/resume analysis",
+            HotkeyAction::ForceRescan => "Force re-scan",
+            HotkeyAction::SwitchSide => "Switch side",
+            HotkeyAction::AnalysisBoard => "Analysis board",
+            HotkeyAction::NextBoard => "Next board candidate",
+            HotkeyAction::ExplainMove => "Explain best move",
+            HotkeyAction::PlayMove => "Play best move",
+            HotkeyAction::ToggleDetections => "Show/hide detection boxes",
+        }
+    }
+}
+
+/// Key bindings, stored as `rdev::Key` debug names ("F2", "KeyB").
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Hotkeys {
+    pub toggle_overlay: String,
+    pub pause_analysis: String,
+    pub force_rescan: String,
+    pub switch_side: String,
+    pub analysis_board: String,
+    pub next_board: String,
+    pub explain_move: String,
+    pub play_move: String,
+    pub toggle_detections: String,
+}
+
+impl Default for Hotkeys {
+    fn default() -> Self {
+        Self {
+            toggle_overlay: "F4".to_string(),
+            pause_analysis: "F5".to_string(),
+            force_rescan: "F6".to_string(),
+            switch_side: "KeyB".to_string(),
+            analysis_board: "F2".to_string(),
+            next_board: "F3".to_string(),
+            explain_move: "F7".to_string(),
+            play_move: "F8".to_string(),
+            toggle_detections: "F9".to_string(),
+        }
+    }
+}
+
+impl Hotkeys {
+    pub fn key(&self, action: HotkeyAction) -> &str {
+        match action {
+            HotkeyAction::ToggleOverlay => &self.toggle_overlay,
+            HotkeyAction::PauseAnalysis => &self.pause_analysis,
+            HotkeyAction::ForceRescan => &self.force_rescan,
+            HotkeyAction::SwitchSide => &self.switch_side,
+            HotkeyAction::AnalysisBoard => &self.analysis_board,
+            HotkeyAction::NextBoard => &self.next_board,
+            HotkeyAction::ExplainMove => &self.explain_move,
+            HotkeyAction::PlayMove => &self.play_move,
+            HotkeyAction::ToggleDetections => &self.toggle_detections,
+        }
+    }
+
+    fn key_mut(&mut self, action: HotkeyAction) -> &mut String {
+        match action {
+            HotkeyAction::ToggleOverlay => &mut self.toggle_overlay,
+            HotkeyAction::PauseAnalysis => &mut self.pause_analysis,
+            HotkeyAction::ForceRescan => &mut self.force_rescan,
+            HotkeyAction::SwitchSide => &mut self.switch_side,
+            HotkeyAction::AnalysisBoard => &mut self.analysis_board,
+            HotkeyAction::NextBoard => &mut self.next_board,
+            HotkeyAction::ExplainMove => &mut self.explain_move,
+            HotkeyAction::PlayMove => &mut self.play_move,
+            HotkeyAction::ToggleDetections => &mut self.toggle_detections,
+        }
+    }
+
+    /// Binds `key` to `action`, unbinding it from any other action first.
+    pub fn bind(&mut self, action: HotkeyAction, key: &str) {
+        for other in HotkeyAction::ALL {
+            if other != action && self.key(other) == key {
+                self.key_mut(other).clear();
+            }
+        }
+        *self.key_mut(action) = key.to_string();
+    }
+
+    fn action_for(&self, key: &str) -> Option<HotkeyAction> {
+        HotkeyAction::ALL
+            .into_iter()
+            .find(|a| !key.is_empty() && self.key(*a) == key)
+    }
+}
+
+/// "KeyB" -> "B", "Num1" -> "1"; other names are shown as-is.
+pub fn display_key(key: &str) -> &str {
+    if key.is_empty() {
+        return "—";
+    }
+    key.strip_prefix("Key")
+        .or_else(|| key.strip_prefix("Num"))
+        .unwrap_or(key)
+}
+
+/// Whether an egui key press matches a binding, for the in-window fallback
+/// when global hooks are unavailable. Compares display names ("B", "F2").
+fn matches_egui(binding: &str, key: egui::Key) -> bool {
+    !binding.is_empty() && display_key(binding).eq_ignore_ascii_case(key.name())
+}
+
+/// Actions bound to keys pressed in `ctx` this frame. Only used when the
+/// global listener failed, so hotkeys still work while a window of ours
+/// has keyboard focus.
+pub fn local_actions(ctx: &egui::Context, hotkeys: &Hotkeys) -> Vec<HotkeyAction> {
+    ctx.input(|i| {
+        i.events
+            .iter()
+            .filter_map(|e| match e {
+                egui::Event::Key {
+                    key,
+                    pressed: true,
+                    repeat: false,
+                    ..
+                } => HotkeyAction::ALL
+                    .into_iter()
+                    .find(|a| matches_egui(hotkeys.key(*a), *key)),
+                _ => None,
+            })
+            .collect()
+    })
+}
+
+/// First key pressed in `ctx` this frame, for capturing a binding without
+/// the global listener.
+pub fn pressed_key(ctx: &egui::Context) -> Option<egui::Key> {
+    ctx.input(|i| {
+        i.events.iter().find_map(|e| match e {
+            egui::Event::Key {
+                key, pressed: true, ..
+            } => Some(*key),
+            _ => None,
+        })
+    })
+}
+
+pub fn trigger(c: &mut AppConfig, action: HotkeyAction, commands: &Sender<WorkerCommand>) {
+    match action {
+        HotkeyAction::ToggleOverlay => c.overlay_hidden = !c.overlay_hidden,
+        HotkeyAction::PauseAnalysis => {
+            if c.running {
+                c.analysis_paused = !c.analysis_paused;
+            } else if c.board_region.is_some() {
+                c.running = true;
+                c.analysis_paused = false;
+            }
+        }
+        HotkeyAction::ForceRescan => {
+            let _ = commands.send(WorkerCommand::Rescan);
+        }
+        HotkeyAction::SwitchSide => {
+            c.show_white_moves = !c.show_white_moves;
+            println!(
+                "Toggled side: {}",
+                if c.show_white_moves { "White" } else { "Black" }
+            );
+        }
+        HotkeyAction::AnalysisBoard => c.request_analysis_board = true,
+        HotkeyAction::NextBoard => c.board_cycle = c.board_cycle.wrapping_add(1),
+        // Pressed again, it closes the popup
+        HotkeyAction::ExplainMove => {
+            let _ = commands.send(WorkerCommand::ToggleExplanation);
+        }
+        HotkeyAction::PlayMove => c.request_auto_move = true,
+        HotkeyAction::ToggleDetections => c.show_detections = !c.show_detections,
+    }
+}
+
+/// Global key and cursor listener, independent of which window has focus
+/// (the overlay never does while it is click-through). Actions are sent to
+/// the UI on `tx`. While the settings window is waiting for a new binding
+/// (`capturing_hotkey`), the next key press is bound instead; Escape
+/// cancels. If the OS refuses the hook, `global_hotkeys_failed` is set and
+/// the UI falls back to keys pressed in its own windows. Wayland doesn't
+/// pass other applications' keys to the hook at all, so there the fallback
+/// is on from the start.
+pub fn spawn_listener(
+    config: Arc<Mutex<AppConfig>>,
+    tx: Sender<HotkeyAction>,
+    errors: SharedErrors,
+) {
+    if display_server() == DisplayServer::Wayland {
+        println!("Global hotkeys unavailable on Wayland");
+        config.lock().unwrap().global_hotkeys_failed = true;
+    }
+    thread::spawn(move || {
+        use rdev::{listen, EventType, Key};
+        let listener_config = config.clone();
+        let result = listen(move |event| match event.event_type {
+            EventType::MouseMove { x, y } => *CURSOR.lock().unwrap() = Some((x, y)),
+            EventType::KeyPress(key) => {
+                let action = {
+                    let mut c = listener_config.lock().unwrap();
+                    if let Some(action) = c.capturing_hotkey.take() {
+                        if key != Key::Escape {
+                            c.hotkeys.bind(action, &format!("{:?}", key));
+                        }
+                        return;
+                    }
+                    c.hotkeys.action_for(&format!("{:?}", key))
+                };
+                if let Some(action) = action {
+                    let _ = tx.send(action);
+                }
+            }
+            _ => {}
+        });
+        if let Err(e) = result {
+            errors.report(AppError::System(format!(
+                "Global hotkeys unavailable: {:?}",
+                e
+            )));
+            config.lock().unwrap().global_hotkeys_failed = true;
+        }
+    });
+}
@@ -1,120 +1,140 @@
+mod assets;
+mod automove;
+mod bench;
 mod capture;
+mod chess_logic;
 mod config;
+mod config_migrate;
+mod config_schema;
+mod degradation;
 mod engine;
+mod errors;
+mod export;
+mod file_watch;
+mod headless;
+mod history;
+mod hotkeys;
+mod memory;
+mod notation;
 mod overlay;
+mod paths;
+mod platform;
+mod recording;
+mod server;
+mod snapshot;
 mod vision;
+mod worker;
 
-use crate::capture::grabber::capture_region;
-use crate::config::AppConfig;
-use crate::engine::stockfish::Stockfish;
-use crate::vision::board::detections_to_fen;
-use crate::vision::inference::Detector;
+use crate::assets::pieces::PieceTheme;
+use crate::capture::monitor::{list_monitors, MonitorInfo};
+use crate::capture::source::CaptureSourceKind;
+use crate::config::{profile_name, profile_names, AppConfig, BoardSide, MonitorChoice};
+use crate::degradation::DegradationLevel;
+use crate::engine::background::BackgroundEngine;
+use crate::engine::diagnose::DOWNLOAD_URL;
+use crate::engine::options::EngineOptions;
+use crate::engine::process::{EnginePriority, EngineProcessSettings};
+use crate::engine::stockfish::SearchMode;
+use crate::engine::supervisor::EngineHealth;
+use crate::errors::{format_age, AppError, SharedErrors};
+use crate::export::diagram::DiagramFormat;
+use crate::hotkeys::{display_key, trigger, HotkeyAction};
+use crate::notation::Notation;
+use crate::overlay::analysis_board::AnalysisBoard;
+use crate::overlay::arrow::{ArrowStyle, MoveHighlight};
+use crate::overlay::arrow_filter::{cap_arrows, select, ArrowCandidates, ArrowFilter, PieceFilter};
+use crate::overlay::hot_zone::{show_quick_controls, HotCorner};
+use crate::overlay::hud::show_hud;
+use crate::overlay::renderer::{BoardFrame, RendererRegistry};
+use crate::overlay::replay::ReplayViewer;
+use crate::overlay::screen_map::ScreenMapping;
+use crate::overlay::theme::theme_editor;
+use crate::overlay::toasts::show_toasts;
+use crate::overlay::widgets::deferred_text_edit;
+use crate::overlay::window::{DesktopKeeper, DesktopMode, OVERLAY_TITLE};
+use crate::platform::{
+    capabilities, display_server, open_screen_recording_settings, Support, ENGINE_BINARY,
+};
+use crate::snapshot::SharedSnapshot;
+use crate::vision::inference::InferenceBackend;
+use crate::vision::site::Site;
+use crate::worker::WorkerCommand;
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
 
-use crossbeam_channel::{unbounded, Receiver};
 use eframe::egui;
 use std::sync::{Arc, Mutex};
-use std::thread;
-use std::time::Duration;
 
 fn main() {
-    println!("Starting Chess Overlay...");
-
-    let config = Arc::new(Mutex::new(AppConfig::load()));
-    let (move_tx, move_rx) = unbounded::<Vec<String>>();
+    if std::env::args().any(|a| a == "--write-config-schema") {
+        match config_schema::write_schema() {
+            Ok(path) => println!("Config schema written to {}", path.display()),
+            Err(e) => println!("Failed to write config schema: {:?}", e),
+        }
+        return;
+    }
 
-    // Background worker thread for Vision + Stockfish
-    let config_clone = config.clone();
-    thread::spawn(move || {
-        let exe_dir = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
-        let model_path = exe_dir.join("best.onnx");
-        let engine_path = exe_dir.join("stockfish.exe");
-
-        if !model_path.exists() || !engine_path.exists() {
-            println!("ERROR: Essential files missing");
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(i) = args.iter().position(|a| a == "--replay-frames") {
+        let Some(dir) = args.get(i + 1) else {
+            println!("Usage: --replay-frames <recording dir>");
             return;
+        };
+        let model_path = paths::model_path(&AppConfig::load().model_path);
+        match recording::replay(std::path::Path::new(dir), &model_path) {
+            Ok(path) => println!("Replay results written to {}", path.display()),
+            Err(e) => println!("Replay failed: {:?}", e),
         }
+        return;
+    }
 
-        let mut detector = match Detector::new(model_path.to_str().unwrap()) {
-            Ok(d) => d,
-            Err(e) => {
-                println!("ERROR: {:?}", e);
-                return;
-            }
+    if let Some(i) = args.iter().position(|a| a == "--bench") {
+        let Some(dir) = args.get(i + 1) else {
+            println!("Usage: --bench <folder> [--model <path>]");
+            std::process::exit(2);
         };
+        let model = args
+            .iter()
+            .position(|a| a == "--model")
+            .and_then(|j| args.get(j + 1))
+            .map(std::path::Path::new);
+        if let Err(e) = bench::bench(std::path::Path::new(dir), model) {
+            println!("Benchmark failed: {:?}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
 
-        let mut sf = match Stockfish::new(engine_path.to_str().unwrap()) {
-            Ok(s) => s,
-            Err(e) => {
-                println!("ERROR: {:?}", e);
-                return;
-            }
+    if args.iter().any(|a| a == "--analyze") {
+        let Some(analyze) = headless::AnalyzeArgs::parse(&args) else {
+            println!("Usage: --analyze <image> [--depth N] [--lines N] [--black] [--json]");
+            std::process::exit(2);
         };
+        if let Err(e) = headless::analyze(&analyze) {
+            println!("Analysis failed: {:?}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
 
-        println!("Worker thread ready");
-        loop {
-            let (region, depth, lines, conf, show_white, fps, running) = {
-                let c = config_clone.lock().unwrap();
-                (
-                    c.board_region.clone(),
-                    c.stockfish_depth,
-                    c.stockfish_lines,
-                    c.confidence_threshold,
-                    c.show_white_moves,
-                    c.fps,
-                    c.running,
-                )
-            };
+    println!("Starting Chess Overlay...");
+    if paths::is_portable() {
+        println!("Portable mode: using {}", paths::app_dir().display());
+    }
 
-            if running {
-                if let Some(r) = region {
-                    if let Ok(img) = capture_region(r.x, r.y, r.width, r.height) {
-                        if let Ok(detections) = detector.detect(&img, conf) {
-                            if let Some(fen) = detections_to_fen(&detections, show_white) {
-                                // Add a retry mechanism for Stockfish
-                                match sf.analyze(&fen, depth, lines) {
-                                    Ok(moves) => {
-                                        let _ = move_tx.send(moves);
-                                    }
-                                    Err(e) => {
-                                        println!("Stockfish Error: {:?}. Attempting restart...", e);
-                                        if let Ok(new_sf) =
-                                            Stockfish::new(engine_path.to_str().unwrap())
-                                        {
-                                            sf = new_sf;
-                                        }
-                                    }
-                                }
-                            } else {
-                                // Illegal FEN (likely missing King in vision)
-                                // Send empty moves to clear old arrows if vision is consistently bad
-                                // let _ = move_tx.send(vec![]);
-                            }
-                        }
-                    }
-                }
-            }
-            thread::sleep(Duration::from_millis(1000 / fps.max(1) as u64));
-        }
-    });
+    let config = Arc::new(Mutex::new(AppConfig::load()));
+    let snapshot = SharedSnapshot::default();
+    let errors = SharedErrors::default();
+
+    // Background worker thread for Vision + Stockfish
+    let (command_tx, command_rx) = unbounded::<WorkerCommand>();
+    worker::spawn(config.clone(), snapshot.clone(), command_rx, errors.clone());
+    server::spawn(config.clone(), snapshot.clone());
+    export::stream::spawn(config.clone(), snapshot.clone(), errors.clone());
 
     // Global Hotkey Listener
-    let config_hotkey = config.clone();
-    thread::spawn(move || {
-        use rdev::{listen, EventType};
-        listen(move |event| {
-            if let EventType::KeyPress(key) = event.event_type {
-                if format!("{:?}", key) == "KeyB" {
-                    let mut c = config_hotkey.lock().unwrap();
-                    c.show_white_moves = !c.show_white_moves;
-                    println!(
-                        "Toggled side: {}",
-                        if c.show_white_moves { "White" } else { "Black" }
-                    );
-                }
-            }
-        })
-        .expect("Failed to listen for hotkeys");
-    });
+    let (hotkey_tx, hotkey_rx) = unbounded::<HotkeyAction>();
+    hotkeys::spawn_listener(config.clone(), hotkey_tx, errors.clone());
 
     // Run Overlay UI
     let options = eframe::NativeOptions {
@@ -130,7 +150,7 @@ fn main() {
 
     let config_ui = config.clone();
     let _ = eframe::run_native(
-        "Chess Overlay Visuals",
+        OVERLAY_TITLE,
         options,
         Box::new(move |cc| {
             let mut visuals = egui::Visuals::dark();
@@ -139,21 +159,89 @@ fn main() {
 
             Ok(Box::new(OverlayWrapper {
                 config: config_ui,
-                move_rx,
-                current_moves: Vec::new(),
+                snapshot,
+                commands: command_tx,
+                errors,
+                hotkey_rx,
+                analysis_board: None,
+                analysis_engine: None,
+                renderers: RendererRegistry::default(),
+                replay: None,
                 selection_mode: false,
+                selection_target: SelectionTarget::Board,
                 selection_start: None,
+                monitors: list_monitors(),
+                placed_monitor: None,
+                quick_controls: None,
+                hud: None,
+                toasts: None,
+                desktop: DesktopKeeper::default(),
             }))
         }),
     );
 }
 
+/// What the rectangle dragged in selection mode is for.
+#[derive(Clone, Copy, PartialEq)]
+enum SelectionTarget {
+    Board,
+    /// Another board, added to `extra_regions`.
+    ExtraBoard,
+    Clock,
+}
+
+/// Search depth, process and engine options of the analysis board's engine.
+type AnalysisSettings = (u32, EngineProcessSettings, EngineOptions);
+
 struct OverlayWrapper {
     config: Arc<Mutex<AppConfig>>,
-    move_rx: Receiver<Vec<String>>,
-    current_moves: Vec<String>,
+    snapshot: SharedSnapshot,
+    /// Requests to the worker, like re-scans and typed moves.
+    commands: Sender<WorkerCommand>,
+    errors: SharedErrors,
+    hotkey_rx: Receiver<HotkeyAction>,
+    analysis_board: Option<AnalysisBoard>,
+    /// Spawned on first use of the analysis board and kept alive
+    /// afterwards, with the depth and engine settings it was started with.
+    analysis_engine: Option<(BackgroundEngine, AnalysisSettings)>,
+    renderers: RendererRegistry,
+    replay: Option<ReplayViewer>,
     selection_mode: bool,
+    selection_target: SelectionTarget,
     selection_start: Option<egui::Pos2>,
+    monitors: Vec<MonitorInfo>,
+    /// Monitor the overlay window was last moved to; `None` until placed.
+    placed_monitor: Option<Option<u32>>,
+    /// Area of the hot-zone quick controls while they are shown.
+    quick_controls: Option<egui::Rect>,
+    /// Area of the info panel as last drawn; the overlay takes the mouse
+    /// over it so the panel can be dragged.
+    hud: Option<egui::Rect>,
+    /// Area of the error toasts as last drawn, likewise interactive.
+    toasts: Option<egui::Rect>,
+    desktop: DesktopKeeper,
+}
+
+impl OverlayWrapper {
+    /// Moves the maximized overlay onto the chosen display (or the primary
+    /// one), so drawing and region selection happen in its coordinates.
+    fn place_on_monitor(&self, ctx: &egui::Context, id: Option<u32>) {
+        let Some(m) = id
+            .and_then(|id| self.monitors.iter().find(|m| m.id == id))
+            .or_else(|| self.monitors.iter().find(|m| m.is_primary))
+        else {
+            return;
+        };
+        ctx.send_viewport_cmd(egui::ViewportCommand::Maximized(false));
+        ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(egui::pos2(
+            m.x as f32, m.y as f32,
+        )));
+        ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(egui::vec2(
+            m.width as f32,
+            m.height as f32,
+        )));
+        ctx.send_viewport_cmd(egui::ViewportCommand::Maximized(true));
+    }
 }
 
 impl eframe::App for OverlayWrapper {
@@ -162,11 +250,34 @@ impl eframe::App for OverlayWrapper {
     }
 
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        while let Ok(moves) = self.move_rx.try_recv() {
-            self.current_moves = moves;
+        // One consistent view of the worker's results for this whole frame
+        let snapshot = self.snapshot.load();
+
+        {
+            let mut c = self.config.lock().unwrap();
+            let mut actions: Vec<HotkeyAction> = self.hotkey_rx.try_iter().collect();
+            if c.global_hotkeys_failed {
+                actions.extend(hotkeys::local_actions(ctx, &c.hotkeys));
+            }
+            for action in actions {
+                trigger(&mut c, action, &self.commands);
+            }
+        }
+
+        let monitor = self.config.lock().unwrap().monitor.as_ref().map(|m| m.id);
+        if self.placed_monitor != Some(monitor) {
+            self.place_on_monitor(ctx, monitor);
+            self.placed_monitor = Some(monitor);
         }
 
         let config_for_settings = self.config.clone();
+        let snapshot_ui = snapshot.clone();
+        let commands = &self.commands;
+        let errors = &self.errors;
+        let boards = &snapshot.boards;
+        let degradation = snapshot.degradation;
+        let engine_health = snapshot.engine.clone();
+        let monitors = &mut self.monitors;
         ctx.show_viewport_immediate(
             egui::ViewportId::from_hash_of("settings_window"),
             egui::ViewportBuilder::default()
@@ -177,6 +288,96 @@ impl eframe::App for OverlayWrapper {
             move |ctx, _class| {
                 egui::CentralPanel::default().show(ctx, |ui| {
                     let mut c = config_for_settings.lock().unwrap();
+                    let status = &snapshot_ui.status;
+                    if c.global_hotkeys_failed {
+                        match c.capturing_hotkey {
+                            Some(action) => {
+                                if let Some(key) = hotkeys::pressed_key(ctx) {
+                                    if key != egui::Key::Escape {
+                                        c.hotkeys.bind(action, key.name());
+                                    }
+                                    c.capturing_hotkey = None;
+                                }
+                            }
+                            None => {
+                                for action in hotkeys::local_actions(ctx, &c.hotkeys) {
+                                    trigger(&mut c, action, commands);
+                                }
+                            }
+                        }
+                    }
+
+                    if !c.config_issues.is_empty() {
+                        ui.colored_label(
+                            egui::Color32::YELLOW,
+                            format!("⚠ {} problem(s) in config.json", c.config_issues.len()),
+                        );
+                        ui.collapsing("Config problems", |ui| {
+                            for issue in &c.config_issues {
+                                ui.label(format!("{}: {}", issue.key, issue.problem));
+                                ui.small(format!("→ {}", issue.suggestion));
+                            }
+                        });
+                        if ui.button("Save corrected config").clicked() {
+                            match c.save() {
+                                Ok(_) => c.config_issues.clear(),
+                                Err(e) => {
+                                    let error = format!("Failed to save config: {:?}", e);
+                                    errors.report(AppError::System(error));
+                                }
+                            }
+                        }
+                        ui.separator();
+                    }
+
+                    ui.horizontal(|ui| {
+                        let current = c.profile.clone();
+                        let mut picked = None;
+                        egui::ComboBox::from_label("Profile")
+                            .selected_text(current.as_deref().unwrap_or("Default"))
+                            .show_ui(ui, |ui| {
+                                let names = profile_names().into_iter().map(Some);
+                                for name in std::iter::once(None).chain(names) {
+                                    let label = name.as_deref().unwrap_or("Default").to_string();
+                                    if ui.selectable_label(name == current, label).clicked() {
+                                        picked = Some(name);
+                                    }
+                                }
+                            });
+                        if let Some(name) = picked.filter(|n| *n != current) {
+                            if let Err(e) = c.switch_profile(name) {
+                                errors.report(AppError::System(format!(
+                                    "Failed to switch profile: {:?}",
+                                    e
+                                )));
+                            }
+                        }
+                    })
+                    .response
+                    .on_hover_text(
+                        "Each profile has its own regions, engine settings and overlay look",
+                    );
+                    ui.horizontal(|ui| {
+                        let id = ui.id().with("new_profile");
+                        let mut name: String =
+                            ui.data_mut(|d| d.get_temp(id)).unwrap_or_default();
+                        ui.add(egui::TextEdit::singleline(&mut name).hint_text("New profile name"));
+                        let valid = profile_name(&name);
+                        let button = ui
+                            .add_enabled(valid.is_ok(), egui::Button::new("➕ Save as profile"))
+                            .on_disabled_hover_text(valid.err().unwrap_or_default());
+                        if button.clicked() {
+                            match c.save_as_profile(&name) {
+                                Ok(_) => name.clear(),
+                                Err(e) => errors.report(AppError::System(format!(
+                                    "Failed to create profile: {:?}",
+                                    e
+                                ))),
+                            }
+                        }
+                        ui.data_mut(|d| d.insert_temp(id, name));
+                    });
+                    ui.separator();
 
                     ui.heading("Analysis Control");
                     ui.horizontal(|ui| {
@@ -184,7 +385,20 @@ impl eframe::App for OverlayWrapper {
                             if ui.button("⏹ STOP").clicked() {
                                 c.running = false;
                             }
-                            ui.label("🟢 Running");
+                            let pause = if c.analysis_paused {
+                                "▶ Resume"
+                            } else {
+                                "⏸ Pause"
+                            };
+                            if ui.button(pause).clicked() {
+                                c.analysis_paused = !c.analysis_paused;
+                            }
+                            match status.auto_paused {
+                                _ if c.analysis_paused => ui.label("⏸ Paused"),
+                                Some(reason) => ui.label(format!("⏸ Paused: {}", reason.label())),
+                                None if status.idle_throttled => ui.label("💤 Idle"),
+                                None => ui.label("🟢 Running"),
+                            };
                         } else {
                             let can_start = c.board_region.is_some();
                             if ui
@@ -192,6 +406,7 @@ impl eframe::App for OverlayWrapper {
                                 .clicked()
                             {
                                 c.running = true;
+                                c.analysis_paused = false;
                             }
                             if !can_start {
                                 ui.label("⚠ Select region");
@@ -201,24 +416,991 @@ impl eframe::App for OverlayWrapper {
                         }
                     });
 
+                    ui.checkbox(&mut c.auto_pause_hidden, "Pause while the game window is hidden")
+                        .on_hover_text("Minimized or on another virtual desktop");
+                    ui.add_enabled(
+                        c.auto_pause_hidden,
+                        egui::Checkbox::new(&mut c.pause_when_unfocused, "Also while it isn't focused"),
+                    );
+
+                    for (i, board) in boards.iter().enumerate() {
+                        if let Some(board) = board {
+                            ui.small(format!(
+                                "Board {}: updated {} ago",
+                                i + 1,
+                                format_age(board.analyzed_at)
+                            ));
+                        }
+                    }
+                    if degradation != DegradationLevel::Normal {
+                        ui.label(format!("⚠ Performance: {}", degradation.label()));
+                    }
+                    if let Some(err) = &status.capture_error {
+                        ui.colored_label(egui::Color32::RED, format!("⚠ Capture failing: {}", err));
+                    }
+                    if status.screen_permission_missing {
+                        ui.colored_label(
+                            egui::Color32::YELLOW,
+                            "⚠ Screen Recording permission needed to see the board",
+                        );
+                        ui.collapsing("How to allow screen recording", |ui| {
+                            for step in [
+                                "1. Open System Settings → Privacy & Security → Screen Recording",
+                                "2. Turn this app on there (add it with + if it's missing)",
+                                "3. Capture starts as soon as macOS reports the permission",
+                            ] {
+                                ui.label(step);
+                            }
+                            ui.small("If it still isn't picked up, quit and reopen the app.");
+                            ui.horizontal(|ui| {
+                                if ui.button("Open Screen Recording settings").clicked() {
+                                    if let Err(e) = open_screen_recording_settings() {
+                                        errors.report(AppError::System(format!(
+                                            "Failed to open System Settings: {:?}",
+                                            e
+                                        )));
+                                    }
+                                }
+                                if ui.button("Check again").clicked() {
+                                    let _ = commands.send(WorkerCommand::CheckCapture);
+                                }
+                            });
+                        });
+                    }
+
+                    if !matches!(engine_health, EngineHealth::Running) {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("⚠ {}", engine_health.label()));
+                            if ui.button("Restart engine").clicked() {
+                                let _ = commands.send(WorkerCommand::RestartEngine);
+                            }
+                        });
+                    }
+                    let updated: Vec<&str> = [
+                        (status.model_updated, "The model"),
+                        (status.engine_updated, ENGINE_BINARY),
+                    ]
+                    .into_iter()
+                    .filter_map(|(updated, name)| updated.then_some(name))
+                    .collect();
+                    if !updated.is_empty() {
+                        ui.colored_label(
+                            egui::Color32::YELLOW,
+                            format!("{} changed on disk", updated.join(" and ")),
+                        );
+                        ui.horizontal(|ui| {
+                            if ui
+                                .button("Reload")
+                                .on_hover_text("Keeps the region and the tracked game")
+                                .clicked()
+                            {
+                                let _ = commands.send(WorkerCommand::ReloadFiles);
+                            }
+                            if ui.button("Ignore").clicked() {
+                                let _ = commands.send(WorkerCommand::IgnoreFileUpdates);
+                            }
+                        });
+                    }
+                    if let Some(problem) = &status.engine_problem {
+                        ui.colored_label(egui::Color32::RED, format!("⚠ {}", problem));
+                        ui.label(problem.advice());
+                        ui.hyperlink_to("Download Stockfish", DOWNLOAD_URL);
+                        ui.small("Restart the app after replacing the engine");
+                    }
+
                     ui.separator();
                     ui.label("Stockfish Settings");
-                    ui.add(egui::Slider::new(&mut c.stockfish_depth, 1..=30).text("Depth"));
-                    ui.add(egui::Slider::new(&mut c.stockfish_lines, 1..=5).text("Lines"));
+                    egui::ComboBox::from_label("Search by")
+                        .selected_text(c.search_mode.label())
+                        .show_ui(ui, |ui| {
+                            for m in SearchMode::ALL {
+                                ui.selectable_value(&mut c.search_mode, m, m.label());
+                            }
+                        });
+                    match c.search_mode {
+                        SearchMode::Depth => {
+                            ui.add(egui::Slider::new(&mut c.stockfish_depth, 1..=30).text("Depth"));
+                        }
+                        SearchMode::MoveTime => {
+                            ui.add(
+                                egui::Slider::new(&mut c.stockfish_time_ms, 50..=10_000)
+                                    .logarithmic(true)
+                                    .text("Move time (ms)"),
+                            );
+                        }
+                        SearchMode::Nodes => {
+                            ui.add(
+                                egui::Slider::new(&mut c.stockfish_nodes, 10_000..=50_000_000)
+                                    .logarithmic(true)
+                                    .text("Nodes"),
+                            );
+                        }
+                        SearchMode::Infinite => {
+                            ui.small("Searches until the position changes; lines update live");
+                        }
+                    }
+                    if status.engine_multipv == Some(false) {
+                        ui.small("Lines: 1 (this engine does not support MultiPV)");
+                    } else {
+                        ui.add(egui::Slider::new(&mut c.stockfish_lines, 1..=5).text("Lines"));
+                    }
+                    ui.checkbox(&mut c.sparring, "Sparring (varied suggestions)");
+                    ui.checkbox(
+                        &mut c.premove_planning,
+                        "Premove planning on opponent's turn",
+                    );
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut c.human_mode, "Human move at");
+                        ui.add_enabled(
+                            c.human_mode,
+                            egui::Slider::new(&mut c.human_elo, 1320..=3190).text("Elo"),
+                        );
+                    })
+                    .response
+                    .on_hover_text("Also mark the move a player of this rating would likely make");
+                    ui.checkbox(&mut c.threat_arrows, "Threat arrows")
+                        .on_hover_text("Show what the opponent would play if you passed");
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut c.blunder_alert, "Blunder alert over");
+                        ui.add_enabled(
+                            c.blunder_alert,
+                            egui::Slider::new(&mut c.blunder_threshold_cp, 50..=1000).text("cp"),
+                        );
+                    })
+                    .response
+                    .on_hover_text("Flash the board and show the refutation after a bad move");
+                    ui.checkbox(&mut c.use_opening_book, "Opening book")
+                        .on_hover_text("Show book moves instead of searching positions in book");
+                    if c.use_opening_book {
+                        ui.horizontal(|ui| {
+                            ui.label("Book file (.bin)");
+                            deferred_text_edit(
+                                ui,
+                                "opening_book_path",
+                                &mut c.opening_book_path,
+                                "book.bin",
+                            );
+                        });
+                        if let Some(message) = &status.book_status {
+                            ui.small(message);
+                        }
+                    }
+                    ui.add(
+                        egui::Slider::new(&mut c.off_turn_depth_ratio, 0.2..=1.0)
+                            .text("Off-turn search share"),
+                    )
+                    .on_hover_text(
+                        "Search limit for the side not to move, relative to the side to move",
+                    );
+                    egui::ComboBox::from_label("Engine priority")
+                        .selected_text(c.engine_process.priority.label())
+                        .show_ui(ui, |ui| {
+                            for p in EnginePriority::ALL {
+                                ui.selectable_value(&mut c.engine_process.priority, p, p.label());
+                            }
+                        });
+                    // macOS has no way to pin a process to cores
+                    if !cfg!(target_os = "macos") {
+                        ui.horizontal(|ui| {
+                            ui.label("CPU affinity mask (0 = all)");
+                            ui.add(
+                                egui::DragValue::new(&mut c.engine_process.affinity_mask)
+                                    .hexadecimal(4, false, true),
+                            );
+                        });
+                    }
+                    ui.small("Priority/affinity apply on next engine start");
+                    ui.collapsing("Engine options", |ui| {
+                        let o = &mut c.engine_options;
+                        let cores = std::thread::available_parallelism()
+                            .map(|n| n.get() as u32)
+                            .unwrap_or(8)
+                            .max(o.threads);
+                        ui.add(egui::Slider::new(&mut o.threads, 1..=cores).text("Threads"));
+                        ui.add(
+                            egui::Slider::new(&mut o.hash_mb, 16..=4096)
+                                .logarithmic(true)
+                                .text("Hash (MB)"),
+                        );
+                        ui.add(egui::Slider::new(&mut o.skill_level, 0..=20).text("Skill Level"));
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut o.limit_strength, "Limit strength to");
+                            ui.add_enabled(
+                                o.limit_strength,
+                                egui::Slider::new(&mut o.elo, 1320..=3190).text("Elo"),
+                            );
+                        });
+                        ui.add(egui::Slider::new(&mut o.contempt, -100..=100).text("Contempt"))
+                            .on_hover_text("Only sent when non-zero; Stockfish 12+ ignores it");
+                        ui.horizontal(|ui| {
+                            ui.label("Syzygy path");
+                            deferred_text_edit(ui, "syzygy_path", &mut o.syzygy_path, "");
+                        });
+                        if *o != EngineOptions::default() && ui.button("Reset options").clicked() {
+                            *o = EngineOptions::default();
+                        }
+                        ui.small("Applied to the running engine right away");
+                    });
+                    for err in &status.engine_option_errors {
+                        ui.colored_label(egui::Color32::RED, format!("⚠ {}", err));
+                    }
 
                     ui.separator();
                     ui.label("Vision Settings");
+                    ui.horizontal(|ui| {
+                        ui.label("Model (.onnx)");
+                        deferred_text_edit(ui, "model_path", &mut c.model_path, "best.onnx");
+                        if ui.button("Browse…").clicked() {
+                            let picked = rfd::FileDialog::new()
+                                .add_filter("ONNX model", &["onnx"])
+                                .set_directory(paths::app_dir())
+                                .pick_file();
+                            if let Some(path) = picked {
+                                c.model_path = path.to_string_lossy().into_owned();
+                            }
+                        }
+                    });
+                    if let Some(message) = &status.model_status {
+                        ui.small(message);
+                    }
+                    egui::ComboBox::from_label("Inference backend")
+                        .selected_text(c.inference_backend.label())
+                        .show_ui(ui, |ui| {
+                            for b in InferenceBackend::ALL {
+                                if b.is_available() {
+                                    ui.selectable_value(&mut c.inference_backend, b, b.label());
+                                }
+                            }
+                        });
+                    match status.inference_active {
+                        Some(active) if active != c.inference_backend => {
+                            ui.colored_label(
+                                egui::Color32::YELLOW,
+                                format!(
+                                    "⚠ {} unavailable, running on {}",
+                                    c.inference_backend.label(),
+                                    active.label()
+                                ),
+                            );
+                        }
+                        Some(active) => {
+                            ui.small(format!("Running on {}", active.label()));
+                        }
+                        None => {}
+                    }
+                    let mut site = c.site;
+                    egui::ComboBox::from_label("Site")
+                        .selected_text(site.label())
+                        .show_ui(ui, |ui| {
+                            for s in Site::ALL {
+                                ui.selectable_value(&mut site, s, s.label());
+                            }
+                        })
+                        .response
+                        .on_hover_text(
+                            "Sets confidence, board margin, minimum board size, coordinate \
+                             strip and grid search for the site's default theme",
+                        );
+                    if site != c.site {
+                        if let Some(profile) = site.profile() {
+                            profile.apply(&mut c);
+                        }
+                        c.site = site;
+                    }
                     ui.add(
                         egui::Slider::new(&mut c.confidence_threshold, 0.1..=1.0)
                             .text("Confidence"),
                     );
+                    ui.add(
+                        egui::Slider::new(&mut c.iou_threshold, 0.1..=0.9).text("Overlap (IoU)"),
+                    )
+                    .on_hover_text(
+                        "Detections overlapping a stronger one by more than this are dropped; \
+                         lower it if pieces are detected twice",
+                    );
+                    ui.checkbox(&mut c.refine_board_grid, "Find squares inside the board box")
+                        .on_hover_text(
+                            "Locates the 8x8 grid from the square edges, for sites that draw \
+                             coordinates or a frame around the board",
+                        );
+                    ui.add(
+                        egui::Slider::new(&mut c.coordinate_strip, 0.0..=0.1)
+                            .text("Coordinate strip")
+                            .custom_formatter(|v, _| format!("{:.1}%", v * 100.0)),
+                    )
+                    .on_hover_text(
+                        "Share of the board box taken by coordinates drawn outside the squares, \
+                         cut from its left and bottom edges",
+                    );
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut c.color_check, "Check piece colors")
+                            .on_hover_text(
+                                "Decides white/black from brightness for pieces the model is \
+                                 unsure about, for unusual board and piece themes",
+                            );
+                        if c.color_check {
+                            ui.add(
+                                egui::Slider::new(&mut c.color_check_below, 0.2..=1.0)
+                                    .text("below confidence"),
+                            );
+                        }
+                    });
+                    let label = "Move doubled pieces to a free neighbor";
+                    ui.checkbox(&mut c.reassign_cell_conflicts, label)
+                        .on_hover_text(
+                            "When two pieces land on one square, the weaker one goes to the empty \
+                             square it leans toward instead of being dropped",
+                        );
                     ui.checkbox(&mut c.show_white_moves, "Show White (B key)");
+                    ui.checkbox(&mut c.auto_side_to_move, "Detect side to move");
+                    if c.auto_side_to_move {
+                        let side = match status.side_to_move {
+                            Some(true) => "White to move",
+                            Some(false) => "Black to move",
+                            None => "Waiting for a move",
+                        };
+                        ui.small(side);
+                    }
+                    ui.checkbox(&mut c.split_region, "Two boards in region (bughouse)");
+                    ui.horizontal(|ui| {
+                        let add = ui.add_enabled(
+                            c.board_region.is_some(),
+                            egui::Button::new("➕ Add board region"),
+                        );
+                        if add
+                            .on_hover_text("Analyze another board too, such as a second game")
+                            .clicked()
+                        {
+                            c.request_extra_selection = true;
+                        }
+                        if !c.extra_regions.is_empty() {
+                            ui.label(format!("{} more", c.extra_regions.len()));
+                            if ui.small_button("✖").on_hover_text("Remove them").clicked() {
+                                let main = c.board_regions().len() - c.extra_regions.len();
+                                c.board_sides.truncate(main);
+                                c.extra_regions.clear();
+                            }
+                        }
+                    });
+                    // The first board is played from the "Show White" side
+                    for i in 1..c.board_regions().len() {
+                        let before = c.board_sides.get(i).copied().unwrap_or_default();
+                        let mut side = before;
+                        egui::ComboBox::from_label(format!("Board {} played as", i + 1))
+                            .selected_text(side.label())
+                            .show_ui(ui, |ui| {
+                                for option in BoardSide::ALL {
+                                    ui.selectable_value(&mut side, option, option.label());
+                                }
+                            });
+                        if side != before {
+                            if c.board_sides.len() <= i {
+                                c.board_sides.resize(i + 1, BoardSide::Main);
+                            }
+                            c.board_sides[i] = side;
+                        }
+                    }
+                    ui.add(
+                        egui::Slider::new(&mut c.min_board_px, 40..=800)
+                            .text("Min board size (px)"),
+                    );
+                    if status.board_candidates > 1 {
+                        ui.horizontal(|ui| {
+                            ui.label(format!(
+                                "{} boards found, using #{}",
+                                status.board_candidates,
+                                c.board_cycle % status.board_candidates + 1
+                            ));
+                            let next = format!(
+                                "Next ({})",
+                                display_key(c.hotkeys.key(HotkeyAction::NextBoard))
+                            );
+                            if ui.button(next).clicked() {
+                                c.board_cycle = c.board_cycle.wrapping_add(1);
+                            }
+                        });
+                    }
+                    if c.collect_samples {
+                        ui.label(format!("📷 Collecting samples: {}", status.samples_collected));
+                        if ui.button("Stop collecting").clicked() {
+                            c.collect_samples = false;
+                        }
+                    } else if status.low_confidence {
+                        ui.colored_label(
+                            egui::Color32::YELLOW,
+                            "⚠ Low recognition confidence for a while (unsupported theme?)",
+                        );
+                        if ui.button("📷 Collect samples for retraining").clicked() {
+                            c.collect_samples = true;
+                        }
+                    }
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut c.record_frames, "⏺ Record frames for replay");
+                        if c.record_frames {
+                            ui.small(format!("{} saved", status.frames_recorded));
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut c.debug_capture, "🐞 Export detection images");
+                        if c.debug_capture {
+                            ui.small(format!("{} written", status.debug_images_written));
+                        }
+                    });
+                    ui.checkbox(&mut c.show_detections, "🔲 Show detection boxes")
+                        .on_hover_text("Draws every detected piece and board with its confidence");
+                    ui.horizontal(|ui| {
+                        let label = "💾 Save failed recognitions";
+                        ui.checkbox(&mut c.dump_failed_recognitions, label)
+                            .on_hover_text(
+                                "Saves the crop, detections and attempted FEN into debug_dumps/ \
+                                 when a position is rejected",
+                            );
+                        if status.failure_dumps_written > 0 {
+                            ui.small(format!("{} saved", status.failure_dumps_written));
+                        }
+                    });
+                    if !status.board_debug.is_empty() {
+                        ui.collapsing(format!("Rejected boards ({})", status.board_debug.len()), |ui| {
+                            for line in &status.board_debug {
+                                ui.small(line);
+                            }
+                        });
+                    }
+                    egui::ComboBox::from_label("Capture from")
+                        .selected_text(c.capture_source.label())
+                        .show_ui(ui, |ui| {
+                            for k in CaptureSourceKind::ALL {
+                                ui.selectable_value(&mut c.capture_source, k, k.label());
+                            }
+                        });
+                    if c.capture_source == CaptureSourceKind::ImageSequence {
+                        ui.horizontal(|ui| {
+                            ui.label("Image folder");
+                            deferred_text_edit(
+                                ui,
+                                "image_sequence_dir",
+                                &mut c.image_sequence_dir,
+                                "",
+                            );
+                        })
+                        .response
+                        .on_hover_text("Regions are in image pixels; images play in name order");
+                    }
+                    ui.add(egui::Slider::new(&mut c.capture_fps, 1..=30).text("Capture FPS"));
+                    ui.checkbox(&mut c.show_capture_stats, "Show capture rate on overlay");
+                    ui.add(
+                        egui::Slider::new(&mut c.post_move_cooldown_ms, 0..=2000)
+                            .text("Post-move cooldown (ms)"),
+                    );
+                    ui.add(egui::Slider::new(&mut c.fps, 1..=10).text("Analysis FPS"));
+                    ui.add(
+                        egui::Slider::new(&mut c.idle_after_secs, 0..=600)
+                            .text("Idle after (s)"),
+                    )
+                    .on_hover_text(
+                        "Capture less often once no board has changed this long; 0 = never",
+                    );
+                    ui.add_enabled(
+                        c.idle_after_secs > 0,
+                        egui::Slider::new(&mut c.idle_interval_ms, 100..=10_000)
+                            .text("Idle capture interval (ms)"),
+                    );
+
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.label("Missed move:");
+                        let response = ui.add(
+                            egui::TextEdit::singleline(&mut c.manual_move_input)
+                                .hint_text("e.g. e5")
+                                .desired_width(80.0),
+                        );
+                        if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                            let text = std::mem::take(&mut c.manual_move_input);
+                            if !text.trim().is_empty() {
+                                let text = c.notation.english_from(&text);
+                                let _ = commands.send(WorkerCommand::PlayMove(text));
+                            }
+                        }
+                    });
+                    if let Some(reason) = &status.rejected_position {
+                        ui.colored_label(
+                            egui::Color32::YELLOW,
+                            format!("⚠ Board not analyzed: {}", reason),
+                        );
+                    }
+                    if status.tracker_lost {
+                        ui.colored_label(
+                            egui::Color32::YELLOW,
+                            "⚠ Lost track of the game, type the missed move",
+                        );
+                    }
+                    let mut log = errors.lock();
+                    if !log.is_empty() {
+                        egui::CollapsingHeader::new("Errors").show(ui, |ui| {
+                            for e in log.entries() {
+                                ui.colored_label(
+                                    egui::Color32::LIGHT_RED,
+                                    format!(
+                                        "{} (×{}, first {} ago, last {} ago)",
+                                        e.error,
+                                        e.count,
+                                        format_age(e.first_seen),
+                                        format_age(e.last_seen)
+                                    ),
+                                );
+                            }
+                        });
+                        if ui.button("Clear errors").clicked() {
+                            log.clear();
+                        }
+                    }
+                    drop(log);
+                    egui::CollapsingHeader::new("Memory").show(ui, |ui| {
+                        match status.memory {
+                            Some(m) => {
+                                let text = format!(
+                                    "{} MB in use ({:+} MB since start)",
+                                    m.resident_mb, m.growth_mb
+                                );
+                                if m.over_limit {
+                                    ui.colored_label(egui::Color32::YELLOW, format!("⚠ {}", text));
+                                } else {
+                                    ui.label(text);
+                                }
+                            }
+                            None => {
+                                ui.label("Memory use unavailable");
+                            }
+                        }
+                        ui.add(
+                            egui::Slider::new(&mut c.memory_warn_mb, 50..=8192)
+                                .logarithmic(true)
+                                .text("Warn after growth (MB)"),
+                        );
+                        ui.add(
+                            egui::Slider::new(&mut c.reclaim_interval_hours, 0..=48)
+                                .text("Reclaim every (hours, 0 = off)"),
+                        );
+                        ui.checkbox(&mut c.reclaim_on_warning, "Reclaim when the warning fires");
+                        if ui.button("Reclaim now").clicked() {
+                            let _ = commands.send(WorkerCommand::ReclaimMemory);
+                        }
+                        ui.small("Reclaiming reloads the model and restarts the engine");
+                    });
+                    egui::CollapsingHeader::new("Platform").show(ui, |ui| {
+                        ui.label(display_server().label());
+                        for capability in capabilities() {
+                            let text =
+                                format!("{} {}", capability.support.icon(), capability.name);
+                            let color = match capability.support {
+                                Support::Full => ui.visuals().text_color(),
+                                Support::Degraded => egui::Color32::YELLOW,
+                                Support::Missing => egui::Color32::LIGHT_RED,
+                            };
+                            let label = ui.colored_label(color, text);
+                            if !capability.note.is_empty() {
+                                label.on_hover_text(capability.note);
+                            }
+                        }
+                        ui.horizontal(|ui| {
+                            match &status.capture_check {
+                                Some(Ok(())) => ui.label("Test capture worked"),
+                                Some(Err(e)) => ui.colored_label(
+                                    egui::Color32::LIGHT_RED,
+                                    format!("Test capture failed: {}", e),
+                                ),
+                                None => ui.label("Test capture pending"),
+                            };
+                            if ui.button("Test again").clicked() {
+                                let _ = commands.send(WorkerCommand::CheckCapture);
+                            }
+                        });
+                    });
+                    egui::CollapsingHeader::new("Sharing").show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut c.api_server, "Local API server")
+                                .on_hover_text(
+                                    "Serves the latest FEN, evaluation and lines as JSON at \
+                                     /position, and streams them over a WebSocket at /ws",
+                                );
+                            ui.label("Port");
+                            ui.add(egui::DragValue::new(&mut c.api_port).range(1024..=65535));
+                        });
+                        if let Some(status) = c.api_status.as_ref().filter(|_| c.api_server) {
+                            ui.small(status);
+                        }
+                        ui.horizontal(|ui| {
+                            ui.label("Allowed web origins");
+                            let mut text = c.api_allowed_origins.join(", ");
+                            let hint = "none; e.g. https://example.com";
+                            if deferred_text_edit(ui, "api_allowed_origins", &mut text, hint) {
+                                c.api_allowed_origins = text
+                                    .split(',')
+                                    .map(|o| o.trim().to_string())
+                                    .filter(|o| !o.is_empty())
+                                    .collect();
+                            }
+                        })
+                        .response
+                        .on_hover_text(
+                            "Web pages from other sites are refused so they can't read the \
+                             analysis; list sites of your own that should be served",
+                        );
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut c.stream_output, "Stream output files")
+                                .on_hover_text(
+                                    "Writes eval.txt, best_line.txt and fen.txt for OBS text \
+                                     sources",
+                                );
+                            ui.checkbox(&mut c.stream_output_html, "HTML page");
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Folder");
+                            deferred_text_edit(
+                                ui,
+                                "stream_output_dir",
+                                &mut c.stream_output_dir,
+                                "stream/ in the app folder",
+                            );
+                        });
+                    });
+                    if let Some(message) = &status.manual_move_status {
+                        ui.small(message);
+                    }
 
                     ui.separator();
-                    if ui.button("📐 Select Board Region").clicked() {
-                        c.request_selection = true;
+                    ui.horizontal(|ui| {
+                        let selected = c
+                            .monitor
+                            .as_ref()
+                            .map_or("Primary".to_string(), |m| m.label.clone());
+                        let mut choice = c.monitor.clone();
+                        egui::ComboBox::from_label("Monitor")
+                            .selected_text(selected)
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut choice, None, "Primary");
+                                for m in monitors.iter() {
+                                    let option = Some(MonitorChoice {
+                                        id: m.id,
+                                        label: m.label.clone(),
+                                    });
+                                    ui.selectable_value(&mut choice, option, &m.label);
+                                }
+                            });
+                        if ui
+                            .small_button("↻")
+                            .on_hover_text("Refresh monitors")
+                            .clicked()
+                        {
+                            *monitors = list_monitors();
+                        }
+                        if choice != c.monitor {
+                            // The old region is relative to the previous display
+                            c.monitor = choice;
+                            c.board_region = None;
+                            c.extra_regions.clear();
+                            c.board_sides.clear();
+                            c.clock_region = None;
+                            c.request_selection = true;
+                        }
+                    });
+                    if let Some(m) = &c.monitor {
+                        if !monitors.iter().any(|info| info.id == m.id) {
+                            ui.colored_label(
+                                egui::Color32::YELLOW,
+                                format!("⚠ {} not connected, using primary", m.label),
+                            );
+                        }
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.button("📐 Select Board Region").clicked() {
+                            c.request_selection = true;
+                        }
+                        if ui.button("🔍 Auto-detect board").clicked() {
+                            let _ = commands.send(WorkerCommand::DetectRegion);
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Margin");
+                        ui.add(
+                            egui::Slider::new(&mut c.auto_region_padding, 0.0..=0.2)
+                                .custom_formatter(|v, _| format!("{:.0}%", v * 100.0)),
+                        );
+                        ui.checkbox(&mut c.auto_track_board, "Follow board");
+                    });
+                    ui.checkbox(&mut c.auto_rescale_region, "Rescale region on zoom")
+                        .on_hover_text(
+                            "Grow or shrink a selected region about its center when the board \
+                             inside it is resized (browser zoom, resized board)",
+                        );
+                    if let Some(message) = &status.auto_detect_status {
+                        ui.small(message);
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.button("⏱ Select clock").clicked() {
+                            c.request_clock_selection = true;
+                        }
+                        if c.clock_region.is_some() && ui.small_button("✖").clicked() {
+                            c.clock_region = None;
+                        }
+                        ui.add_enabled(
+                            c.clock_region.is_some(),
+                            egui::Checkbox::new(&mut c.clock_time_management, "Adapt to clock"),
+                        )
+                        .on_hover_text(
+                            "Read your remaining time and search faster in a time scramble, \
+                             deeper when there is plenty of time",
+                        );
+                    });
+                    if c.clock_time_management && c.clock_region.is_some() {
+                        match status.clock_remaining {
+                            Some(left) => ui.small(format!(
+                                "Clock: {}:{:02}",
+                                left.as_secs() / 60,
+                                left.as_secs() % 60
+                            )),
+                            None => ui.small("Clock: not read yet"),
+                        };
+                    }
+
+                    egui::ComboBox::from_label("Notation")
+                        .selected_text(c.notation.label())
+                        .show_ui(ui, |ui| {
+                            for n in Notation::ALL {
+                                ui.selectable_value(&mut c.notation, n, n.label());
+                            }
+                        });
+                    egui::ComboBox::from_label("Piece set")
+                        .selected_text(c.piece_theme.label())
+                        .show_ui(ui, |ui| {
+                            for t in PieceTheme::ALL {
+                                ui.selectable_value(&mut c.piece_theme, t, t.label());
+                            }
+                        });
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut c.hot_zone.enabled, "Hot corner");
+                        egui::ComboBox::from_id_source("hot_corner")
+                            .selected_text(c.hot_zone.corner.label())
+                            .show_ui(ui, |ui| {
+                                for corner in HotCorner::ALL {
+                                    ui.selectable_value(
+                                        &mut c.hot_zone.corner,
+                                        corner,
+                                        corner.label(),
+                                    );
+                                }
+                            });
+                        ui.add(egui::DragValue::new(&mut c.hot_zone.size).range(8.0..=200.0));
+                    })
+                    .response
+                    .on_hover_text("Hover this corner to use the overlay with the mouse");
+                    ui.collapsing("Hotkeys", |ui| {
+                        if c.global_hotkeys_failed {
+                            ui.colored_label(
+                                egui::Color32::YELLOW,
+                                "⚠ Global hotkeys unavailable; they only work while this window has focus",
+                            );
+                        }
+                        egui::Grid::new("hotkeys").show(ui, |ui| {
+                            for action in HotkeyAction::ALL {
+                                ui.label(action.label());
+                                let text = if c.capturing_hotkey == Some(action) {
+                                    "Press a key… (Esc cancels)".to_string()
+                                } else {
+                                    display_key(c.hotkeys.key(action)).to_string()
+                                };
+                                if ui.button(text).clicked() {
+                                    c.capturing_hotkey = Some(action);
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    });
+                    ui.collapsing("Click-to-play", |ui| {
+                        let key = display_key(c.hotkeys.key(HotkeyAction::PlayMove)).to_string();
+                        let auto = &mut c.auto_move;
+                        ui.checkbox(&mut auto.enabled, format!("Play best move on {}", key))
+                            .on_hover_text("Clicks the first board's best move with the mouse");
+                        ui.add_enabled_ui(auto.enabled, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.label("Delay (ms)");
+                                ui.add(
+                                    egui::DragValue::new(&mut auto.min_delay_ms).range(0..=10_000),
+                                );
+                                ui.label("to");
+                                ui.add(
+                                    egui::DragValue::new(&mut auto.max_delay_ms).range(0..=10_000),
+                                );
+                            });
+                            ui.checkbox(&mut auto.own_turn_only, "Only on my turn");
+                            ui.checkbox(&mut auto.restore_cursor, "Put the cursor back afterwards");
+                        });
+                        if let Some(status) = &c.auto_move_status {
+                            ui.small(status);
+                        }
+                    });
+                    egui::ComboBox::from_label("Virtual desktops")
+                        .selected_text(c.overlay_desktop.label())
+                        .show_ui(ui, |ui| {
+                            for m in DesktopMode::ALL {
+                                ui.selectable_value(&mut c.overlay_desktop, m, m.label());
+                            }
+                        });
+                    ui.checkbox(&mut c.show_presentation_window, "Presentation window");
+                    ui.add_enabled_ui(c.show_presentation_window, |ui| {
+                        ui.indent("move_list_settings", |ui| {
+                            ui.checkbox(&mut c.overlay_elements.mini_board, "Mini-board");
+                            ui.checkbox(&mut c.show_move_list, "Move list panel");
+                            ui.add_enabled(
+                                c.show_move_list,
+                                egui::Checkbox::new(&mut c.show_move_clocks, "Time used per side"),
+                            )
+                            .on_hover_text(
+                                "Measured between detected moves; on-screen clocks are not read",
+                            );
+                        });
+                    });
+                    ui.collapsing("Overlay elements", |ui| {
+                        let elements = &mut c.overlay_elements;
+                        ui.checkbox(&mut elements.arrows, "Arrows");
+                        ui.checkbox(&mut elements.quality_badges, "Quality badges on arrows");
+                        ui.checkbox(&mut elements.move_colors, "Color arrows by move quality");
+                        ui.checkbox(&mut elements.legend, "Arrow color legend");
+                        ui.checkbox(&mut elements.premoves, "Premove arrows");
+                        ui.checkbox(&mut elements.threats, "Threat arrow");
+                        ui.checkbox(&mut elements.human_move, "Human-mode move");
+                        ui.checkbox(&mut elements.book, "Opening book moves");
+                        ui.checkbox(&mut elements.blunder, "Blunder alerts");
+                        ui.checkbox(&mut elements.labels, "Move labels");
+                        ui.checkbox(&mut elements.heatmap, "Target square heatmap");
+                        ui.checkbox(&mut elements.eval_bar, "Evaluation bar");
+                        ui.checkbox(&mut elements.hud, "Status / HUD");
+                        ui.checkbox(&mut elements.info_panel, "Info panel (eval, depth, speed)");
+                        ui.checkbox(&mut elements.error_toasts, "Error notifications");
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut c.overlay_elements.variation, "Principal variation");
+                            ui.add_enabled(
+                                c.overlay_elements.variation,
+                                egui::Slider::new(&mut c.pv_length, 2..=12).text("plies"),
+                            );
+                        });
+                        ui.separator();
+                        egui::ComboBox::from_label("Show moves as")
+                            .selected_text(c.move_highlight.label())
+                            .show_ui(ui, |ui| {
+                                for h in MoveHighlight::ALL {
+                                    ui.selectable_value(&mut c.move_highlight, h, h.label());
+                                }
+                            });
+                        ui.label("Arrow style");
+                        let style = &mut c.arrow_style;
+                        ui.add(
+                            egui::Slider::new(&mut style.thickness, 0.04..=0.4)
+                                .text("Thickness (squares)"),
+                        );
+                        ui.add(egui::Slider::new(&mut style.opacity, 0.1..=1.0).text("Opacity"));
+                        ui.checkbox(&mut style.rounded_caps, "Rounded tails");
+                        ui.checkbox(&mut style.knight_bends, "L-shaped knight arrows");
+                        if *style != ArrowStyle::default() && ui.button("Reset style").clicked() {
+                            *style = ArrowStyle::default();
+                        }
+                        ui.separator();
+                        ui.collapsing("Theme", |ui| {
+                            // Two fields at once need a plain reference, not the guard
+                            let config = &mut *c;
+                            theme_editor(
+                                ui,
+                                &mut config.overlay_theme,
+                                &mut config.arrow_style,
+                                config.overlay_elements.move_colors,
+                            );
+                        });
+                        ui.separator();
+                        ui.label("Arrow filters");
+                        let filter = &mut c.arrow_filter;
+                        ui.checkbox(&mut filter.captures_only, "Only captures");
+                        ui.checkbox(&mut filter.checks_only, "Only checks");
+                        ui.checkbox(&mut filter.exclude_king_moves, "Hide king moves");
+                        egui::ComboBox::from_label("Moves of")
+                            .selected_text(filter.piece.label())
+                            .show_ui(ui, |ui| {
+                                for p in PieceFilter::ALL {
+                                    ui.selectable_value(&mut filter.piece, p, p.label());
+                                }
+                            });
+                        if filter.is_active() && ui.button("Clear filters").clicked() {
+                            *filter = ArrowFilter::default();
+                        }
+                        ui.add(
+                            egui::Slider::new(&mut c.max_arrows, 1..=20).text("Max arrows"),
+                        )
+                        .on_hover_text(
+                            "Across all boards; the side to move and better lines win",
+                        );
+                    });
+
+                    let can_export = boards.iter().any(|b| b.is_some());
+                    ui.horizontal(|ui| {
+                        for format in DiagramFormat::ALL {
+                            let label = format!("🖼 Export {}", format.label());
+                            if !ui.add_enabled(can_export, egui::Button::new(label)).clicked() {
+                                continue;
+                            }
+                            for (i, board) in boards.iter().enumerate() {
+                                let Some(board) = board else { continue };
+                                match crate::export::diagram::export_diagram(
+                                    &board.fen,
+                                    &board.moves,
+                                    c.piece_theme,
+                                    i,
+                                    format,
+                                ) {
+                                    Ok(path) => println!("Diagram saved to {}", path.display()),
+                                    Err(e) => errors.report(AppError::System(format!(
+                                        "Diagram export failed: {:?}",
+                                        e
+                                    ))),
+                                }
+                            }
+                        }
+                    });
+                    let games: Vec<_> = snapshot_ui
+                        .games
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(i, g)| Some((i, g.as_ref()?)))
+                        .filter(|(_, g)| !g.moves.is_empty())
+                        .collect();
+                    if ui
+                        .add_enabled(!games.is_empty(), egui::Button::new("📜 Export PGN"))
+                        .on_hover_text("The tracked games with engine evaluations and ?/?? marks")
+                        .clicked()
+                    {
+                        for (board, game) in games {
+                            match crate::export::pgn::export_pgn(game, board) {
+                                Ok(path) => println!("PGN saved to {}", path.display()),
+                                Err(e) => errors.report(AppError::System(format!(
+                                    "PGN export failed: {:?}",
+                                    e
+                                ))),
+                            }
+                        }
+                    }
+                    ui.checkbox(&mut c.record_history, "Record session history")
+                        .on_hover_text("One file per run in history/; the last 20 are kept");
+                    if ui.button("⏪ Replay Session").clicked() {
+                        c.request_replay = true;
                     }
 
+                    ui.horizontal(|ui| {
+                        ui.label("Shared settings folder");
+                        deferred_text_edit(ui, "shared_config_dir", &mut c.shared_config_dir, "");
+                    })
+                    .response
+                    .on_hover_text(
+                        "Where config.json lives, e.g. a synced drive; regions, monitors \
+                         and engine paths stay in config.local.json on this machine",
+                    );
                     if ui.button("💾 Save Settings").clicked() {
                         let _ = c.save();
                     }
@@ -226,15 +1408,243 @@ impl eframe::App for OverlayWrapper {
             },
         );
 
+        if std::mem::take(&mut self.config.lock().unwrap().request_replay) {
+            self.replay = Some(ReplayViewer::new());
+        }
+        let (notation, arrow_style, piece_theme, mini_board) = {
+            let c = self.config.lock().unwrap();
+            (
+                c.notation,
+                c.arrow_style,
+                c.piece_theme,
+                c.overlay_elements.mini_board,
+            )
+        };
+        if let Some(replay) = &mut self.replay {
+            let closed = ctx.show_viewport_immediate(
+                egui::ViewportId::from_hash_of("replay_window"),
+                egui::ViewportBuilder::default()
+                    .with_title("Session Replay")
+                    .with_inner_size([420.0, 640.0]),
+                |ctx, _class| {
+                    egui::CentralPanel::default().show(ctx, |ui| {
+                        replay.show(ui, notation, &arrow_style, piece_theme)
+                    });
+                    ctx.input(|i| i.viewport().close_requested())
+                },
+            );
+            if closed {
+                self.replay = None;
+            }
+        }
+
+        let open_analysis_board = {
+            let mut c = self.config.lock().unwrap();
+            std::mem::take(&mut c.request_analysis_board)
+        };
+        if open_analysis_board {
+            let fen = snapshot
+                .boards
+                .iter()
+                .flatten()
+                .next()
+                .map(|b| b.fen.clone());
+            self.analysis_board = fen.as_deref().and_then(AnalysisBoard::new);
+            if self.analysis_board.is_none() {
+                self.errors.report(AppError::Fen(
+                    "Analysis board needs a detected position first".to_string(),
+                ));
+            }
+        }
+        if let Some(board) = &mut self.analysis_board {
+            let settings = {
+                let c = self.config.lock().unwrap();
+                (
+                    c.stockfish_depth,
+                    c.engine_process,
+                    c.engine_options.clone(),
+                )
+            };
+            let current = self.analysis_engine.as_ref().map(|(_, s)| s);
+            if current.is_some_and(|s| *s != settings) {
+                // Dropping it ends the old process; the position is searched
+                // again by one started with the new settings
+                self.analysis_engine = None;
+                board.reevaluate();
+            }
+            let (engine, _) = self.analysis_engine.get_or_insert_with(|| {
+                let (depth, engine_process, engine_options) = settings.clone();
+                let engine = BackgroundEngine::spawn(
+                    &paths::engine_path(),
+                    depth,
+                    engine_process,
+                    engine_options,
+                );
+                (engine, settings)
+            });
+            let closed = ctx.show_viewport_immediate(
+                egui::ViewportId::from_hash_of("analysis_board"),
+                egui::ViewportBuilder::default()
+                    .with_title("Analysis Board (F2)")
+                    .with_inner_size([380.0, 480.0])
+                    .with_always_on_top(),
+                |ctx, _class| {
+                    egui::CentralPanel::default()
+                        .show(ctx, |ui| board.show(ui, engine, notation, piece_theme));
+                    ctx.input(|i| i.viewport().close_requested())
+                },
+            );
+            if closed {
+                self.analysis_board = None;
+            }
+        }
+
+        let (show_presentation, show_move_list, show_move_clocks, hovered_line, pv_length) = {
+            let c = self.config.lock().unwrap();
+            (
+                c.show_presentation_window,
+                c.show_move_list,
+                c.show_move_clocks,
+                c.hovered_line,
+                c.pv_length as usize,
+            )
+        };
+        if show_presentation {
+            let closed = ctx.show_viewport_immediate(
+                egui::ViewportId::from_hash_of("presentation_window"),
+                egui::ViewportBuilder::default()
+                    .with_title("Chess Overlay Presentation")
+                    .with_inner_size([620.0, 560.0]),
+                |ctx, _class| {
+                    egui::SidePanel::right("move_list")
+                        .resizable(true)
+                        .default_width(180.0)
+                        .show_animated(ctx, show_move_list, |ui| {
+                            ui.heading("Moves");
+                            let mut shown = self.config.lock().unwrap().move_list_board;
+                            if snapshot.games.len() > 1 {
+                                egui::ComboBox::from_label("Game")
+                                    .selected_text(format!("Board {}", shown + 1))
+                                    .show_ui(ui, |ui| {
+                                        for i in 0..snapshot.games.len() {
+                                            let label = format!("Board {}", i + 1);
+                                            ui.selectable_value(&mut shown, i, label);
+                                        }
+                                    });
+                                self.config.lock().unwrap().move_list_board = shown;
+                            }
+                            crate::overlay::move_list::draw_move_list(
+                                ui,
+                                snapshot.games.get(shown).and_then(Option::as_ref),
+                                notation,
+                                show_move_clocks,
+                            );
+                        });
+                    egui::CentralPanel::default().show(ctx, |ui| {
+                        let toggle = if show_move_list {
+                            "Hide moves ▶"
+                        } else {
+                            "◀ Moves"
+                        };
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+                            if ui.small_button(toggle).clicked() {
+                                self.config.lock().unwrap().show_move_list = !show_move_list;
+                            }
+                        });
+                        let mut hovered = None;
+                        ui.horizontal_top(|ui| {
+                            let boards = snapshot.boards.iter().enumerate();
+                            for (i, board) in boards.filter_map(|(i, b)| Some((i, b.as_ref()?))) {
+                                let focus =
+                                    hovered_line.filter(|&(b, _)| b == i).and_then(|(_, line)| {
+                                        let pv = board.variations.get(line)?;
+                                        Some((line, &pv[..pv.len().min(pv_length)]))
+                                    });
+                                ui.vertical(|ui| {
+                                    ui.set_width(400.0);
+                                    let fen = Some(board.fen.as_str());
+                                    let line = if mini_board {
+                                        crate::overlay::presentation::draw_presentation(
+                                            ui,
+                                            fen,
+                                            &board.moves,
+                                            focus,
+                                            notation,
+                                            &arrow_style,
+                                            piece_theme,
+                                        )
+                                    } else {
+                                        crate::overlay::presentation::draw_lines(
+                                            ui,
+                                            fen,
+                                            &board.moves,
+                                            focus,
+                                            notation,
+                                        )
+                                    };
+                                    hovered = hovered.or(line.map(|line| (i, line)));
+                                });
+                            }
+                        });
+                        self.config.lock().unwrap().hovered_line = hovered;
+                    });
+                    ctx.input(|i| i.viewport().close_requested())
+                },
+            );
+            if closed {
+                let mut c = self.config.lock().unwrap();
+                c.show_presentation_window = false;
+                c.hovered_line = None;
+            }
+        }
+
+        // Regions and worker results are in capture pixels; the overlay
+        // draws in points, which differ under display scaling
+        let mapping = ScreenMapping::new(
+            ctx,
+            monitor
+                .and_then(|id| self.monitors.iter().find(|m| m.id == id))
+                .or_else(|| self.monitors.iter().find(|m| m.is_primary)),
+        );
+        let (desktop_mode, game_point) = {
+            let c = self.config.lock().unwrap();
+            let point = c
+                .board_region
+                .as_ref()
+                .map(|r| mapping.region_center_on_desktop(r));
+            (c.overlay_desktop, point)
+        };
+        self.desktop.update(desktop_mode, game_point);
+
+        let play_move = std::mem::take(&mut self.config.lock().unwrap().request_auto_move);
+        if play_move {
+            let errors = self.errors.clone();
+            automove::play_best_move(self.config.clone(), &snapshot, &mapping, errors);
+        }
+
         egui::CentralPanel::default()
             .frame(egui::Frame::none())
             .show(ctx, |ui| {
                 let painter = ui.painter();
                 {
                     let mut c = self.config.lock().unwrap();
-                    if c.request_selection {
+                    let requested = [
+                        (
+                            std::mem::take(&mut c.request_selection),
+                            SelectionTarget::Board,
+                        ),
+                        (
+                            std::mem::take(&mut c.request_extra_selection),
+                            SelectionTarget::ExtraBoard,
+                        ),
+                        (
+                            std::mem::take(&mut c.request_clock_selection),
+                            SelectionTarget::Clock,
+                        ),
+                    ];
+                    if let Some(&(_, target)) = requested.iter().find(|(asked, _)| *asked) {
                         self.selection_mode = true;
-                        c.request_selection = false;
+                        self.selection_target = target;
                     }
                 }
 
@@ -244,7 +1654,11 @@ impl eframe::App for OverlayWrapper {
                     painter.text(
                         ui.max_rect().center(),
                         egui::Align2::CENTER_CENTER,
-                        "DRAG TO SELECT BOARD",
+                        match self.selection_target {
+                            SelectionTarget::Board => "DRAG TO SELECT BOARD",
+                            SelectionTarget::ExtraBoard => "DRAG TO ADD A BOARD",
+                            SelectionTarget::Clock => "DRAG TO SELECT YOUR CLOCK",
+                        },
                         egui::FontId::proportional(30.0),
                         egui::Color32::WHITE,
                     );
@@ -267,35 +1681,148 @@ impl eframe::App for OverlayWrapper {
                             );
                             if response.drag_stopped() {
                                 let mut c = self.config.lock().unwrap();
-                                c.board_region = Some(crate::config::BoardRegion {
-                                    x: rect.min.x as u32,
-                                    y: rect.min.y as u32,
-                                    width: rect.width() as u32,
-                                    height: rect.height() as u32,
-                                });
+                                let region = mapping.points_to_region(rect);
+                                match self.selection_target {
+                                    SelectionTarget::Board => {
+                                        c.region_auto_detected = false;
+                                        c.region_board_fraction = None;
+                                        c.board_region = Some(region);
+                                    }
+                                    SelectionTarget::ExtraBoard => c.extra_regions.push(region),
+                                    SelectionTarget::Clock => c.clock_region = Some(region),
+                                }
                                 self.selection_mode = false;
                                 self.selection_start = None;
                             }
                         }
                     }
                 } else {
-                    ctx.send_viewport_cmd(egui::ViewportCommand::MousePassthrough(true));
+                    // Hovering the hot corner (or the controls it opened)
+                    // makes the overlay interactive
+                    let cursor = hotkeys::cursor_position()
+                        .map(|(x, y)| mapping.desktop_to_points(egui::pos2(x as f32, y as f32)))
+                        .or_else(|| ctx.input(|i| i.pointer.hover_pos()));
+                    let zone = self.config.lock().unwrap().hot_zone.clone();
+                    let in_zone = zone.enabled
+                        && cursor.is_some_and(|p| zone.rect(ui.max_rect()).contains(p));
+                    let in_controls = self
+                        .quick_controls
+                        .zip(cursor)
+                        .is_some_and(|(r, p)| r.expand(8.0).contains(p));
+                    if in_zone || in_controls {
+                        let mut c = self.config.lock().unwrap();
+                        self.quick_controls =
+                            Some(show_quick_controls(ctx, &zone, &mut c, &self.commands));
+                    } else {
+                        self.quick_controls = None;
+                    }
+                    let in_panel = [self.hud, self.toasts]
+                        .into_iter()
+                        .flatten()
+                        .any(|r| cursor.is_some_and(|p| r.contains(p)));
+                    ctx.send_viewport_cmd(egui::ViewportCommand::MousePassthrough(
+                        self.quick_controls.is_none() && !in_panel,
+                    ));
+
                     let config = self.config.lock().unwrap();
-                    if let Some(region) = &config.board_region {
-                        let rect = egui::Rect::from_min_size(
-                            egui::pos2(region.x as f32, region.y as f32),
-                            egui::vec2(region.width as f32, region.height as f32),
-                        );
-                        for (i, m) in self.current_moves.iter().enumerate() {
-                            let opacity = match i {
-                                0 => 255,
-                                1 => 160,
-                                _ => 80,
-                            };
-                            let color = egui::Color32::from_rgba_unmultiplied(0, 255, 0, opacity);
-                            crate::overlay::window::draw_arrow(painter, rect, m, color);
-                        }
+                    if config.overlay_hidden {
+                        self.hud = None;
+                        self.toasts = None;
+                        return;
+                    }
+                    // Arrow filters, then the global cap, pick moves with
+                    // their badges and classes
+                    let mut candidates: Vec<_> = snapshot
+                        .boards
+                        .iter()
+                        .enumerate()
+                        .map(|(i, board)| {
+                            let board = board.as_ref()?;
+                            let white = board.fen.split_whitespace().nth(1) == Some("w");
+                            Some(ArrowCandidates {
+                                ours: white == config.shows_white(i),
+                                keep: config.arrow_filter.keep(&board.fen, &board.moves),
+                                evals: &board.evals,
+                            })
+                        })
+                        .collect();
+                    let hidden = cap_arrows(&mut candidates, config.max_arrows as usize);
+                    let filtered: Vec<_> = snapshot
+                        .boards
+                        .iter()
+                        .zip(&candidates)
+                        .enumerate()
+                        .map(|(b, (board, candidates))| {
+                            let (board, keep) = (board.as_ref()?, &candidates.as_ref()?.keep);
+                            // A line hovered in the presentation window takes
+                            // over the variation, if it has an arrow here
+                            let focus = config
+                                .hovered_line
+                                .filter(|&(hovered, _)| hovered == b)
+                                .and_then(|(_, line)| keep.iter().position(|&i| i == line));
+                            let shown = focus.map_or(keep.first(), |f| keep.get(f));
+                            Some((
+                                select(&board.moves, keep),
+                                select(&board.quality, keep),
+                                select(&board.classes, keep),
+                                shown.and_then(|&i| board.variations.get(i)),
+                                focus,
+                            ))
+                        })
+                        .collect();
+                    let mut frames = Vec::new();
+                    let sub_regions = config.board_regions();
+                    let boards = snapshot.boards.iter().zip(filtered.iter().zip(&hidden));
+                    let boards = sub_regions.iter().zip(boards).enumerate();
+                    for (i, (sub, (board, (kept, &hidden)))) in boards {
+                        let (Some(board), Some((moves, quality, classes, variation, focus))) =
+                            (board, kept)
+                        else {
+                            continue;
+                        };
+                        let rect = match board.grid {
+                            Some([x, y, w, h]) => mapping.rect_to_points(
+                                egui::Rect::from_min_size(egui::pos2(x, y), egui::vec2(w, h)),
+                            ),
+                            None => mapping.region_to_points(sub),
+                        };
+                        frames.push(BoardFrame {
+                            rect,
+                            fen: &board.fen,
+                            moves,
+                            variation: variation.map(Vec::as_slice).unwrap_or_default(),
+                            focus: *focus,
+                            premoves: &board.premoves,
+                            book: &board.book,
+                            human_move: board.human_move.as_deref(),
+                            threat: &board.threat,
+                            blunder: board.blunder.as_ref(),
+                            eval: board.eval,
+                            quality,
+                            classes,
+                            hidden,
+                            shows_white: config.shows_white(i),
+                        });
                     }
+                    self.renderers.draw(
+                        painter,
+                        ui.max_rect(),
+                        &mapping,
+                        &frames,
+                        &snapshot,
+                        &config,
+                    );
+                    self.hud = if config.overlay_elements.info_panel {
+                        show_hud(ctx, &snapshot, &config)
+                    } else {
+                        None
+                    };
+                    self.toasts = if config.overlay_elements.error_toasts {
+                        show_toasts(ctx, &mut self.errors.lock())
+                    } else {
+                        None
+                    };
+                    drop(config);
                 }
             });
         ctx.request_repaint();
@@ -0,0 +1,100 @@
+use std::time::{Duration, Instant};
+
+/// How often the worker samples the process size.
+const SAMPLE_EVERY: Duration = Duration::from_secs(10);
+
+/// Resident memory of this process in bytes, where the platform exposes it.
+#[cfg(windows)]
+pub fn resident_bytes() -> Option<u64> {
+    use windows::Win32::System::ProcessStatus::{GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS};
+    use windows::Win32::System::Threading::GetCurrentProcess;
+
+    let cb = std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32;
+    let mut counters = PROCESS_MEMORY_COUNTERS {
+        cb,
+        ..Default::default()
+    };
+    unsafe { GetProcessMemoryInfo(GetCurrentProcess(), &mut counters, cb) }.ok()?;
+    Some(counters.WorkingSetSize as u64)
+}
+
+#[cfg(target_os = "linux")]
+pub fn resident_bytes() -> Option<u64> {
+    // Second field of statm is resident pages
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+    Some(pages * 4096)
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+pub fn resident_bytes() -> Option<u64> {
+    None
+}
+
+/// Latest memory reading for the settings window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MemoryStatus {
+    pub resident_mb: u64,
+    /// Growth since the first reading after startup or the last reclaim.
+    pub growth_mb: i64,
+    pub over_limit: bool,
+}
+
+/// Samples process memory and decides when long sessions should drop and
+/// rebuild their heavy state (ONNX session, engine hash) to undo slow
+/// growth from leaks or fragmentation.
+pub struct MemoryGuard {
+    baseline_mb: Option<u64>,
+    last_sample: Option<Instant>,
+    last_reclaim: Instant,
+}
+
+impl Default for MemoryGuard {
+    fn default() -> Self {
+        Self {
+            baseline_mb: None,
+            last_sample: None,
+            last_reclaim: Instant::now(),
+        }
+    }
+}
+
+impl MemoryGuard {
+    /// Takes a reading when one is due. `warn_growth_mb` is the growth over
+    /// the baseline that counts as too much.
+    pub fn sample(&mut self, warn_growth_mb: u32) -> Option<MemoryStatus> {
+        if self.last_sample.is_some_and(|t| t.elapsed() < SAMPLE_EVERY) {
+            return None;
+        }
+        self.last_sample = Some(Instant::now());
+        let resident_mb = resident_bytes()? / (1024 * 1024);
+        let baseline = *self.baseline_mb.get_or_insert(resident_mb);
+        let growth_mb = resident_mb as i64 - baseline as i64;
+        Some(MemoryStatus {
+            resident_mb,
+            growth_mb,
+            over_limit: growth_mb > warn_growth_mb as i64,
+        })
+    }
+
+    /// Whether to reclaim now: on schedule (`interval_hours`, 0 = never) or
+    /// right away when `status` is over the limit and `on_warning` is set.
+    pub fn reclaim_due(
+        &self,
+        status: Option<MemoryStatus>,
+        interval_hours: u32,
+        on_warning: bool,
+    ) -> bool {
+        let scheduled = interval_hours > 0
+            && self.last_reclaim.elapsed() >= Duration::from_secs(interval_hours as u64 * 3600);
+        let over = on_warning && status.is_some_and(|s| s.over_limit);
+        scheduled || over
+    }
+
+    /// Call after reclaiming; the next reading becomes the new baseline.
+    pub fn reclaimed(&mut self) {
+        self.baseline_mb = None;
+        self.last_sample = None;
+        self.last_reclaim = Instant::now();
+    }
+}
@@ -0,0 +1,113 @@
+use crate::chess_logic::line_to_san;
+use serde::{Deserialize, Serialize};
+
+/// How SAN moves are shown: English letters, the piece letters of another
+/// language, or figurine symbols.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Notation {
+    #[default]
+    English,
+    German,
+    French,
+    Spanish,
+    Italian,
+    Dutch,
+    Figurine,
+}
+
+impl Notation {
+    pub const ALL: [Notation; 7] = [
+        Notation::English,
+        Notation::German,
+        Notation::French,
+        Notation::Spanish,
+        Notation::Italian,
+        Notation::Dutch,
+        Notation::Figurine,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Notation::English => "English (K Q R B N)",
+            Notation::German => "Deutsch (K D T L S)",
+            Notation::French => "Français (R D T F C)",
+            Notation::Spanish => "Español (R D T A C)",
+            Notation::Italian => "Italiano (R D T A C)",
+            Notation::Dutch => "Nederlands (K D T L P)",
+            Notation::Figurine => "Figurine (♔ ♕ ♖ ♗ ♘)",
+        }
+    }
+
+    /// Symbols for king, queen, rook, bishop and knight, in that order.
+    fn pieces(&self) -> [&'static str; 5] {
+        match self {
+            Notation::English => ["K", "Q", "R", "B", "N"],
+            Notation::German => ["K", "D", "T", "L", "S"],
+            Notation::French => ["R", "D", "T", "F", "C"],
+            Notation::Spanish | Notation::Italian => ["R", "D", "T", "A", "C"],
+            Notation::Dutch => ["K", "D", "T", "L", "P"],
+            Notation::Figurine => ["♔", "♕", "♖", "♗", "♘"],
+        }
+    }
+
+    /// Renders an English SAN move ("Nxe5+", "e8=Q") in this notation.
+    /// Files are lowercase in SAN, so every uppercase letter other than the
+    /// castling `O` is a piece.
+    pub fn format_san(&self, san: &str) -> String {
+        let pieces = self.pieces();
+        san.chars()
+            .map(|c| match c {
+                'K' => pieces[0].to_string(),
+                'Q' => pieces[1].to_string(),
+                'R' => pieces[2].to_string(),
+                'B' => pieces[3].to_string(),
+                'N' => pieces[4].to_string(),
+                other => other.to_string(),
+            })
+            .collect()
+    }
+
+    /// "12. Nf3 Nc6 13. d4" (or "12... Nc6 13. d4" with Black to move) for a
+    /// line played from `fen`.
+    pub fn format_line(&self, fen: &str, line: &[String]) -> String {
+        let mut fields = fen.split_whitespace().skip(1);
+        let mut white = fields.next() != Some("b");
+        let mut number: u32 = fields.nth(3).and_then(|n| n.parse().ok()).unwrap_or(1);
+        let mut text = String::new();
+        for (i, san) in line_to_san(fen, line).iter().enumerate() {
+            if white {
+                text.push_str(&format!("{}. ", number));
+            } else if i == 0 {
+                text.push_str(&format!("{}... ", number));
+            }
+            text.push_str(&self.format_san(san));
+            text.push(' ');
+            if !white {
+                number += 1;
+            }
+            white = !white;
+        }
+        text.trim_end().to_string()
+    }
+
+    /// Turns a move typed in this notation back into English SAN so it can
+    /// be parsed. Figurine symbols are accepted whatever the setting.
+    pub fn english_from(&self, text: &str) -> String {
+        let english = Notation::English.pieces();
+        let figurine = Notation::Figurine.pieces();
+        let local = self.pieces();
+        text.chars()
+            .map(|c| {
+                let s = c.to_string();
+                let index = figurine
+                    .iter()
+                    .position(|p| *p == s)
+                    .or_else(|| local.iter().position(|p| *p == s));
+                match index {
+                    Some(i) => english[i].to_string(),
+                    None => s,
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,153 @@
+use crate::assets::pieces::PieceTheme;
+use crate::chess_logic::{position_fen, position_from_fen};
+use crate::engine::background::BackgroundEngine;
+use crate::engine::stockfish::Evaluation;
+use crate::notation::Notation;
+use crate::overlay::miniboard::draw_mini_board;
+use eframe::egui;
+use shakmaty::san::SanPlus;
+use shakmaty::{Chess, Color, File, Position, Rank, Role, Square};
+
+/// "What if" board seeded from the detected position. Moves tried here are
+/// evaluated by a separate engine and never touch the live overlay.
+pub struct AnalysisBoard {
+    /// Positions after each tried move; the first entry is the detected one.
+    history: Vec<Chess>,
+    tried: Vec<String>,
+    drag_from: Option<Square>,
+    eval: Option<Evaluation>,
+    pending_fen: Option<String>,
+    needs_eval: bool,
+}
+
+impl AnalysisBoard {
+    pub fn new(fen: &str) -> Option<Self> {
+        let start = position_from_fen(fen)?;
+        Some(Self {
+            history: vec![start],
+            tried: Vec::new(),
+            drag_from: None,
+            eval: None,
+            pending_fen: None,
+            needs_eval: true,
+        })
+    }
+
+    /// Asks for the current position again, e.g. from a restarted engine.
+    pub fn reevaluate(&mut self) {
+        self.needs_eval = true;
+    }
+
+    fn current(&self) -> &Chess {
+        self.history.last().unwrap()
+    }
+
+    fn request_eval(&mut self, engine: &BackgroundEngine) {
+        let fen = position_fen(self.current());
+        engine.request(fen.clone());
+        self.pending_fen = Some(fen);
+        self.eval = None;
+        self.needs_eval = false;
+    }
+
+    fn try_move(&mut self, from: Square, to: Square, engine: &BackgroundEngine) {
+        let pos = self.current().clone();
+        // Promote to a queen when dropping a pawn on the last rank
+        let Some(m) = pos.legal_moves().into_iter().find(|m| {
+            m.from() == Some(from) && m.to() == to && m.promotion().is_none_or(|r| r == Role::Queen)
+        }) else {
+            return;
+        };
+        let mut next = pos;
+        let san = SanPlus::from_move_and_play_unchecked(&mut next, &m).to_string();
+        self.tried.push(san);
+        self.history.push(next);
+        self.request_eval(engine);
+    }
+
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        engine: &BackgroundEngine,
+        notation: Notation,
+        theme: PieceTheme,
+    ) {
+        if let Some((fen, eval)) = engine.try_result() {
+            if self.pending_fen.as_deref() == Some(fen.as_str()) {
+                self.pending_fen = None;
+                self.eval = eval;
+            }
+        }
+        if self.needs_eval {
+            self.request_eval(engine);
+        }
+
+        let side = ui.available_width().min(360.0);
+        let (rect, response) =
+            ui.allocate_exact_size(egui::vec2(side, side), egui::Sense::click_and_drag());
+        let painter = ui.painter_at(rect);
+        draw_mini_board(&painter, rect, Some(&position_fen(self.current())), theme);
+
+        let square_at = |pos: egui::Pos2| -> Option<Square> {
+            let col = ((pos.x - rect.min.x) / rect.width() * 8.0).floor() as i32;
+            let row = ((pos.y - rect.min.y) / rect.height() * 8.0).floor() as i32;
+            ((0..8).contains(&col) && (0..8).contains(&row))
+                .then(|| Square::from_coords(File::new(col as u32), Rank::new(7 - row as u32)))
+        };
+
+        if response.drag_started() {
+            self.drag_from = response.interact_pointer_pos().and_then(square_at);
+        }
+        if let Some(from) = self.drag_from {
+            let cell = rect.width() / 8.0;
+            let min = rect.min
+                + egui::vec2(
+                    u32::from(from.file()) as f32 * cell,
+                    (7 - u32::from(from.rank())) as f32 * cell,
+                );
+            painter.rect_filled(
+                egui::Rect::from_min_size(min, egui::vec2(cell, cell)),
+                0.0,
+                egui::Color32::from_rgba_unmultiplied(255, 255, 0, 80),
+            );
+            if response.drag_stopped() {
+                if let Some(to) = response.interact_pointer_pos().and_then(square_at) {
+                    self.try_move(from, to, engine);
+                }
+                self.drag_from = None;
+            }
+        }
+
+        ui.horizontal(|ui| {
+            let turn = match self.current().turn() {
+                Color::White => "White",
+                Color::Black => "Black",
+            };
+            ui.label(format!("{} to move", turn));
+            match (&self.pending_fen, self.eval) {
+                (Some(_), _) => ui.label("Eval: …"),
+                (None, Some(eval)) => {
+                    let white_to_move = self.current().turn() == Color::White;
+                    ui.label(format!("Eval: {}", eval.for_white(white_to_move).display()))
+                }
+                (None, None) => ui.label("Eval: n/a"),
+            };
+        });
+        if !self.tried.is_empty() {
+            let tried: Vec<String> = self.tried.iter().map(|s| notation.format_san(s)).collect();
+            ui.label(tried.join(" "));
+        }
+        ui.horizontal(|ui| {
+            if ui.button("⟲ Undo").clicked() && self.history.len() > 1 {
+                self.history.pop();
+                self.tried.pop();
+                self.request_eval(engine);
+            }
+            if ui.button("⏮ Reset").clicked() {
+                self.history.truncate(1);
+                self.tried.clear();
+                self.request_eval(engine);
+            }
+        });
+    }
+}
@@ -0,0 +1,210 @@
+use crate::overlay::window::square_center;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+
+/// How suggestion arrows look. Sizes are fractions of a square, so arrows
+/// scale with the board.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct ArrowStyle {
+    /// Shaft width.
+    pub thickness: f32,
+    /// Multiplies the alpha of every arrow color.
+    pub opacity: f32,
+    /// Round the tail of the shaft instead of cutting it square.
+    pub rounded_caps: bool,
+    /// Draw knight moves as an L (long leg first) like chess GUIs do.
+    pub knight_bends: bool,
+}
+
+impl Default for ArrowStyle {
+    fn default() -> Self {
+        Self {
+            thickness: 0.16,
+            opacity: 1.0,
+            rounded_caps: true,
+            knight_bends: true,
+        }
+    }
+}
+
+/// How suggested moves are marked on the board.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MoveHighlight {
+    #[default]
+    Arrows,
+    /// Tinted origin and destination squares, like a chess GUI's last move.
+    Squares,
+    Both,
+}
+
+impl MoveHighlight {
+    pub const ALL: [MoveHighlight; 3] = [
+        MoveHighlight::Arrows,
+        MoveHighlight::Squares,
+        MoveHighlight::Both,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            MoveHighlight::Arrows => "Arrows",
+            MoveHighlight::Squares => "Highlighted squares",
+            MoveHighlight::Both => "Arrows and squares",
+        }
+    }
+
+    pub fn arrows(&self) -> bool {
+        *self != MoveHighlight::Squares
+    }
+
+    pub fn squares(&self) -> bool {
+        *self != MoveHighlight::Arrows
+    }
+}
+
+/// Head length and half-width relative to the shaft width.
+const HEAD_LENGTH: f32 = 2.2;
+const HEAD_HALF_WIDTH: f32 = 1.4;
+
+/// Badge size and inset from the square's corner, relative to a square.
+const BADGE_RADIUS: f32 = 0.18;
+const BADGE_INSET: f32 = 0.22;
+
+/// Piece a UCI move promotes to ("e7e8q" gives 'q'), if it is one.
+pub fn promotion(m: &str) -> Option<char> {
+    m.chars()
+        .nth(4)
+        .filter(|c| matches!(c, 'q' | 'r' | 'b' | 'n'))
+}
+
+/// Marks the promotion piece of move `m`, when it has one, with a round
+/// badge holding the piece's symbol in the top-right corner of the
+/// destination square. Opaque, so drawing it twice changes nothing.
+fn draw_promotion_badge(painter: &egui::Painter, rect: egui::Rect, m: &str, color: egui::Color32) {
+    let (Some(piece), Some(center)) = (
+        promotion(m),
+        m.get(2..4).and_then(|sq| square_center(rect, sq)),
+    ) else {
+        return;
+    };
+    let symbol = match piece {
+        'q' => "♕",
+        'r' => "♖",
+        'b' => "♗",
+        _ => "♘",
+    };
+    let square = rect.width() / 8.0;
+    let offset = square * (0.5 - BADGE_INSET);
+    let pos = center + egui::vec2(offset, -offset);
+    let [r, g, b, _] = color.to_srgba_unmultiplied();
+    painter.circle(
+        pos,
+        square * BADGE_RADIUS,
+        egui::Color32::from_rgb(r, g, b),
+        egui::Stroke::new(1.0, egui::Color32::WHITE),
+    );
+    painter.text(
+        pos,
+        egui::Align2::CENTER_CENTER,
+        symbol,
+        egui::FontId::proportional(square * BADGE_RADIUS * 1.6),
+        egui::Color32::WHITE,
+    );
+}
+
+/// Square where an L-shaped knight arrow turns, or `None` for any other
+/// move. The longer leg comes first.
+fn knight_corner(from: egui::Pos2, to: egui::Pos2, square: f32) -> Option<egui::Pos2> {
+    let dx = ((to.x - from.x) / square).round().abs();
+    let dy = ((to.y - from.y) / square).round().abs();
+    match (dx as u32, dy as u32) {
+        (2, 1) => Some(egui::pos2(to.x, from.y)),
+        (1, 2) => Some(egui::pos2(from.x, to.y)),
+        _ => None,
+    }
+}
+
+/// Draws move `m` (UCI, "e2e4" or "e7e8q") on a board occupying `rect`,
+/// with a badge for the promotion piece. The shaft, tail cap and head are
+/// separate shapes that don't overlap, so translucent arrows have an even
+/// tint.
+pub fn draw_arrow(
+    painter: &egui::Painter,
+    rect: egui::Rect,
+    m: &str,
+    color: egui::Color32,
+    style: &ArrowStyle,
+) {
+    let (Some(start), Some(end)) = (
+        m.get(0..2).and_then(|sq| square_center(rect, sq)),
+        m.get(2..4).and_then(|sq| square_center(rect, sq)),
+    ) else {
+        return;
+    };
+    if start == end {
+        return;
+    }
+    let square = rect.width() / 8.0;
+    let width = (style.thickness * square).max(1.0);
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    let color = egui::Color32::from_rgba_unmultiplied(
+        r,
+        g,
+        b,
+        (a as f32 * style.opacity.clamp(0.0, 1.0)).round() as u8,
+    );
+
+    let mut points = vec![start];
+    if style.knight_bends {
+        points.extend(knight_corner(start, end, square));
+    }
+    let last_leg = *points.last().unwrap();
+    let dir = (end - last_leg).normalized();
+    let norm = egui::vec2(-dir.y, dir.x);
+    // Keep the head inside short legs
+    let head_length = (width * HEAD_LENGTH).min((end - last_leg).length() * 0.6);
+    let base = end - dir * head_length;
+    points.push(base);
+
+    if style.rounded_caps {
+        let tail = (points[1] - start).normalized();
+        let back = (-tail.y).atan2(-tail.x);
+        let cap = (0..=12)
+            .map(|i| {
+                let angle = back - PI / 2.0 + PI * i as f32 / 12.0;
+                start + egui::vec2(angle.cos(), angle.sin()) * width / 2.0
+            })
+            .collect();
+        painter.add(egui::Shape::convex_polygon(cap, color, egui::Stroke::NONE));
+    }
+    painter.add(egui::Shape::line(points, egui::Stroke::new(width, color)));
+
+    let half = width * HEAD_HALF_WIDTH;
+    painter.add(egui::Shape::convex_polygon(
+        vec![end, base + norm * half, base - norm * half],
+        color,
+        egui::Stroke::NONE,
+    ));
+    draw_promotion_badge(painter, rect, m, color);
+}
+
+/// Fills the origin and destination squares of move `m` on a board
+/// occupying `rect` with `fill`, which callers keep translucent so pieces
+/// stay readable. A promotion's badge is drawn in `color`, the move's.
+pub fn draw_square_highlight(
+    painter: &egui::Painter,
+    rect: egui::Rect,
+    m: &str,
+    fill: egui::Color32,
+    color: egui::Color32,
+) {
+    let size = egui::vec2(rect.width() / 8.0, rect.height() / 8.0);
+    for sq in [m.get(0..2), m.get(2..4)].into_iter().flatten() {
+        if let Some(center) = square_center(rect, sq) {
+            painter.rect_filled(egui::Rect::from_center_size(center, size), 0.0, fill);
+        }
+    }
+    draw_promotion_badge(painter, rect, m, color);
+}
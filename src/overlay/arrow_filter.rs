@@ -0,0 +1,166 @@
+use crate::chess_logic::{move_facts, MoveFacts};
+use crate::engine::stockfish::Evaluation;
+use serde::{Deserialize, Serialize};
+use shakmaty::Role;
+
+/// Piece type an arrow must move, for themed training.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum PieceFilter {
+    #[default]
+    Any,
+    Pawn,
+    Knight,
+    Bishop,
+    Rook,
+    Queen,
+    King,
+}
+
+impl PieceFilter {
+    pub const ALL: [PieceFilter; 7] = [
+        PieceFilter::Any,
+        PieceFilter::Pawn,
+        PieceFilter::Knight,
+        PieceFilter::Bishop,
+        PieceFilter::Rook,
+        PieceFilter::Queen,
+        PieceFilter::King,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PieceFilter::Any => "Any piece",
+            PieceFilter::Pawn => "Pawns",
+            PieceFilter::Knight => "Knights",
+            PieceFilter::Bishop => "Bishops",
+            PieceFilter::Rook => "Rooks",
+            PieceFilter::Queen => "Queens",
+            PieceFilter::King => "King",
+        }
+    }
+
+    fn role(&self) -> Option<Role> {
+        match self {
+            PieceFilter::Any => None,
+            PieceFilter::Pawn => Some(Role::Pawn),
+            PieceFilter::Knight => Some(Role::Knight),
+            PieceFilter::Bishop => Some(Role::Bishop),
+            PieceFilter::Rook => Some(Role::Rook),
+            PieceFilter::Queen => Some(Role::Queen),
+            PieceFilter::King => Some(Role::King),
+        }
+    }
+}
+
+/// Which engine suggestions are drawn as arrows. Each enabled option adds a
+/// rule to the chain; a move is shown only if it passes every rule.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(default)]
+pub struct ArrowFilter {
+    pub captures_only: bool,
+    pub checks_only: bool,
+    pub exclude_king_moves: bool,
+    pub piece: PieceFilter,
+}
+
+type Rule = Box<dyn Fn(&MoveFacts) -> bool>;
+
+impl ArrowFilter {
+    pub fn is_active(&self) -> bool {
+        *self != ArrowFilter::default()
+    }
+
+    fn chain(&self) -> Vec<Rule> {
+        let mut rules: Vec<Rule> = Vec::new();
+        if self.captures_only {
+            rules.push(Box::new(|m| m.capture));
+        }
+        if self.checks_only {
+            rules.push(Box::new(|m| m.check));
+        }
+        if self.exclude_king_moves {
+            rules.push(Box::new(|m| m.role != Role::King));
+        }
+        if let Some(role) = self.piece.role() {
+            rules.push(Box::new(move |m| m.role == role));
+        }
+        rules
+    }
+
+    /// Indices of the `moves` (UCI, played from `fen`) that pass the chain,
+    /// in their original order. Moves that can't be parsed are dropped
+    /// while any rule is active.
+    pub fn keep(&self, fen: &str, moves: &[String]) -> Vec<usize> {
+        let rules = self.chain();
+        if rules.is_empty() {
+            return (0..moves.len()).collect();
+        }
+        moves
+            .iter()
+            .enumerate()
+            .filter(|(_, mv)| move_facts(fen, mv).is_some_and(|f| rules.iter().all(|r| r(&f))))
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+/// One board's arrows competing for the global cap.
+pub struct ArrowCandidates<'a> {
+    /// The user's side is to move on this board.
+    pub ours: bool,
+    /// Line indices still shown after filtering, best first.
+    pub keep: Vec<usize>,
+    /// Score per line for the side to move.
+    pub evals: &'a [Option<Evaluation>],
+}
+
+/// Trims the boards' `keep` lists to at most `max` arrows in total. Boards
+/// where the user's side is to move go first, then lines by score, so with
+/// two boards the weaker lines of the waiting side go first. Returns how
+/// many arrows were hidden per board.
+pub fn cap_arrows(boards: &mut [Option<ArrowCandidates>], max: usize) -> Vec<usize> {
+    let mut ranked: Vec<(usize, usize, bool, i32)> = boards
+        .iter()
+        .enumerate()
+        .filter_map(|(b, board)| Some((b, board.as_ref()?)))
+        .flat_map(|(b, board)| {
+            board.keep.iter().enumerate().map(move |(rank, &line)| {
+                let score = board
+                    .evals
+                    .get(line)
+                    .copied()
+                    .flatten()
+                    .map_or(i32::MIN, |e| e.as_centipawns());
+                (b, rank, board.ours, score)
+            })
+        })
+        .collect();
+    ranked.sort_by_key(|&(b, rank, ours, score)| (!ours, std::cmp::Reverse(score), rank, b));
+
+    let mut shown = vec![Vec::new(); boards.len()];
+    for &(b, rank, _, _) in ranked.iter().take(max) {
+        shown[b].push(rank);
+    }
+    boards
+        .iter_mut()
+        .zip(shown)
+        .map(|(board, ranks)| {
+            let Some(board) = board else {
+                return 0;
+            };
+            let hidden = board.keep.len() - ranks.len();
+            let mut rank = 0;
+            board.keep.retain(|_| {
+                rank += 1;
+                ranks.contains(&(rank - 1))
+            });
+            hidden
+        })
+        .collect()
+}
+
+/// The entries of `items` at the `keep` indices, e.g. quality badges that
+/// belong to the moves kept by `ArrowFilter::keep`.
+pub fn select<T: Clone>(items: &[T], keep: &[usize]) -> Vec<T> {
+    keep.iter().filter_map(|i| items.get(*i).cloned()).collect()
+}
@@ -0,0 +1,139 @@
+use crate::config::AppConfig;
+use crate::worker::WorkerCommand;
+use crossbeam_channel::Sender;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum HotCorner {
+    TopLeft,
+    #[default]
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl HotCorner {
+    pub const ALL: [HotCorner; 4] = [
+        HotCorner::TopLeft,
+        HotCorner::TopRight,
+        HotCorner::BottomLeft,
+        HotCorner::BottomRight,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            HotCorner::TopLeft => "Top left",
+            HotCorner::TopRight => "Top right",
+            HotCorner::BottomLeft => "Bottom left",
+            HotCorner::BottomRight => "Bottom right",
+        }
+    }
+
+    fn align(&self) -> egui::Align2 {
+        match self {
+            HotCorner::TopLeft => egui::Align2::LEFT_TOP,
+            HotCorner::TopRight => egui::Align2::RIGHT_TOP,
+            HotCorner::BottomLeft => egui::Align2::LEFT_BOTTOM,
+            HotCorner::BottomRight => egui::Align2::RIGHT_BOTTOM,
+        }
+    }
+}
+
+/// Screen corner that makes the click-through overlay interactive while
+/// the mouse is over it, showing a few quick controls.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct HotZone {
+    pub enabled: bool,
+    pub corner: HotCorner,
+    /// Side length of the square zone, in points.
+    pub size: f32,
+}
+
+impl Default for HotZone {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            corner: HotCorner::default(),
+            size: 24.0,
+        }
+    }
+}
+
+impl HotZone {
+    pub fn rect(&self, screen: egui::Rect) -> egui::Rect {
+        self.corner
+            .align()
+            .align_size_within_rect(egui::Vec2::splat(self.size), screen)
+    }
+}
+
+/// Quick controls anchored at the hot corner. Returns the area they cover
+/// so the overlay stays interactive while the mouse is on them.
+pub fn show_quick_controls(
+    ctx: &egui::Context,
+    zone: &HotZone,
+    c: &mut AppConfig,
+    commands: &Sender<WorkerCommand>,
+) -> egui::Rect {
+    let align = zone.corner.align();
+    let offset = egui::vec2(
+        if align.x() == egui::Align::Min {
+            4.0
+        } else {
+            -4.0
+        },
+        if align.y() == egui::Align::Min {
+            4.0
+        } else {
+            -4.0
+        },
+    );
+    egui::Area::new(egui::Id::new("hot_zone_controls"))
+        .anchor(align, offset)
+        .order(egui::Order::Foreground)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                let running = if c.running { "⏹ Stop" } else { "▶ Start" };
+                if ui
+                    .add_enabled(c.board_region.is_some(), egui::Button::new(running))
+                    .clicked()
+                {
+                    c.running = !c.running;
+                    c.analysis_paused = false;
+                }
+                if c.running {
+                    let pause = if c.analysis_paused {
+                        "▶ Resume"
+                    } else {
+                        "⏸ Pause"
+                    };
+                    if ui.button(pause).clicked() {
+                        c.analysis_paused = !c.analysis_paused;
+                    }
+                }
+                let side = if c.show_white_moves { "White" } else { "Black" };
+                if ui.button(format!("⇄ Side: {}", side)).clicked() {
+                    c.show_white_moves = !c.show_white_moves;
+                }
+                if ui.button("⟳ Re-scan").clicked() {
+                    let _ = commands.send(WorkerCommand::Rescan);
+                }
+                let hidden = if c.overlay_hidden {
+                    "👁 Show overlay"
+                } else {
+                    "🚫 Hide overlay"
+                };
+                if ui.button(hidden).clicked() {
+                    c.overlay_hidden = !c.overlay_hidden;
+                }
+                if ui.button("📐 Select region").clicked() {
+                    c.request_selection = true;
+                }
+            });
+        })
+        .response
+        .rect
+        .union(zone.rect(ctx.screen_rect()))
+}
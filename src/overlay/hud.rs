@@ -0,0 +1,96 @@
+use crate::config::AppConfig;
+use crate::snapshot::OverlaySnapshot;
+use eframe::egui;
+
+/// Plies of the best line shown per board.
+const LINE_PLIES: usize = 6;
+
+/// "1.2 Mn/s", "850 kn/s".
+fn format_nps(nps: u64) -> String {
+    if nps >= 1_000_000 {
+        format!("{:.1} Mn/s", nps as f64 / 1_000_000.0)
+    } else {
+        format!("{} kn/s", nps / 1000)
+    }
+}
+
+/// Small movable panel on the overlay with the numbers behind the arrows:
+/// evaluation, best line, depth, engine speed, detection confidence,
+/// orientation and FEN of each board. Returns its area so the overlay can
+/// take the mouse while it is dragged.
+pub fn show_hud(
+    ctx: &egui::Context,
+    snapshot: &OverlaySnapshot,
+    config: &AppConfig,
+) -> Option<egui::Rect> {
+    let frame = egui::Frame::popup(&ctx.style()).fill(egui::Color32::from_black_alpha(190));
+    egui::Window::new("Analysis")
+        .id(egui::Id::new("overlay_hud"))
+        .frame(frame)
+        .resizable(false)
+        .collapsible(true)
+        .default_pos(egui::pos2(12.0, 40.0))
+        .show(ctx, |ui| {
+            let scale = config.overlay_theme.hud_scale();
+            for font in ui.style_mut().text_styles.values_mut() {
+                font.size *= scale;
+            }
+            let boards = snapshot.boards.iter().enumerate();
+            let analyzed: Vec<_> = boards.filter_map(|(i, b)| Some((i, b.as_ref()?))).collect();
+            if analyzed.is_empty() {
+                ui.weak("No position analyzed yet");
+            }
+            for (i, board) in analyzed {
+                if snapshot.boards.len() > 1 {
+                    ui.strong(format!("Board {}", i + 1));
+                }
+                egui::Grid::new(("hud_board", i))
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        let bottom = if config.shows_white(i) {
+                            "White"
+                        } else {
+                            "Black"
+                        };
+                        ui.label("At bottom");
+                        ui.label(bottom);
+                        ui.end_row();
+                        let white = board.fen.split_whitespace().nth(1) != Some("b");
+                        ui.label("To move");
+                        ui.label(if white { "White" } else { "Black" });
+                        ui.end_row();
+                        ui.label("Eval");
+                        match board.eval {
+                            Some(eval) => ui.monospace(eval.display()),
+                            None if !board.book.is_empty() => ui.label("Book"),
+                            None => ui.weak("—"),
+                        };
+                        ui.end_row();
+                        if let Some(line) = board.variations.first().filter(|l| !l.is_empty()) {
+                            let shown = &line[..line.len().min(LINE_PLIES)];
+                            ui.label("Best");
+                            ui.monospace(config.notation.format_line(&board.fen, shown));
+                            ui.end_row();
+                        }
+                        ui.label("Depth");
+                        ui.monospace(board.depth.to_string());
+                        ui.end_row();
+                        ui.label("Speed");
+                        match board.nps {
+                            Some(nps) => ui.monospace(format_nps(nps)),
+                            None => ui.weak("—"),
+                        };
+                        ui.end_row();
+                        ui.label("Min confidence");
+                        match board.min_confidence {
+                            Some(c) => ui.monospace(format!("{:.0}%", c * 100.0)),
+                            None => ui.weak("—"),
+                        };
+                        ui.end_row();
+                    });
+                let fen = egui::RichText::new(&board.fen).small().monospace();
+                ui.add(egui::Label::new(fen).wrap());
+            }
+        })
+        .map(|r| r.response.rect)
+}
@@ -0,0 +1,106 @@
+use crate::assets::pieces::{draw_piece, PieceTheme};
+use crate::overlay::window::square_center;
+use eframe::egui;
+
+const LIGHT_SQUARE: egui::Color32 = egui::Color32::from_rgb(240, 217, 181);
+const DARK_SQUARE: egui::Color32 = egui::Color32::from_rgb(181, 136, 99);
+
+/// FEN letter of the piece on `sq` ("e4"), if any.
+pub fn piece_at(fen: &str, sq: &str) -> Option<char> {
+    let mut chars = sq.chars();
+    let col = (chars.next()? as u32).checked_sub('a' as u32)? as usize;
+    let rank = chars.next()?.to_digit(10)? as usize;
+    let row = 8usize.checked_sub(rank)?;
+    let placement = fen.split_whitespace().next()?;
+    let mut file = 0;
+    for c in placement.split('/').nth(row)?.chars() {
+        if let Some(empty) = c.to_digit(10) {
+            file += empty as usize;
+        } else if file == col {
+            return Some(c);
+        } else {
+            file += 1;
+        }
+        if file > col {
+            break;
+        }
+    }
+    None
+}
+
+/// Paints an 8x8 board with the pieces of `fen` (placement field only is
+/// used) into `rect`, rank 8 at the top.
+pub fn draw_mini_board(
+    painter: &egui::Painter,
+    rect: egui::Rect,
+    fen: Option<&str>,
+    theme: PieceTheme,
+) {
+    let cell = egui::vec2(rect.width() / 8.0, rect.height() / 8.0);
+    let cell_rect = |col: usize, row: usize| {
+        egui::Rect::from_min_size(
+            rect.min + egui::vec2(col as f32 * cell.x, row as f32 * cell.y),
+            cell,
+        )
+    };
+
+    for row in 0..8 {
+        for col in 0..8 {
+            let color = if (row + col) % 2 == 0 {
+                LIGHT_SQUARE
+            } else {
+                DARK_SQUARE
+            };
+            painter.rect_filled(cell_rect(col, row), 0.0, color);
+        }
+    }
+
+    let Some(placement) = fen.and_then(|f| f.split_whitespace().next()) else {
+        return;
+    };
+    for (row, rank) in placement.split('/').take(8).enumerate() {
+        let mut col = 0;
+        for c in rank.chars() {
+            if let Some(empty) = c.to_digit(10) {
+                col += empty as usize;
+                continue;
+            }
+            if col >= 8 {
+                break;
+            }
+            draw_piece(painter, cell_rect(col, row), c, theme, egui::Color32::WHITE);
+            col += 1;
+        }
+    }
+}
+
+/// Translucent copy of the piece moved by `m` (UCI) on its destination
+/// square, to preview a suggestion.
+pub fn draw_ghost(
+    painter: &egui::Painter,
+    rect: egui::Rect,
+    fen: &str,
+    m: &str,
+    theme: PieceTheme,
+) {
+    let (Some(piece), Some(center)) = (
+        m.get(0..2).and_then(|sq| piece_at(fen, sq)),
+        m.get(2..4).and_then(|sq| square_center(rect, sq)),
+    ) else {
+        return;
+    };
+    // A promotion shows the new piece
+    let piece = match m.chars().nth(4) {
+        Some(p) if piece.is_ascii_uppercase() => p.to_ascii_uppercase(),
+        Some(p) => p,
+        None => piece,
+    };
+    let cell = egui::Rect::from_center_size(center, rect.size() / 8.0);
+    draw_piece(
+        painter,
+        cell,
+        piece,
+        theme,
+        egui::Color32::from_white_alpha(110),
+    );
+}
@@ -1 +1,15 @@
+pub mod analysis_board;
+pub mod arrow;
+pub mod arrow_filter;
+pub mod hot_zone;
+pub mod hud;
+pub mod miniboard;
+pub mod move_list;
+pub mod presentation;
+pub mod renderer;
+pub mod replay;
+pub mod screen_map;
+pub mod theme;
+pub mod toasts;
+pub mod widgets;
 pub mod window;
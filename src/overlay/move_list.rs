@@ -0,0 +1,90 @@
+use crate::notation::Notation;
+use crate::snapshot::GameRecord;
+use eframe::egui;
+use std::time::Duration;
+
+const CURRENT_MOVE: egui::Color32 = egui::Color32::from_rgb(255, 210, 80);
+
+fn format_clock(d: Duration) -> String {
+    let secs = d.as_secs();
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+/// Numbered move list of the tracked game with the last move highlighted.
+/// With `clocks`, shows the time each side has spent between the moves the
+/// tracker saw; on-screen clocks are not read, so this is measured time
+/// since tracking started, not the site's remaining time.
+pub fn draw_move_list(
+    ui: &mut egui::Ui,
+    game: Option<&GameRecord>,
+    notation: Notation,
+    clocks: bool,
+) {
+    let Some(game) = game.filter(|g| g.start.is_some()) else {
+        ui.weak("No game tracked yet");
+        return;
+    };
+
+    if clocks {
+        let mut used = game.time_used;
+        if let (Some(white), Some(since)) = (game.white_to_move, game.turn_started) {
+            used[if white { 0 } else { 1 }] += since.elapsed();
+            ui.ctx().request_repaint_after(Duration::from_millis(500));
+        }
+        egui::Grid::new("move_clocks")
+            .num_columns(2)
+            .show(ui, |ui| {
+                for (side, (name, d)) in ["White", "Black"].iter().zip(used).enumerate() {
+                    let text = egui::RichText::new(format_clock(d)).monospace();
+                    let to_move = game.white_to_move == Some(side == 0);
+                    ui.label(*name);
+                    ui.label(if to_move { text.strong() } else { text });
+                    ui.end_row();
+                }
+            });
+        ui.weak("Time used since tracking started");
+        ui.separator();
+    }
+
+    let (white_first, first_number) = game.start.unwrap_or((true, 1));
+    // Pad a leading Black move so each row holds one full move
+    let mut plies: Vec<Option<&String>> = Vec::new();
+    if !white_first {
+        plies.push(None);
+    }
+    plies.extend(game.moves.iter().map(Some));
+    let last = plies.len().saturating_sub(1);
+
+    egui::ScrollArea::vertical()
+        .stick_to_bottom(true)
+        .auto_shrink([false, false])
+        .show(ui, |ui| {
+            if game.moves.is_empty() {
+                ui.weak("No moves yet");
+            }
+            egui::Grid::new("move_list")
+                .num_columns(3)
+                .striped(true)
+                .show(ui, |ui| {
+                    for (row, pair) in plies.chunks(2).enumerate() {
+                        ui.label(format!("{}.", first_number as usize + row));
+                        for (col, san) in pair.iter().enumerate() {
+                            match san {
+                                Some(san) => {
+                                    let text = egui::RichText::new(notation.format_san(san));
+                                    if row * 2 + col == last {
+                                        ui.label(text.strong().color(CURRENT_MOVE));
+                                    } else {
+                                        ui.label(text);
+                                    }
+                                }
+                                None => {
+                                    ui.label("…");
+                                }
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+}
@@ -0,0 +1,89 @@
+use crate::assets::pieces::PieceTheme;
+use crate::chess_logic::uci_to_san;
+use crate::notation::Notation;
+use crate::overlay::arrow::{draw_arrow, ArrowStyle};
+use crate::overlay::miniboard::{draw_ghost, draw_mini_board};
+use eframe::egui;
+
+/// Contents of the optional presentation window: a mini-board with the
+/// current suggestions and the list of lines, for a second monitor or for
+/// window capture when streaming. With `focus` set (the line index and
+/// its variation), only that line is drawn brightly, with its follow-up
+/// moves; returns the line under the pointer.
+pub fn draw_presentation(
+    ui: &mut egui::Ui,
+    fen: Option<&str>,
+    moves: &[String],
+    focus: Option<(usize, &[String])>,
+    notation: Notation,
+    style: &ArrowStyle,
+    theme: PieceTheme,
+) -> Option<usize> {
+    let side = ui
+        .available_width()
+        .min(ui.available_height() - 80.0)
+        .max(80.0);
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(side, side), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    draw_mini_board(&painter, rect, fen, theme);
+    if let (Some(fen), Some(best)) = (fen, moves.first()) {
+        draw_ghost(&painter, rect, fen, best, theme);
+    }
+    for (i, m) in moves.iter().enumerate() {
+        let opacity = match (focus, i) {
+            (Some((f, _)), _) if f == i => 255,
+            (Some(_), _) => 40,
+            (None, 0) => 255,
+            (None, 1) => 160,
+            (None, _) => 80,
+        };
+        let color = egui::Color32::from_rgba_unmultiplied(0, 160, 0, opacity);
+        draw_arrow(&painter, rect, m, color, style);
+    }
+    if let Some((_, line)) = focus {
+        let thin = ArrowStyle {
+            thickness: style.thickness * 0.6,
+            ..*style
+        };
+        // The first move already has its arrow
+        for (ply, m) in line.iter().enumerate().skip(1) {
+            let base = if ply % 2 == 0 {
+                egui::Color32::from_rgb(0, 200, 120)
+            } else {
+                egui::Color32::from_rgb(255, 150, 60)
+            };
+            let fade = 1.0 - 0.5 * ply as f32 / line.len() as f32;
+            draw_arrow(&painter, rect, m, base.gamma_multiply(fade * 0.8), &thin);
+        }
+    }
+
+    ui.separator();
+    draw_lines(ui, fen, moves, focus, notation)
+}
+
+/// The list of lines alone, for when the mini-board is turned off; returns
+/// the line under the pointer.
+pub fn draw_lines(
+    ui: &mut egui::Ui,
+    fen: Option<&str>,
+    moves: &[String],
+    focus: Option<(usize, &[String])>,
+    notation: Notation,
+) -> Option<usize> {
+    if moves.is_empty() {
+        ui.label("No suggestions yet");
+    }
+    let mut hovered = None;
+    for (i, m) in moves.iter().enumerate() {
+        let text = fen
+            .and_then(|fen| uci_to_san(fen, m))
+            .map_or_else(|| m.clone(), |san| notation.format_san(&san));
+        let text = egui::RichText::new(format!("{}. {}", i + 1, text));
+        let focused = focus.is_some_and(|(f, _)| f == i);
+        let response = ui.label(if focused { text.strong() } else { text });
+        if response.hovered() {
+            hovered = Some(i);
+        }
+    }
+    hovered
+}
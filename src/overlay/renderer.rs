@@ -0,0 +1,1004 @@
+use crate::chess_logic::uci_to_san;
+use crate::config::{AppConfig, OverlayElements};
+use crate::degradation::DegradationLevel;
+use crate::engine::book::BookMove;
+use crate::engine::eval_history::BlunderAlert;
+use crate::engine::explain::null_move_fen;
+use crate::engine::premove::Premove;
+use crate::engine::quality::{MoveClass, Quality};
+use crate::engine::stockfish::Evaluation;
+use crate::engine::supervisor::EngineHealth;
+use crate::overlay::arrow::{draw_arrow, draw_square_highlight, ArrowStyle};
+use crate::overlay::screen_map::ScreenMapping;
+use crate::overlay::theme::dim_alpha;
+use crate::overlay::window::square_center;
+use crate::snapshot::OverlaySnapshot;
+use crate::vision::board::piece_for_class;
+use eframe::egui;
+use shakmaty::Color;
+
+/// Everything a renderer may draw for one analyzed board.
+pub struct BoardFrame<'a> {
+    /// Screen rectangle of the board.
+    pub rect: egui::Rect,
+    pub fen: &'a str,
+    /// Suggested moves in UCI notation, best first.
+    pub moves: &'a [String],
+    /// Principal variation of the first entry of `moves`, or of the
+    /// focused one.
+    pub variation: &'a [String],
+    /// Entry of `moves` hovered in the presentation window's line list;
+    /// the other arrows are dimmed.
+    pub focus: Option<usize>,
+    pub premoves: &'a [Premove],
+    /// Opening book moves, when the position was not searched.
+    pub book: &'a [BookMove],
+    /// Human-plausible move at the configured rating, in human mode.
+    pub human_move: Option<&'a str>,
+    /// The opponent's line if the side to move passed, with threat arrows
+    /// on; empty when there is no threat.
+    pub threat: &'a [String],
+    /// The move that led here was a blunder.
+    pub blunder: Option<&'a BlunderAlert>,
+    /// Best line's score from White's point of view.
+    pub eval: Option<Evaluation>,
+    /// Trust badge per entry of `moves`.
+    pub quality: &'a [Quality],
+    /// Move class per entry of `moves`.
+    pub classes: &'a [MoveClass],
+    /// Suggestions left out by the arrow cap.
+    pub hidden: usize,
+    /// The player is White on this board; their end of the eval bar is at
+    /// the bottom.
+    pub shows_white: bool,
+}
+
+/// A visualization layer of the transparent overlay. Implement this and add
+/// it with `RendererRegistry::register` to draw custom visuals without
+/// touching the update loop.
+pub trait OverlayRenderer {
+    fn name(&self) -> &'static str;
+
+    /// Called once per analyzed board, in screen coordinates.
+    fn draw_board(&mut self, _painter: &egui::Painter, _board: &BoardFrame, _config: &AppConfig) {}
+
+    /// Called once per frame with the whole overlay area, after all boards.
+    fn draw_screen(
+        &mut self,
+        _painter: &egui::Painter,
+        _screen: egui::Rect,
+        _mapping: &ScreenMapping,
+        _snapshot: &OverlaySnapshot,
+        _config: &AppConfig,
+    ) {
+    }
+}
+
+/// Suggested moves as arrows and/or highlighted squares, fading with line
+/// rank.
+pub struct ArrowRenderer;
+
+impl OverlayRenderer for ArrowRenderer {
+    fn name(&self) -> &'static str {
+        "arrows"
+    }
+
+    fn draw_board(&mut self, painter: &egui::Painter, board: &BoardFrame, config: &AppConfig) {
+        let theme = &config.overlay_theme;
+        let white = board.fen.split_whitespace().nth(1) != Some("b");
+        for (i, m) in board.moves.iter().enumerate() {
+            let opacity = match board.focus {
+                Some(f) if f == i => 1.0,
+                Some(_) => 0.16,
+                None => theme.line(i).1,
+            };
+            let class = board.classes.get(i).copied();
+            let base = theme.arrow_color(i, class, white, config.overlay_elements.move_colors);
+            let color = base.with_alpha(opacity);
+            if config.move_highlight.squares() {
+                let tint = theme.highlight.unwrap_or(base);
+                let alpha = opacity * theme.highlight_opacity * config.arrow_style.opacity;
+                draw_square_highlight(painter, board.rect, m, tint.with_alpha(alpha), color);
+            }
+            if config.move_highlight.arrows() {
+                draw_arrow(painter, board.rect, m, color, &config.arrow_style);
+            }
+        }
+        if board.hidden > 0 {
+            let pos = board.rect.right_bottom() - egui::vec2(4.0, 4.0);
+            let galley = painter.layout_no_wrap(
+                format!("+{} hidden", board.hidden),
+                egui::FontId::proportional(12.0),
+                egui::Color32::WHITE,
+            );
+            let rect = egui::Align2::RIGHT_BOTTOM.anchor_size(pos, galley.size());
+            painter.rect_filled(rect.expand(3.0), 3.0, egui::Color32::from_black_alpha(170));
+            painter.galley(rect.min, galley, egui::Color32::WHITE);
+        }
+    }
+}
+
+/// Follow-up moves of the top suggestion: numbered arrows that fade along
+/// the line, with the line in SAN pinned to the right of the board.
+pub struct VariationRenderer;
+
+/// Colors of the two sides' moves in a variation.
+const VARIATION_OURS: egui::Color32 = egui::Color32::from_rgb(0, 200, 120);
+const VARIATION_THEIRS: egui::Color32 = egui::Color32::from_rgb(255, 150, 60);
+
+impl OverlayRenderer for VariationRenderer {
+    fn name(&self) -> &'static str {
+        "variation"
+    }
+
+    fn draw_board(&mut self, painter: &egui::Painter, board: &BoardFrame, config: &AppConfig) {
+        let shown = board.variation.len().min(config.pv_length as usize);
+        if shown < 2 {
+            return;
+        }
+        let line = &board.variation[..shown];
+        let style = ArrowStyle {
+            thickness: config.arrow_style.thickness * 0.6,
+            ..config.arrow_style
+        };
+        // The first move already has its arrow
+        for (ply, m) in line.iter().enumerate().skip(1) {
+            let base = if ply % 2 == 0 {
+                VARIATION_OURS
+            } else {
+                VARIATION_THEIRS
+            };
+            let fade = 1.0 - 0.5 * ply as f32 / shown as f32;
+            let color = base.gamma_multiply(fade * 0.8);
+            draw_arrow(painter, board.rect, m, color, &style);
+            if !config.overlay_elements.labels {
+                continue;
+            }
+            let (Some(from), Some(to)) = (
+                m.get(0..2).and_then(|sq| square_center(board.rect, sq)),
+                m.get(2..4).and_then(|sq| square_center(board.rect, sq)),
+            ) else {
+                continue;
+            };
+            let at = from + (to - from) * 0.5;
+            let radius = board.rect.width() / 8.0 * 0.16;
+            painter.circle(
+                at,
+                radius,
+                base,
+                egui::Stroke::new(1.0, egui::Color32::BLACK),
+            );
+            painter.text(
+                at,
+                egui::Align2::CENTER_CENTER,
+                (ply + 1).to_string(),
+                egui::FontId::proportional(radius * 1.4),
+                egui::Color32::BLACK,
+            );
+        }
+
+        let text = config.notation.format_line(board.fen, line);
+        if text.is_empty() || !config.overlay_elements.labels {
+            return;
+        }
+        let galley = painter.layout(
+            text,
+            egui::FontId::proportional(13.0),
+            egui::Color32::WHITE,
+            160.0,
+        );
+        let pos = board.rect.right_top() + egui::vec2(EVAL_BAR_GAP + 4.0, 4.0);
+        let rect = egui::Rect::from_min_size(pos, galley.size()).expand(4.0);
+        painter.rect_filled(rect, 4.0, egui::Color32::from_black_alpha(170));
+        painter.galley(pos, galley, egui::Color32::WHITE);
+    }
+}
+
+/// Faint conditional arrows for premove planning: the opponent's likely
+/// reply (grey) and our prepared answer (light blue).
+pub struct PremoveRenderer;
+
+impl OverlayRenderer for PremoveRenderer {
+    fn name(&self) -> &'static str {
+        "premoves"
+    }
+
+    fn draw_board(&mut self, painter: &egui::Painter, board: &BoardFrame, config: &AppConfig) {
+        for p in board.premoves {
+            let reply = egui::Color32::from_rgba_unmultiplied(200, 200, 200, 60);
+            let response = egui::Color32::from_rgba_unmultiplied(80, 170, 255, 90);
+            draw_arrow(painter, board.rect, &p.reply, reply, &config.arrow_style);
+            draw_arrow(
+                painter,
+                board.rect,
+                &p.response,
+                response,
+                &config.arrow_style,
+            );
+            if !config.overlay_elements.labels {
+                continue;
+            }
+            // "if Nf6 → e5" next to where the reply lands
+            let (Some(reply_san), Some(response_san), Some(pos)) = (
+                &p.reply_san,
+                &p.response_san,
+                p.reply
+                    .get(2..4)
+                    .and_then(|sq| square_center(board.rect, sq)),
+            ) else {
+                continue;
+            };
+            painter.text(
+                pos,
+                egui::Align2::CENTER_CENTER,
+                format!(
+                    "if {} → {}",
+                    config.notation.format_san(reply_san),
+                    config.notation.format_san(response_san)
+                ),
+                egui::FontId::proportional(11.0),
+                egui::Color32::from_rgba_unmultiplied(160, 210, 255, 200),
+            );
+        }
+    }
+}
+
+/// After a blunder: a pulsing red border around the board for a few
+/// seconds and the refutation as a thick red arrow until the next move.
+pub struct BlunderRenderer;
+
+const BLUNDER_COLOR: egui::Color32 = egui::Color32::from_rgb(230, 30, 30);
+/// How long the border pulses after the blunder is first seen.
+const BLUNDER_PULSE_SECS: f32 = 3.0;
+
+impl OverlayRenderer for BlunderRenderer {
+    fn name(&self) -> &'static str {
+        "blunder"
+    }
+
+    fn draw_board(&mut self, painter: &egui::Painter, board: &BoardFrame, config: &AppConfig) {
+        let Some(alert) = board.blunder else {
+            return;
+        };
+        let age = alert.raised_at.elapsed().as_secs_f32();
+        if age < BLUNDER_PULSE_SECS {
+            // Two pulses a second, fading out towards the end
+            let pulse = 0.5 + 0.5 * (age * 4.0 * std::f32::consts::PI).cos();
+            let fade = 1.0 - age / BLUNDER_PULSE_SECS;
+            let color = BLUNDER_COLOR.gamma_multiply(pulse * fade);
+            painter.rect_stroke(board.rect.expand(3.0), 2.0, egui::Stroke::new(6.0, color));
+        }
+        if let Some(m) = &alert.refutation {
+            let style = ArrowStyle {
+                thickness: config.arrow_style.thickness * 1.4,
+                opacity: 1.0,
+                ..config.arrow_style
+            };
+            draw_arrow(painter, board.rect, m, BLUNDER_COLOR, &style);
+        }
+        let side = if alert.by_white { "White" } else { "Black" };
+        let galley = painter.layout_no_wrap(
+            format!(
+                "⚠ {} blundered (-{:.1})",
+                side,
+                alert.loss_cp as f32 / 100.0
+            ),
+            egui::FontId::proportional(13.0),
+            egui::Color32::WHITE,
+        );
+        let pos = board.rect.center_top() - egui::vec2(0.0, 6.0);
+        let rect = egui::Align2::CENTER_BOTTOM.anchor_size(pos, galley.size());
+        painter.rect_filled(rect.expand(3.0), 3.0, BLUNDER_COLOR);
+        painter.galley(rect.min, galley, egui::Color32::WHITE);
+    }
+}
+
+/// The human-mode move as a yellow arrow tagged with the rating, so it
+/// stands apart from the engine's best move. When both agree only the tag
+/// is drawn, on the best arrow.
+pub struct HumanMoveRenderer;
+
+const HUMAN_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 200, 40);
+
+impl OverlayRenderer for HumanMoveRenderer {
+    fn name(&self) -> &'static str {
+        "human_move"
+    }
+
+    fn draw_board(&mut self, painter: &egui::Painter, board: &BoardFrame, config: &AppConfig) {
+        let Some(m) = board.human_move else {
+            return;
+        };
+        let agrees = board.moves.first().is_some_and(|best| best == m);
+        if !agrees {
+            let style = ArrowStyle {
+                thickness: config.arrow_style.thickness * 0.8,
+                ..config.arrow_style
+            };
+            draw_arrow(
+                painter,
+                board.rect,
+                m,
+                HUMAN_COLOR.gamma_multiply(0.85),
+                &style,
+            );
+        }
+        if !config.overlay_elements.labels {
+            return;
+        }
+        let Some(target) = m.get(2..4).and_then(|sq| square_center(board.rect, sq)) else {
+            return;
+        };
+        let tag = if agrees {
+            format!("👤 {} = best", config.human_elo)
+        } else {
+            format!("👤 {}", config.human_elo)
+        };
+        let galley =
+            painter.layout_no_wrap(tag, egui::FontId::proportional(11.0), egui::Color32::BLACK);
+        let pos = target - egui::vec2(0.0, board.rect.height() / 16.0);
+        let rect = egui::Align2::CENTER_BOTTOM.anchor_size(pos, galley.size());
+        painter.rect_filled(rect.expand(2.0), 3.0, HUMAN_COLOR);
+        painter.galley(rect.min, galley, egui::Color32::BLACK);
+    }
+}
+
+/// Darkens each analyzed board by the theme's `board_dim`, under every
+/// other drawing, so arrows stand out on busy board themes.
+pub struct BoardDimRenderer;
+
+impl OverlayRenderer for BoardDimRenderer {
+    fn name(&self) -> &'static str {
+        "board_dim"
+    }
+
+    fn draw_board(&mut self, painter: &egui::Painter, board: &BoardFrame, config: &AppConfig) {
+        let alpha = dim_alpha(&config.overlay_theme);
+        if alpha > 0 {
+            painter.rect_filled(board.rect, 0.0, egui::Color32::from_black_alpha(alpha));
+        }
+    }
+}
+
+/// The opponent's threat as a thin magenta arrow with square ends, tagged
+/// with the move, so it reads apart from the suggestions.
+pub struct ThreatRenderer;
+
+const THREAT_COLOR: egui::Color32 = egui::Color32::from_rgb(220, 60, 200);
+
+impl OverlayRenderer for ThreatRenderer {
+    fn name(&self) -> &'static str {
+        "threats"
+    }
+
+    fn draw_board(&mut self, painter: &egui::Painter, board: &BoardFrame, config: &AppConfig) {
+        let Some(m) = board.threat.first() else {
+            return;
+        };
+        let style = ArrowStyle {
+            thickness: config.arrow_style.thickness * 0.6,
+            rounded_caps: false,
+            ..config.arrow_style
+        };
+        draw_arrow(
+            painter,
+            board.rect,
+            m,
+            THREAT_COLOR.gamma_multiply(0.8),
+            &style,
+        );
+        if !config.overlay_elements.labels {
+            return;
+        }
+        let Some(target) = m.get(2..4).and_then(|sq| square_center(board.rect, sq)) else {
+            return;
+        };
+        // The threat is played by the side not to move, after a pass
+        let san = null_move_fen(board.fen)
+            .and_then(|null| uci_to_san(&null, m))
+            .unwrap_or_else(|| m.clone());
+        let galley = painter.layout_no_wrap(
+            format!("⚠ {}", config.notation.format_san(&san)),
+            egui::FontId::proportional(11.0),
+            egui::Color32::WHITE,
+        );
+        let pos = target + egui::vec2(0.0, board.rect.height() / 16.0);
+        let rect = egui::Align2::CENTER_TOP.anchor_size(pos, galley.size());
+        painter.rect_filled(rect.expand(2.0), 3.0, THREAT_COLOR);
+        painter.galley(rect.min, galley, egui::Color32::WHITE);
+    }
+}
+
+/// Opening book moves as purple arrows, thicker and more opaque the more
+/// weight the book gives them, with the moves and shares listed under the
+/// board.
+pub struct BookRenderer;
+
+const BOOK_COLOR: egui::Color32 = egui::Color32::from_rgb(170, 110, 255);
+
+impl OverlayRenderer for BookRenderer {
+    fn name(&self) -> &'static str {
+        "book"
+    }
+
+    fn draw_board(&mut self, painter: &egui::Painter, board: &BoardFrame, config: &AppConfig) {
+        if board.book.is_empty() {
+            return;
+        }
+        for m in board.book.iter().rev() {
+            let style = ArrowStyle {
+                thickness: config.arrow_style.thickness * (0.5 + 0.5 * m.share),
+                ..config.arrow_style
+            };
+            let color = BOOK_COLOR.gamma_multiply(0.35 + 0.65 * m.share);
+            draw_arrow(painter, board.rect, &m.uci, color, &style);
+        }
+        if !config.overlay_elements.labels {
+            return;
+        }
+
+        let text = board
+            .book
+            .iter()
+            .map(|m| {
+                let san = uci_to_san(board.fen, &m.uci).unwrap_or_else(|| m.uci.clone());
+                format!(
+                    "{} {:.0}%",
+                    config.notation.format_san(&san),
+                    m.share * 100.0
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("  ");
+        let galley = painter.layout(
+            format!("📖 {}", text),
+            egui::FontId::proportional(13.0),
+            egui::Color32::WHITE,
+            board.rect.width().max(160.0),
+        );
+        let pos = board.rect.left_bottom() + egui::vec2(0.0, 6.0);
+        let rect = egui::Rect::from_min_size(pos, galley.size()).expand(4.0);
+        painter.rect_filled(rect, 4.0, egui::Color32::from_black_alpha(170));
+        painter.rect_stroke(rect, 4.0, egui::Stroke::new(1.0, BOOK_COLOR));
+        painter.galley(pos, galley, egui::Color32::WHITE);
+    }
+}
+
+/// Shades the squares the suggested moves land on, strongest for the best
+/// move, to see at a glance where the play is.
+pub struct HeatmapRenderer;
+
+const HEATMAP_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 120, 0);
+
+impl OverlayRenderer for HeatmapRenderer {
+    fn name(&self) -> &'static str {
+        "heatmap"
+    }
+
+    fn draw_board(&mut self, painter: &egui::Painter, board: &BoardFrame, _config: &AppConfig) {
+        let cell = board.rect.size() / 8.0;
+        for (i, m) in board.moves.iter().enumerate() {
+            let Some(center) = m.get(2..4).and_then(|sq| square_center(board.rect, sq)) else {
+                continue;
+            };
+            let heat = 0.45 / (i + 1) as f32;
+            let rect = egui::Rect::from_center_size(center, cell);
+            painter.rect_filled(rect, 0.0, HEATMAP_COLOR.gamma_multiply(heat));
+        }
+    }
+}
+
+/// Coloured dot on each arrow's starting square telling how far to trust
+/// the suggestion (red: shallow/unstable, green: deep and consistent).
+pub struct QualityBadgeRenderer;
+
+impl OverlayRenderer for QualityBadgeRenderer {
+    fn name(&self) -> &'static str {
+        "quality_badges"
+    }
+
+    fn draw_board(&mut self, painter: &egui::Painter, board: &BoardFrame, config: &AppConfig) {
+        if !config.overlay_elements.arrows {
+            return;
+        }
+        let radius = board.rect.width() / 8.0 * 0.12;
+        for (m, quality) in board.moves.iter().zip(board.quality) {
+            let Some(center) = m.get(0..2).and_then(|sq| square_center(board.rect, sq)) else {
+                continue;
+            };
+            painter.circle(
+                center,
+                radius,
+                quality.color(),
+                egui::Stroke::new(1.0, egui::Color32::BLACK),
+            );
+        }
+    }
+}
+
+/// Vertical bar left of the board showing who is winning, lichess style.
+pub struct EvalBarRenderer;
+
+/// Width of the evaluation bar and its gap to the board, in points.
+const EVAL_BAR_WIDTH: f32 = 14.0;
+const EVAL_BAR_GAP: f32 = 4.0;
+
+/// White's share of the bar: 0.5 is equal, 1.0 is winning for White. Uses
+/// lichess' win-probability curve so small advantages stay readable.
+fn white_share(eval: Evaluation) -> f32 {
+    match eval {
+        Evaluation::Mate(n) if n > 0 => 1.0,
+        Evaluation::Mate(n) if n < 0 => 0.0,
+        // Mate on the board; the side to move is already lost
+        Evaluation::Mate(_) => 0.5,
+        Evaluation::Centipawns(cp) => {
+            let winning_chances = 2.0 / (1.0 + (-0.003_682_08 * cp as f32).exp()) - 1.0;
+            0.5 + 0.5 * winning_chances
+        }
+    }
+}
+
+impl OverlayRenderer for EvalBarRenderer {
+    fn name(&self) -> &'static str {
+        "eval_bar"
+    }
+
+    fn draw_board(&mut self, painter: &egui::Painter, board: &BoardFrame, config: &AppConfig) {
+        let Some(eval) = board.eval else {
+            return;
+        };
+        let bar = egui::Rect::from_min_max(
+            egui::pos2(
+                board.rect.min.x - EVAL_BAR_GAP - EVAL_BAR_WIDTH,
+                board.rect.min.y,
+            ),
+            egui::pos2(board.rect.min.x - EVAL_BAR_GAP, board.rect.max.y),
+        );
+        let white_height = bar.height() * white_share(eval);
+        // White's share grows from the bottom when White is at the bottom
+        let white = if board.shows_white {
+            egui::Rect::from_min_max(egui::pos2(bar.min.x, bar.max.y - white_height), bar.max)
+        } else {
+            egui::Rect::from_min_max(bar.min, egui::pos2(bar.max.x, bar.min.y + white_height))
+        };
+        let (white_color, black_color) = (
+            config.overlay_theme.eval_white.color32(),
+            config.overlay_theme.eval_black.color32(),
+        );
+        painter.rect_filled(bar, 2.0, black_color);
+        painter.rect_filled(white, 2.0, white_color);
+
+        let white_ahead = eval.as_centipawns() >= 0;
+        let text = match eval {
+            Evaluation::Centipawns(cp) => format!("{:.1}", cp.abs() as f32 / 100.0),
+            Evaluation::Mate(n) => format!("M{}", n.abs()),
+        };
+        // Label sits at the winning side's end of the bar, like lichess
+        let at_bottom = white_ahead == board.shows_white;
+        let (pos, align) = if at_bottom {
+            (
+                bar.center_bottom() - egui::vec2(0.0, 2.0),
+                egui::Align2::CENTER_BOTTOM,
+            )
+        } else {
+            (
+                bar.center_top() + egui::vec2(0.0, 2.0),
+                egui::Align2::CENTER_TOP,
+            )
+        };
+        let color = if white_ahead {
+            black_color
+        } else {
+            white_color
+        };
+        painter.text(pos, align, text, egui::FontId::proportional(9.0), color);
+    }
+}
+
+/// Outline and 8x8 lines of an empty board found by the classical grid
+/// detector, to verify region calibration on setup/editor screens.
+pub struct EmptyGridRenderer;
+
+impl OverlayRenderer for EmptyGridRenderer {
+    fn name(&self) -> &'static str {
+        "empty_grid"
+    }
+
+    fn draw_screen(
+        &mut self,
+        painter: &egui::Painter,
+        _screen: egui::Rect,
+        mapping: &ScreenMapping,
+        snapshot: &OverlaySnapshot,
+        _config: &AppConfig,
+    ) {
+        let Some([x, y, w, h]) = snapshot.empty_board_grid else {
+            return;
+        };
+        let rect = mapping.rect_to_points(egui::Rect::from_min_size(
+            egui::pos2(x, y),
+            egui::vec2(w, h),
+        ));
+        let stroke =
+            egui::Stroke::new(1.0, egui::Color32::from_rgba_unmultiplied(0, 200, 255, 160));
+        for i in 0..=8 {
+            let fx = rect.min.x + rect.width() * i as f32 / 8.0;
+            let fy = rect.min.y + rect.height() * i as f32 / 8.0;
+            painter.line_segment(
+                [egui::pos2(fx, rect.min.y), egui::pos2(fx, rect.max.y)],
+                stroke,
+            );
+            painter.line_segment(
+                [egui::pos2(rect.min.x, fy), egui::pos2(rect.max.x, fy)],
+                stroke,
+            );
+        }
+        painter.text(
+            rect.left_top() - egui::vec2(0.0, 4.0),
+            egui::Align2::LEFT_BOTTOM,
+            "Empty board detected",
+            egui::FontId::proportional(14.0),
+            egui::Color32::from_rgb(0, 200, 255),
+        );
+    }
+}
+
+/// Key for the move-quality arrow colors, bottom-left of the overlay.
+pub struct LegendRenderer;
+
+impl OverlayRenderer for LegendRenderer {
+    fn name(&self) -> &'static str {
+        "legend"
+    }
+
+    fn draw_screen(
+        &mut self,
+        painter: &egui::Painter,
+        screen: egui::Rect,
+        _mapping: &ScreenMapping,
+        _snapshot: &OverlaySnapshot,
+        config: &AppConfig,
+    ) {
+        if !config.overlay_elements.move_colors {
+            return;
+        }
+        let row = 18.0;
+        let origin = screen.left_bottom() + egui::vec2(10.0, -10.0 - row * 4.0);
+        let background =
+            egui::Rect::from_min_size(origin, egui::vec2(150.0, row * 4.0)).expand(4.0);
+        painter.rect_filled(background, 4.0, egui::Color32::from_black_alpha(160));
+        for (i, class) in MoveClass::ALL.iter().enumerate() {
+            let y = origin.y + row * (i as f32 + 0.5);
+            painter.line_segment(
+                [egui::pos2(origin.x, y), egui::pos2(origin.x + 20.0, y)],
+                egui::Stroke::new(5.0, config.overlay_theme.class_color(*class).color32()),
+            );
+            painter.text(
+                egui::pos2(origin.x + 28.0, y),
+                egui::Align2::LEFT_CENTER,
+                class.label(),
+                egui::FontId::proportional(13.0),
+                egui::Color32::WHITE,
+            );
+        }
+    }
+}
+
+/// Popup under the first board explaining its best move, opened with the
+/// explain hotkey.
+pub struct ExplanationRenderer;
+
+impl OverlayRenderer for ExplanationRenderer {
+    fn name(&self) -> &'static str {
+        "explanation"
+    }
+
+    fn draw_screen(
+        &mut self,
+        painter: &egui::Painter,
+        _screen: egui::Rect,
+        mapping: &ScreenMapping,
+        snapshot: &OverlaySnapshot,
+        config: &AppConfig,
+    ) {
+        let Some(explanation) = &snapshot.status.explanation else {
+            return;
+        };
+        let Some(board) = config
+            .board_region
+            .as_ref()
+            .and_then(|r| r.sub_regions(config.split_region).first().cloned())
+        else {
+            return;
+        };
+        let notation = config.notation;
+        let line = |sans: &[String]| {
+            sans.iter()
+                .map(|san| notation.format_san(san))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let score = |eval: Option<Evaluation>| eval.map(|e| format!(" ({})", e.display()));
+
+        let mut text = format!("Why {}?", notation.format_san(&explanation.best));
+        match &explanation.threat {
+            Some(threat) if !threat.is_empty() => text.push_str(&format!(
+                "\nIf you pass: {}{}",
+                line(threat),
+                score(explanation.threat_eval).unwrap_or_default()
+            )),
+            Some(_) => text.push_str("\nNo threat if you pass"),
+            None => text.push_str("\nIn check: the move must answer it"),
+        }
+        if let Some((alt, reply)) = &explanation.alternative {
+            text.push_str(&format!(
+                "\n{} instead: {}{}",
+                notation.format_san(alt),
+                line(reply),
+                score(explanation.alternative_eval).unwrap_or_default()
+            ));
+        }
+
+        let rect = mapping.region_to_points(&board);
+        let galley = painter.layout(
+            text,
+            egui::FontId::proportional(14.0),
+            egui::Color32::WHITE,
+            rect.width().max(220.0),
+        );
+        let pos = rect.left_bottom() + egui::vec2(0.0, 34.0);
+        let background = egui::Rect::from_min_size(pos, galley.size()).expand(6.0);
+        painter.rect_filled(background, 6.0, egui::Color32::from_black_alpha(200));
+        painter.rect_stroke(
+            background,
+            6.0,
+            egui::Stroke::new(1.0, egui::Color32::from_rgb(0, 200, 120)),
+        );
+        painter.galley(pos, galley, egui::Color32::WHITE);
+    }
+}
+
+/// Status line in the top-left corner (performance degradation).
+pub struct StatusRenderer;
+
+impl OverlayRenderer for StatusRenderer {
+    fn name(&self) -> &'static str {
+        "status"
+    }
+
+    fn draw_screen(
+        &mut self,
+        painter: &egui::Painter,
+        screen: egui::Rect,
+        _mapping: &ScreenMapping,
+        snapshot: &OverlaySnapshot,
+        config: &AppConfig,
+    ) {
+        let scale = config.overlay_theme.hud_scale();
+        if config.show_capture_stats {
+            painter.text(
+                screen.left_bottom() + egui::vec2(10.0, -10.0),
+                egui::Align2::LEFT_BOTTOM,
+                format!(
+                    "Capture: {:.1} fps, {} dropped",
+                    snapshot.capture.fps, snapshot.capture.dropped
+                ),
+                egui::FontId::proportional(12.0 * scale),
+                egui::Color32::LIGHT_GRAY,
+            );
+        }
+        if snapshot.degradation != DegradationLevel::Normal {
+            painter.text(
+                screen.left_top() + egui::vec2(10.0, 10.0),
+                egui::Align2::LEFT_TOP,
+                format!("Performance: {}", snapshot.degradation.label()),
+                egui::FontId::proportional(14.0 * scale),
+                egui::Color32::YELLOW,
+            );
+        }
+        if !matches!(snapshot.engine, EngineHealth::Running) {
+            painter.text(
+                screen.left_top() + egui::vec2(10.0, 10.0 + 18.0 * scale),
+                egui::Align2::LEFT_TOP,
+                snapshot.engine.label(),
+                egui::FontId::proportional(14.0 * scale),
+                egui::Color32::YELLOW,
+            );
+        }
+    }
+}
+
+/// Box, class and confidence of every model detection, to see what the
+/// model saw when a position comes out wrong.
+pub struct DetectionRenderer;
+
+impl OverlayRenderer for DetectionRenderer {
+    fn name(&self) -> &'static str {
+        "detections"
+    }
+
+    fn draw_screen(
+        &mut self,
+        painter: &egui::Painter,
+        _screen: egui::Rect,
+        mapping: &ScreenMapping,
+        snapshot: &OverlaySnapshot,
+        config: &AppConfig,
+    ) {
+        if !config.show_detections {
+            return;
+        }
+        for d in snapshot.detections.iter().flatten() {
+            let [x, y, w, h] = d.rect;
+            let rect = mapping.rect_to_points(egui::Rect::from_min_size(
+                egui::pos2(x, y),
+                egui::vec2(w, h),
+            ));
+            let piece = piece_for_class(d.class_id);
+            let color = match piece.map(|p| p.color) {
+                None => egui::Color32::from_rgb(0, 220, 90),
+                Some(Color::White) => egui::Color32::from_rgb(255, 210, 0),
+                Some(Color::Black) => egui::Color32::from_rgb(230, 60, 230),
+            };
+            painter.rect_stroke(rect, 0.0, egui::Stroke::new(1.5, color));
+            let class = piece.map_or("board".to_string(), |p| p.char().to_string());
+            painter.text(
+                rect.left_top() + egui::vec2(2.0, 1.0),
+                egui::Align2::LEFT_TOP,
+                format!("{} {:.2}", class, d.confidence),
+                egui::FontId::monospace(10.0),
+                color,
+            );
+        }
+    }
+}
+
+/// Ordered list of renderers; later ones draw on top.
+pub struct RendererRegistry {
+    renderers: Vec<Box<dyn OverlayRenderer>>,
+}
+
+impl Default for RendererRegistry {
+    fn default() -> Self {
+        let mut registry = Self {
+            renderers: Vec::new(),
+        };
+        registry.register(Box::new(BoardDimRenderer));
+        registry.register(Box::new(HeatmapRenderer));
+        registry.register(Box::new(PremoveRenderer));
+        registry.register(Box::new(VariationRenderer));
+        registry.register(Box::new(ArrowRenderer));
+        registry.register(Box::new(ThreatRenderer));
+        registry.register(Box::new(HumanMoveRenderer));
+        registry.register(Box::new(BlunderRenderer));
+        registry.register(Box::new(BookRenderer));
+        registry.register(Box::new(QualityBadgeRenderer));
+        registry.register(Box::new(EvalBarRenderer));
+        registry.register(Box::new(EmptyGridRenderer));
+        registry.register(Box::new(DetectionRenderer));
+        registry.register(Box::new(LegendRenderer));
+        registry.register(Box::new(ExplanationRenderer));
+        registry.register(Box::new(StatusRenderer));
+        registry
+    }
+}
+
+impl RendererRegistry {
+    /// A renderer is only drawn once `OverlayElements::enabled` knows its
+    /// name.
+    pub fn register(&mut self, renderer: Box<dyn OverlayRenderer>) {
+        let name = renderer.name();
+        if OverlayElements::default().enabled(name).is_none() {
+            println!("Renderer {} has no overlay element toggle; not drawn", name);
+        }
+        self.renderers.push(renderer);
+    }
+
+    /// Swaps the renderer of the same name for `renderer`, keeping its
+    /// place in the drawing order, e.g. to restyle a default one. A new
+    /// name is registered on top.
+    #[allow(dead_code)]
+    pub fn replace(&mut self, renderer: Box<dyn OverlayRenderer>) {
+        let name = renderer.name();
+        match self.renderers.iter_mut().find(|r| r.name() == name) {
+            Some(slot) => *slot = renderer,
+            None => self.register(renderer),
+        }
+    }
+
+    /// Removes a renderer by name, e.g. to turn a default one off for good.
+    /// Returns whether it was registered.
+    #[allow(dead_code)]
+    pub fn unregister(&mut self, name: &str) -> bool {
+        let before = self.renderers.len();
+        self.renderers.retain(|r| r.name() != name);
+        self.renderers.len() != before
+    }
+
+    pub fn draw(
+        &mut self,
+        painter: &egui::Painter,
+        screen: egui::Rect,
+        mapping: &ScreenMapping,
+        boards: &[BoardFrame],
+        snapshot: &OverlaySnapshot,
+        config: &AppConfig,
+    ) {
+        for renderer in &mut self.renderers {
+            if config.overlay_elements.enabled(renderer.name()) != Some(true) {
+                continue;
+            }
+            for board in boards {
+                renderer.draw_board(painter, board, config);
+            }
+            renderer.draw_screen(painter, screen, mapping, snapshot, config);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    /// Draws nothing; flags when it is dropped from the registry.
+    struct Stub {
+        name: &'static str,
+        dropped: Rc<Cell<bool>>,
+    }
+
+    impl Stub {
+        fn new(name: &'static str) -> (Box<Self>, Rc<Cell<bool>>) {
+            let dropped = Rc::new(Cell::new(false));
+            let stub = Stub {
+                name,
+                dropped: dropped.clone(),
+            };
+            (Box::new(stub), dropped)
+        }
+    }
+
+    impl Drop for Stub {
+        fn drop(&mut self) {
+            self.dropped.set(true);
+        }
+    }
+
+    impl OverlayRenderer for Stub {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+    }
+
+    fn names(registry: &RendererRegistry) -> Vec<&'static str> {
+        registry.renderers.iter().map(|r| r.name()).collect()
+    }
+
+    #[test]
+    fn replace_keeps_the_drawing_order() {
+        let mut registry = RendererRegistry::default();
+        let order = names(&registry);
+        let (first, first_dropped) = Stub::new("arrows");
+        registry.replace(first);
+        assert_eq!(names(&registry), order);
+
+        let (second, second_dropped) = Stub::new("arrows");
+        registry.replace(second);
+        assert!(first_dropped.get());
+        assert!(!second_dropped.get());
+        assert_eq!(names(&registry), order);
+    }
+
+    #[test]
+    fn unregister_removes_by_name() {
+        let mut registry = RendererRegistry::default();
+        assert!(registry.unregister("arrows"));
+        assert!(!names(&registry).contains(&"arrows"));
+        assert!(!registry.unregister("arrows"));
+
+        let (stub, _) = Stub::new("arrows");
+        registry.replace(stub);
+        assert_eq!(names(&registry).last(), Some(&"arrows"));
+    }
+}
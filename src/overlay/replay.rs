@@ -0,0 +1,106 @@
+use crate::assets::pieces::PieceTheme;
+use crate::history::{list_sessions, load_session, HistoryEntry};
+use crate::notation::Notation;
+use crate::overlay::arrow::ArrowStyle;
+use crate::overlay::presentation::draw_presentation;
+use eframe::egui;
+use std::path::PathBuf;
+
+/// Steps through a recorded session on the mini-board with a timeline
+/// scrubber, to review what the app saw without re-running inference.
+pub struct ReplayViewer {
+    sessions: Vec<PathBuf>,
+    selected: Option<PathBuf>,
+    entries: Vec<HistoryEntry>,
+    index: usize,
+}
+
+impl ReplayViewer {
+    pub fn new() -> Self {
+        let mut viewer = Self {
+            sessions: list_sessions(),
+            selected: None,
+            entries: Vec::new(),
+            index: 0,
+        };
+        if let Some(latest) = viewer.sessions.first().cloned() {
+            viewer.open(latest);
+        }
+        viewer
+    }
+
+    fn open(&mut self, path: PathBuf) {
+        self.entries = load_session(&path).unwrap_or_else(|e| {
+            println!("Failed to load session {}: {:?}", path.display(), e);
+            Vec::new()
+        });
+        self.index = 0;
+        self.selected = Some(path);
+    }
+
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        notation: Notation,
+        style: &ArrowStyle,
+        theme: PieceTheme,
+    ) {
+        let selected_name = self
+            .selected
+            .as_ref()
+            .and_then(|p| p.file_name())
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "No sessions recorded".to_string());
+        let mut chosen = None;
+        egui::ComboBox::from_label("Session")
+            .selected_text(selected_name)
+            .show_ui(ui, |ui| {
+                for path in &self.sessions {
+                    let name = path.file_name().unwrap_or_default().to_string_lossy();
+                    if ui
+                        .selectable_label(self.selected.as_ref() == Some(path), name)
+                        .clicked()
+                    {
+                        chosen = Some(path.clone());
+                    }
+                }
+            });
+        if let Some(path) = chosen {
+            self.open(path);
+        }
+
+        if self.entries.is_empty() {
+            ui.label("Session is empty");
+            return;
+        }
+
+        let last = self.entries.len() - 1;
+        ui.horizontal(|ui| {
+            if ui.button("◀").clicked() {
+                self.index = self.index.saturating_sub(1);
+            }
+            ui.add(egui::Slider::new(&mut self.index, 0..=last).text("Position"));
+            if ui.button("▶").clicked() {
+                self.index = (self.index + 1).min(last);
+            }
+        });
+
+        let entry = &self.entries[self.index];
+        let start = self.entries[0].timestamp_ms;
+        ui.label(format!(
+            "+{:.1}s  board {}  {}",
+            entry.timestamp_ms.saturating_sub(start) as f32 / 1000.0,
+            entry.board + 1,
+            entry.fen
+        ));
+        draw_presentation(
+            ui,
+            Some(&entry.fen),
+            &entry.moves,
+            None,
+            notation,
+            style,
+            theme,
+        );
+    }
+}
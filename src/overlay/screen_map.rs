@@ -0,0 +1,106 @@
+use crate::capture::monitor::MonitorInfo;
+use crate::config::BoardRegion;
+use crate::overlay::window::square_center;
+use eframe::egui;
+
+/// Converts between capture coordinates (board regions and everything the
+/// worker reports, relative to the chosen monitor) and the overlay's egui
+/// points. The two differ under display scaling (125%, 150%, ...) and when
+/// the overlay's corner isn't exactly the monitor's.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScreenMapping {
+    /// Capture pixels per egui point.
+    scale: f32,
+    /// Capture position of the overlay's top-left corner on the monitor.
+    offset: egui::Vec2,
+    /// The monitor's top-left corner in desktop capture coordinates.
+    monitor: egui::Vec2,
+}
+
+impl Default for ScreenMapping {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            offset: egui::Vec2::ZERO,
+            monitor: egui::Vec2::ZERO,
+        }
+    }
+}
+
+impl ScreenMapping {
+    /// Mapping for the overlay viewport of `ctx`, placed on `monitor`.
+    pub fn new(ctx: &egui::Context, monitor: Option<&MonitorInfo>) -> Self {
+        let Some(m) = monitor else {
+            return Self::default();
+        };
+        let (monitor_size, inner_rect) = ctx.input(|i| {
+            let viewport = i.viewport();
+            (viewport.monitor_size, viewport.inner_rect)
+        });
+        // Measured against the monitor instead of trusting the DPI setting,
+        // so it holds whichever pixel units the capture backend reports
+        let scale = monitor_size
+            .filter(|size| size.x > 0.0)
+            .map_or(ctx.pixels_per_point(), |size| m.width as f32 / size.x);
+        let monitor = egui::vec2(m.x as f32, m.y as f32);
+        let offset = inner_rect.map_or(egui::Vec2::ZERO, |r| r.min.to_vec2() * scale - monitor);
+        Self {
+            scale,
+            offset,
+            monitor,
+        }
+    }
+
+    /// Capture pixels on the monitor to overlay points.
+    pub fn to_points(self, p: egui::Pos2) -> egui::Pos2 {
+        ((p.to_vec2() - self.offset) / self.scale).to_pos2()
+    }
+
+    /// Overlay points to capture pixels on the monitor.
+    pub fn to_capture(self, p: egui::Pos2) -> egui::Pos2 {
+        (p.to_vec2() * self.scale + self.offset).to_pos2()
+    }
+
+    /// A desktop-wide pixel position (the global cursor) to overlay points.
+    pub fn desktop_to_points(&self, p: egui::Pos2) -> egui::Pos2 {
+        self.to_points(p - self.monitor)
+    }
+
+    /// Center of `r` in desktop-wide pixels, for finding the window under it.
+    pub fn region_center_on_desktop(&self, r: &BoardRegion) -> (i32, i32) {
+        (
+            self.monitor.x as i32 + (r.x + r.width / 2) as i32,
+            self.monitor.y as i32 + (r.y + r.height / 2) as i32,
+        )
+    }
+
+    /// Center of square `sq` ("e4") of the board whose squares fill `board`
+    /// (capture pixels on the monitor), in desktop-wide pixels, for
+    /// simulated clicks.
+    pub fn square_on_desktop(&self, board: egui::Rect, sq: &str) -> Option<(f64, f64)> {
+        let p = square_center(board, sq)? + self.monitor;
+        Some((p.x as f64, p.y as f64))
+    }
+
+    pub fn rect_to_points(&self, r: egui::Rect) -> egui::Rect {
+        egui::Rect::from_min_max(self.to_points(r.min), self.to_points(r.max))
+    }
+
+    pub fn region_to_points(&self, r: &BoardRegion) -> egui::Rect {
+        self.rect_to_points(egui::Rect::from_min_size(
+            egui::pos2(r.x as f32, r.y as f32),
+            egui::vec2(r.width as f32, r.height as f32),
+        ))
+    }
+
+    /// A rectangle drawn on the overlay as a capture region.
+    pub fn points_to_region(&self, r: egui::Rect) -> BoardRegion {
+        let (min, max) = (self.to_capture(r.min), self.to_capture(r.max));
+        BoardRegion {
+            x: min.x.max(0.0).round() as u32,
+            y: min.y.max(0.0).round() as u32,
+            width: (max.x - min.x.max(0.0)).max(0.0).round() as u32,
+            height: (max.y - min.y.max(0.0)).max(0.0).round() as u32,
+        }
+    }
+}
@@ -0,0 +1,521 @@
+use crate::engine::quality::MoveClass;
+use crate::overlay::arrow::{draw_arrow, ArrowStyle};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+/// Font size the HUD is drawn at with a scale of 1, egui's body size.
+const BASE_HUD_FONT: f32 = 12.5;
+
+/// An opaque color, stored as "#rrggbb" so config files stay readable.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    /// Parses "#rrggbb" (the '#' is optional).
+    pub fn parse(text: &str) -> Option<Self> {
+        let hex = text.trim().trim_start_matches('#');
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        Some(Rgb(channel(0)?, channel(2)?, channel(4)?))
+    }
+
+    pub fn color32(self) -> egui::Color32 {
+        egui::Color32::from_rgb(self.0, self.1, self.2)
+    }
+
+    /// The color at `alpha` (0..=1) opacity.
+    pub fn with_alpha(self, alpha: f32) -> egui::Color32 {
+        let a = (alpha.clamp(0.0, 1.0) * 255.0).round() as u8;
+        egui::Color32::from_rgba_unmultiplied(self.0, self.1, self.2, a)
+    }
+
+    fn from_color32(color: egui::Color32) -> Self {
+        let [r, g, b, _] = color.to_srgba_unmultiplied();
+        Rgb(r, g, b)
+    }
+}
+
+impl TryFrom<String> for Rgb {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        Rgb::parse(&text).ok_or_else(|| format!("\"{}\" is not a #rrggbb color", text))
+    }
+}
+
+impl From<Rgb> for String {
+    fn from(c: Rgb) -> Self {
+        format!("#{:02x}{:02x}{:02x}", c.0, c.1, c.2)
+    }
+}
+
+/// Colors, opacities and sizes of the overlay's drawings. Arrow width and
+/// overall opacity stay in `ArrowStyle`, which presets also set.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct OverlayTheme {
+    /// Arrow colors of the first, second and further suggestions, when
+    /// arrows aren't colored by move quality.
+    pub first_line: Rgb,
+    pub second_line: Rgb,
+    pub other_lines: Rgb,
+    /// Opacity of the first, second and further suggestions.
+    pub first_line_opacity: f32,
+    pub second_line_opacity: f32,
+    pub other_lines_opacity: f32,
+    /// Move quality colors, when arrows are colored by it.
+    pub best: Rgb,
+    pub good: Rgb,
+    pub inaccuracy: Rgb,
+    pub mistake: Rgb,
+    /// Color set the line and quality colors came from; `Custom` once
+    /// one of them is edited.
+    pub palette: Palette,
+    /// Arrow color for White's and Black's suggestions, over the line and
+    /// quality colors; `None` leaves those in charge.
+    pub white_arrows: Option<Rgb>,
+    pub black_arrows: Option<Rgb>,
+    /// Evaluation bar colors of White's and Black's share.
+    pub eval_white: Rgb,
+    pub eval_black: Rgb,
+    /// Tint of highlighted squares; `None` uses the move's arrow color.
+    pub highlight: Option<Rgb>,
+    pub highlight_opacity: f32,
+    /// Text size of the analysis panel and status lines.
+    pub hud_font_size: f32,
+    /// How much each analyzed board is darkened under the drawings, from 0
+    /// (not at all) to 1 (black).
+    pub board_dim: f32,
+}
+
+impl Default for OverlayTheme {
+    fn default() -> Self {
+        let class = |c: MoveClass| Rgb::from_color32(c.color());
+        Self {
+            first_line: Rgb(0, 255, 0),
+            second_line: Rgb(0, 255, 0),
+            other_lines: Rgb(0, 255, 0),
+            first_line_opacity: 1.0,
+            second_line_opacity: 0.63,
+            other_lines_opacity: 0.31,
+            best: class(MoveClass::Best),
+            good: class(MoveClass::Good),
+            inaccuracy: class(MoveClass::Inaccuracy),
+            mistake: class(MoveClass::Mistake),
+            palette: Palette::Standard,
+            white_arrows: None,
+            black_arrows: None,
+            eval_white: Rgb(240, 240, 240),
+            eval_black: Rgb(64, 61, 57),
+            highlight: None,
+            highlight_opacity: 0.45,
+            hud_font_size: BASE_HUD_FONT,
+            board_dim: 0.0,
+        }
+    }
+}
+
+impl OverlayTheme {
+    /// Color and opacity of the suggestion at `rank` (0 is the best line).
+    pub fn line(&self, rank: usize) -> (Rgb, f32) {
+        match rank {
+            0 => (self.first_line, self.first_line_opacity),
+            1 => (self.second_line, self.second_line_opacity),
+            _ => (self.other_lines, self.other_lines_opacity),
+        }
+    }
+
+    pub fn class_color(&self, class: MoveClass) -> Rgb {
+        match class {
+            MoveClass::Best => self.best,
+            MoveClass::Good => self.good,
+            MoveClass::Inaccuracy => self.inaccuracy,
+            MoveClass::Mistake => self.mistake,
+        }
+    }
+
+    /// Arrow color for a suggestion at `rank` with move class `class`, on
+    /// a board where White is to move when `white`: the side's color when
+    /// set, then the quality color when `move_colors`, then the line's.
+    pub fn arrow_color(
+        &self,
+        rank: usize,
+        class: Option<MoveClass>,
+        white: bool,
+        move_colors: bool,
+    ) -> Rgb {
+        let side = if white {
+            self.white_arrows
+        } else {
+            self.black_arrows
+        };
+        match (side, class) {
+            (Some(color), _) => color,
+            (None, Some(class)) if move_colors => self.class_color(class),
+            _ => self.line(rank).0,
+        }
+    }
+
+    /// HUD text size relative to egui's default.
+    pub fn hud_scale(&self) -> f32 {
+        self.hud_font_size / BASE_HUD_FONT
+    }
+}
+
+/// Line and move quality colors chosen to stay apart for a kind of color
+/// vision.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Palette {
+    /// Green for good moves, red for bad ones.
+    #[default]
+    Standard,
+    /// Blue and orange (Okabe–Ito) instead of green and red.
+    Deuteranopia,
+    /// Like deuteranopia, with bright yellow and orange in place of the
+    /// reds that look dark without red cones.
+    Protanopia,
+    /// Teal and red, avoiding the blue/yellow pairs tritanopes confuse.
+    Tritanopia,
+    /// Colors edited by hand.
+    Custom,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 5] = [
+        Palette::Standard,
+        Palette::Deuteranopia,
+        Palette::Protanopia,
+        Palette::Tritanopia,
+        Palette::Custom,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Palette::Standard => "Standard",
+            Palette::Deuteranopia => "Deuteranopia (red-green)",
+            Palette::Protanopia => "Protanopia (red-green)",
+            Palette::Tritanopia => "Tritanopia (blue-yellow)",
+            Palette::Custom => "Custom",
+        }
+    }
+
+    /// First, second and other line colors, then best, good, inaccuracy
+    /// and mistake. `None` for `Custom`.
+    fn colors(&self) -> Option<([Rgb; 3], [Rgb; 4])> {
+        let standard = OverlayTheme::default();
+        match self {
+            Palette::Standard => Some((
+                [
+                    standard.first_line,
+                    standard.second_line,
+                    standard.other_lines,
+                ],
+                [
+                    standard.best,
+                    standard.good,
+                    standard.inaccuracy,
+                    standard.mistake,
+                ],
+            )),
+            Palette::Deuteranopia => Some((
+                [Rgb(0, 114, 178), Rgb(86, 180, 233), Rgb(204, 121, 167)],
+                [
+                    Rgb(0, 114, 178),
+                    Rgb(86, 180, 233),
+                    Rgb(230, 159, 0),
+                    Rgb(213, 94, 0),
+                ],
+            )),
+            Palette::Protanopia => Some((
+                [Rgb(0, 114, 178), Rgb(86, 180, 233), Rgb(240, 228, 66)],
+                [
+                    Rgb(0, 114, 178),
+                    Rgb(86, 180, 233),
+                    Rgb(240, 228, 66),
+                    Rgb(230, 159, 0),
+                ],
+            )),
+            Palette::Tritanopia => Some((
+                [Rgb(0, 160, 176), Rgb(127, 212, 224), Rgb(255, 143, 163)],
+                [
+                    Rgb(0, 160, 176),
+                    Rgb(127, 212, 224),
+                    Rgb(255, 143, 163),
+                    Rgb(208, 28, 28),
+                ],
+            )),
+            Palette::Custom => None,
+        }
+    }
+
+    /// Sets the theme's line and quality colors to the palette's.
+    pub fn apply(&self, theme: &mut OverlayTheme) {
+        theme.palette = *self;
+        let Some(([first, second, other], [best, good, inaccuracy, mistake])) = self.colors()
+        else {
+            return;
+        };
+        theme.first_line = first;
+        theme.second_line = second;
+        theme.other_lines = other;
+        theme.best = best;
+        theme.good = good;
+        theme.inaccuracy = inaccuracy;
+        theme.mistake = mistake;
+    }
+}
+
+/// Built-in looks, applied to the theme and arrow style as a starting
+/// point for further edits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThemePreset {
+    Standard,
+    Subtle,
+    HighContrast,
+    /// The deuteranopia palette, whose blue and orange also stay apart
+    /// for most other kinds of color blindness.
+    ColorblindSafe,
+}
+
+impl ThemePreset {
+    pub const ALL: [ThemePreset; 4] = [
+        ThemePreset::Standard,
+        ThemePreset::Subtle,
+        ThemePreset::HighContrast,
+        ThemePreset::ColorblindSafe,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ThemePreset::Standard => "Standard",
+            ThemePreset::Subtle => "Subtle",
+            ThemePreset::HighContrast => "High contrast",
+            ThemePreset::ColorblindSafe => "Colorblind-safe",
+        }
+    }
+
+    /// The preset's theme, with the arrow width and opacity it goes with.
+    pub fn theme(&self) -> (OverlayTheme, f32, f32) {
+        let standard = OverlayTheme::default();
+        let defaults = ArrowStyle::default();
+        match self {
+            ThemePreset::Standard => (standard, defaults.thickness, defaults.opacity),
+            ThemePreset::Subtle => (
+                OverlayTheme {
+                    first_line: Rgb(120, 220, 140),
+                    second_line: Rgb(120, 220, 140),
+                    other_lines: Rgb(120, 220, 140),
+                    second_line_opacity: 0.45,
+                    other_lines_opacity: 0.2,
+                    best: Rgb(120, 220, 140),
+                    good: Rgb(180, 220, 120),
+                    inaccuracy: Rgb(230, 200, 110),
+                    mistake: Rgb(230, 120, 110),
+                    palette: Palette::Custom,
+                    highlight_opacity: 0.3,
+                    hud_font_size: 11.0,
+                    ..standard
+                },
+                0.1,
+                0.7,
+            ),
+            ThemePreset::HighContrast => (
+                OverlayTheme {
+                    second_line: Rgb(0, 200, 255),
+                    other_lines: Rgb(255, 0, 255),
+                    second_line_opacity: 0.85,
+                    other_lines_opacity: 0.6,
+                    good: Rgb(200, 255, 0),
+                    inaccuracy: Rgb(255, 210, 0),
+                    mistake: Rgb(255, 30, 30),
+                    palette: Palette::Custom,
+                    highlight_opacity: 0.6,
+                    hud_font_size: 15.0,
+                    board_dim: 0.35,
+                    ..standard
+                },
+                0.22,
+                1.0,
+            ),
+            ThemePreset::ColorblindSafe => {
+                let mut theme = standard;
+                Palette::Deuteranopia.apply(&mut theme);
+                (theme, defaults.thickness, defaults.opacity)
+            }
+        }
+    }
+
+    pub fn apply(&self, theme: &mut OverlayTheme, style: &mut ArrowStyle) {
+        let (preset, thickness, opacity) = self.theme();
+        *theme = preset;
+        style.thickness = thickness;
+        style.opacity = opacity;
+    }
+}
+
+fn edit_color(ui: &mut egui::Ui, color: &mut Rgb) -> egui::Response {
+    let mut rgb = [color.0, color.1, color.2];
+    let response = ui.color_edit_button_srgb(&mut rgb);
+    *color = Rgb(rgb[0], rgb[1], rgb[2]);
+    response
+}
+
+/// A color button with its label; true when the color was changed.
+fn color_row(ui: &mut egui::Ui, label: &str, color: &mut Rgb) -> bool {
+    ui.horizontal(|ui| {
+        let changed = edit_color(ui, color).changed();
+        ui.label(label);
+        changed
+    })
+    .inner
+}
+
+/// A checkbox turning an optional color on (starting at `initial`) or
+/// off, with its color button while on.
+fn optional_color_row(ui: &mut egui::Ui, label: &str, color: &mut Option<Rgb>, initial: Rgb) {
+    ui.horizontal(|ui| {
+        let mut on = color.is_some();
+        if ui.checkbox(&mut on, label).changed() {
+            *color = on.then_some(initial);
+        }
+        if let Some(color) = color {
+            edit_color(ui, color);
+        }
+    });
+}
+
+/// Small board with sample arrows drawn the way the overlay would, so
+/// theme edits can be judged before looking at a real game.
+fn preview(ui: &mut egui::Ui, theme: &OverlayTheme, style: &ArrowStyle, move_colors: bool) {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(160.0, 160.0), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let square = rect.width() / 8.0;
+    for rank in 0..8 {
+        for file in 0..8 {
+            let min = rect.min + egui::vec2(file as f32 * square, rank as f32 * square);
+            let light = (rank + file) % 2 == 0;
+            let fill = if light {
+                egui::Color32::from_rgb(240, 217, 181)
+            } else {
+                egui::Color32::from_rgb(181, 136, 99)
+            };
+            let cell = egui::Rect::from_min_size(min, egui::vec2(square, square));
+            painter.rect_filled(cell, 0.0, fill);
+        }
+    }
+    painter.rect_filled(rect, 0.0, egui::Color32::from_black_alpha(dim_alpha(theme)));
+    let samples = [
+        ("e2e4", MoveClass::Best),
+        ("g1f3", MoveClass::Good),
+        ("d2d4", MoveClass::Inaccuracy),
+        ("b1a3", MoveClass::Mistake),
+    ];
+    for (rank, (m, class)) in samples.iter().enumerate().rev() {
+        let base = theme.arrow_color(rank, Some(*class), true, move_colors);
+        draw_arrow(
+            &painter,
+            rect,
+            m,
+            base.with_alpha(theme.line(rank).1),
+            style,
+        );
+    }
+    // Evaluation bar at +1, White's share from the bottom
+    let bar = egui::Rect::from_min_size(rect.min, egui::vec2(square / 4.0, rect.height()));
+    painter.rect_filled(bar, 0.0, theme.eval_black.color32());
+    let white = egui::Rect::from_min_max(
+        egui::pos2(bar.min.x, bar.max.y - bar.height() * 0.6),
+        bar.max,
+    );
+    painter.rect_filled(white, 0.0, theme.eval_white.color32());
+}
+
+/// Alpha of the black layer that darkens boards by `board_dim`.
+pub fn dim_alpha(theme: &OverlayTheme) -> u8 {
+    (theme.board_dim.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Theme settings: presets, colors, opacities and sizes, next to a live
+/// preview. `move_colors` picks which arrow colors the preview shows.
+pub fn theme_editor(
+    ui: &mut egui::Ui,
+    theme: &mut OverlayTheme,
+    style: &mut ArrowStyle,
+    move_colors: bool,
+) {
+    ui.horizontal_wrapped(|ui| {
+        ui.label("Presets:");
+        for preset in ThemePreset::ALL {
+            if ui.button(preset.label()).clicked() {
+                preset.apply(theme, style);
+            }
+        }
+    });
+    egui::ComboBox::from_label("Palette")
+        .selected_text(theme.palette.label())
+        .show_ui(ui, |ui| {
+            for palette in Palette::ALL {
+                if ui
+                    .selectable_label(theme.palette == palette, palette.label())
+                    .clicked()
+                {
+                    palette.apply(theme);
+                }
+            }
+        });
+    ui.horizontal(|ui| {
+        ui.vertical(|ui| {
+            let edited = if move_colors {
+                color_row(ui, "Best", &mut theme.best)
+                    | color_row(ui, "Good", &mut theme.good)
+                    | color_row(ui, "Inaccuracy", &mut theme.inaccuracy)
+                    | color_row(ui, "Mistake", &mut theme.mistake)
+            } else {
+                color_row(ui, "First line", &mut theme.first_line)
+                    | color_row(ui, "Second line", &mut theme.second_line)
+                    | color_row(ui, "Other lines", &mut theme.other_lines)
+            };
+            if edited {
+                theme.palette = Palette::Custom;
+            }
+            optional_color_row(
+                ui,
+                "White's moves",
+                &mut theme.white_arrows,
+                Rgb(240, 240, 240),
+            );
+            optional_color_row(
+                ui,
+                "Black's moves",
+                &mut theme.black_arrows,
+                Rgb(40, 40, 40),
+            );
+            optional_color_row(ui, "Square tint", &mut theme.highlight, Rgb(255, 220, 60));
+            color_row(ui, "Eval bar White", &mut theme.eval_white);
+            color_row(ui, "Eval bar Black", &mut theme.eval_black);
+        })
+        .response
+        .on_hover_text(
+            "Side colors replace the line and quality colors for that side's moves; \
+             without a square tint, squares take the move's arrow color",
+        );
+        preview(ui, theme, style, move_colors);
+    });
+    ui.add(egui::Slider::new(&mut theme.first_line_opacity, 0.05..=1.0).text("First line opacity"));
+    ui.add(
+        egui::Slider::new(&mut theme.second_line_opacity, 0.05..=1.0).text("Second line opacity"),
+    );
+    ui.add(
+        egui::Slider::new(&mut theme.other_lines_opacity, 0.05..=1.0).text("Other lines opacity"),
+    );
+    ui.add(egui::Slider::new(&mut theme.highlight_opacity, 0.05..=1.0).text("Square tint opacity"));
+    ui.add(egui::Slider::new(&mut theme.hud_font_size, 8.0..=24.0).text("HUD text size"));
+    ui.add(egui::Slider::new(&mut theme.board_dim, 0.0..=0.8).text("Dim boards"))
+        .on_hover_text("Darken analyzed boards so the drawings stand out");
+    if *theme != OverlayTheme::default() && ui.button("Reset theme").clicked() {
+        *theme = OverlayTheme::default();
+    }
+}
@@ -0,0 +1,60 @@
+use crate::errors::{format_age, ErrorLog};
+use eframe::egui;
+use std::time::Duration;
+
+/// Errors seen this recently are shown as toasts.
+const TOAST_FOR: Duration = Duration::from_secs(15);
+/// At most this many toasts at once, newest first.
+const MAX_TOASTS: usize = 3;
+
+/// Recent errors as toasts in the overlay's bottom-right corner, each with
+/// its age, a "Copy details" button and a close button. Returns their area
+/// so the overlay can take the mouse over them, or `None` when there are
+/// none.
+pub fn show_toasts(ctx: &egui::Context, errors: &mut ErrorLog) -> Option<egui::Rect> {
+    let recent: Vec<_> = errors
+        .recent(TOAST_FOR)
+        .into_iter()
+        .take(MAX_TOASTS)
+        .cloned()
+        .collect();
+    if recent.is_empty() {
+        return None;
+    }
+    let mut dismissed = Vec::new();
+    let response = egui::Area::new(egui::Id::new("error_toasts"))
+        .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-12.0, -12.0))
+        .order(egui::Order::Foreground)
+        .show(ctx, |ui| {
+            for entry in &recent {
+                egui::Frame::popup(ui.style())
+                    .fill(egui::Color32::from_rgb(70, 20, 20))
+                    .show(ui, |ui| {
+                        ui.set_max_width(320.0);
+                        ui.horizontal(|ui| {
+                            ui.strong(entry.error.kind());
+                            ui.weak(format!("{} ago", format_age(entry.last_seen)));
+                            if entry.count > 1 {
+                                ui.weak(format!("×{}", entry.count));
+                            }
+                            ui.with_layout(
+                                egui::Layout::right_to_left(egui::Align::Center),
+                                |ui| {
+                                    if ui.small_button("✕").clicked() {
+                                        dismissed.push(entry.error.clone());
+                                    }
+                                    if ui.small_button("Copy details").clicked() {
+                                        ui.output_mut(|o| o.copied_text = entry.details());
+                                    }
+                                },
+                            );
+                        });
+                        ui.label(entry.error.message());
+                    });
+            }
+        });
+    for error in &dismissed {
+        errors.dismiss(error);
+    }
+    Some(response.response.rect)
+}
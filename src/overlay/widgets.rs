@@ -0,0 +1,29 @@
+//! Small widgets shared by several sections of the settings window.
+
+use eframe::egui;
+
+/// Single-line text field for `value` that writes it back only when
+/// editing ends, not on every keystroke, so paths and lists aren't acted
+/// on half-typed. The text being edited is kept in egui's temp data under
+/// `id_source`. Returns whether `value` was written.
+pub fn deferred_text_edit(
+    ui: &mut egui::Ui,
+    id_source: &str,
+    value: &mut String,
+    hint: &str,
+) -> bool {
+    let id = ui.id().with(id_source);
+    let mut text = ui
+        .data_mut(|d| d.get_temp::<String>(id))
+        .unwrap_or_else(|| value.clone());
+    let response = ui.add(egui::TextEdit::singleline(&mut text).hint_text(hint));
+    if response.lost_focus() {
+        *value = text;
+        ui.data_mut(|d| d.remove::<String>(id));
+        return true;
+    }
+    if response.has_focus() {
+        ui.data_mut(|d| d.insert_temp(id, text));
+    }
+    false
+}
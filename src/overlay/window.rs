@@ -1,45 +1,162 @@
+use crate::capture::target_window::TargetWindow;
 use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
 
-pub fn draw_arrow(painter: &egui::Painter, rect: egui::Rect, m: &str, color: egui::Color32) {
-    if m.len() < 4 {
-        return;
+/// Screen position of the centre of a square like "e4" on a board drawn
+/// with White at the bottom.
+pub fn square_center(rect: egui::Rect, sq: &str) -> Option<egui::Pos2> {
+    let mut chars = sq.chars();
+    let col = (chars.next()? as u32).checked_sub('a' as u32)?;
+    let row = chars.next()?.to_digit(10)?.checked_sub(1)?;
+
+    let cell_w = rect.width() / 8.0;
+    let cell_h = rect.height() / 8.0;
+
+    Some(egui::pos2(
+        rect.min.x + (col as f32 + 0.5) * cell_w,
+        rect.min.y + (7.0 - row as f32 + 0.5) * cell_h,
+    ))
+}
+
+/// Title of the main overlay window, used to find its native handle.
+pub const OVERLAY_TITLE: &str = "Chess Overlay Visuals";
+/// How often the overlay's virtual desktop is checked.
+const DESKTOP_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Which virtual desktops the overlay shows on.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DesktopMode {
+    /// Move to whichever desktop the game window is on.
+    #[default]
+    FollowGame,
+    /// Show on every desktop.
+    Pinned,
+    /// Leave it to the OS: the desktop the app was started on.
+    Stay,
+}
+
+impl DesktopMode {
+    pub const ALL: [DesktopMode; 3] = [
+        DesktopMode::FollowGame,
+        DesktopMode::Pinned,
+        DesktopMode::Stay,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DesktopMode::FollowGame => "Follow the game window",
+            DesktopMode::Pinned => "All desktops",
+            DesktopMode::Stay => "Starting desktop",
+        }
     }
-    let from_sq = &m[0..2];
-    let to_sq = &m[2..4];
+}
+
+/// Keeps the overlay on the virtual desktop(s) chosen by `DesktopMode`, so
+/// switching desktops doesn't leave arrows drawn over the wrong one.
+#[derive(Default)]
+pub struct DesktopKeeper {
+    applied: Option<DesktopMode>,
+    last_check: Option<Instant>,
+    game: TargetWindow,
+}
 
-    let sq_to_pos = |sq: &str| -> egui::Pos2 {
-        let col_char = sq.chars().nth(0).unwrap();
-        let row_char = sq.chars().nth(1).unwrap();
+impl DesktopKeeper {
+    /// `game_point` is the board region's center in desktop pixels.
+    pub fn update(&mut self, mode: DesktopMode, game_point: Option<(i32, i32)>) {
+        if self
+            .last_check
+            .is_some_and(|t| t.elapsed() < DESKTOP_CHECK_INTERVAL)
+        {
+            return;
+        }
+        self.last_check = Some(Instant::now());
+        if self.applied != Some(mode) {
+            match set_pinned(mode == DesktopMode::Pinned) {
+                Ok(()) => self.applied = Some(mode),
+                Err(e) => println!("Overlay desktop mode not applied: {:?}", e),
+            }
+        }
+        if mode != DesktopMode::FollowGame {
+            return;
+        }
+        let Some(point) = game_point else {
+            self.game.forget();
+            return;
+        };
+        self.game.track(point);
+        if let Some(game) = self.game.handle() {
+            if let Err(e) = follow(game) {
+                println!("Moving the overlay to the game's desktop failed: {:?}", e);
+            }
+        }
+    }
+}
 
-        let col = col_char as u32 - 'a' as u32;
-        let row = row_char.to_digit(10).unwrap_or(1) - 1;
+#[cfg(windows)]
+fn overlay_window() -> anyhow::Result<windows::Win32::Foundation::HWND> {
+    use windows::core::{HSTRING, PCWSTR};
+    use windows::Win32::UI::WindowsAndMessaging::{FindWindowW, GetWindowThreadProcessId};
 
-        let cell_w = rect.width() / 8.0;
-        let cell_h = rect.height() / 8.0;
+    let window = unsafe { FindWindowW(PCWSTR::null(), &HSTRING::from(OVERLAY_TITLE))? };
+    let mut pid = 0;
+    unsafe { GetWindowThreadProcessId(window, Some(&mut pid as *mut u32)) };
+    if pid != std::process::id() {
+        anyhow::bail!("overlay window not found");
+    }
+    Ok(window)
+}
 
-        egui::pos2(
-            rect.min.x + (col as f32 + 0.5) * cell_w,
-            rect.min.y + (7.0 - row as f32 + 0.5) * cell_h,
-        )
+/// Tool windows aren't tracked by the virtual desktop manager, so they show
+/// on every desktop.
+#[cfg(windows)]
+fn set_pinned(pinned: bool) -> anyhow::Result<()> {
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetWindowLongPtrW, SetWindowLongPtrW, ShowWindow, GWL_EXSTYLE, SW_HIDE, SW_SHOWNOACTIVATE,
+        WS_EX_TOOLWINDOW,
     };
 
-    let start = sq_to_pos(from_sq);
-    let end = sq_to_pos(to_sq);
-
-    painter.line_segment([start, end], egui::Stroke::new(5.0, color));
+    let window = overlay_window()?;
+    unsafe {
+        let style = GetWindowLongPtrW(window, GWL_EXSTYLE);
+        let tool = WS_EX_TOOLWINDOW.0 as isize;
+        let wanted = if pinned { style | tool } else { style & !tool };
+        if wanted != style {
+            // The shell only notices the style change when the window reappears
+            let _ = ShowWindow(window, SW_HIDE);
+            SetWindowLongPtrW(window, GWL_EXSTYLE, wanted);
+            let _ = ShowWindow(window, SW_SHOWNOACTIVATE);
+        }
+    }
+    Ok(())
+}
 
-    // Draw arrowhead
-    let dir = (end - start).normalized();
-    if dir.length() > 0.0 {
-        let norm = egui::vec2(-dir.y, dir.x);
-        let arrow_head_size = 15.0;
-        let p1 = end - dir * arrow_head_size + norm * arrow_head_size * 0.5;
-        let p2 = end - dir * arrow_head_size - norm * arrow_head_size * 0.5;
+#[cfg(windows)]
+fn follow(game: isize) -> anyhow::Result<()> {
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_ALL};
+    use windows::Win32::UI::Shell::{IVirtualDesktopManager, VirtualDesktopManager};
 
-        painter.add(egui::Shape::convex_polygon(
-            vec![end, p1, p2],
-            color,
-            egui::Stroke::NONE,
-        ));
+    let window = overlay_window()?;
+    let game = HWND(game as _);
+    unsafe {
+        let manager: IVirtualDesktopManager =
+            CoCreateInstance(&VirtualDesktopManager, None, CLSCTX_ALL)?;
+        let desktop = manager.GetWindowDesktopId(game)?;
+        if manager.GetWindowDesktopId(window)? != desktop {
+            manager.MoveWindowToDesktop(window, &desktop)?;
+        }
     }
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn set_pinned(_pinned: bool) -> anyhow::Result<()> {
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn follow(_game: isize) -> anyhow::Result<()> {
+    Ok(())
 }
@@ -0,0 +1,92 @@
+use crate::platform::ENGINE_BINARY;
+use directories::ProjectDirs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Marker file that enables portable mode when placed next to the executable.
+const PORTABLE_MARKER: &str = "portable.txt";
+
+static PORTABLE: OnceLock<bool> = OnceLock::new();
+
+fn exe_dir() -> Option<PathBuf> {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|p| p.to_path_buf()))
+}
+
+/// Portable mode keeps every file the app reads or writes next to the
+/// executable, so it can run from a USB stick or a sandboxed folder.
+pub fn is_portable() -> bool {
+    *PORTABLE.get_or_init(|| {
+        std::env::args().any(|a| a == "--portable")
+            || exe_dir().is_some_and(|d| d.join(PORTABLE_MARKER).exists())
+    })
+}
+
+/// Directory holding config, models, engine and any session data.
+pub fn app_dir() -> PathBuf {
+    if is_portable() {
+        if let Some(dir) = exe_dir() {
+            return dir;
+        }
+    }
+    std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."))
+}
+
+/// Directory holding the config files: the app folder in portable mode,
+/// otherwise the platform's config folder (`%APPDATA%\MoveOverlay\config`
+/// on Windows, `~/.config/moveoverlay` on Linux, `~/Library/Application
+/// Support/MoveOverlay` on macOS), or the app folder when there is none.
+pub fn config_dir() -> PathBuf {
+    if is_portable() {
+        return app_dir();
+    }
+    ProjectDirs::from("", "", "MoveOverlay")
+        .map(|d| d.config_dir().to_path_buf())
+        .unwrap_or_else(app_dir)
+}
+
+/// Directory of a named profile's config files; `None` is the default
+/// profile, kept directly in `config_dir`.
+pub fn profile_dir(profile: Option<&str>) -> PathBuf {
+    match profile {
+        Some(name) => config_dir().join("profiles").join(name),
+        None => config_dir(),
+    }
+}
+
+/// Path of the Stockfish binary: the one in the app folder, or outside
+/// Windows one installed on the `PATH` (e.g. by the package manager) when
+/// the app folder has none.
+pub fn engine_path() -> PathBuf {
+    let bundled = app_dir().join(ENGINE_BINARY);
+    if bundled.exists() || cfg!(windows) {
+        return bundled;
+    }
+    std::env::var_os("PATH")
+        .iter()
+        .flat_map(std::env::split_paths)
+        // Debian and Ubuntu install it outside the default PATH
+        .chain([PathBuf::from("/usr/games")])
+        .map(|dir| dir.join(ENGINE_BINARY))
+        .find(|path| path.is_file())
+        .unwrap_or(bundled)
+}
+
+/// Model file to load: the configured path (relative paths are taken from
+/// the app folder), or `best.onnx` in the app folder, falling back to the
+/// executable's folder when the app was launched from somewhere else.
+pub fn model_path(configured: &str) -> PathBuf {
+    let configured = configured.trim();
+    if !configured.is_empty() {
+        return app_dir().join(configured);
+    }
+    let default = app_dir().join("best.onnx");
+    if default.exists() {
+        return default;
+    }
+    exe_dir()
+        .map(|d| d.join("best.onnx"))
+        .filter(|p| p.exists())
+        .unwrap_or(default)
+}
@@ -0,0 +1,250 @@
+//! What the overlay can do on the OS and display server it runs on, so
+//! features that can't work there are explained in the settings window
+//! instead of failing silently.
+
+use crate::capture::monitor::screen_for;
+use anyhow::Result;
+use std::sync::OnceLock;
+
+/// File name of the Stockfish binary on this OS.
+pub const ENGINE_BINARY: &str = if cfg!(windows) {
+    "stockfish.exe"
+} else {
+    "stockfish"
+};
+
+/// System Settings page listing the apps allowed to record the screen.
+const SCREEN_RECORDING_SETTINGS: &str =
+    "x-apple.systempreferences:com.apple.preference.security?Privacy_ScreenCapture";
+
+/// Side of the capture check, in pixels; small so the Wayland portal or a
+/// permission prompt is the only cost.
+const CHECK_SIZE: u32 = 16;
+
+/// Windowing system the overlay is drawn on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DisplayServer {
+    Windows,
+    MacOs,
+    X11,
+    Wayland,
+    Unknown,
+}
+
+impl DisplayServer {
+    /// Wayland wins over X11 when both are set, as winit picks it too.
+    pub fn detect() -> Self {
+        if cfg!(windows) {
+            return DisplayServer::Windows;
+        }
+        if cfg!(target_os = "macos") {
+            return DisplayServer::MacOs;
+        }
+        let set = |var: &str| std::env::var_os(var).is_some_and(|v| !v.is_empty());
+        if set("WAYLAND_DISPLAY") {
+            DisplayServer::Wayland
+        } else if set("DISPLAY") {
+            DisplayServer::X11
+        } else {
+            DisplayServer::Unknown
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DisplayServer::Windows => "Windows",
+            DisplayServer::MacOs => "macOS",
+            DisplayServer::X11 => "Linux (X11)",
+            DisplayServer::Wayland => "Linux (Wayland)",
+            DisplayServer::Unknown => "Unknown display server",
+        }
+    }
+}
+
+/// How well a feature works on this platform.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Support {
+    Full,
+    /// Works with limits explained by the capability's note.
+    Degraded,
+    Missing,
+}
+
+impl Support {
+    pub fn icon(&self) -> &'static str {
+        match self {
+            Support::Full => "✔",
+            Support::Degraded => "⚠",
+            Support::Missing => "✖",
+        }
+    }
+}
+
+/// One overlay feature and how it behaves here.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Capability {
+    pub name: &'static str,
+    pub support: Support,
+    pub note: &'static str,
+}
+
+fn capability(name: &'static str, support: Support, note: &'static str) -> Capability {
+    Capability {
+        name,
+        support,
+        note,
+    }
+}
+
+/// The display server found at startup.
+pub fn display_server() -> DisplayServer {
+    static DISPLAY: OnceLock<DisplayServer> = OnceLock::new();
+    *DISPLAY.get_or_init(DisplayServer::detect)
+}
+
+/// Overlay features and their support on this display server.
+pub fn capabilities() -> Vec<Capability> {
+    use Support::*;
+    match display_server() {
+        DisplayServer::Windows => vec![
+            capability("Screen capture", Full, ""),
+            capability("Click-through overlay", Full, ""),
+            capability("Always on top", Full, ""),
+            capability("Monitor placement", Full, ""),
+            capability("Global hotkeys", Full, ""),
+            capability("Game window tracking", Full, ""),
+        ],
+        DisplayServer::MacOs => vec![
+            capability(
+                "Screen capture",
+                Full,
+                "Needs the Screen Recording permission under System Settings → \
+                 Privacy & Security",
+            ),
+            capability("Click-through overlay", Full, ""),
+            capability("Always on top", Full, ""),
+            capability("Monitor placement", Full, ""),
+            capability(
+                "Global hotkeys",
+                Degraded,
+                "Needs Accessibility permission; otherwise keys only work in the \
+                 settings window",
+            ),
+            capability(
+                "Game window tracking",
+                Missing,
+                "Auto-pause and following the game's desktop are Windows only",
+            ),
+            capability(
+                "Engine CPU affinity",
+                Missing,
+                "macOS can't pin the engine to cores; the priority still applies",
+            ),
+        ],
+        DisplayServer::X11 => vec![
+            capability("Screen capture", Full, ""),
+            capability(
+                "Click-through overlay",
+                Degraded,
+                "Needs a compositing window manager for transparency; without one \
+                 the overlay covers the screen in black",
+            ),
+            capability("Always on top", Full, ""),
+            capability("Monitor placement", Full, ""),
+            capability("Global hotkeys", Full, ""),
+            capability(
+                "Game window tracking",
+                Missing,
+                "Auto-pause and following the game's desktop are Windows only",
+            ),
+        ],
+        DisplayServer::Wayland => vec![
+            capability(
+                "Screen capture",
+                Degraded,
+                "Goes through the desktop portal, which may ask for permission and \
+                 limits the capture rate",
+            ),
+            capability("Click-through overlay", Full, ""),
+            capability(
+                "Always on top",
+                Degraded,
+                "Up to the compositor; unset WAYLAND_DISPLAY to run under XWayland \
+                 if the overlay falls behind the browser",
+            ),
+            capability(
+                "Monitor placement",
+                Missing,
+                "Wayland windows can't place themselves; the compositor picks the \
+                 monitor",
+            ),
+            capability(
+                "Global hotkeys",
+                Missing,
+                "Keys only work while the settings window has focus",
+            ),
+            capability(
+                "Game window tracking",
+                Missing,
+                "Auto-pause and following the game's desktop are Windows only",
+            ),
+        ],
+        DisplayServer::Unknown => vec![capability(
+            "Screen capture",
+            Missing,
+            "Neither WAYLAND_DISPLAY nor DISPLAY is set",
+        )],
+    }
+}
+
+#[cfg(target_os = "macos")]
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGPreflightScreenCaptureAccess() -> bool;
+    fn CGRequestScreenCaptureAccess() -> bool;
+}
+
+/// Whether the app may capture other apps' windows. Without macOS' Screen
+/// Recording permission captures only show the wallpaper and menu bar, so
+/// they succeed but never contain a board. Always true elsewhere.
+#[cfg(target_os = "macos")]
+pub fn screen_recording_allowed() -> bool {
+    unsafe { CGPreflightScreenCaptureAccess() }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn screen_recording_allowed() -> bool {
+    true
+}
+
+/// Shows macOS' permission prompt, once per app install; later calls only
+/// add the app to the Screen Recording list.
+#[cfg(target_os = "macos")]
+pub fn request_screen_recording() {
+    unsafe { CGRequestScreenCaptureAccess() };
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn request_screen_recording() {}
+
+/// Opens the Screen Recording page of System Settings.
+pub fn open_screen_recording_settings() -> Result<()> {
+    std::process::Command::new("open")
+        .arg(SCREEN_RECORDING_SETTINGS)
+        .spawn()?;
+    Ok(())
+}
+
+/// Grabs a few pixels of the chosen monitor to confirm capture works
+/// before the board region is needed.
+pub fn check_capture(monitor: Option<u32>) -> Result<()> {
+    if !screen_recording_allowed() {
+        anyhow::bail!("Screen Recording permission is missing");
+    }
+    let screen = screen_for(monitor)?;
+    let image = screen.capture_area(0, 0, CHECK_SIZE, CHECK_SIZE)?;
+    if image.width() == 0 || image.height() == 0 {
+        anyhow::bail!("the capture came back empty");
+    }
+    Ok(())
+}
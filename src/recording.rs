@@ -0,0 +1,248 @@
+//! Raw frame recording and deterministic offline replay.
+//!
+//! While recording, every analyzed board crop is saved together with the
+//! settings and sparring seed it was analyzed with. `--replay-frames <dir>`
+//! feeds those crops back through detection, board selection, FEN building
+//! and game tracking, writing one result line per frame, so two builds can
+//! be diffed to bisect a recognition or tracker regression.
+
+use crate::chess_logic::{GameTracker, TurnDetector};
+use crate::engine::sparring::Sparring;
+use crate::vision::board::{min_board_fraction, select_board};
+use crate::vision::color::correct_colors;
+use crate::vision::inference::{Detector, InferenceBackend, DEFAULT_IOU_THRESHOLD};
+use crate::vision::site::board_grid;
+use crate::worker::{board_position, MAX_SKILL_LEVEL};
+use anyhow::{Context, Result};
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const FRAMES_FILE: &str = "frames.jsonl";
+const RESULTS_FILE: &str = "replay.jsonl";
+
+/// Settings that change what the pipeline produces for a frame.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct PipelineParams {
+    pub confidence_threshold: f32,
+    /// Missing in recordings made before it was configurable.
+    #[serde(default = "default_iou_threshold")]
+    pub iou_threshold: f32,
+    #[serde(default)]
+    pub reassign_conflicts: bool,
+    #[serde(default)]
+    pub refine_grid: bool,
+    #[serde(default)]
+    pub coordinate_strip: f32,
+    /// Confidence below which piece colors are checked, when on.
+    #[serde(default)]
+    pub color_check: Option<f32>,
+    pub min_board_px: u32,
+    pub board_cycle: usize,
+    pub show_white_moves: bool,
+    pub auto_side_to_move: bool,
+    pub sparring: bool,
+}
+
+fn default_iou_threshold() -> f32 {
+    DEFAULT_IOU_THRESHOLD
+}
+
+/// One recorded crop and how it was analyzed.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RecordedFrame {
+    pub index: u64,
+    pub board: usize,
+    /// Milliseconds since the Unix epoch.
+    pub timestamp_ms: u64,
+    /// Region size on screen; the crop itself may be scaled.
+    pub region_px: u32,
+    /// Sparring seed used for this frame.
+    pub seed: u64,
+    pub params: PipelineParams,
+    pub image: String,
+}
+
+/// What the pipeline made of a frame, as written by the replay.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FrameResult {
+    pub index: u64,
+    pub board: usize,
+    pub detections: usize,
+    pub fen: Option<String>,
+    pub opponent_fen: Option<String>,
+    /// Why no position was produced.
+    pub error: Option<String>,
+    pub skill: u32,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Per-session seed from the clock; frame seeds are derived from it.
+pub fn session_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0x9E37_79B9_7F4A_7C15)
+}
+
+/// Seed for frame `index` of a session (splitmix64), so sparring choices
+/// can be reproduced frame by frame.
+pub fn frame_seed(session: u64, index: u64) -> u64 {
+    let mut z = session.wrapping_add(index.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Saves analyzed crops into `recordings/rec-<timestamp>/` as PNGs, with
+/// their metadata appended to `frames.jsonl`.
+pub struct FrameRecorder {
+    dir: PathBuf,
+    index: File,
+    frames: u64,
+}
+
+impl FrameRecorder {
+    pub fn new() -> Result<Self> {
+        let dir = crate::paths::app_dir()
+            .join("recordings")
+            .join(format!("rec-{}", now_ms() / 1000));
+        fs::create_dir_all(&dir)?;
+        let index = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(FRAMES_FILE))?;
+        println!("Recording frames to {}", dir.display());
+        Ok(Self {
+            dir,
+            index,
+            frames: 0,
+        })
+    }
+
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    pub fn record(
+        &mut self,
+        frame: u64,
+        board: usize,
+        img: &DynamicImage,
+        region_px: u32,
+        seed: u64,
+        params: PipelineParams,
+    ) -> Result<()> {
+        let image = format!("frame-{:06}-b{}.png", frame, board);
+        img.save(self.dir.join(&image))?;
+        let entry = RecordedFrame {
+            index: frame,
+            board,
+            timestamp_ms: now_ms(),
+            region_px,
+            seed,
+            params,
+            image,
+        };
+        writeln!(self.index, "{}", serde_json::to_string(&entry)?)?;
+        self.frames += 1;
+        Ok(())
+    }
+}
+
+fn read_frames(dir: &Path) -> Result<Vec<RecordedFrame>> {
+    let file = File::open(dir.join(FRAMES_FILE))
+        .with_context(|| format!("no {} in {}", FRAMES_FILE, dir.display()))?;
+    let mut frames = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        frames.push(serde_json::from_str(&line)?);
+    }
+    Ok(frames)
+}
+
+/// Re-runs a recording through the vision and tracking pipeline with the
+/// recorded settings and seeds, writing `replay.jsonl` next to the frames.
+/// The engine is not involved, so the output is the same on every run.
+pub fn replay(dir: &Path, model_path: &Path) -> Result<PathBuf> {
+    let frames = read_frames(dir)?;
+    // CPU only, so results don't depend on the machine's GPU
+    let mut detector = Detector::new(
+        model_path.to_str().unwrap_or_default(),
+        InferenceBackend::Cpu,
+    )?;
+    let mut trackers: Vec<(GameTracker, TurnDetector)> = Vec::new();
+    let out_path = dir.join(RESULTS_FILE);
+    let mut out = File::create(&out_path)?;
+
+    for frame in &frames {
+        if trackers.len() <= frame.board {
+            trackers.resize_with(frame.board + 1, Default::default);
+        }
+        let (game, turn) = &mut trackers[frame.board];
+        let img = image::open(dir.join(&frame.image))
+            .with_context(|| format!("failed to load {}", frame.image))?;
+        let p = frame.params;
+        let mut detections = detector.detect(&img, p.confidence_threshold, p.iou_threshold)?;
+        if let Some(below) = p.color_check {
+            correct_colors(&img, &mut detections, below);
+        }
+        let min_fraction = min_board_fraction(p.min_board_px, frame.region_px);
+        let candidates = select_board(&detections, min_fraction, p.board_cycle);
+        let grid = candidates
+            .selected
+            .and_then(|b| board_grid(&img, b, p.coordinate_strip, p.refine_grid));
+        let position = board_position(
+            &detections,
+            grid.as_ref().or(candidates.selected),
+            p.reassign_conflicts,
+            p.show_white_moves,
+            p.auto_side_to_move,
+            game,
+            turn,
+        );
+        let skill = if p.sparring {
+            Sparring::with_seed(frame.seed).skill_level()
+        } else {
+            MAX_SKILL_LEVEL
+        };
+        let (fen, opponent_fen, error) = match position {
+            Ok((fen, opponent)) => (Some(fen), opponent, None),
+            Err(e) => (None, None, Some(e.to_string())),
+        };
+        let result = FrameResult {
+            index: frame.index,
+            board: frame.board,
+            detections: detections.len(),
+            fen,
+            opponent_fen,
+            error,
+            skill,
+        };
+        println!(
+            "#{} b{}: {}",
+            result.index,
+            result.board,
+            result
+                .fen
+                .as_deref()
+                .or(result.error.as_deref())
+                .unwrap_or("no position")
+        );
+        writeln!(out, "{}", serde_json::to_string(&result)?)?;
+    }
+    println!("Replayed {} frames", frames.len());
+    Ok(out_path)
+}
@@ -0,0 +1,270 @@
+//! Optional local server for OBS overlays, Discord bots and loggers:
+//! `GET /position` answers with the latest analysis as JSON, and a
+//! WebSocket on `/ws` sends the same JSON whenever it changes. Only
+//! listens on 127.0.0.1, and refuses requests made by web pages (which a
+//! browser tags with their `Origin`) unless that origin was allowed, so a
+//! site open in the browser can't read the analysis or notice the app.
+
+use crate::chess_logic::{line_to_san, uci_to_san};
+use crate::config::AppConfig;
+use crate::engine::stockfish::Evaluation;
+use crate::snapshot::{OverlaySnapshot, SharedSnapshot};
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tungstenite::Message;
+
+/// How often the listener checks for connections and settings changes,
+/// and WebSocket clients for a new analysis.
+const POLL: Duration = Duration::from_millis(100);
+/// Largest request head read from a client.
+const MAX_REQUEST: usize = 8192;
+
+#[derive(Serialize)]
+pub struct LineReport {
+    /// UCI move.
+    #[serde(rename = "move")]
+    pub mv: String,
+    pub san: Option<String>,
+    /// Score from White's point of view: centipawns or moves to mate.
+    pub cp: Option<i32>,
+    pub mate: Option<i32>,
+    pub pv: Vec<String>,
+    pub pv_san: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct BoardReport {
+    pub board: usize,
+    pub fen: String,
+    /// Best line's score from White's point of view.
+    pub cp: Option<i32>,
+    pub mate: Option<i32>,
+    pub depth: u32,
+    pub lines: Vec<LineReport>,
+}
+
+/// Latest analysis of every analyzed board, as served to clients.
+#[derive(Serialize)]
+pub struct PositionReport {
+    pub boards: Vec<BoardReport>,
+}
+
+fn score(eval: Option<Evaluation>) -> (Option<i32>, Option<i32>) {
+    match eval {
+        Some(Evaluation::Centipawns(cp)) => (Some(cp), None),
+        Some(Evaluation::Mate(n)) => (None, Some(n)),
+        None => (None, None),
+    }
+}
+
+pub fn position_report(snapshot: &OverlaySnapshot) -> PositionReport {
+    let boards = snapshot
+        .boards
+        .iter()
+        .enumerate()
+        .filter_map(|(board, analysis)| {
+            let a = analysis.as_ref()?;
+            let white_to_move = a.fen.split_whitespace().nth(1) == Some("w");
+            let (cp, mate) = score(a.eval);
+            let lines = a
+                .moves
+                .iter()
+                .enumerate()
+                .map(|(i, mv)| {
+                    let eval = a.evals.get(i).copied().flatten();
+                    let (cp, mate) = score(eval.map(|e| e.for_white(white_to_move)));
+                    let pv = a.variations.get(i).cloned().unwrap_or_default();
+                    LineReport {
+                        mv: mv.clone(),
+                        san: uci_to_san(&a.fen, mv),
+                        cp,
+                        mate,
+                        pv_san: line_to_san(&a.fen, &pv),
+                        pv,
+                    }
+                })
+                .collect();
+            Some(BoardReport {
+                board,
+                fen: a.fen.clone(),
+                cp,
+                mate,
+                depth: a.depth,
+                lines,
+            })
+        })
+        .collect();
+    PositionReport { boards }
+}
+
+pub fn spawn(config: Arc<Mutex<AppConfig>>, snapshot: SharedSnapshot) -> JoinHandle<()> {
+    thread::spawn(move || run(config, snapshot))
+}
+
+/// Listens while `api_server` is on, rebinding when the port changes.
+fn run(config: Arc<Mutex<AppConfig>>, snapshot: SharedSnapshot) {
+    // Port last bound (or tried), so a busy port isn't retried every poll
+    let mut serving: Option<u16> = None;
+    let mut listener: Option<TcpListener> = None;
+    loop {
+        let wanted = {
+            let c = config.lock().unwrap();
+            c.api_server.then_some(c.api_port)
+        };
+        if wanted != serving {
+            serving = wanted;
+            listener = None;
+            let status = wanted.map(|port| match bind(port) {
+                Ok(l) => {
+                    listener = Some(l);
+                    format!("Serving on http://127.0.0.1:{}/position", port)
+                }
+                Err(e) => format!("Cannot listen on port {}: {}", port, e),
+            });
+            if let Some(status) = &status {
+                println!("{}", status);
+            }
+            config.lock().unwrap().api_status = status;
+        }
+        if let Some(l) = &listener {
+            while let Ok((stream, _)) = l.accept() {
+                let snapshot = snapshot.clone();
+                let allowed = config.lock().unwrap().api_allowed_origins.clone();
+                thread::spawn(move || {
+                    if let Err(e) = handle(stream, &snapshot, &allowed) {
+                        println!("API client: {:#}", e);
+                    }
+                });
+            }
+        }
+        thread::sleep(POLL);
+    }
+}
+
+fn bind(port: u16) -> std::io::Result<TcpListener> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    listener.set_nonblocking(true)?;
+    Ok(listener)
+}
+
+/// Value of the header `name` in a request head.
+fn header<'a>(request: &'a str, name: &str) -> Option<&'a str> {
+    request
+        .lines()
+        .skip(1)
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim())
+}
+
+/// Whether a request with this `Origin` header may be served: none (curl,
+/// bots, OBS), a local file, or one of `allowed`.
+fn origin_allowed(origin: Option<&str>, allowed: &[String]) -> bool {
+    match origin {
+        None | Some("null") => true,
+        Some(origin) if origin.starts_with("file://") => true,
+        Some(origin) => allowed
+            .iter()
+            .any(|a| a.trim().trim_end_matches('/').eq_ignore_ascii_case(origin)),
+    }
+}
+
+/// Whether the `Host` header names this computer, so a web page can't
+/// reach the server through a domain pointed at 127.0.0.1.
+fn host_allowed(host: Option<&str>) -> bool {
+    let Some(host) = host else {
+        return true;
+    };
+    let name = host.rsplit_once(':').map_or(host, |(name, _)| name);
+    matches!(name, "127.0.0.1" | "localhost" | "[::1]")
+}
+
+fn handle(stream: TcpStream, snapshot: &SharedSnapshot, allowed: &[String]) -> Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    // The WebSocket handshake needs the request unread, so only peek
+    let mut head = [0u8; 2048];
+    let n = stream.peek(&mut head)?;
+    let request = String::from_utf8_lossy(&head[..n]);
+    let origin = header(&request, "Origin");
+    if !host_allowed(header(&request, "Host")) || !origin_allowed(origin, allowed) {
+        return respond(
+            stream,
+            "403 Forbidden",
+            r#"{"error":"origin not allowed"}"#,
+            None,
+        );
+    }
+    let mut parts = request.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    match (method, path) {
+        ("GET", "/ws") => stream_updates(stream, snapshot),
+        ("GET", "/position") => {
+            let body = serde_json::to_string(&position_report(&snapshot.load()))?;
+            respond(stream, "200 OK", &body, origin)
+        }
+        _ => respond(
+            stream,
+            "404 Not Found",
+            r#"{"error":"try GET /position or /ws"}"#,
+            origin,
+        ),
+    }
+}
+
+/// Writes a JSON response; `origin`, an allowed web origin, may read it.
+fn respond(mut stream: TcpStream, status: &str, body: &str, origin: Option<&str>) -> Result<()> {
+    // Read the whole head so closing doesn't reset the connection
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < MAX_REQUEST {
+        let n = stream.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+    let cors = origin
+        .map(|o| format!("Access-Control-Allow-Origin: {}\r\nVary: Origin\r\n", o))
+        .unwrap_or_default();
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         {}Connection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        cors,
+        body
+    )?;
+    Ok(())
+}
+
+/// Sends the report on connect and again whenever it changes, until the
+/// client goes away.
+fn stream_updates(stream: TcpStream, snapshot: &SharedSnapshot) -> Result<()> {
+    let mut socket =
+        tungstenite::accept(stream).map_err(|e| anyhow!("WebSocket handshake failed: {}", e))?;
+    // Reads time out each poll to check for a new analysis
+    socket.get_ref().set_read_timeout(Some(POLL))?;
+    let mut last: Option<String> = None;
+    loop {
+        let report = serde_json::to_string(&position_report(&snapshot.load()))?;
+        if last.as_ref() != Some(&report) {
+            socket.send(Message::text(report.clone()))?;
+            last = Some(report);
+        }
+        match socket.read() {
+            Ok(Message::Close(_)) | Err(tungstenite::Error::ConnectionClosed) => return Ok(()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
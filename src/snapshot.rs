@@ -0,0 +1,185 @@
+use crate::capture::stream::CaptureStats;
+use crate::capture::target_window::Hidden;
+use crate::degradation::DegradationLevel;
+use crate::engine::book::BookMove;
+use crate::engine::diagnose::EngineProblem;
+use crate::engine::eval_history::BlunderAlert;
+use crate::engine::explain::Explanation;
+use crate::engine::premove::Premove;
+use crate::engine::quality::{MoveClass, Quality};
+use crate::engine::stockfish::Evaluation;
+use crate::engine::supervisor::EngineHealth;
+use crate::memory::MemoryStatus;
+use crate::vision::inference::InferenceBackend;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Latest analysis of one board.
+#[derive(Clone)]
+pub struct BoardAnalysis {
+    pub fen: String,
+    pub moves: Vec<String>,
+    /// Principal variation per entry of `moves`, starting with that move.
+    pub variations: Vec<Vec<String>>,
+    /// Conditional answers to the opponent's likely replies, when it is
+    /// their turn.
+    pub premoves: Vec<Premove>,
+    /// Score of the best line, from White's point of view.
+    pub eval: Option<Evaluation>,
+    /// Score per entry of `moves`, for the side to move.
+    pub evals: Vec<Option<Evaluation>>,
+    /// Trust badge per entry of `moves`.
+    pub quality: Vec<Quality>,
+    /// Best/good/inaccuracy per entry of `moves`, relative to the best line.
+    pub classes: Vec<MoveClass>,
+    /// Deepest line's search depth.
+    pub depth: u32,
+    /// Engine speed reported by the search, in nodes per second.
+    pub nps: Option<u64>,
+    /// Lowest confidence among the detected pieces the position came from.
+    pub min_confidence: Option<f32>,
+    /// Squares' grid found inside the region (x, y, w, h in capture
+    /// pixels), when refined; otherwise the board fills the region.
+    pub grid: Option<[f32; 4]>,
+    /// Set when the move that led here lost more than the blunder
+    /// threshold.
+    pub blunder: Option<BlunderAlert>,
+    /// What a player of the configured rating would likely play here, in
+    /// human mode; may equal the first of `moves`.
+    pub human_move: Option<String>,
+    /// The opponent's best line (UCI) if the side to move passed, when
+    /// that would cost them; empty otherwise or with threat arrows off.
+    pub threat: Vec<String>,
+    /// Opening book moves; when set the position was not searched and
+    /// `moves` is empty.
+    pub book: Vec<BookMove>,
+    pub analyzed_at: Instant,
+}
+
+/// Moves the game tracker has seen on one board since it last synced, with
+/// the thinking time measured between them.
+#[derive(Clone, Default, PartialEq)]
+pub struct GameRecord {
+    /// Played moves in SAN.
+    pub moves: Vec<String>,
+    /// Whether White made the first listed move, and its move number.
+    pub start: Option<(bool, u32)>,
+    /// Full FEN of the position before the first listed move.
+    pub start_fen: Option<String>,
+    /// Score for White of the start position, then after each move; `None`
+    /// where the position was never analyzed.
+    pub evals: Vec<Option<Evaluation>>,
+    /// Time used per side (White, Black), counted between detected moves.
+    pub time_used: [Duration; 2],
+    /// When the side to move started thinking.
+    pub turn_started: Option<Instant>,
+    pub white_to_move: Option<bool>,
+}
+
+/// A model detection placed on the screen, for the detection debug view.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DetectionBox {
+    /// Left, top, width and height in capture pixels.
+    pub rect: [f32; 4],
+    pub class_id: usize,
+    pub confidence: f32,
+}
+
+/// What the worker reports about itself for the settings window and the
+/// HUD, published with the boards it belongs to.
+#[derive(Clone, Default, PartialEq)]
+pub struct WorkerStatus {
+    /// Last time read from the clock region.
+    pub clock_remaining: Option<Duration>,
+    pub auto_detect_status: Option<String>,
+    /// Set once every capture backend keeps failing.
+    pub capture_error: Option<String>,
+    /// Result of the test capture at startup or on request.
+    pub capture_check: Option<Result<(), String>>,
+    /// macOS hasn't granted Screen Recording, so captures miss every
+    /// window; the worker checks again until it has.
+    pub screen_permission_missing: bool,
+    /// Board candidates rejected by the size filter in the last analysis.
+    pub board_debug: Vec<String>,
+    /// Usable board candidates in the last analysis.
+    pub board_candidates: usize,
+    /// What the last typed move did.
+    pub manual_move_status: Option<String>,
+    /// Why the first board's last detection was not a legal position.
+    pub rejected_position: Option<String>,
+    /// The first board's game tracker no longer matches the detected board.
+    pub tracker_lost: bool,
+    /// Engine options that were rejected or out of range at engine start.
+    pub engine_option_errors: Vec<String>,
+    /// Lines behind the first board's best move, shown in a popup.
+    pub explanation: Option<Explanation>,
+    /// Which model is loaded, or why the chosen one was not.
+    pub model_status: Option<String>,
+    /// Outcome of loading the opening book.
+    pub book_status: Option<String>,
+    /// Whether the running engine supports MultiPV, once known.
+    pub engine_multipv: Option<bool>,
+    /// Backend the model session is actually running on.
+    pub inference_active: Option<InferenceBackend>,
+    /// `best.onnx` was replaced on disk since it was loaded.
+    pub model_updated: bool,
+    /// The engine binary was replaced on disk since it was started.
+    pub engine_updated: bool,
+    /// Why analysis is paused on its own, while the game window is hidden.
+    pub auto_paused: Option<Hidden>,
+    /// Capture is slowed down because no board has changed for a while.
+    pub idle_throttled: bool,
+    /// Why the engine binary could not be started, when recognized.
+    pub engine_problem: Option<EngineProblem>,
+    /// Piece confidence has stayed low for minutes.
+    pub low_confidence: bool,
+    pub samples_collected: usize,
+    pub frames_recorded: u64,
+    pub debug_images_written: usize,
+    pub failure_dumps_written: usize,
+    /// Side to move in the first board's last analysis (true = White).
+    pub side_to_move: Option<bool>,
+    /// Latest process memory reading.
+    pub memory: Option<MemoryStatus>,
+}
+
+/// Everything the overlay draws. The worker builds a new one per cycle and
+/// publishes it whole, so the UI never renders a mix of two cycles.
+#[derive(Clone, Default)]
+pub struct OverlaySnapshot {
+    /// Indexed like `AppConfig::board_regions`.
+    pub boards: Vec<Option<BoardAnalysis>>,
+    pub degradation: DegradationLevel,
+    pub engine: EngineHealth,
+    pub capture: CaptureStats,
+    /// Screen rect (x, y, w, h) of an empty board found by the classical
+    /// grid detector when the model saw no pieces.
+    pub empty_board_grid: Option<[f32; 4]>,
+    /// Every detection of the last analysis per board, while the
+    /// detection view is on. Indexed like `boards`.
+    pub detections: Vec<Vec<DetectionBox>>,
+    /// Move list per board. Indexed like `boards`.
+    pub games: Vec<Option<GameRecord>>,
+    pub status: WorkerStatus,
+    /// Incremented on every publish.
+    pub generation: u64,
+    pub produced_at: Option<Instant>,
+}
+
+/// The current snapshot, shared between worker and UI. Only the `Arc` is
+/// swapped under the lock; readers keep their copy for the whole frame.
+#[derive(Clone, Default)]
+pub struct SharedSnapshot(Arc<Mutex<Arc<OverlaySnapshot>>>);
+
+impl SharedSnapshot {
+    pub fn publish(&self, mut snapshot: OverlaySnapshot) {
+        let mut current = self.0.lock().unwrap();
+        snapshot.generation = current.generation + 1;
+        snapshot.produced_at = Some(Instant::now());
+        *current = Arc::new(snapshot);
+    }
+
+    pub fn load(&self) -> Arc<OverlaySnapshot> {
+        self.0.lock().unwrap().clone()
+    }
+}
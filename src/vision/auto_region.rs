@@ -0,0 +1,111 @@
+use crate::capture::monitor::screen_for;
+use crate::config::BoardRegion;
+use crate::vision::board::INPUT_SIZE;
+use crate::vision::inference::{Detection, Detector};
+use anyhow::Result;
+use image::DynamicImage;
+
+/// Board center may move this share of the region before it counts as drift.
+const DRIFT_OFFSET: f32 = 0.05;
+/// Board size may change by this share before it counts as drift.
+const DRIFT_SCALE: f32 = 0.1;
+
+/// Captures the whole monitor, runs the detector and returns the region
+/// around the most confident board, grown by `padding` (a share of the
+/// board side) and clamped to the screen. Coordinates are relative to the
+/// monitor, like a manually selected region.
+pub fn detect_board_region(
+    detector: &mut Detector,
+    monitor: Option<u32>,
+    conf: f32,
+    iou: f32,
+    padding: f32,
+) -> Result<Option<BoardRegion>> {
+    let screen = screen_for(monitor)?;
+    let img = DynamicImage::ImageRgba8(screen.capture()?);
+    let detections = detector.detect(&img, conf, iou)?;
+    let best = detections
+        .iter()
+        .filter(|d| d.class_id == 0)
+        .max_by(|a, b| a.confidence.total_cmp(&b.confidence));
+    Ok(best.map(|d| region_around(d, img.width(), img.height(), padding)))
+}
+
+fn region_around(d: &Detection, width: u32, height: u32, padding: f32) -> BoardRegion {
+    let sx = width as f32 / INPUT_SIZE;
+    let sy = height as f32 / INPUT_SIZE;
+    let (w, h) = (d.bbox[2] * sx, d.bbox[3] * sy);
+    let pad = w.max(h) * padding;
+    let x0 = (d.bbox[0] * sx - w / 2.0 - pad).max(0.0);
+    let y0 = (d.bbox[1] * sy - h / 2.0 - pad).max(0.0);
+    let x1 = (d.bbox[0] * sx + w / 2.0 + pad).min(width as f32);
+    let y1 = (d.bbox[1] * sy + h / 2.0 + pad).min(height as f32);
+    BoardRegion {
+        x: x0 as u32,
+        y: y0 as u32,
+        width: (x1 - x0) as u32,
+        height: (y1 - y0) as u32,
+    }
+}
+
+/// True when the board detected inside an auto-detected region no longer
+/// sits where detection put it (page scrolled, window moved or resized).
+/// `board` is in model input space of the region capture.
+pub fn has_drifted(board: &Detection, padding: f32) -> bool {
+    let expected = INPUT_SIZE / (1.0 + 2.0 * padding);
+    let center = INPUT_SIZE / 2.0;
+    let offset = (board.bbox[0] - center)
+        .abs()
+        .max((board.bbox[1] - center).abs());
+    let scale = (board.bbox[2].max(board.bbox[3]) / expected - 1.0).abs();
+    offset > DRIFT_OFFSET * INPUT_SIZE || scale > DRIFT_SCALE
+}
+
+/// Consecutive frames that must agree on a new board size before a
+/// manually selected region is rescaled.
+const RESCALE_FRAMES: u32 = 3;
+/// How far those frames' scale ratios may differ from each other.
+const RESCALE_AGREEMENT: f32 = 0.05;
+
+/// Side of the board detected in a region capture, as a share of the region.
+pub fn board_fraction(board: &Detection) -> f32 {
+    board.bbox[2].max(board.bbox[3]) / INPUT_SIZE
+}
+
+/// Notices the board growing or shrinking inside a fixed region (browser
+/// zoom, resized board widget) and reports the factor to rescale the region
+/// by. `reference` is the board's share of the region when it was set.
+#[derive(Default)]
+pub struct RescaleTracker {
+    pending: Option<(f32, u32)>,
+}
+
+impl RescaleTracker {
+    pub fn reset(&mut self) {
+        self.pending = None;
+    }
+
+    /// A size change was seen but not yet confirmed.
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    pub fn observe(&mut self, fraction: f32, reference: f32) -> Option<f32> {
+        let ratio = fraction / reference;
+        if (ratio - 1.0).abs() <= DRIFT_SCALE {
+            self.pending = None;
+            return None;
+        }
+        let frames = match self.pending {
+            Some((prev, n)) if (ratio / prev - 1.0).abs() <= RESCALE_AGREEMENT => n + 1,
+            _ => 1,
+        };
+        self.pending = Some((ratio, frames));
+        if frames >= RESCALE_FRAMES {
+            self.pending = None;
+            Some(ratio)
+        } else {
+            None
+        }
+    }
+}
@@ -1,132 +1,340 @@
+use crate::chess_logic::position_fen;
 use crate::vision::inference::Detection;
-use shakmaty::{fen::Fen, Board, Color, Piece, Role, Setup, Square};
+use shakmaty::{
+    fen::Fen, Board, CastlingMode, Chess, Color, FromSetup, Piece, PositionError,
+    PositionErrorKinds, Role, Setup, Square,
+};
 
-pub fn detections_to_fen(detections: &[Detection], show_white_moves: bool) -> Option<String> {
-    let mut board = Board::empty();
+/// Side length of the model input the detection boxes are expressed in.
+pub const INPUT_SIZE: f32 = 640.0;
 
-    // Map class_id to Piece
-    let class_to_piece = |id: usize| -> Option<Piece> {
-        match id {
-            1 => Some(Piece {
-                color: Color::White,
-                role: Role::King,
-            }),
-            2 => Some(Piece {
-                color: Color::White,
-                role: Role::Queen,
-            }),
-            3 => Some(Piece {
-                color: Color::White,
-                role: Role::Rook,
-            }),
-            4 => Some(Piece {
-                color: Color::White,
-                role: Role::Bishop,
-            }),
-            5 => Some(Piece {
-                color: Color::White,
-                role: Role::Knight,
-            }),
-            6 => Some(Piece {
-                color: Color::White,
-                role: Role::Pawn,
-            }),
-            7 => Some(Piece {
-                color: Color::Black,
-                role: Role::King,
-            }),
-            8 => Some(Piece {
-                color: Color::Black,
-                role: Role::Queen,
-            }),
-            9 => Some(Piece {
-                color: Color::Black,
-                role: Role::Rook,
-            }),
-            10 => Some(Piece {
-                color: Color::Black,
-                role: Role::Bishop,
-            }),
-            11 => Some(Piece {
-                color: Color::Black,
-                role: Role::Knight,
-            }),
-            12 => Some(Piece {
-                color: Color::Black,
-                role: Role::Pawn,
-            }),
-            _ => None,
-        }
+/// Board (class 0) detections split into the one used for the grid and the
+/// ones rejected as too small.
+pub struct BoardCandidates<'a> {
+    pub selected: Option<&'a Detection>,
+    /// Boards large enough to use, most preferred first.
+    pub ranked: Vec<&'a Detection>,
+    pub rejected: Vec<&'a Detection>,
+}
+
+/// Minimum board side, relative to the model input, for a board at least
+/// `min_board_px` screen pixels wide inside a region of `region_px` pixels.
+pub fn min_board_fraction(min_board_px: u32, region_px: u32) -> f32 {
+    (min_board_px as f32 / region_px.max(1) as f32).clamp(0.05, 0.9)
+}
+
+/// Distance from the region center to the box center, or infinity if the
+/// box does not cover the center at all.
+fn center_distance(d: &Detection) -> f32 {
+    let c = INPUT_SIZE / 2.0;
+    let (dx, dy) = ((d.bbox[0] - c).abs(), (d.bbox[1] - c).abs());
+    if dx > d.bbox[2] / 2.0 || dy > d.bbox[3] / 2.0 {
+        return f32::INFINITY;
+    }
+    (dx * dx + dy * dy).sqrt()
+}
+
+/// Ranks board detections at least `min_fraction` of the input on both
+/// sides: boards covering the region center come first (closest first),
+/// then the rest by confidence. `cycle` picks the n-th ranked board, so the
+/// user can step through candidates when a page shows several boards.
+pub fn select_board(
+    detections: &[Detection],
+    min_fraction: f32,
+    cycle: usize,
+) -> BoardCandidates<'_> {
+    let min_side = min_fraction * INPUT_SIZE;
+    let (mut ranked, rejected): (Vec<&Detection>, Vec<&Detection>) = detections
+        .iter()
+        .filter(|d| d.class_id == 0)
+        .partition(|d| d.bbox[2] >= min_side && d.bbox[3] >= min_side);
+    ranked.sort_by(|a, b| {
+        center_distance(a)
+            .total_cmp(&center_distance(b))
+            .then(b.confidence.total_cmp(&a.confidence))
+    });
+    let selected = if ranked.is_empty() {
+        None
+    } else {
+        Some(ranked[cycle % ranked.len()])
     };
+    BoardCandidates {
+        selected,
+        ranked,
+        rejected,
+    }
+}
 
-    // Find the board bounding box to normalize coordinates
-    let board_box = detections.iter().find(|d| d.class_id == 0);
-    let (bx, by, bw, bh) = if let Some(b) = board_box {
-        (
+/// Piece for a model class id; class 0 is the board itself.
+pub fn piece_for_class(id: usize) -> Option<Piece> {
+    match id {
+        1 => Some(Piece {
+            color: Color::White,
+            role: Role::King,
+        }),
+        2 => Some(Piece {
+            color: Color::White,
+            role: Role::Queen,
+        }),
+        3 => Some(Piece {
+            color: Color::White,
+            role: Role::Rook,
+        }),
+        4 => Some(Piece {
+            color: Color::White,
+            role: Role::Bishop,
+        }),
+        5 => Some(Piece {
+            color: Color::White,
+            role: Role::Knight,
+        }),
+        6 => Some(Piece {
+            color: Color::White,
+            role: Role::Pawn,
+        }),
+        7 => Some(Piece {
+            color: Color::Black,
+            role: Role::King,
+        }),
+        8 => Some(Piece {
+            color: Color::Black,
+            role: Role::Queen,
+        }),
+        9 => Some(Piece {
+            color: Color::Black,
+            role: Role::Rook,
+        }),
+        10 => Some(Piece {
+            color: Color::Black,
+            role: Role::Bishop,
+        }),
+        11 => Some(Piece {
+            color: Color::Black,
+            role: Role::Knight,
+        }),
+        12 => Some(Piece {
+            color: Color::Black,
+            role: Role::Pawn,
+        }),
+        _ => None,
+    }
+}
+
+/// Board area in model input coordinates as (left, top, width, height);
+/// the whole input when no board was detected.
+pub fn board_area(board_box: Option<&Detection>) -> (f32, f32, f32, f32) {
+    match board_box {
+        Some(b) => (
             b.bbox[0] - b.bbox[2] / 2.0,
             b.bbox[1] - b.bbox[3] / 2.0,
             b.bbox[2],
             b.bbox[3],
-        )
-    } else {
-        (0.0, 0.0, 640.0, 640.0)
-    };
+        ),
+        None => (0.0, 0.0, INPUT_SIZE, INPUT_SIZE),
+    }
+}
 
-    let mut white_king_count = 0;
-    let mut black_king_count = 0;
+/// Grid cell (column, row from the top) holding the center of `d`.
+pub fn cell_of(d: &Detection, area: (f32, f32, f32, f32)) -> Option<(u32, u32)> {
+    let (bx, by, bw, bh) = area;
+    let col = ((d.bbox[0] - bx) / bw * 8.0).floor() as i32;
+    let row = ((d.bbox[1] - by) / bh * 8.0).floor() as i32;
+    ((0..8).contains(&col) && (0..8).contains(&row)).then_some((col as u32, row as u32))
+}
 
-    for d in detections {
-        if d.class_id == 0 {
-            continue;
-        }
-        if let Some(piece) = class_to_piece(d.class_id) {
-            if piece.role == Role::King {
-                if piece.color == Color::White {
-                    white_king_count += 1;
-                } else {
-                    black_king_count += 1;
-                }
-            }
+/// Why detections could not be turned into a position for the engine.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BoardError {
+    /// Each side needs exactly one king.
+    KingCount {
+        white: usize,
+        black: usize,
+    },
+    PawnOnBackRank,
+    /// The side that just moved is still in check, so the side to move
+    /// is probably wrong.
+    OppositeCheck,
+    /// Any other reason shakmaty rejected the setup.
+    Illegal(String),
+}
 
-            // Calculate square from bbox
-            let rel_x = (d.bbox[0] - bx) / bw;
-            let rel_y = (d.bbox[1] - by) / bh;
+impl std::fmt::Display for BoardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BoardError::KingCount { white, black } => {
+                write!(
+                    f,
+                    "expected one king each, found {} white and {} black",
+                    white, black
+                )
+            }
+            BoardError::PawnOnBackRank => write!(f, "pawn on the first or last rank"),
+            BoardError::OppositeCheck => {
+                write!(f, "side not to move is in check (wrong side to move?)")
+            }
+            BoardError::Illegal(reason) => write!(f, "illegal position: {}", reason),
+        }
+    }
+}
 
-            let col = (rel_x * 8.0).floor() as i32;
-            let row = (rel_y * 8.0).floor() as i32;
+impl std::error::Error for BoardError {}
 
-            if col >= 0 && col < 8 && row >= 0 && row < 8 {
-                let square = Square::from_coords(
-                    shakmaty::File::new(col as u32),
-                    shakmaty::Rank::new(7 - row as u32),
-                );
-                board.set_piece_at(square, piece);
+impl BoardError {
+    fn from_position(error: PositionError<Chess>, board: &Board) -> Self {
+        let kinds = error.kinds();
+        if kinds.intersects(PositionErrorKinds::MISSING_KING | PositionErrorKinds::TOO_MANY_KINGS) {
+            let kings = |color| {
+                board
+                    .by_piece(Piece {
+                        color,
+                        role: Role::King,
+                    })
+                    .count()
+            };
+            BoardError::KingCount {
+                white: kings(Color::White),
+                black: kings(Color::Black),
             }
+        } else if kinds.contains(PositionErrorKinds::PAWNS_ON_BACKRANK) {
+            BoardError::PawnOnBackRank
+        } else if kinds.contains(PositionErrorKinds::OPPOSITE_CHECK) {
+            BoardError::OppositeCheck
+        } else {
+            BoardError::Illegal(error.to_string())
         }
     }
+}
+
+/// Checks that a FEN is a legal position the engine can search.
+pub fn validate_fen(fen: &str) -> Result<(), BoardError> {
+    let fen = Fen::from_ascii(fen.as_bytes()).map_err(|e| BoardError::Illegal(e.to_string()))?;
+    let board = fen.as_setup().board.clone();
+    fen.into_position::<Chess>(CastlingMode::Standard)
+        .map(|_| ())
+        .map_err(|e| BoardError::from_position(e, &board))
+}
+
+/// Confidence a detection gives up per cell of distance between its center
+/// and the center of its cell, when two compete for the same cell.
+const OFF_CENTER_PENALTY: f32 = 0.2;
+/// How far from its cell's center, in cells, a losing detection must sit
+/// to be moved toward that side.
+const NEIGHBOR_OFFSET: f32 = 0.25;
+
+/// A detected piece in its cell (column, row), with its offset from the
+/// cell's center in cells and its score.
+type Candidate = (Piece, (u32, u32), (f32, f32), f32);
 
-    // BASIC VALIDATION: A chess position MUST have exactly one king of each color
-    // If vision missed a king, don't generate a FEN as it will confuse Stockfish
-    if white_king_count != 1 || black_king_count != 1 {
-        println!(
-            "VALIDATION FAILED: Kings count W:{} B:{}",
-            white_king_count, black_king_count
-        );
-        return None;
+/// Puts each piece detection on its grid cell. When several land on the
+/// same cell the most confident one wins, with detections far off the
+/// cell's center counting as less confident. With `reassign_conflicts`, a
+/// loser leaning toward an adjacent empty cell is placed there instead of
+/// being dropped.
+fn place_pieces(
+    detections: &[Detection],
+    area: (f32, f32, f32, f32),
+    reassign_conflicts: bool,
+) -> Board {
+    let (bx, by, bw, bh) = area;
+    let mut candidates: Vec<Candidate> = detections
+        .iter()
+        .filter_map(|d| {
+            let piece = piece_for_class(d.class_id)?;
+            let (col, row) = cell_of(d, area)?;
+            // Offset from the cell's center, in cells
+            let dx = (d.bbox[0] - bx) / bw * 8.0 - col as f32 - 0.5;
+            let dy = (d.bbox[1] - by) / bh * 8.0 - row as f32 - 0.5;
+            let score = d.confidence - OFF_CENTER_PENALTY * dx.hypot(dy);
+            Some((piece, (col, row), (dx, dy), score))
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.3.total_cmp(&a.3));
+
+    let square = |(col, row): (u32, u32)| {
+        Square::from_coords(shakmaty::File::new(col), shakmaty::Rank::new(7 - row))
+    };
+    let mut board = Board::empty();
+    let mut losers = Vec::new();
+    for (piece, cell, offset, _) in candidates {
+        if board.piece_at(square(cell)).is_some() {
+            losers.push((piece, cell, offset));
+        } else {
+            board.set_piece_at(square(cell), piece);
+        }
     }
+    if !reassign_conflicts {
+        return board;
+    }
+    let step = |offset: f32| {
+        if offset >= NEIGHBOR_OFFSET {
+            1
+        } else if offset <= -NEIGHBOR_OFFSET {
+            -1
+        } else {
+            0
+        }
+    };
+    for (piece, (col, row), (dx, dy)) in losers {
+        let (sx, sy) = (step(dx), step(dy));
+        if (sx, sy) == (0, 0) {
+            continue;
+        }
+        let (col, row) = (col as i32 + sx, row as i32 + sy);
+        if !(0..8).contains(&col) || !(0..8).contains(&row) {
+            continue;
+        }
+        let target = square((col as u32, row as u32));
+        if board.piece_at(target).is_none() {
+            board.set_piece_at(target, piece);
+        }
+    }
+    board
+}
 
-    let turn = if show_white_moves {
+/// Setup seen in `detections` (inside `board_box`, or the whole input)
+/// with `show_white_moves` deciding the side to move, before any legality
+/// check.
+fn detected_setup(
+    detections: &[Detection],
+    board_box: Option<&Detection>,
+    show_white_moves: bool,
+    reassign_conflicts: bool,
+) -> Setup {
+    let mut setup = Setup::empty();
+    setup.board = place_pieces(detections, board_area(board_box), reassign_conflicts);
+    setup.turn = if show_white_moves {
         Color::White
     } else {
         Color::Black
     };
-    let mut setup = Setup::empty();
-    setup.board = board;
-    setup.turn = turn;
-
-    // Check if the position is actually legal (e.g. king isn't being captured)
-    // Although for engine analysis we can be a bit more relaxed,
-    // basic sanity is required.
-    let fen = Fen::from_setup(setup);
-    Some(fen.to_string())
+    setup
+}
+
+/// FEN of whatever was detected, legal or not, for reporting a position
+/// that `detections_to_fen` rejected.
+pub fn attempted_fen(
+    detections: &[Detection],
+    board_box: Option<&Detection>,
+    show_white_moves: bool,
+    reassign_conflicts: bool,
+) -> String {
+    let setup = detected_setup(detections, board_box, show_white_moves, reassign_conflicts);
+    Fen::from_setup(setup).to_string()
+}
+
+/// Builds the position seen in `detections` (inside `board_box`, or the
+/// whole input) with `show_white_moves` deciding the side to move. The
+/// setup goes through shakmaty's legality checks, so only positions the
+/// engine can search come back as a FEN.
+pub fn detections_to_fen(
+    detections: &[Detection],
+    board_box: Option<&Detection>,
+    show_white_moves: bool,
+    reassign_conflicts: bool,
+) -> Result<String, BoardError> {
+    let setup = detected_setup(detections, board_box, show_white_moves, reassign_conflicts);
+    let board = setup.board.clone();
+    let pos = Chess::from_setup(setup, CastlingMode::Standard)
+        .map_err(|e| BoardError::from_position(e, &board))?;
+    Ok(position_fen(&pos))
 }
@@ -0,0 +1,261 @@
+use image::DynamicImage;
+use std::time::Duration;
+
+/// Digits shorter than this share of the tallest glyph are separators.
+const SEPARATOR_HEIGHT: f32 = 0.6;
+/// Holes smaller than this share of the glyph are noise.
+const MIN_HOLE: f32 = 0.02;
+
+/// Foreground mask of a clock crop: the text is whichever of the light and
+/// dark pixels is rarer, so both light-on-dark and dark-on-light clocks
+/// work.
+struct Mask {
+    width: usize,
+    height: usize,
+    fg: Vec<bool>,
+}
+
+impl Mask {
+    fn new(img: &DynamicImage) -> Self {
+        let gray = img.to_luma8();
+        let (width, height) = (gray.width() as usize, gray.height() as usize);
+        let sum = gray.pixels().map(|p| p[0] as u64).sum::<u64>();
+        let mean = sum / (width * height).max(1) as u64;
+        let light: Vec<bool> = gray.pixels().map(|p| p[0] as u64 > mean).collect();
+        let light_count = light.iter().filter(|l| **l).count();
+        let text_is_light = light_count * 2 < light.len();
+        Self {
+            width,
+            height,
+            fg: light.into_iter().map(|l| l == text_is_light).collect(),
+        }
+    }
+
+    fn at(&self, x: usize, y: usize) -> bool {
+        self.fg[y * self.width + x]
+    }
+}
+
+/// One glyph's pixels, cut to its bounding box.
+struct Glyph {
+    width: usize,
+    height: usize,
+    fg: Vec<bool>,
+}
+
+impl Glyph {
+    fn at(&self, x: usize, y: usize) -> bool {
+        self.fg[y * self.width + x]
+    }
+
+    /// Share of foreground in the box given as fractions of the glyph.
+    fn fill(&self, x0: f32, x1: f32, y0: f32, y1: f32) -> f32 {
+        let (w, h) = (self.width as f32, self.height as f32);
+        let xs = (x0 * w) as usize..((x1 * w).ceil() as usize).min(self.width);
+        let ys = (y0 * h) as usize..((y1 * h).ceil() as usize).min(self.height);
+        let total = xs.len() * ys.len();
+        if total == 0 {
+            return 0.0;
+        }
+        let set = ys
+            .flat_map(|y| xs.clone().map(move |x| (x, y)))
+            .filter(|&(x, y)| self.at(x, y))
+            .count();
+        set as f32 / total as f32
+    }
+
+    /// Enclosed background areas as (vertical center, height), both as
+    /// fractions of the glyph height.
+    fn holes(&self) -> Vec<(f32, f32)> {
+        // Padded by one pixel so the outside is one connected area
+        let (w, h) = (self.width + 2, self.height + 2);
+        let mut seen: Vec<bool> = (0..w * h)
+            .map(|i| {
+                let (x, y) = (i % w, i / w);
+                x > 0 && y > 0 && x <= self.width && y <= self.height && self.at(x - 1, y - 1)
+            })
+            .collect();
+        let flood = |start: usize, seen: &mut [bool]| {
+            let mut stack = vec![start];
+            let mut rows = Vec::new();
+            seen[start] = true;
+            while let Some(i) = stack.pop() {
+                rows.push(i / w);
+                let (x, y) = (i % w, i / w);
+                let neighbors = [
+                    (x > 0).then(|| i - 1),
+                    (x + 1 < w).then(|| i + 1),
+                    (y > 0).then(|| i - w),
+                    (y + 1 < h).then(|| i + w),
+                ];
+                for n in neighbors.into_iter().flatten() {
+                    if !seen[n] {
+                        seen[n] = true;
+                        stack.push(n);
+                    }
+                }
+            }
+            rows
+        };
+        flood(0, &mut seen);
+        let mut holes = Vec::new();
+        for i in 0..w * h {
+            if seen[i] {
+                continue;
+            }
+            let rows = flood(i, &mut seen);
+            if (rows.len() as f32) < MIN_HOLE * (self.width * self.height) as f32 {
+                continue;
+            }
+            let top = *rows.iter().min().unwrap() as f32 - 1.0;
+            let bottom = *rows.iter().max().unwrap() as f32;
+            let center = rows.iter().sum::<usize>() as f32 / rows.len() as f32 - 1.0;
+            let height = self.height as f32;
+            holes.push((center / height, (bottom - top) / height));
+        }
+        holes
+    }
+
+    /// The digit, from its holes and where its strokes are. Tuned for the
+    /// plain sans-serif digits chess sites use for clocks.
+    fn digit(&self) -> Option<char> {
+        if (self.width as f32) < 0.4 * self.height as f32 {
+            return Some('1');
+        }
+        let holes = self.holes();
+        match holes.as_slice() {
+            [_, _] => Some('8'),
+            [(_, size)] if *size > 0.45 => Some('0'),
+            // 4's top is a narrow point, 9's a full arc
+            [(center, _)] if *center < 0.5 => Some(if self.fill(0.0, 1.0, 0.0, 0.15) < 0.35 {
+                '4'
+            } else {
+                '9'
+            }),
+            [_] => Some('6'),
+            [] => {
+                let top = self.fill(0.0, 1.0, 0.0, 0.15);
+                let bottom = self.fill(0.0, 1.0, 0.85, 1.0);
+                let upper_left = self.fill(0.0, 0.3, 0.2, 0.45);
+                let upper_right = self.fill(0.7, 1.0, 0.2, 0.45);
+                if bottom < 0.3 && top > 0.5 {
+                    Some('7')
+                } else if bottom > 0.5 {
+                    Some('2')
+                } else if upper_left > upper_right {
+                    Some('5')
+                } else {
+                    Some('3')
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Text of a clock crop, digits plus ':' and '.' separators, read glyph by
+/// glyph from gaps between columns.
+fn read_text(img: &DynamicImage) -> Option<String> {
+    let mask = Mask::new(img);
+    let rows: Vec<usize> = (0..mask.height)
+        .filter(|&y| (0..mask.width).any(|x| mask.at(x, y)))
+        .collect();
+    let (&top, &bottom) = (rows.first()?, rows.last()?);
+    let text_height = (bottom - top + 1) as f32;
+
+    let filled = |x: usize| (top..=bottom).any(|y| mask.at(x, y));
+    let mut spans = Vec::new();
+    let mut start = None;
+    for x in 0..=mask.width {
+        match (x < mask.width && filled(x), start) {
+            (true, None) => start = Some(x),
+            (false, Some(s)) => {
+                spans.push((s, x));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    let mut text = String::new();
+    for (x0, x1) in spans {
+        let ys: Vec<usize> = (top..=bottom)
+            .filter(|&y| (x0..x1).any(|x| mask.at(x, y)))
+            .collect();
+        let (y0, y1) = (*ys.first()?, *ys.last()? + 1);
+        // Digits are one stroke top to bottom; a colon's dots leave a gap
+        let gap = ys.windows(2).any(|p| p[1] > p[0] + 1);
+        if gap || ((y1 - y0) as f32) < SEPARATOR_HEIGHT * text_height {
+            // A colon has a dot in each half; a decimal point sits low
+            let mid = top as f32 + text_height / 2.0;
+            let upper = ys.iter().any(|&y| (y as f32) < mid);
+            let lower = ys.iter().any(|&y| (y as f32) >= mid);
+            text.push(if upper && lower { ':' } else { '.' });
+            continue;
+        }
+        let glyph = Glyph {
+            width: x1 - x0,
+            height: y1 - y0,
+            fg: (y0..y1)
+                .flat_map(|y| (x0..x1).map(move |x| (x, y)))
+                .map(|(x, y)| mask.at(x, y))
+                .collect(),
+        };
+        text.push(glyph.digit()?);
+    }
+    Some(text)
+}
+
+/// Remaining time in "1:05:30", "3:25", "0:09.5" or "9.5" form; tenths
+/// are dropped.
+fn parse_clock(text: &str) -> Option<Duration> {
+    let whole = text.split('.').next()?;
+    let mut seconds = 0u64;
+    for (i, part) in whole.split(':').enumerate() {
+        let value: u64 = part.parse().ok()?;
+        if i > 0 && (value >= 60 || part.len() != 2) {
+            return None;
+        }
+        seconds = seconds * 60 + value;
+    }
+    Some(Duration::from_secs(seconds))
+}
+
+/// Reads the remaining time from a crop of a chess site's clock, or `None`
+/// when the crop doesn't look like a clock.
+pub fn read_clock(img: &DynamicImage) -> Option<Duration> {
+    let text = read_text(img)?;
+    if !text.contains([':', '.']) {
+        return None;
+    }
+    parse_clock(&text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minutes_and_seconds() {
+        assert_eq!(parse_clock("3:25"), Some(Duration::from_secs(205)));
+        assert_eq!(parse_clock("0:07"), Some(Duration::from_secs(7)));
+    }
+
+    #[test]
+    fn hours_minutes_and_seconds() {
+        assert_eq!(parse_clock("1:05:30"), Some(Duration::from_secs(3930)));
+    }
+
+    #[test]
+    fn tenths_are_dropped() {
+        assert_eq!(parse_clock("0:09.5"), Some(Duration::from_secs(9)));
+        assert_eq!(parse_clock("9.5"), Some(Duration::from_secs(9)));
+    }
+
+    #[test]
+    fn misread_fields_are_rejected() {
+        assert_eq!(parse_clock("3:5"), None);
+        assert_eq!(parse_clock("3:75"), None);
+        assert_eq!(parse_clock("3::25"), None);
+    }
+}
@@ -0,0 +1,104 @@
+use crate::vision::board::{piece_for_class, INPUT_SIZE};
+use crate::vision::inference::Detection;
+use image::{DynamicImage, GrayImage};
+use shakmaty::Color;
+
+/// Luma difference from the square's background for a pixel to count as
+/// part of the piece.
+const PIECE_CONTRAST: u8 = 30;
+/// Class ids of a piece's two colors are this far apart.
+const COLOR_OFFSET: usize = 6;
+/// Without confident pieces of both colors to compare with, pieces lighter
+/// than this are taken as white.
+const DEFAULT_SPLIT: f32 = 128.0;
+
+/// Median luma of the piece inside detection `d`: the pixels of the
+/// box's middle that stand out from the square's background, which is
+/// taken from the box's outer ring.
+fn piece_luma(gray: &GrayImage, d: &Detection) -> Option<f32> {
+    let sx = gray.width() as f32 / INPUT_SIZE;
+    let sy = gray.height() as f32 / INPUT_SIZE;
+    let [cx, cy, w, h] = d.bbox;
+    let x0 = ((cx - w / 2.0) * sx).max(0.0) as u32;
+    let y0 = ((cy - h / 2.0) * sy).max(0.0) as u32;
+    let x1 = (((cx + w / 2.0) * sx) as u32).min(gray.width());
+    let y1 = (((cy + h / 2.0) * sy) as u32).min(gray.height());
+    if x1 < x0 + 8 || y1 < y0 + 8 {
+        return None;
+    }
+    let (bw, bh) = (x1 - x0, y1 - y0);
+
+    let mut ring: Vec<u8> = Vec::new();
+    let mut inner: Vec<u8> = Vec::new();
+    for y in y0..y1 {
+        for x in x0..x1 {
+            let (fx, fy) = ((x - x0) as f32 / bw as f32, (y - y0) as f32 / bh as f32);
+            let luma = gray.get_pixel(x, y)[0];
+            if !(0.1..0.9).contains(&fx) || !(0.1..0.9).contains(&fy) {
+                ring.push(luma);
+            } else if (0.25..0.75).contains(&fx) && (0.2..0.8).contains(&fy) {
+                inner.push(luma);
+            }
+        }
+    }
+    ring.sort_unstable();
+    let background = *ring.get(ring.len() / 2)?;
+    let mut piece: Vec<u8> = inner
+        .into_iter()
+        .filter(|l| l.abs_diff(background) >= PIECE_CONTRAST)
+        .collect();
+    piece.sort_unstable();
+    piece.get(piece.len() / 2).map(|l| *l as f32)
+}
+
+/// Second opinion on the color of low-confidence pieces, for board and
+/// piece themes the model was not trained on: pieces scoring below
+/// `below` get the color their brightness is closest to, measured against
+/// the confidently detected pieces of the same frame. Returns how many
+/// were changed.
+pub fn correct_colors(img: &DynamicImage, detections: &mut [Detection], below: f32) -> usize {
+    if detections
+        .iter()
+        .all(|d| d.class_id == 0 || d.confidence >= below)
+    {
+        return 0;
+    }
+    let gray = img.to_luma8();
+
+    // Reference brightness per color from the pieces the model is sure of
+    let mut sums = [(0.0, 0); 2];
+    for d in detections.iter().filter(|d| d.confidence >= below) {
+        let (Some(piece), Some(luma)) = (piece_for_class(d.class_id), piece_luma(&gray, d)) else {
+            continue;
+        };
+        let side = &mut sums[(piece.color == Color::Black) as usize];
+        side.0 += luma;
+        side.1 += 1;
+    }
+    let split = match sums {
+        [(white, nw), (black, nb)] if nw > 0 && nb > 0 => {
+            (white / nw as f32 + black / nb as f32) / 2.0
+        }
+        _ => DEFAULT_SPLIT,
+    };
+
+    let mut changed = 0;
+    for d in detections.iter_mut().filter(|d| d.confidence < below) {
+        let (Some(piece), Some(luma)) = (piece_for_class(d.class_id), piece_luma(&gray, d)) else {
+            continue;
+        };
+        let seen = if luma >= split {
+            Color::White
+        } else {
+            Color::Black
+        };
+        if seen != piece.color {
+            d.class_id = match seen {
+                Color::White => d.class_id - COLOR_OFFSET,
+                Color::Black => d.class_id + COLOR_OFFSET,
+            };
+            changed += 1;
+        }
+    }
+    changed
+}
@@ -0,0 +1,141 @@
+use crate::vision::board::INPUT_SIZE;
+use crate::vision::inference::Detection;
+use image::{DynamicImage, GrayImage};
+
+/// How much stronger than average the grid lines must be to accept a grid.
+const MIN_STRENGTH: f32 = 2.5;
+
+/// An 8x8 grid found in an image, in image pixels.
+#[derive(Debug, Clone, Copy)]
+pub struct GridEstimate {
+    pub x: f32,
+    pub y: f32,
+    pub cell_w: f32,
+    pub cell_h: f32,
+}
+
+/// Mean absolute luma step between neighboring columns (or rows when
+/// `vertical`), which peaks on the borders between squares.
+fn edge_profile(gray: &GrayImage, vertical: bool) -> Vec<f32> {
+    let (w, h) = gray.dimensions();
+    let (len, across) = if vertical { (h, w) } else { (w, h) };
+    let mut profile = vec![0.0; len as usize];
+    for i in 1..len {
+        let mut sum = 0u32;
+        for j in 0..across {
+            let (a, b) = if vertical {
+                (gray.get_pixel(j, i)[0], gray.get_pixel(j, i - 1)[0])
+            } else {
+                (gray.get_pixel(i, j)[0], gray.get_pixel(i - 1, j)[0])
+            };
+            sum += a.abs_diff(b) as u32;
+        }
+        profile[i as usize] = sum as f32 / across.max(1) as f32;
+    }
+    profile
+}
+
+/// Finds 9 evenly spaced peaks (the 8 squares' borders) in an edge profile,
+/// with a period within `periods`, returning (start, period) of the
+/// strongest such comb.
+fn find_comb(profile: &[f32], periods: std::ops::RangeInclusive<usize>) -> Option<(usize, usize)> {
+    let len = profile.len();
+    let mean = profile.iter().sum::<f32>() / len.max(1) as f32;
+    if mean <= 0.0 {
+        return None;
+    }
+    // Allow a pixel of slack for boards whose size isn't a multiple of 8
+    let peak = |i: usize| {
+        let lo = i.saturating_sub(1);
+        let hi = (i + 1).min(len - 1);
+        profile[lo..=hi].iter().cloned().fold(0.0, f32::max)
+    };
+
+    let mut best: Option<(usize, usize, f32)> = None;
+    for period in periods {
+        for start in 0..len.saturating_sub(8 * period) {
+            let score = (0..=8).map(|k| peak(start + k * period)).sum::<f32>() / 9.0;
+            if best.is_none_or(|(_, _, s)| score > s) {
+                best = Some((start, period, score));
+            }
+        }
+    }
+    let (start, period, score) = best?;
+    (score / mean >= MIN_STRENGTH).then_some((start, period))
+}
+
+/// Periods of an 8x8 grid covering a third to all of `len` pixels.
+fn any_period(len: usize) -> std::ops::RangeInclusive<usize> {
+    (len / 24).max(4)..=len / 8
+}
+
+/// Classical fallback for screens without pieces (editor/setup boards):
+/// finds the 8x8 square pattern from the periodicity of luma edges, so the
+/// region/grid calibration can still be verified.
+pub fn detect_empty_board(img: &DynamicImage) -> Option<GridEstimate> {
+    let gray = img.to_luma8();
+    let (w, h) = gray.dimensions();
+    let (x, cell_w) = find_comb(&edge_profile(&gray, false), any_period(w as usize))?;
+    let (y, cell_h) = find_comb(&edge_profile(&gray, true), any_period(h as usize))?;
+    // Squares should be roughly square
+    let ratio = cell_w as f32 / cell_h as f32;
+    if !(0.8..=1.25).contains(&ratio) {
+        return None;
+    }
+    Some(GridEstimate {
+        x: x as f32,
+        y: y as f32,
+        cell_w: cell_w as f32,
+        cell_h: cell_h as f32,
+    })
+}
+
+/// Share of the detected board box searched around it, per side, so an
+/// inner grid cut off by a tight box is still found.
+const REFINE_MARGIN: f32 = 0.06;
+/// Inner grid size accepted, relative to the detected board box.
+const REFINE_SIZE: std::ops::RangeInclusive<f32> = 0.75..=1.08;
+
+/// Finds the squares' grid inside a detected board box from the luma edges
+/// of the crop `img`, for sites that draw coordinates or a frame around
+/// the squares, or boxes the model cut slightly short. Returns the grid as
+/// a board detection in model input coordinates, or `None` when no clear
+/// grid close to the box's size is found.
+pub fn refine_board(img: &DynamicImage, board: &Detection) -> Option<Detection> {
+    let sx = img.width() as f32 / INPUT_SIZE;
+    let sy = img.height() as f32 / INPUT_SIZE;
+    let [cx, cy, bw, bh] = board.bbox;
+    let (bw_px, bh_px) = (bw * sx, bh * sy);
+    let x0 = ((cx - bw / 2.0) * sx - bw_px * REFINE_MARGIN).max(0.0) as u32;
+    let y0 = ((cy - bh / 2.0) * sy - bh_px * REFINE_MARGIN).max(0.0) as u32;
+    let x1 = (((cx + bw / 2.0) * sx + bw_px * REFINE_MARGIN) as u32).min(img.width());
+    let y1 = (((cy + bh / 2.0) * sy + bh_px * REFINE_MARGIN) as u32).min(img.height());
+    if x1 <= x0 + 16 || y1 <= y0 + 16 {
+        return None;
+    }
+    let gray = img.crop_imm(x0, y0, x1 - x0, y1 - y0).to_luma8();
+
+    // Only grids about the size of the box, not a 4x4 block of squares
+    let periods = |size: f32| {
+        let lo = (size * REFINE_SIZE.start() / 8.0).floor() as usize;
+        let hi = (size * REFINE_SIZE.end() / 8.0).ceil() as usize;
+        lo.max(4)..=hi
+    };
+    let (gx, cell_w) = find_comb(&edge_profile(&gray, false), periods(bw_px))?;
+    let (gy, cell_h) = find_comb(&edge_profile(&gray, true), periods(bh_px))?;
+    let ratio = cell_w as f32 / cell_h as f32;
+    if !(0.8..=1.25).contains(&ratio) {
+        return None;
+    }
+    let (w, h) = (cell_w as f32 * 8.0, cell_h as f32 * 8.0);
+    Some(Detection {
+        class_id: board.class_id,
+        confidence: board.confidence,
+        bbox: [
+            (x0 as f32 + gx as f32 + w / 2.0) / sx,
+            (y0 as f32 + gy as f32 + h / 2.0) / sy,
+            w / sx,
+            h / sy,
+        ],
+    })
+}
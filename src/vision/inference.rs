@@ -1,65 +1,219 @@
-use anyhow::Result;
-use image::{imageops::FilterType, DynamicImage};
-use ndarray::Array4;
+use crate::vision::preprocess::letterbox;
+use anyhow::{bail, Result};
+use image::DynamicImage;
+use ort::execution_providers::{
+    CUDAExecutionProvider, CoreMLExecutionProvider, DirectMLExecutionProvider,
+    ExecutionProviderDispatch, TensorRTExecutionProvider,
+};
 use ort::session::Session;
+use serde::{Deserialize, Serialize};
+
+/// Hardware the ONNX session runs the model on.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum InferenceBackend {
+    /// NVIDIA GPUs.
+    Cuda,
+    /// Any DirectX 12 GPU on Windows.
+    DirectMl,
+    /// Apple GPUs and Neural Engine.
+    CoreMl,
+    /// NVIDIA GPUs through TensorRT; slow first start while it builds engines.
+    TensorRt,
+    Cpu,
+}
+
+/// CoreML on macOS, where there is no CUDA; CUDA elsewhere.
+impl Default for InferenceBackend {
+    fn default() -> Self {
+        if cfg!(target_os = "macos") {
+            InferenceBackend::CoreMl
+        } else {
+            InferenceBackend::Cuda
+        }
+    }
+}
+
+impl InferenceBackend {
+    pub const ALL: [InferenceBackend; 5] = [
+        InferenceBackend::Cuda,
+        InferenceBackend::DirectMl,
+        InferenceBackend::CoreMl,
+        InferenceBackend::TensorRt,
+        InferenceBackend::Cpu,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            InferenceBackend::Cuda => "CUDA",
+            InferenceBackend::DirectMl => "DirectML",
+            InferenceBackend::CoreMl => "CoreML",
+            InferenceBackend::TensorRt => "TensorRT",
+            InferenceBackend::Cpu => "CPU",
+        }
+    }
+
+    /// Whether the backend can run on this OS at all.
+    pub fn is_available(self) -> bool {
+        match self {
+            InferenceBackend::Cuda | InferenceBackend::TensorRt => !cfg!(target_os = "macos"),
+            InferenceBackend::DirectMl => cfg!(windows),
+            InferenceBackend::CoreMl => cfg!(target_os = "macos"),
+            InferenceBackend::Cpu => true,
+        }
+    }
+
+    fn provider(self) -> Option<ExecutionProviderDispatch> {
+        let provider = match self {
+            InferenceBackend::Cuda => CUDAExecutionProvider::default().build(),
+            InferenceBackend::DirectMl => DirectMLExecutionProvider::default().build(),
+            InferenceBackend::CoreMl => CoreMLExecutionProvider::default().build(),
+            InferenceBackend::TensorRt => TensorRTExecutionProvider::default().build(),
+            InferenceBackend::Cpu => return None,
+        };
+        // Without this ort quietly runs on the CPU and we'd report the wrong backend
+        Some(provider.error_on_failure())
+    }
+}
+
+/// Input name and shape the detector feeds: one 640x640 RGB image.
+const INPUT_NAME: &str = "images";
+const INPUT_SHAPE: [i64; 4] = [1, 3, 640, 640];
+/// Output name and shape it reads: box plus one score per class, for each
+/// of the 8400 YOLOv8 anchors.
+const OUTPUT_NAME: &str = "output0";
+const NUM_CLASSES: usize = 13;
+const NUM_BOXES: usize = 8400;
+
+/// Overlap above which the weaker of two boxes is dropped, unless
+/// configured otherwise.
+pub const DEFAULT_IOU_THRESHOLD: f32 = 0.45;
+
+/// Checks that `session` takes and produces what `detect` expects, so a
+/// wrong model fails when loaded rather than on every frame. Dynamic
+/// dimensions (-1) are accepted.
+fn validate_model(session: &Session) -> Result<()> {
+    let matches = |shape: Option<&[i64]>, expected: &[i64]| {
+        shape.is_some_and(|s| {
+            s.len() == expected.len() && s.iter().zip(expected).all(|(d, e)| *d == -1 || d == e)
+        })
+    };
+    let Some(input) = session.inputs().iter().find(|i| i.name() == INPUT_NAME) else {
+        bail!("Model has no \"{}\" input", INPUT_NAME);
+    };
+    let Some(output) = session.outputs().iter().find(|o| o.name() == OUTPUT_NAME) else {
+        bail!("Model has no \"{}\" output", OUTPUT_NAME);
+    };
+    let input_shape = input.dtype().tensor_shape().map(|s| s.to_vec());
+    if !matches(input_shape.as_deref(), &INPUT_SHAPE) {
+        bail!(
+            "Model input is {:?}, expected {:?}",
+            input_shape.unwrap_or_default(),
+            INPUT_SHAPE
+        );
+    }
+    let expected_output = [1, 4 + NUM_CLASSES as i64, NUM_BOXES as i64];
+    let output_shape = output.dtype().tensor_shape().map(|s| s.to_vec());
+    if !matches(output_shape.as_deref(), &expected_output) {
+        bail!(
+            "Model output is {:?}, expected {:?} ({} piece classes)",
+            output_shape.unwrap_or_default(),
+            expected_output,
+            NUM_CLASSES
+        );
+    }
+    Ok(())
+}
 
 pub struct Detector {
     session: Session,
+    backend: InferenceBackend,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Serialize, Debug, Clone)]
 pub struct Detection {
     pub class_id: usize,
     pub confidence: f32,
-    pub bbox: [f32; 4], // x, y, w, h (normalized)
+    pub bbox: [f32; 4], // x, y, w, h (640x640 input, stretched over the image)
 }
 
 impl Detector {
-    pub fn new(model_path: &str) -> Result<Self> {
-        println!("Attempting to create ONNX session with CUDA (NVIDIA)...");
-
-        // Try CUDA (maximum speed for NVIDIA GPUs), fall back to CPU if it fails
-        let session = match Session::builder()?
-            .with_execution_providers([
-                ort::execution_providers::CUDAExecutionProvider::default().build()
-            ])?
-            .commit_from_file(model_path)
-        {
-            Ok(s) => {
-                println!("CUDA execution provider loaded successfully!");
-                s
-            }
-            Err(e) => {
-                println!("CUDA failed: {:?}, falling back to CPU...", e);
-                Session::builder()?.commit_from_file(model_path)?
-            }
+    /// Builds the session on `backend`, falling back to the CPU when that
+    /// provider isn't available on this machine or build. A backend for
+    /// another OS (CUDA on macOS, say) is swapped for this OS's default
+    /// first. Fails if the model's input or output shape doesn't match the
+    /// detector.
+    pub fn new(model_path: &str, backend: InferenceBackend) -> Result<Self> {
+        let backend = if backend.is_available() {
+            backend
+        } else {
+            let native = InferenceBackend::default();
+            println!(
+                "{} is not available on this OS, using {}",
+                backend.label(),
+                native.label()
+            );
+            native
         };
+        if let Some(provider) = backend.provider() {
+            println!(
+                "Attempting to create ONNX session with {}...",
+                backend.label()
+            );
+            let session = Session::builder()
+                .and_then(|b| b.with_execution_providers([provider]))
+                .and_then(|b| b.commit_from_file(model_path));
+            match session {
+                Ok(session) => {
+                    validate_model(&session)?;
+                    println!(
+                        "{} execution provider loaded successfully!",
+                        backend.label()
+                    );
+                    return Ok(Self { session, backend });
+                }
+                Err(e) => println!(
+                    "{} failed: {:?}, falling back to CPU...",
+                    backend.label(),
+                    e
+                ),
+            }
+        }
 
+        let session = Session::builder()?.commit_from_file(model_path)?;
+        validate_model(&session)?;
         println!("ONNX Session created successfully");
-        Ok(Self { session })
+        Ok(Self {
+            session,
+            backend: InferenceBackend::Cpu,
+        })
     }
 
-    pub fn detect(&mut self, img: &DynamicImage, conf_threshold: f32) -> Result<Vec<Detection>> {
-        let resized = img.resize_exact(640, 640, FilterType::Triangle);
-        let rgb = resized.to_rgb8();
-
-        let mut input = Array4::<f32>::zeros((1, 3, 640, 640));
-        for (x, y, pixel) in rgb.enumerate_pixels() {
-            input[[0, 0, y as usize, x as usize]] = pixel[0] as f32 / 255.0;
-            input[[0, 1, y as usize, x as usize]] = pixel[1] as f32 / 255.0;
-            input[[0, 2, y as usize, x as usize]] = pixel[2] as f32 / 255.0;
-        }
+    /// The backend actually running the model, after any fallback.
+    pub fn backend(&self) -> InferenceBackend {
+        self.backend
+    }
 
+    /// Runs the model on `img`, keeping boxes scoring above
+    /// `conf_threshold` and dropping any that overlap a better one by at
+    /// least `iou_threshold`.
+    pub fn detect(
+        &mut self,
+        img: &DynamicImage,
+        conf_threshold: f32,
+        iou_threshold: f32,
+    ) -> Result<Vec<Detection>> {
+        let (input, fit) = letterbox(img);
         let input_tensor = ort::value::Tensor::from_array(input)?;
         let mut detections = Vec::new();
 
         {
-            let outputs = self.session.run(ort::inputs!["images" => input_tensor])?;
-            let output_tensor = outputs["output0"].try_extract_tensor::<f32>()?;
+            let outputs = self.session.run(ort::inputs![INPUT_NAME => input_tensor])?;
+            let output_tensor = outputs[OUTPUT_NAME].try_extract_tensor::<f32>()?;
             let (_shape, data) = output_tensor;
 
-            let num_classes = 13;
-            let num_boxes = 8400;
+            let num_classes = NUM_CLASSES;
+            let num_boxes = NUM_BOXES;
 
             for i in 0..num_boxes {
                 let mut max_conf = 0.0;
@@ -75,8 +229,8 @@ impl Detector {
                 }
 
                 if max_conf > conf_threshold {
-                    let x = data[0 * num_boxes + i];
-                    let y = data[1 * num_boxes + i];
+                    let x = data[i];
+                    let y = data[num_boxes + i];
                     let w = data[2 * num_boxes + i];
                     let h = data[3 * num_boxes + i];
 
@@ -89,15 +243,20 @@ impl Detector {
             }
         }
 
-        Ok(self.nms(detections))
+        // Suppress overlaps on the undistorted boxes, then map them back
+        let mut detections = self.nms(detections, iou_threshold);
+        for d in &mut detections {
+            d.bbox = fit.unmap(d.bbox);
+        }
+        Ok(detections)
     }
 
-    fn nms(&self, mut detections: Vec<Detection>) -> Vec<Detection> {
+    fn nms(&self, mut detections: Vec<Detection>, iou_threshold: f32) -> Vec<Detection> {
         detections.sort_by(|a, b| b.confidence.partial_cmp(&a.confidence).unwrap());
         let mut result = Vec::new();
         while !detections.is_empty() {
             let best = detections.remove(0);
-            detections.retain(|d| self.iou(&best.bbox, &d.bbox) < 0.45);
+            detections.retain(|d| self.iou(&best.bbox, &d.bbox) < iou_threshold);
             result.push(best);
         }
         result
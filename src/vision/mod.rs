@@ -1,2 +1,9 @@
+pub mod auto_region;
 pub mod board;
+pub mod clock;
+pub mod color;
+pub mod grid;
 pub mod inference;
+pub mod preprocess;
+pub mod samples;
+pub mod site;
@@ -0,0 +1,72 @@
+use image::{imageops::FilterType, DynamicImage};
+use ndarray::Array4;
+
+/// Side length of the square model input.
+pub const INPUT_SIZE: u32 = 640;
+/// Gray the letterbox borders are filled with, as in YOLO training.
+const PAD_VALUE: f32 = 114.0 / 255.0;
+
+/// How an image was fitted into the model input: scaled to keep its aspect
+/// ratio, then centered with gray borders.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Letterbox {
+    scale: f32,
+    pad_x: f32,
+    pad_y: f32,
+    width: f32,
+    height: f32,
+}
+
+impl Letterbox {
+    pub fn new(width: u32, height: u32) -> Self {
+        let (width, height) = (width.max(1) as f32, height.max(1) as f32);
+        let size = INPUT_SIZE as f32;
+        let scale = (size / width).min(size / height);
+        Self {
+            scale,
+            pad_x: (size - width * scale) / 2.0,
+            pad_y: (size - height * scale) / 2.0,
+            width,
+            height,
+        }
+    }
+
+    /// Size of the scaled image inside the padding.
+    fn scaled_size(&self) -> (u32, u32) {
+        (
+            ((self.width * self.scale).round() as u32).clamp(1, INPUT_SIZE),
+            ((self.height * self.scale).round() as u32).clamp(1, INPUT_SIZE),
+        )
+    }
+
+    /// Maps a center/size box from letterboxed input coordinates to input
+    /// coordinates of the whole image stretched to the input size, which is
+    /// what every consumer of `Detection::bbox` expects.
+    pub fn unmap(&self, [x, y, w, h]: [f32; 4]) -> [f32; 4] {
+        let size = INPUT_SIZE as f32;
+        let sx = size / (self.width * self.scale);
+        let sy = size / (self.height * self.scale);
+        [(x - self.pad_x) * sx, (y - self.pad_y) * sy, w * sx, h * sy]
+    }
+}
+
+/// Letterboxes `img` into a normalized NCHW tensor for the model.
+pub fn letterbox(img: &DynamicImage) -> (Array4<f32>, Letterbox) {
+    let fit = Letterbox::new(img.width(), img.height());
+    let (w, h) = fit.scaled_size();
+    let rgb = img.resize_exact(w, h, FilterType::Triangle).to_rgb8();
+    let (ox, oy) = (fit.pad_x.floor() as usize, fit.pad_y.floor() as usize);
+
+    let size = INPUT_SIZE as usize;
+    let mut input = Array4::<f32>::from_elem((1, 3, size, size), PAD_VALUE);
+    for (x, y, pixel) in rgb.enumerate_pixels() {
+        let (x, y) = (x as usize + ox, y as usize + oy);
+        if x >= size || y >= size {
+            continue;
+        }
+        input[[0, 0, y, x]] = pixel[0] as f32 / 255.0;
+        input[[0, 1, y, x]] = pixel[1] as f32 / 255.0;
+        input[[0, 2, y, x]] = pixel[2] as f32 / 255.0;
+    }
+    (input, fit)
+}
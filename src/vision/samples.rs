@@ -0,0 +1,141 @@
+use crate::vision::inference::Detection;
+use anyhow::Result;
+use image::DynamicImage;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Average piece confidence below which a frame counts as poorly recognized.
+const LOW_CONFIDENCE: f32 = 0.6;
+/// How long confidence must stay low before offering sample collection.
+const LOW_FOR: Duration = Duration::from_secs(180);
+/// Minimum time between two saved samples.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(10);
+/// Samples saved per collection run.
+const MAX_SAMPLES: usize = 50;
+/// Side length of the model input the detection boxes are expressed in.
+const INPUT_SIZE: f32 = 640.0;
+
+/// Watches recognition confidence and reports when it has stayed low long
+/// enough to suggest the board/piece theme is not covered by the model.
+#[derive(Default)]
+pub struct LowConfidenceMonitor {
+    low_since: Option<Instant>,
+}
+
+impl LowConfidenceMonitor {
+    /// Feeds one frame's detections; returns true once confidence has been
+    /// low for `LOW_FOR`.
+    pub fn observe(&mut self, detections: &[Detection]) -> bool {
+        let pieces: Vec<f32> = detections
+            .iter()
+            .filter(|d| d.class_id != 0)
+            .map(|d| d.confidence)
+            .collect();
+        let average = if pieces.is_empty() {
+            0.0
+        } else {
+            pieces.iter().sum::<f32>() / pieces.len() as f32
+        };
+        if average >= LOW_CONFIDENCE {
+            self.low_since = None;
+            return false;
+        }
+        self.low_since.get_or_insert_with(Instant::now).elapsed() >= LOW_FOR
+    }
+}
+
+/// Saves board crops with YOLO-format labels from the current detections
+/// into `samples/<run>/{images,labels}/`, ready for model retraining. Only
+/// the board itself is kept, so usernames or chat around it never end up
+/// in a sample.
+pub struct SampleCollector {
+    dir: PathBuf,
+    saved: usize,
+    last_saved: Option<Instant>,
+}
+
+impl SampleCollector {
+    pub fn new() -> Result<Self> {
+        let stamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let dir = crate::paths::app_dir()
+            .join("samples")
+            .join(format!("run-{}", stamp));
+        fs::create_dir_all(dir.join("images"))?;
+        fs::create_dir_all(dir.join("labels"))?;
+        Ok(Self {
+            dir,
+            saved: 0,
+            last_saved: None,
+        })
+    }
+
+    pub fn saved(&self) -> usize {
+        self.saved
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.saved >= MAX_SAMPLES
+    }
+
+    /// Saves a sample if the interval has passed and a board was detected.
+    pub fn offer(
+        &mut self,
+        img: &DynamicImage,
+        detections: &[Detection],
+        board: Option<&Detection>,
+    ) -> Result<()> {
+        if self.is_done()
+            || self
+                .last_saved
+                .is_some_and(|t| t.elapsed() < SAMPLE_INTERVAL)
+        {
+            return Ok(());
+        }
+        let Some(board) = board else {
+            return Ok(());
+        };
+
+        // Board box in model input coordinates -> image pixels
+        let sx = img.width() as f32 / INPUT_SIZE;
+        let sy = img.height() as f32 / INPUT_SIZE;
+        let bx = (board.bbox[0] - board.bbox[2] / 2.0).max(0.0);
+        let by = (board.bbox[1] - board.bbox[3] / 2.0).max(0.0);
+        let (bw, bh) = (board.bbox[2], board.bbox[3]);
+        let crop = img.crop_imm(
+            (bx * sx) as u32,
+            (by * sy) as u32,
+            (bw * sx).max(1.0) as u32,
+            (bh * sy).max(1.0) as u32,
+        );
+
+        let mut labels = String::new();
+        for d in detections.iter().filter(|d| d.class_id != 0) {
+            let cx = (d.bbox[0] - bx) / bw;
+            let cy = (d.bbox[1] - by) / bh;
+            if !(0.0..=1.0).contains(&cx) || !(0.0..=1.0).contains(&cy) {
+                continue;
+            }
+            let _ = writeln!(
+                labels,
+                "{} {:.6} {:.6} {:.6} {:.6}",
+                d.class_id,
+                cx,
+                cy,
+                d.bbox[2] / bw,
+                d.bbox[3] / bh
+            );
+        }
+
+        let name = format!("{:04}", self.saved);
+        crop.save(self.dir.join("images").join(format!("{}.png", name)))?;
+        fs::write(
+            self.dir.join("labels").join(format!("{}.txt", name)),
+            labels,
+        )?;
+        self.saved += 1;
+        self.last_saved = Some(Instant::now());
+        Ok(())
+    }
+}
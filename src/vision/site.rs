@@ -0,0 +1,125 @@
+use crate::config::AppConfig;
+use crate::vision::grid::refine_board;
+use crate::vision::inference::Detection;
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+
+/// Chess site the board is on, picking detection settings tuned for its
+/// default board and piece themes.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Site {
+    /// Settings chosen by hand.
+    #[default]
+    Custom,
+    Lichess,
+    ChessCom,
+    /// Chess.com with coordinates set to "Outside".
+    ChessComOutside,
+}
+
+impl Site {
+    pub const ALL: [Site; 4] = [
+        Site::Custom,
+        Site::Lichess,
+        Site::ChessCom,
+        Site::ChessComOutside,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Site::Custom => "Custom",
+            Site::Lichess => "Lichess",
+            Site::ChessCom => "Chess.com",
+            Site::ChessComOutside => "Chess.com (outside coordinates)",
+        }
+    }
+
+    pub fn profile(&self) -> Option<SiteProfile> {
+        match self {
+            Site::Custom => None,
+            // Flat default pieces on a plain board: confident detections,
+            // and a thin border around the squares
+            Site::Lichess => Some(SiteProfile {
+                confidence_threshold: 0.45,
+                board_margin: 0.03,
+                min_board_px: 200,
+                coordinate_strip: 0.0,
+                refine_grid: true,
+            }),
+            // Shaded pieces score lower; the eval bar and player boxes
+            // sit right next to the board
+            Site::ChessCom => Some(SiteProfile {
+                confidence_threshold: 0.4,
+                board_margin: 0.05,
+                min_board_px: 200,
+                coordinate_strip: 0.0,
+                refine_grid: true,
+            }),
+            Site::ChessComOutside => Some(SiteProfile {
+                confidence_threshold: 0.4,
+                board_margin: 0.05,
+                min_board_px: 200,
+                coordinate_strip: 0.035,
+                refine_grid: true,
+            }),
+        }
+    }
+}
+
+/// Detection settings for one site, copied into the config when the site
+/// is picked so each can still be adjusted afterwards.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SiteProfile {
+    pub confidence_threshold: f32,
+    /// Margin added around an auto-detected board, as a share of its side.
+    pub board_margin: f32,
+    /// Smallest board side, in screen pixels, accepted as a board.
+    pub min_board_px: u32,
+    /// Share of the detected board box taken by coordinate labels drawn
+    /// outside the squares, along its left and bottom edges.
+    pub coordinate_strip: f32,
+    pub refine_grid: bool,
+}
+
+impl SiteProfile {
+    pub fn apply(&self, c: &mut AppConfig) {
+        c.confidence_threshold = self.confidence_threshold;
+        c.auto_region_padding = self.board_margin;
+        c.min_board_px = self.min_board_px;
+        c.coordinate_strip = self.coordinate_strip;
+        c.refine_board_grid = self.refine_grid;
+    }
+}
+
+/// The board box without a coordinate strip of `strip` times its size on
+/// the left (ranks) and bottom (files), where sites draw them whichever
+/// side is at the bottom. `None` when there is no strip.
+fn strip_coordinates(board: &Detection, strip: f32) -> Option<Detection> {
+    if strip <= 0.0 {
+        return None;
+    }
+    let [cx, cy, w, h] = board.bbox;
+    let (dx, dy) = (w * strip, h * strip);
+    Some(Detection {
+        bbox: [cx + dx / 2.0, cy - dy / 2.0, w - dx, h - dy],
+        ..board.clone()
+    })
+}
+
+/// The squares' area to read the position from and draw on, for the
+/// board detection `board` in the crop `img`: the box without its
+/// coordinate strip, then narrowed to the squares' grid when `refine` is
+/// on and a grid is found. `None` when that is the detection unchanged.
+pub fn board_grid(
+    img: &DynamicImage,
+    board: &Detection,
+    coordinate_strip: f32,
+    refine: bool,
+) -> Option<Detection> {
+    let stripped = strip_coordinates(board, coordinate_strip);
+    let inner = stripped.as_ref().unwrap_or(board);
+    refine
+        .then(|| refine_board(img, inner))
+        .flatten()
+        .or(stripped)
+}
@@ -0,0 +1,1734 @@
+//! Background analysis loop: capture → detection → FEN → Stockfish.
+//!
+//! Runs on its own thread and publishes results as snapshots so the overlay
+//! never waits on capture, inference or engine searches.
+
+use crate::capture::diff::{ChangeDetector, FrameSignature};
+use crate::capture::monitor::screen_for;
+use crate::capture::source::{CaptureSourceKind, SourceSpec};
+use crate::capture::stream::CaptureStream;
+use crate::capture::target_window::TargetWindow;
+use crate::chess_logic::{complete_fen, with_turn, GameTracker, TurnDetector};
+use crate::config::{board_regions, AppConfig, BoardRegion, BoardSide};
+use crate::degradation::{CpuMeter, DegradationLadder, Effective};
+use crate::engine::book::{self, BookMove, OpeningBook};
+use crate::engine::eval_history::EvalHistory;
+use crate::engine::explain::{explain, threat_line};
+use crate::engine::human::human_move;
+use crate::engine::options::EngineOptions;
+use crate::engine::premove::{plan_premoves, ponder_hit, Premove};
+use crate::engine::process::EngineProcessSettings;
+use crate::engine::quality::{MoveClass, Quality};
+use crate::engine::sparring::Sparring;
+use crate::engine::stockfish::{Evaluation, PvLine, SearchLimit};
+use crate::engine::supervisor::{EngineHealth, EngineSupervisor};
+use crate::engine::time_control;
+use crate::errors::{AppError, SharedErrors};
+use crate::export::detections::DetectionExporter;
+use crate::export::failures::FailureDumper;
+use crate::file_watch::FileWatch;
+use crate::history::SessionRecorder;
+use crate::memory::MemoryGuard;
+use crate::notation::Notation;
+use crate::paths;
+use crate::platform;
+use crate::recording::{frame_seed, session_seed, FrameRecorder, PipelineParams};
+use crate::snapshot::{
+    BoardAnalysis, DetectionBox, GameRecord, OverlaySnapshot, SharedSnapshot, WorkerStatus,
+};
+use crate::vision::auto_region::{
+    board_fraction, detect_board_region, has_drifted, RescaleTracker,
+};
+use crate::vision::board::{
+    attempted_fen, detections_to_fen, min_board_fraction, select_board, validate_fen, BoardError,
+    INPUT_SIZE,
+};
+use crate::vision::clock::read_clock;
+use crate::vision::color::correct_colors;
+use crate::vision::grid::detect_empty_board;
+use crate::vision::inference::{Detection, Detector, InferenceBackend};
+use crate::vision::samples::{LowConfidenceMonitor, SampleCollector};
+use crate::vision::site::board_grid;
+
+use crossbeam_channel::Receiver;
+use image::DynamicImage;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// True if both FENs have the same side to move.
+fn same_turn(a: &str, b: &str) -> bool {
+    a.split_whitespace().nth(1) == b.split_whitespace().nth(1)
+}
+
+/// FEN to analyze from one frame's detections, plus the position to plan
+/// premoves in when it is the opponent's turn. Shared with the offline
+/// replay so both run the same code. Only legal positions are returned.
+pub fn board_position(
+    detections: &[Detection],
+    board_box: Option<&Detection>,
+    reassign_conflicts: bool,
+    show_white: bool,
+    auto_side: bool,
+    game: &mut GameTracker,
+    turn: &mut TurnDetector,
+) -> Result<(String, Option<String>), BoardError> {
+    let to_fen = |white| detections_to_fen(detections, board_box, white, reassign_conflicts);
+    let detected = match to_fen(show_white) {
+        // My side gave check, so it must be the opponent's turn
+        Err(BoardError::OppositeCheck) if auto_side => to_fen(!show_white)?,
+        other => other?,
+    };
+    // In auto mode the game tracker resyncs from the inferred turn
+    let inferred = match turn.observe(&detected) {
+        Some(white) if auto_side => with_turn(&detected, white),
+        _ => detected.clone(),
+    };
+    let inferred = complete_fen(&inferred, turn.en_passant());
+    // Prefer the tracked FEN (castling/en passant) when it agrees
+    let tracked = game.observe(&inferred);
+    if auto_side {
+        // One search for whoever is to move
+        let fen = tracked.unwrap_or(inferred);
+        validate_fen(&fen)?;
+        let mine = (fen.split_whitespace().nth(1) == Some("w")) == show_white;
+        let theirs = (!mine).then(|| fen.clone());
+        return Ok((fen, theirs));
+    }
+    let theirs = tracked.clone().filter(|full| !same_turn(full, &detected));
+    let fen = match tracked {
+        Some(full) if same_turn(&full, &detected) => full,
+        _ => complete_fen(&detected, turn.en_passant()),
+    };
+    validate_fen(&fen)?;
+    Ok((fen, theirs))
+}
+
+fn game_record(game: &GameTracker) -> GameRecord {
+    GameRecord {
+        moves: game.moves().to_vec(),
+        start: game.start(),
+        start_fen: game.start_fen().map(str::to_string),
+        evals: game.evals().to_vec(),
+        time_used: game.time_used(),
+        turn_started: game.turn_started(),
+        white_to_move: game.white_to_move(),
+    }
+}
+
+/// Overlay data for a board from the engine's lines for `fen`. `eval` is
+/// the best line's score for White, taken before any sparring reorder;
+/// `previous` is the board's last analysis, for the agreement part of the
+/// quality badge.
+fn board_analysis(
+    fen: String,
+    pv_lines: Vec<PvLine>,
+    eval: Option<Evaluation>,
+    previous: Option<&(String, Vec<String>)>,
+    premoves: Vec<Premove>,
+) -> BoardAnalysis {
+    let previous = previous.filter(|(prev_fen, _)| *prev_fen == fen);
+    let quality: Vec<Quality> = pv_lines
+        .iter()
+        .map(|l| {
+            let agreed = previous.map(|(_, moves)| moves.contains(&l.mv));
+            Quality::assess(l, agreed)
+        })
+        .collect();
+    let evals: Vec<_> = pv_lines.iter().map(|l| l.eval).collect();
+    let depth = pv_lines.iter().map(|l| l.depth).max().unwrap_or(0);
+    let classes = MoveClass::classify(&evals);
+    let (moves, variations) = pv_lines.into_iter().map(|l| (l.mv, l.pv)).unzip();
+    BoardAnalysis {
+        fen,
+        moves,
+        variations,
+        premoves,
+        eval,
+        evals,
+        quality,
+        classes,
+        depth,
+        nps: None,
+        min_confidence: None,
+        grid: None,
+        blunder: None,
+        human_move: None,
+        threat: Vec::new(),
+        book: Vec::new(),
+        analyzed_at: Instant::now(),
+    }
+}
+
+/// Detection box as (x, y, w, h) in capture pixels. Boxes are centers and
+/// sizes in the model's input space, stretched over the crop of region `r`.
+fn capture_rect(d: &Detection, r: &BoardRegion, img: &DynamicImage) -> [f32; 4] {
+    let sx = img.width() as f32 / INPUT_SIZE;
+    let sy = img.height() as f32 / INPUT_SIZE;
+    let [x, y, w, h] = d.bbox;
+    [
+        r.x as f32 + (x - w / 2.0) * sx,
+        r.y as f32 + (y - h / 2.0) * sy,
+        w * sx,
+        h * sy,
+    ]
+}
+
+/// Detections as screen boxes, for the detection debug view.
+fn detection_boxes(
+    detections: &[Detection],
+    r: &BoardRegion,
+    img: &DynamicImage,
+) -> Vec<DetectionBox> {
+    detections
+        .iter()
+        .map(|d| DetectionBox {
+            rect: capture_rect(d, r, img),
+            class_id: d.class_id,
+            confidence: d.confidence,
+        })
+        .collect()
+}
+
+/// Overlay data for a board whose position is in the opening book; the
+/// engine is not asked.
+fn book_analysis(fen: String, book: Vec<BookMove>) -> BoardAnalysis {
+    BoardAnalysis {
+        fen,
+        moves: Vec::new(),
+        variations: Vec::new(),
+        premoves: Vec::new(),
+        eval: None,
+        evals: Vec::new(),
+        quality: Vec::new(),
+        classes: Vec::new(),
+        depth: 0,
+        nps: None,
+        min_confidence: None,
+        grid: None,
+        blunder: None,
+        human_move: None,
+        threat: Vec::new(),
+        book,
+        analyzed_at: Instant::now(),
+    }
+}
+
+/// Scales the (single) board region by `factor` about its center after
+/// the board inside it was resized, and saves the config so the adjusted
+/// region survives a restart. Returns the status line for the settings
+/// window.
+fn rescale_region(
+    c: &mut AppConfig,
+    region: &BoardRegion,
+    factor: f32,
+    monitor: Option<u32>,
+    errors: &SharedErrors,
+) -> String {
+    let (max_w, max_h) = screen_for(monitor)
+        .map(|s| (s.display_info.width, s.display_info.height))
+        .unwrap_or((u32::MAX, u32::MAX));
+    let scaled = region.scaled_about_center(factor, max_w, max_h);
+    let status = format!(
+        "Board resized ×{:.2}: region now {}x{} at ({}, {})",
+        factor, scaled.width, scaled.height, scaled.x, scaled.y
+    );
+    println!("{}", status);
+    c.board_region = Some(scaled);
+    if let Err(e) = c.save() {
+        errors.report(AppError::System(format!(
+            "Failed to save rescaled region: {:?}",
+            e
+        )));
+    }
+    status
+}
+
+/// Minimum time between automatic board re-detections after drift.
+const REDETECT_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Stockfish's default (full strength) Skill Level.
+pub const MAX_SKILL_LEVEL: u32 = 20;
+
+/// Per-board worker state when the region holds more than one board.
+#[derive(Default)]
+struct BoardTracker {
+    changes: ChangeDetector,
+    last_analysis: Option<Instant>,
+    game: GameTracker,
+    turn: TurnDetector,
+    /// FEN and suggested moves of the previous analysis, for the
+    /// agreement part of the quality badge.
+    previous: Option<(String, Vec<String>)>,
+    /// Lowest piece confidence in the last detection.
+    min_confidence: Option<f32>,
+    /// Refined squares' grid of the last detection, in capture pixels.
+    grid: Option<[f32; 4]>,
+    /// Scores of the positions analyzed, for blunder alerts.
+    evals: EvalHistory,
+}
+
+/// Requests from the UI to the worker, handled on its next cycle.
+pub enum WorkerCommand {
+    /// Find the board on the whole monitor and set the region from it.
+    DetectRegion,
+    /// Repeat the test capture.
+    CheckCapture,
+    /// Load the updated model and/or engine.
+    ReloadFiles,
+    /// Keep running the loaded model and engine after they changed on disk.
+    IgnoreFileUpdates,
+    /// Start a fresh engine process (after it failed for good).
+    RestartEngine,
+    /// Re-analyze every board even if unchanged.
+    Rescan,
+    /// A move typed by the user, applied to the first board's game tracker.
+    PlayMove(String),
+    /// Explain the first board's best move, or close the explanation shown.
+    ToggleExplanation,
+    /// Rebuild the model session and restart the engine.
+    ReclaimMemory,
+}
+
+/// Commands received since the last cycle, merged.
+#[derive(Default)]
+struct Requests {
+    detect_region: bool,
+    check_capture: bool,
+    reload_files: bool,
+    ignore_updates: bool,
+    restart_engine: bool,
+    rescan: bool,
+    moves: Vec<String>,
+    explain: bool,
+    reclaim: bool,
+}
+
+impl Requests {
+    fn collect(&mut self, commands: &Receiver<WorkerCommand>) {
+        for command in commands.try_iter() {
+            match command {
+                WorkerCommand::DetectRegion => self.detect_region = true,
+                WorkerCommand::CheckCapture => self.check_capture = true,
+                WorkerCommand::ReloadFiles => self.reload_files = true,
+                WorkerCommand::IgnoreFileUpdates => self.ignore_updates = true,
+                WorkerCommand::RestartEngine => self.restart_engine = true,
+                WorkerCommand::Rescan => self.rescan = true,
+                WorkerCommand::PlayMove(text) => self.moves.push(text),
+                WorkerCommand::ToggleExplanation => self.explain = !self.explain,
+                WorkerCommand::ReclaimMemory => self.reclaim = true,
+            }
+        }
+    }
+}
+
+/// Start the analysis worker. What the overlay draws, and the worker's
+/// status, is published to `snapshot`; settings come from `config` and
+/// requests from the UI from `commands`.
+pub fn spawn(
+    config: Arc<Mutex<AppConfig>>,
+    snapshot: SharedSnapshot,
+    commands: Receiver<WorkerCommand>,
+    errors: SharedErrors,
+) -> JoinHandle<()> {
+    thread::spawn(move || run(config, snapshot, commands, errors))
+}
+
+/// Runs the test capture on the configured monitor and records the result
+/// for the settings window.
+fn check_capture(monitor: Option<u32>, status: &mut WorkerStatus, errors: &SharedErrors) {
+    let result = platform::check_capture(monitor).map_err(|e| format!("{:#}", e));
+    status.screen_permission_missing = !platform::screen_recording_allowed();
+    if let Err(e) = &result {
+        errors.report(AppError::Capture(format!("Test capture failed: {}", e)));
+    }
+    status.capture_check = Some(result);
+}
+
+/// Repeats the test capture when the settings window asked for it, or
+/// once a missing Screen Recording permission has been granted.
+fn recheck_capture(
+    requested: bool,
+    monitor: Option<u32>,
+    status: &mut WorkerStatus,
+    errors: &SharedErrors,
+) {
+    if requested || (status.screen_permission_missing && platform::screen_recording_allowed()) {
+        check_capture(monitor, status, errors);
+    }
+}
+
+fn monitor_id(config: &Mutex<AppConfig>) -> Option<u32> {
+    config.lock().unwrap().monitor.as_ref().map(|m| m.id)
+}
+
+/// Settings the worker uses, read from the config once per cycle.
+struct Settings {
+    region: Option<BoardRegion>,
+    split: bool,
+    extra_regions: Vec<BoardRegion>,
+    /// Clock region, while the clock is used for time management.
+    clock: Option<BoardRegion>,
+    limit: SearchLimit,
+    lines: u32,
+    conf: f32,
+    iou: f32,
+    color_check: Option<f32>,
+    show_white: bool,
+    board_sides: Vec<BoardSide>,
+    auto_side: bool,
+    fps: u32,
+    capture_fps: u32,
+    running: bool,
+    sparring: bool,
+    min_board_px: u32,
+    record_history: bool,
+    premove_planning: bool,
+    off_turn_ratio: f32,
+    human_elo: Option<u32>,
+    threat_arrows: bool,
+    blunder_threshold: Option<i32>,
+    monitor: Option<u32>,
+    auto_track: bool,
+    auto_rescale: bool,
+    padding: f32,
+    cooldown: Duration,
+    /// Pause while the game window is hidden; the flag says whether losing
+    /// focus counts as hidden.
+    pause_hidden: Option<bool>,
+    manual_pause: bool,
+    idle_after: Duration,
+    idle_interval: Duration,
+    source: SourceSpec,
+    board_cycle: usize,
+    show_detections: bool,
+    collect_samples: bool,
+    record_frames: bool,
+    debug_capture: bool,
+    dump_failures: bool,
+    reassign: bool,
+    refine: bool,
+    strip: f32,
+    model_path: PathBuf,
+    backend: InferenceBackend,
+    book: Option<PathBuf>,
+    engine_process: EngineProcessSettings,
+    engine_options: EngineOptions,
+    warn_mb: u32,
+    reclaim_hours: u32,
+    reclaim_on_warning: bool,
+    notation: Notation,
+}
+
+impl Settings {
+    fn read(c: &AppConfig) -> Self {
+        Self {
+            region: c.board_region.clone(),
+            split: c.split_region,
+            extra_regions: c.extra_regions.clone(),
+            clock: c.clock_region.clone().filter(|_| c.clock_time_management),
+            limit: c.search_limit(),
+            lines: c.stockfish_lines,
+            conf: c.confidence_threshold,
+            iou: c.iou_threshold,
+            color_check: c.color_check.then_some(c.color_check_below),
+            show_white: c.show_white_moves,
+            board_sides: c.board_sides.clone(),
+            auto_side: c.auto_side_to_move,
+            fps: c.fps,
+            capture_fps: c.capture_fps,
+            running: c.running,
+            sparring: c.sparring,
+            min_board_px: c.min_board_px,
+            record_history: c.record_history,
+            premove_planning: c.premove_planning,
+            off_turn_ratio: c.off_turn_depth_ratio,
+            human_elo: c.human_mode.then_some(c.human_elo),
+            threat_arrows: c.threat_arrows,
+            blunder_threshold: c.blunder_alert.then_some(c.blunder_threshold_cp as i32),
+            monitor: c.monitor.as_ref().map(|m| m.id),
+            auto_track: c.auto_track_board && c.region_auto_detected,
+            auto_rescale: c.auto_rescale_region,
+            padding: c.auto_region_padding,
+            cooldown: Duration::from_millis(c.post_move_cooldown_ms as u64),
+            // Only a board on screen sits in a window that can hide
+            pause_hidden: (c.auto_pause_hidden && c.capture_source == CaptureSourceKind::Monitor)
+                .then_some(c.pause_when_unfocused),
+            manual_pause: c.analysis_paused,
+            idle_after: Duration::from_secs(c.idle_after_secs as u64),
+            idle_interval: Duration::from_millis(c.idle_interval_ms as u64),
+            source: SourceSpec {
+                kind: c.capture_source,
+                monitor: c.monitor.as_ref().map(|m| m.id),
+                folder: c.image_sequence_dir.clone(),
+            },
+            board_cycle: c.board_cycle,
+            show_detections: c.show_detections,
+            collect_samples: c.collect_samples,
+            record_frames: c.record_frames,
+            debug_capture: c.debug_capture,
+            dump_failures: c.dump_failed_recognitions,
+            reassign: c.reassign_cell_conflicts,
+            refine: c.refine_board_grid,
+            strip: c.coordinate_strip,
+            model_path: paths::model_path(&c.model_path),
+            backend: c.inference_backend,
+            book: c
+                .use_opening_book
+                .then(|| book::resolve_path(&c.opening_book_path)),
+            engine_process: c.engine_process,
+            engine_options: c.engine_options.clone(),
+            warn_mb: c.memory_warn_mb,
+            reclaim_hours: c.reclaim_interval_hours,
+            reclaim_on_warning: c.reclaim_on_warning,
+            notation: c.notation,
+        }
+    }
+
+    /// Settings whose change must re-analyze boards even if unchanged.
+    fn analysis_params(&self) -> AnalysisParams {
+        AnalysisParams {
+            limit: self.limit,
+            lines: self.lines,
+            conf: self.conf,
+            iou: self.iou,
+            color_check: self.color_check,
+            sides: (self.show_white, self.board_sides.clone()),
+            auto_side: self.auto_side,
+            split: self.split,
+            sparring: self.sparring,
+            off_turn_ratio: self.off_turn_ratio,
+            human_elo: self.human_elo,
+            monitor: self.monitor,
+        }
+    }
+}
+
+#[derive(PartialEq)]
+struct AnalysisParams {
+    limit: SearchLimit,
+    lines: u32,
+    conf: f32,
+    iou: f32,
+    color_check: Option<f32>,
+    sides: (bool, Vec<BoardSide>),
+    auto_side: bool,
+    split: bool,
+    sparring: bool,
+    off_turn_ratio: f32,
+    human_elo: Option<u32>,
+    monitor: Option<u32>,
+}
+
+/// Region, split, extra boards and source the current results belong to.
+type Layout = (Option<BoardRegion>, bool, Vec<BoardRegion>, SourceSpec);
+
+/// What one board's frame yielded: every detection, the chosen board and
+/// the box the position is read from (the refined grid, when found).
+struct BoardDetections {
+    detections: Vec<Detection>,
+    selected: Option<Detection>,
+    board_box: Option<Detection>,
+}
+
+/// The worker's state between cycles.
+struct Worker {
+    config: Arc<Mutex<AppConfig>>,
+    snapshot: SharedSnapshot,
+    commands: Receiver<WorkerCommand>,
+    errors: SharedErrors,
+    pending: Requests,
+    /// What the next publish shows; `dirty` once it differs from the last.
+    latest: OverlaySnapshot,
+    dirty: bool,
+    detector: Detector,
+    model_path: PathBuf,
+    backend: InferenceBackend,
+    model_watch: FileWatch,
+    /// A picked model that failed to load; not retried until the path changes
+    model_rejected: Option<PathBuf>,
+    engine: EngineSupervisor,
+    engine_starts: u64,
+    engine_watch: FileWatch,
+    boards: Vec<BoardTracker>,
+    last_params: Option<AnalysisParams>,
+    last_layout: Option<Layout>,
+    ladder: DegradationLadder,
+    cpu: CpuMeter,
+    session_seed: u64,
+    frame: u64,
+    frame_recorder: Option<FrameRecorder>,
+    exporter: Option<DetectionExporter>,
+    failure_dumper: FailureDumper,
+    capture: CaptureStream,
+    recorder: SessionRecorder,
+    low_confidence: LowConfidenceMonitor,
+    collector: Option<SampleCollector>,
+    redetect: bool,
+    last_redetect: Option<Instant>,
+    rescale: RescaleTracker,
+    memory: MemoryGuard,
+    /// Board and FEN of a running infinite search, and the depth last shown
+    live: Option<(usize, String)>,
+    live_depth: u32,
+    target: TargetWindow,
+    target_point: Option<(i32, i32)>,
+    opening_book: Option<OpeningBook>,
+    /// Book file last tried, so a missing one isn't re-read every cycle
+    book_source: Option<PathBuf>,
+    /// Last time any board changed, for the idle capture throttle
+    last_activity: Instant,
+}
+
+fn run(
+    config: Arc<Mutex<AppConfig>>,
+    snapshot: SharedSnapshot,
+    commands: Receiver<WorkerCommand>,
+    errors: SharedErrors,
+) {
+    let mut worker = Worker::start(config, snapshot, commands, errors);
+    println!("Worker thread ready");
+    loop {
+        let pause = worker.cycle();
+        thread::sleep(pause);
+    }
+}
+
+impl Worker {
+    /// Runs the startup capture check, waits for a model that loads and
+    /// starts the engine.
+    fn start(
+        config: Arc<Mutex<AppConfig>>,
+        snapshot: SharedSnapshot,
+        commands: Receiver<WorkerCommand>,
+        errors: SharedErrors,
+    ) -> Self {
+        let engine_path = paths::engine_path();
+        if !platform::screen_recording_allowed() {
+            platform::request_screen_recording();
+        }
+        let mut latest = OverlaySnapshot::default();
+        check_capture(monitor_id(&config), &mut latest.status, &errors);
+        snapshot.publish(latest.clone());
+        let mut pending = Requests::default();
+
+        // Without a model there is nothing to do; wait for one to be picked
+        // in the settings window (or copied into place)
+        let mut backend = config.lock().unwrap().inference_backend;
+        let mut model_tried = None;
+        let (detector, model_path) = loop {
+            let (wanted, wanted_backend) = {
+                let c = config.lock().unwrap();
+                (paths::model_path(&c.model_path), c.inference_backend)
+            };
+            if model_tried.as_ref() != Some(&(wanted.clone(), wanted.exists()))
+                || wanted_backend != backend
+            {
+                backend = wanted_backend;
+                model_tried = Some((wanted.clone(), wanted.exists()));
+                let loaded = if wanted.exists() {
+                    Detector::new(&wanted.to_string_lossy(), backend)
+                } else {
+                    Err(anyhow::anyhow!("Model not found: {}", wanted.display()))
+                };
+                match loaded {
+                    Ok(d) => {
+                        latest.status.model_status = Some(format!("Loaded {}", wanted.display()));
+                        break (d, wanted);
+                    }
+                    Err(e) => {
+                        latest.status.model_status = Some(format!("{:#}", e));
+                        errors.report(AppError::Inference(format!("{:#}", e)));
+                    }
+                }
+            }
+            pending.collect(&commands);
+            let requested = std::mem::take(&mut pending.check_capture);
+            recheck_capture(requested, monitor_id(&config), &mut latest.status, &errors);
+            if snapshot.load().status != latest.status {
+                snapshot.publish(latest.clone());
+            }
+            thread::sleep(Duration::from_millis(500));
+        };
+
+        let (engine_process, engine_options) = {
+            let c = config.lock().unwrap();
+            (c.engine_process, c.engine_options.clone())
+        };
+        let engine = EngineSupervisor::new(
+            engine_path.to_str().unwrap(),
+            engine_process,
+            engine_options,
+        );
+        latest.status.inference_active = Some(detector.backend());
+        Self {
+            config,
+            snapshot,
+            commands,
+            errors,
+            pending,
+            latest,
+            dirty: true,
+            model_watch: FileWatch::new(model_path.clone()),
+            detector,
+            model_path,
+            backend,
+            model_rejected: None,
+            engine,
+            engine_starts: 0,
+            engine_watch: FileWatch::new(engine_path),
+            boards: Vec::new(),
+            last_params: None,
+            last_layout: None,
+            ladder: DegradationLadder::default(),
+            cpu: CpuMeter::default(),
+            session_seed: session_seed(),
+            frame: 0,
+            frame_recorder: None,
+            exporter: None,
+            failure_dumper: FailureDumper::default(),
+            capture: CaptureStream::spawn(),
+            recorder: SessionRecorder::new(),
+            low_confidence: LowConfidenceMonitor::default(),
+            collector: None,
+            redetect: false,
+            last_redetect: None,
+            rescale: RescaleTracker::default(),
+            memory: MemoryGuard::default(),
+            live: None,
+            live_depth: 0,
+            target: TargetWindow::default(),
+            target_point: None,
+            opening_book: None,
+            book_source: None,
+            last_activity: Instant::now(),
+        }
+    }
+
+    /// One pass: settings and requests, model and engine upkeep, capture,
+    /// analysis and publishing. Returns how long to wait before the next.
+    fn cycle(&mut self) -> Duration {
+        let mut s = Settings::read(&self.config.lock().unwrap());
+        self.pending.collect(&self.commands);
+        let requests = std::mem::take(&mut self.pending);
+
+        self.detect_region(&mut s, requests.detect_region);
+        let sub_regions = self.update_layout(&s);
+        if self.last_params.as_ref() != Some(&s.analysis_params()) {
+            self.boards.iter_mut().for_each(|b| b.changes.reset());
+            self.last_params = Some(s.analysis_params());
+        }
+
+        self.swap_model(&s);
+        recheck_capture(
+            requests.check_capture,
+            s.monitor,
+            &mut self.latest.status,
+            &self.errors,
+        );
+        self.reload_files(requests.reload_files, requests.ignore_updates);
+        self.update_book(&s);
+        self.update_backend(&s);
+        self.update_engine(&s, requests.restart_engine);
+
+        if requests.rescan {
+            self.last_activity = Instant::now();
+            self.reset_boards();
+        }
+        self.play_manual_moves(requests.moves, s.notation);
+        self.update_explanation(requests.explain, s.limit);
+        self.manage_memory(&s, requests.reclaim);
+
+        let paused = self.update_pause(&s);
+        let effective = self.ladder.apply(s.limit, s.lines, s.capture_fps);
+        if self.latest.degradation != self.ladder.level() {
+            self.latest.degradation = self.ladder.level();
+            self.dirty = true;
+        }
+        // The clock, when read, is captured after the boards
+        let targets: Vec<BoardRegion> =
+            sub_regions.iter().cloned().chain(s.clock.clone()).collect();
+        self.update_capture(&s, paused, &effective, &targets);
+
+        // Only the newest capture is analyzed; older ones were dropped
+        let captured = self
+            .capture
+            .latest()
+            .filter(|f| s.running && !paused && f.regions == targets);
+        // Time left on the player's clock, kept while a frame can't be read
+        if s.clock.is_none() {
+            self.latest.status.clock_remaining = None;
+        }
+        if let Some(captured) = captured {
+            let reading = captured
+                .images
+                .get(sub_regions.len())
+                .filter(|_| s.clock.is_some())
+                .and_then(read_clock);
+            if reading.is_some() {
+                self.latest.status.clock_remaining = reading;
+            }
+            let frames = sub_regions.iter().zip(&captured.images);
+            for (board, (r, img)) in frames.enumerate() {
+                self.analyze_frame(&mut s, &effective, board, r, img, sub_regions.len());
+            }
+        } else if !s.running {
+            self.boards.iter_mut().for_each(|b| b.changes.reset());
+        }
+
+        self.follow_live_search(&s, paused, &effective);
+        // Status changes are published like board changes
+        if std::mem::take(&mut self.dirty) || self.snapshot.load().status != self.latest.status {
+            self.snapshot.publish(self.latest.clone());
+        }
+        Duration::from_millis(1000 / effective.capture_fps.max(1) as u64)
+    }
+
+    fn reset_boards(&mut self) {
+        for b in self.boards.iter_mut() {
+            b.changes.reset();
+            b.last_analysis = None;
+        }
+    }
+
+    /// Finds the board on the whole monitor when asked to, or when a
+    /// tracked board drifted, and moves the region there.
+    fn detect_region(&mut self, s: &mut Settings, requested: bool) {
+        let drift_due = self
+            .last_redetect
+            .is_none_or(|t| t.elapsed() >= REDETECT_INTERVAL);
+        if !(requested || (self.redetect && drift_due)) {
+            return;
+        }
+        self.redetect = false;
+        self.last_redetect = Some(Instant::now());
+        if requested {
+            // Scanning the whole monitor takes a moment
+            self.latest.status.auto_detect_status = Some("Searching…".to_string());
+            self.snapshot.publish(self.latest.clone());
+        }
+        let found = detect_board_region(&mut self.detector, s.monitor, s.conf, s.iou, s.padding);
+        let status = &mut self.latest.status;
+        match found {
+            Ok(Some(found)) => {
+                status.auto_detect_status = Some(format!(
+                    "Board found: {}x{} at ({}, {})",
+                    found.width, found.height, found.x, found.y
+                ));
+                let mut c = self.config.lock().unwrap();
+                c.board_region = Some(found.clone());
+                c.region_auto_detected = true;
+                c.region_board_fraction = None;
+                s.region = Some(found);
+            }
+            Ok(None) => {
+                status.auto_detect_status = Some("No board found on screen".to_string());
+            }
+            Err(e) => {
+                let error = format!("Board auto-detection failed: {:?}", e);
+                self.errors.report(AppError::Inference(error));
+            }
+        }
+    }
+
+    /// Drops results for a different region, display or source, and sizes
+    /// the per-board state. Returns the boards' regions.
+    fn update_layout(&mut self, s: &Settings) -> Vec<BoardRegion> {
+        let layout = (
+            s.region.clone(),
+            s.split,
+            s.extra_regions.clone(),
+            s.source.clone(),
+        );
+        if self.last_layout.as_ref() != Some(&layout) {
+            self.latest.boards.clear();
+            self.latest.empty_board_grid = None;
+            self.latest.detections.clear();
+            self.latest.games.clear();
+            self.rescale.reset();
+            self.target.forget();
+            // The window is looked up under the middle of the region
+            self.target_point = s.region.as_ref().and_then(|r| {
+                let d = screen_for(s.monitor).ok()?.display_info;
+                Some((
+                    d.x + (r.x + r.width / 2) as i32,
+                    d.y + (r.y + r.height / 2) as i32,
+                ))
+            });
+            self.dirty = true;
+            self.last_layout = Some(layout);
+        }
+        let sub_regions = board_regions(s.region.as_ref(), s.split, &s.extra_regions);
+        self.boards
+            .resize_with(sub_regions.len(), BoardTracker::default);
+        self.latest.boards.resize(sub_regions.len(), None);
+        self.latest.games.resize(sub_regions.len(), None);
+        sub_regions
+    }
+
+    /// A different model file is swapped in without restarting; if it
+    /// doesn't load, the current one keeps running.
+    fn swap_model(&mut self, s: &Settings) {
+        let wanted = &s.model_path;
+        if *wanted == self.model_path || Some(wanted) == self.model_rejected.as_ref() {
+            return;
+        }
+        println!("Loading model {}", wanted.display());
+        let status = &mut self.latest.status;
+        match Detector::new(&wanted.to_string_lossy(), self.backend) {
+            Ok(d) => {
+                self.detector = d;
+                status.inference_active = Some(self.detector.backend());
+                status.model_status = Some(format!("Loaded {}", wanted.display()));
+                self.model_watch = FileWatch::new(wanted.clone());
+                self.model_path = wanted.clone();
+                self.model_rejected = None;
+                status.model_updated = false;
+                self.boards.iter_mut().for_each(|b| b.changes.reset());
+            }
+            Err(e) => {
+                status.model_status = Some(format!("{:#}; still using the previous model", e));
+                let error = format!("Model swap failed: {:#}", e);
+                self.errors.report(AppError::Inference(error));
+                self.model_rejected = Some(wanted.clone());
+            }
+        }
+    }
+
+    /// Notes model and engine files replaced on disk, and loads them when
+    /// asked to. Only the model and engine are swapped: region, board
+    /// trackers and game tracking carry over.
+    fn reload_files(&mut self, reload: bool, ignore: bool) {
+        let status = &mut self.latest.status;
+        if self.model_watch.changed() {
+            println!("Model file updated on disk");
+            status.model_updated = true;
+        }
+        if self.engine_watch.changed() {
+            println!("Engine binary updated on disk");
+            status.engine_updated = true;
+        }
+        if ignore {
+            status.model_updated = false;
+            status.engine_updated = false;
+        }
+        if !reload {
+            return;
+        }
+        if std::mem::take(&mut status.model_updated) {
+            println!("Reloading model");
+            match Detector::new(&self.model_path.to_string_lossy(), self.backend) {
+                Ok(d) => {
+                    self.detector = d;
+                    self.latest.status.inference_active = Some(self.detector.backend());
+                    self.boards.iter_mut().for_each(|b| b.changes.reset());
+                }
+                Err(e) => {
+                    let error = format!("Model reload failed: {:?}", e);
+                    self.errors.report(AppError::Inference(error));
+                }
+            }
+        }
+        if std::mem::take(&mut self.latest.status.engine_updated) {
+            println!("Restarting the updated engine");
+            self.engine.restart();
+            self.live = None;
+            self.boards.iter_mut().for_each(|b| b.changes.reset());
+        }
+    }
+
+    fn update_book(&mut self, s: &Settings) {
+        if s.book == self.book_source {
+            return;
+        }
+        self.opening_book = None;
+        let status = s.book.as_ref().map(|path| match OpeningBook::open(path) {
+            Ok(b) => {
+                let status = format!("{} book entries loaded", b.entry_count());
+                self.opening_book = Some(b);
+                status
+            }
+            Err(e) => format!("Opening book unavailable: {}", e),
+        });
+        if let Some(status) = &status {
+            println!("{}", status);
+        }
+        self.latest.status.book_status = status;
+        self.book_source = s.book.clone();
+        self.boards.iter_mut().for_each(|b| b.changes.reset());
+    }
+
+    fn update_backend(&mut self, s: &Settings) {
+        if s.backend == self.backend {
+            return;
+        }
+        self.backend = s.backend;
+        match Detector::new(&self.model_path.to_string_lossy(), self.backend) {
+            Ok(d) => {
+                self.detector = d;
+                self.latest.status.inference_active = Some(self.detector.backend());
+            }
+            Err(e) => {
+                let error = format!("Model reload failed: {:?}", e);
+                self.errors.report(AppError::Inference(error));
+            }
+        }
+    }
+
+    /// Applies engine settings, restarts on request and follows the
+    /// supervisor's view of the engine's health.
+    fn update_engine(&mut self, s: &Settings, restart: bool) {
+        let engine = &mut self.engine;
+        engine.set_process(s.engine_process);
+        if restart {
+            engine.restart();
+        }
+        if s.engine_options != *engine.options() {
+            let applied = engine.apply_options(s.engine_options.clone());
+            self.latest.status.engine_option_errors = engine.option_errors().to_vec();
+            if let Err(e) = applied {
+                let error = format!("Applying engine options failed: {:?}", e);
+                self.errors.report(AppError::Engine(error));
+            }
+            self.live = None;
+            self.reset_boards();
+        }
+        let engine = &mut self.engine;
+        engine.poll();
+        if engine.starts() != self.engine_starts {
+            // A fresh process: nothing of the old one's search survives
+            self.engine_starts = engine.starts();
+            self.live = None;
+            let status = &mut self.latest.status;
+            status.engine_option_errors = engine.option_errors().to_vec();
+            status.engine_multipv = None;
+            status.engine_problem = None;
+        }
+        if self.latest.engine != *engine.health() {
+            let health = engine.health().clone();
+            match &health {
+                EngineHealth::Restarting { .. } | EngineHealth::Failed(_) => {
+                    self.errors.report(AppError::Engine(health.label()));
+                    self.latest.status.engine_problem = engine.problem().cloned();
+                }
+                EngineHealth::Running if self.latest.engine != EngineHealth::Starting => {
+                    println!("Engine recovered");
+                }
+                _ => {}
+            }
+            self.latest.engine = health;
+            self.dirty = true;
+        }
+    }
+
+    /// Applies moves typed by the user to the first board's game tracker.
+    fn play_manual_moves(&mut self, moves: Vec<String>, notation: Notation) {
+        let Some(primary) = self.boards.first_mut() else {
+            return;
+        };
+        for text in moves {
+            let status = match primary.game.play_manual(&text) {
+                Ok(_) => {
+                    primary.changes.reset();
+                    self.latest.games[0] = Some(game_record(&primary.game));
+                    self.dirty = true;
+                    let san = primary.game.moves().last().unwrap();
+                    format!("Played {}", notation.format_san(san))
+                }
+                Err(e) => e,
+            };
+            self.latest.status.manual_move_status = Some(status);
+        }
+    }
+
+    /// Explains the first board's best move when asked to (or closes the
+    /// explanation shown), and drops one the board has moved past.
+    fn update_explanation(&mut self, requested: bool, limit: SearchLimit) {
+        let best = self
+            .latest
+            .boards
+            .first()
+            .and_then(|b| b.as_ref())
+            .filter(|a| !a.moves.is_empty());
+        let explanation = &mut self.latest.status.explanation;
+        if requested && explanation.take().is_none() {
+            let target = best.map(|a| (a.fen.clone(), a.moves[0].clone(), a.moves.get(1).cloned()));
+            match (target, self.engine.engine()) {
+                (Some((fen, mv, alternative)), Some(sf)) => {
+                    // The explanation needs the engine to itself for a moment
+                    if self.live.take().is_some() {
+                        let _ = sf.stop();
+                    }
+                    let result = explain(sf, &fen, &mv, alternative.as_deref(), limit.reduced());
+                    if let Some(primary) = self.boards.first_mut() {
+                        primary.changes.reset();
+                    }
+                    match result {
+                        Ok(explanation) => self.latest.status.explanation = Some(explanation),
+                        Err(e) => self.engine.report_error(&e),
+                    }
+                }
+                _ => println!("Nothing to explain: no suggestion on the first board yet"),
+            }
+        } else {
+            // An explanation only holds for the position it was made for
+            let fen = best.map(|a| a.fen.as_str());
+            if explanation
+                .as_ref()
+                .is_some_and(|e| Some(e.fen.as_str()) != fen)
+            {
+                *explanation = None;
+            }
+        }
+    }
+
+    /// Samples memory use and, when asked to or when due, rebuilds the
+    /// model session and restarts the engine.
+    fn manage_memory(&mut self, s: &Settings, requested: bool) {
+        let status = self.memory.sample(s.warn_mb);
+        if let Some(status) = status {
+            if status.over_limit {
+                self.errors.report(AppError::System(format!(
+                    "WARNING: memory grew by more than {} MB, possible leak",
+                    s.warn_mb
+                )));
+            }
+            self.latest.status.memory = Some(status);
+        }
+        let due = self
+            .memory
+            .reclaim_due(status, s.reclaim_hours, s.reclaim_on_warning);
+        if !requested && !due {
+            return;
+        }
+        // Dropping the session and engine returns their arenas and hash
+        // to the OS; a fresh start costs about one analysis.
+        println!("Reclaiming memory: rebuilding model session and engine");
+        drop(self.collector.take());
+        match Detector::new(&self.model_path.to_string_lossy(), self.backend) {
+            Ok(d) => self.detector = d,
+            Err(e) => {
+                let error = format!("Model reload failed: {:?}", e);
+                self.errors.report(AppError::Inference(error));
+            }
+        }
+        self.engine.restart();
+        self.live = None;
+        for b in self.boards.iter_mut() {
+            b.changes.reset();
+            b.last_analysis = None;
+            b.previous = None;
+        }
+        self.memory.reclaimed();
+    }
+
+    /// Whether analysis is paused this cycle: by the load ladder, by the
+    /// user, or while the game window is hidden.
+    fn update_pause(&mut self, s: &Settings) -> bool {
+        let hidden = match (s.pause_hidden, self.target_point) {
+            (Some(require_focus), Some(point)) if s.running => {
+                self.target.track(point);
+                self.target.hidden(require_focus)
+            }
+            _ => None,
+        };
+        if self.latest.status.auto_paused != hidden {
+            match hidden {
+                Some(reason) => println!("Pausing: {}", reason.label()),
+                None => println!("Resuming: game window visible"),
+            }
+            self.latest.status.auto_paused = hidden;
+        }
+        // Change detection keeps its last frame, so an unchanged board
+        // isn't re-analyzed when the window comes back
+        self.ladder.is_paused() || hidden.is_some() || s.manual_pause
+    }
+
+    /// Points the capture stream at `targets`, slowed down while no board
+    /// changes, and takes over its error and stats.
+    fn update_capture(
+        &mut self,
+        s: &Settings,
+        paused: bool,
+        effective: &Effective,
+        targets: &[BoardRegion],
+    ) {
+        // A paused or stopped session starts out active when it resumes
+        if !s.running || paused {
+            self.last_activity = Instant::now();
+        }
+        let idle = !s.idle_after.is_zero() && self.last_activity.elapsed() >= s.idle_after;
+        if self.latest.status.idle_throttled != idle {
+            if idle {
+                println!(
+                    "No board change for {:?}, capturing less often",
+                    s.idle_after
+                );
+            } else {
+                println!("Board changed, capturing at full rate");
+            }
+            self.latest.status.idle_throttled = idle;
+        }
+        let mut capture_interval =
+            Duration::from_millis(1000 / effective.capture_fps.max(1) as u64);
+        if idle {
+            capture_interval = capture_interval.max(s.idle_interval);
+        }
+        self.capture.set_target(
+            (s.running && !paused).then_some(targets),
+            &s.source,
+            capture_interval,
+        );
+        let error = self.capture.error();
+        if let Some(err) = error
+            .as_ref()
+            .filter(|_| self.latest.status.capture_error != error)
+        {
+            self.errors.report(AppError::Capture(err.to_string()));
+        }
+        self.latest.status.capture_error = error;
+        let stats = self.capture.stats();
+        if self.latest.capture != stats {
+            self.latest.capture = stats;
+            self.dirty = true;
+        }
+    }
+
+    /// Analyzes board `board`'s crop when it changed and its analysis
+    /// interval has passed, and counts the time against the load budget.
+    fn analyze_frame(
+        &mut self,
+        s: &mut Settings,
+        effective: &Effective,
+        board: usize,
+        r: &BoardRegion,
+        img: &DynamicImage,
+        board_count: usize,
+    ) {
+        let analysis_interval = Duration::from_millis(1000 / s.fps.max(1) as u64);
+        let tracker = &mut self.boards[board];
+        let changed = tracker
+            .changes
+            .observe(FrameSignature::from_image(img), s.cooldown);
+        if changed || tracker.changes.is_pending() {
+            self.last_activity = Instant::now();
+        }
+        let due = tracker
+            .last_analysis
+            .is_none_or(|t| t.elapsed() >= analysis_interval);
+        if !(changed && due) {
+            return;
+        }
+        tracker.changes.mark_analyzed();
+        self.frame += 1;
+        let seed = frame_seed(self.session_seed, self.frame);
+        let started = Instant::now();
+        tracker.last_analysis = Some(started);
+        let load = self.cpu.idle_load();
+        // A board may be played from the other side than the main one
+        let side = s.board_sides.get(board).copied().unwrap_or_default();
+        let show_white = side.shows_white(s.show_white);
+
+        if let Some(found) = self.detect_board(s, board, r, img, board_count) {
+            self.save_frame(s, board, r, img, seed, show_white, &found);
+            let position = self.read_position(s, board, img, show_white, &found);
+            if let Some((fen, opponent_fen)) = position {
+                if !self.search_board(s, effective, board, seed, fen, opponent_fen) {
+                    return;
+                }
+            }
+        }
+        // An infinite search keeps the engine busy between cycles
+        let load = load.filter(|_| self.live.is_none());
+        self.ladder
+            .record(started.elapsed(), analysis_interval, load);
+        self.cpu.mark();
+    }
+
+    /// Runs the detector on a board's crop and picks the board in it;
+    /// follows a tracked or resized board and updates the detection view.
+    fn detect_board(
+        &mut self,
+        s: &Settings,
+        board: usize,
+        r: &BoardRegion,
+        img: &DynamicImage,
+        board_count: usize,
+    ) -> Option<BoardDetections> {
+        let mut detections = match self.detector.detect(img, s.conf, s.iou) {
+            Ok(detections) => detections,
+            Err(e) => {
+                let error = format!("Detection failed: {:?}", e);
+                self.errors.report(AppError::Inference(error));
+                return None;
+            }
+        };
+        if let Some(below) = s.color_check {
+            correct_colors(img, &mut detections, below);
+        }
+        let tracker = &mut self.boards[board];
+        tracker.min_confidence = detections
+            .iter()
+            .filter(|d| d.class_id != 0)
+            .map(|d| d.confidence)
+            .reduce(f32::min);
+        // No pieces at all: check for an empty board so the grid
+        // calibration can still be shown
+        let empty_grid = (board == 0).then(|| {
+            if detections.iter().all(|d| d.class_id == 0) {
+                detect_empty_board(img).map(|g| {
+                    [
+                        r.x as f32 + g.x,
+                        r.y as f32 + g.y,
+                        g.cell_w * 8.0,
+                        g.cell_h * 8.0,
+                    ]
+                })
+            } else {
+                None
+            }
+        });
+        let min_fraction = min_board_fraction(s.min_board_px, r.width.min(r.height));
+        let candidates = select_board(&detections, min_fraction, s.board_cycle);
+        // Tracking and rescaling only follow a main region holding one board
+        let single_main = board == 0 && board_count == s.extra_regions.len() + 1;
+        if s.auto_track && single_main {
+            self.redetect |= candidates
+                .selected
+                .is_some_and(|b| has_drifted(b, s.padding));
+        } else if s.auto_rescale && single_main {
+            if let Some(b) = candidates.selected {
+                let fraction = board_fraction(b);
+                let mut c = self.config.lock().unwrap();
+                match c.region_board_fraction {
+                    None => c.region_board_fraction = Some(fraction),
+                    Some(reference) => {
+                        if let Some(factor) = self.rescale.observe(fraction, reference) {
+                            let status = rescale_region(&mut c, r, factor, s.monitor, &self.errors);
+                            self.latest.status.auto_detect_status = Some(status);
+                        } else if self.rescale.is_pending() {
+                            // Confirm on the next frame even if nothing moves
+                            tracker.changes.reset();
+                        }
+                    }
+                }
+            }
+        }
+        let status = &mut self.latest.status;
+        status.board_candidates = candidates.ranked.len();
+        status.board_debug = candidates
+            .rejected
+            .iter()
+            .map(|d| {
+                format!(
+                    "{:.0}x{:.0} @ {:.2} (min {:.0})",
+                    d.bbox[2],
+                    d.bbox[3],
+                    d.confidence,
+                    min_fraction * 640.0
+                )
+            })
+            .collect();
+        status.low_confidence = self.low_confidence.observe(&detections);
+        if s.show_detections {
+            self.latest.detections.resize(board_count, Vec::new());
+            self.latest.detections[board] = detection_boxes(&detections, r, img);
+            self.dirty = true;
+        } else if !self.latest.detections.is_empty() {
+            self.latest.detections.clear();
+            self.dirty = true;
+        }
+        if let Some(grid) = empty_grid {
+            self.dirty |= self.latest.empty_board_grid != grid;
+            self.latest.empty_board_grid = grid;
+        }
+
+        // The squares' area inside the board box, without the coordinates
+        // or narrowed to the grid, replaces the box for reading the
+        // position and drawing
+        let selected = candidates.selected.cloned();
+        let grid = selected
+            .as_ref()
+            .and_then(|b| board_grid(img, b, s.strip, s.refine));
+        tracker.grid = grid.as_ref().map(|g| capture_rect(g, r, img));
+        let board_box = grid.or_else(|| selected.clone());
+        Some(BoardDetections {
+            detections,
+            selected,
+            board_box,
+        })
+    }
+
+    /// Writes the frame to the debug, recording and sample folders that
+    /// are switched on. A folder that can't be created switches its
+    /// option off again.
+    #[allow(clippy::too_many_arguments)]
+    fn save_frame(
+        &mut self,
+        s: &mut Settings,
+        board: usize,
+        r: &BoardRegion,
+        img: &DynamicImage,
+        seed: u64,
+        show_white: bool,
+        found: &BoardDetections,
+    ) {
+        let detections = &found.detections;
+        let board_box = found.board_box.as_ref();
+        if !s.debug_capture {
+            self.exporter = None;
+        } else if self.exporter.is_none() {
+            self.exporter = DetectionExporter::new()
+                .map_err(|e| {
+                    let error = format!("Debug capture failed: {:?}", e);
+                    self.errors.report(AppError::System(error));
+                    self.config.lock().unwrap().debug_capture = false;
+                    s.debug_capture = false;
+                })
+                .ok();
+        }
+        if let Some(ex) = &mut self.exporter {
+            if let Err(e) = ex.export(self.frame, board, img, detections, board_box) {
+                let error = format!("Failed to write detection image: {:?}", e);
+                self.errors.report(AppError::System(error));
+            }
+            self.latest.status.debug_images_written = ex.written();
+        }
+
+        if !s.record_frames {
+            self.frame_recorder = None;
+        } else if self.frame_recorder.is_none() {
+            self.frame_recorder = FrameRecorder::new()
+                .map_err(|e| {
+                    let error = format!("Frame recording failed: {:?}", e);
+                    self.errors.report(AppError::System(error));
+                    self.config.lock().unwrap().record_frames = false;
+                    s.record_frames = false;
+                })
+                .ok();
+        }
+        if let Some(rec) = &mut self.frame_recorder {
+            let params = PipelineParams {
+                confidence_threshold: s.conf,
+                iou_threshold: s.iou,
+                reassign_conflicts: s.reassign,
+                refine_grid: s.refine,
+                coordinate_strip: s.strip,
+                color_check: s.color_check,
+                min_board_px: s.min_board_px,
+                board_cycle: s.board_cycle,
+                show_white_moves: show_white,
+                auto_side_to_move: s.auto_side,
+                sparring: s.sparring,
+            };
+            let region_px = r.width.min(r.height);
+            if let Err(e) = rec.record(self.frame, board, img, region_px, seed, params) {
+                let error = format!("Failed to record frame: {:?}", e);
+                self.errors.report(AppError::System(error));
+            }
+            self.latest.status.frames_recorded = rec.frames();
+        }
+
+        if s.collect_samples {
+            if self.collector.is_none() {
+                self.collector = SampleCollector::new()
+                    .map_err(|e| {
+                        let error = format!("Sample collection failed: {:?}", e);
+                        self.errors.report(AppError::System(error));
+                    })
+                    .ok();
+            }
+            if let Some(col) = &mut self.collector {
+                if let Err(e) = col.offer(img, detections, found.selected.as_ref()) {
+                    let error = format!("Failed to save sample: {:?}", e);
+                    self.errors.report(AppError::System(error));
+                }
+                self.latest.status.samples_collected = col.saved();
+                if col.is_done() {
+                    self.config.lock().unwrap().collect_samples = false;
+                    s.collect_samples = false;
+                }
+            }
+        }
+        if !s.collect_samples || self.collector.as_ref().is_some_and(|c| c.is_done()) {
+            self.collector = None;
+        }
+    }
+
+    /// The position to analyze and, on the opponent's turn, the one to
+    /// plan premoves in. A position that isn't legal is reported and, when
+    /// switched on, dumped for later inspection.
+    fn read_position(
+        &mut self,
+        s: &Settings,
+        board: usize,
+        img: &DynamicImage,
+        show_white: bool,
+        found: &BoardDetections,
+    ) -> Option<(String, Option<String>)> {
+        let detections = &found.detections;
+        let board_box = found.board_box.as_ref();
+        let tracker = &mut self.boards[board];
+        let position = board_position(
+            detections,
+            board_box,
+            s.reassign,
+            show_white,
+            s.auto_side,
+            &mut tracker.game,
+            &mut tracker.turn,
+        );
+        if board == 0 {
+            self.latest.status.rejected_position = position.as_ref().err().map(|e| e.to_string());
+            if let Err(e) = &position {
+                self.errors.report(AppError::Fen(e.to_string()));
+            }
+        }
+        // An empty crop is no board, not a misrecognized one
+        let pieces = detections.iter().any(|d| d.class_id != 0);
+        if let (Err(e), true, true) = (&position, s.dump_failures, pieces) {
+            let fen = attempted_fen(detections, board_box, show_white, s.reassign);
+            let dumped = self
+                .failure_dumper
+                .dump(board, img, detections, &fen, &e.to_string());
+            match dumped {
+                Ok(Some(dir)) => println!("Saved failed recognition to {}", dir.display()),
+                Ok(None) => {}
+                Err(e) => {
+                    let error = format!("Failed to save recognition dump: {:?}", e);
+                    self.errors.report(AppError::System(error));
+                }
+            }
+            self.latest.status.failure_dumps_written = self.failure_dumper.written();
+        }
+        let (fen, opponent_fen) = position.ok()?;
+        if board == 0 {
+            self.latest.status.tracker_lost = tracker.game.is_lost();
+            self.latest.status.side_to_move = Some(fen.split_whitespace().nth(1) == Some("w"));
+        }
+        let record = game_record(&tracker.game);
+        if self.latest.games[board].as_ref() != Some(&record) {
+            self.latest.games[board] = Some(record);
+            self.dirty = true;
+        }
+        Some((fen, opponent_fen))
+    }
+
+    /// Shows the book moves for `fen`, or searches it and shows the lines
+    /// with their extras (premoves, human move, threat). Returns false when
+    /// nothing was searched: a book position, or no engine to ask.
+    fn search_board(
+        &mut self,
+        s: &Settings,
+        effective: &Effective,
+        board: usize,
+        seed: u64,
+        fen: String,
+        opponent_fen: Option<String>,
+    ) -> bool {
+        let tracker = &mut self.boards[board];
+        let book_moves = self
+            .opening_book
+            .as_ref()
+            .map(|b| b.moves(&fen))
+            .unwrap_or_default();
+        if !book_moves.is_empty() {
+            // A book position needs no search; stop one still running for
+            // this board
+            if self.live.as_ref().is_some_and(|(b, _)| *b == board) {
+                if let Some(sf) = self.engine.engine() {
+                    let _ = sf.stop();
+                }
+                self.live = None;
+            }
+            tracker.previous = None;
+            let mut analysis = book_analysis(fen, book_moves);
+            analysis.min_confidence = tracker.min_confidence;
+            analysis.grid = tracker.grid;
+            self.latest.boards[board] = Some(analysis);
+            self.dirty = true;
+            return false;
+        }
+        // A predicted reply was played: show the prepared answer right
+        // away, while the new search runs
+        let hit = self.latest.boards[board]
+            .as_ref()
+            .and_then(|a| ponder_hit(&a.premoves, &fen))
+            .map(|p| p.response.clone());
+        if let Some(response) = hit {
+            let line = PvLine {
+                mv: response.clone(),
+                pv: vec![response],
+                eval: None,
+                depth: 0,
+                stable_since: 0,
+            };
+            let mut analysis = board_analysis(fen.clone(), vec![line], None, None, Vec::new());
+            analysis.min_confidence = tracker.min_confidence;
+            analysis.grid = tracker.grid;
+            self.latest.boards[board] = Some(analysis);
+            self.snapshot.publish(self.latest.clone());
+        }
+        let mut sparring = Sparring::with_seed(seed);
+        let engine_options = self.engine.options().clone();
+        let base_skill = engine_options.skill_level;
+        self.engine.set_skill(if s.sparring {
+            sparring.skill_level().min(base_skill)
+        } else {
+            base_skill
+        });
+        // Without an engine the board waits for the restart
+        let Some(sf) = self.engine.engine() else {
+            tracker.changes.reset();
+            return false;
+        };
+        // Arrows for the side not to move need less precision.
+        // `opponent_fen` is only set on the opponent's turn, in auto-side
+        // mode too, where it equals `fen`
+        let off_turn = opponent_fen.is_some();
+        let side_limit = effective.limit.scaled(s.off_turn_ratio);
+        let limit = match self.latest.status.clock_remaining {
+            _ if off_turn => side_limit,
+            Some(left) => time_control::budget(effective.limit, left),
+            None => effective.limit,
+        };
+        let result = if limit == SearchLimit::Infinite {
+            sf.start_infinite(&fen, effective.lines).map(|_| None)
+        } else {
+            sf.analyze(&fen, limit, effective.lines).map(Some)
+        };
+        self.latest.status.engine_multipv = sf.supports_multipv();
+        let mut pv_lines = match result {
+            Ok(Some(pv_lines)) => pv_lines,
+            Ok(None) => {
+                // Lines are picked up as the search deepens
+                self.live = Some((board, fen));
+                self.live_depth = 0;
+                return true;
+            }
+            Err(e) => {
+                // The supervisor restarts it after a backoff
+                self.engine.report_error(&e);
+                tracker.changes.reset();
+                self.live = None;
+                return true;
+            }
+        };
+        self.live = None;
+        let white_to_move = fen.split_whitespace().nth(1) == Some("w");
+        let eval = pv_lines
+            .first()
+            .and_then(|l| l.eval)
+            .map(|e| e.for_white(white_to_move));
+        if let Some(e) = eval {
+            if tracker.game.record_eval(&fen, e) {
+                self.latest.games[board] = Some(game_record(&tracker.game));
+            }
+        }
+        // The refutation is the engine's real best reply, taken before
+        // sparring reorders lines
+        let best = pv_lines.first().map(|l| l.mv.clone());
+        if let Some(e) = eval {
+            let threshold = s.blunder_threshold.unwrap_or(i32::MAX);
+            tracker.evals.record(&fen, e, best.as_deref(), threshold);
+        }
+        if s.sparring {
+            sparring.pick(&mut pv_lines);
+        }
+        let premoves = match (&opponent_fen, s.premove_planning) {
+            (Some(opp), true) => plan_premoves(sf, opp, side_limit, 3).unwrap_or_else(|e| {
+                let error = format!("Premove planning failed: {:?}", e);
+                self.errors.report(AppError::Engine(error));
+                Vec::new()
+            }),
+            _ => Vec::new(),
+        };
+        let mut analysis = board_analysis(fen, pv_lines, eval, tracker.previous.as_ref(), premoves);
+        analysis.nps = sf.analysis().nps;
+        analysis.min_confidence = tracker.min_confidence;
+        analysis.grid = tracker.grid;
+        analysis.blunder = tracker.evals.alert().cloned();
+        // Only our own moves get a human-plausible alternative; on the
+        // opponent's turn the extra search would only cost engine time
+        if let Some(elo) = s.human_elo.filter(|_| !off_turn) {
+            let fen = &analysis.fen;
+            analysis.human_move =
+                human_move(sf, fen, limit, elo, &engine_options).unwrap_or_else(|e| {
+                    let error = format!("Human move search failed: {:?}", e);
+                    self.errors.report(AppError::Engine(error));
+                    None
+                });
+        }
+        // Threats are what we must defend against, so only on our turn; on
+        // the opponent's they'd be threats against them
+        if s.threat_arrows && !off_turn {
+            let fen = &analysis.fen;
+            // Back to the mover's view (negation undoes itself)
+            let own = eval.map(|e| e.for_white(white_to_move));
+            analysis.threat = threat_line(sf, fen, own, limit.reduced()).unwrap_or_else(|e| {
+                let error = format!("Threat search failed: {:?}", e);
+                self.errors.report(AppError::Engine(error));
+                Vec::new()
+            });
+        }
+        tracker.previous = Some((analysis.fen.clone(), analysis.moves.clone()));
+        if s.record_history {
+            if let Err(e) = self.recorder.record(board, &analysis.fen, &analysis.moves) {
+                let error = format!("History write failed: {:?}", e);
+                self.errors.report(AppError::System(error));
+            }
+        }
+        self.latest.boards[board] = Some(analysis);
+        self.dirty = true;
+        true
+    }
+
+    /// Shows a running infinite search's lines as it deepens, or stops it
+    /// once it is no longer wanted.
+    fn follow_live_search(&mut self, s: &Settings, paused: bool, effective: &Effective) {
+        let Some((board, fen)) = self.live.clone() else {
+            return;
+        };
+        let Some(sf) = self.engine.engine() else {
+            return;
+        };
+        if !s.running || paused || effective.limit != SearchLimit::Infinite {
+            if let Err(e) = sf.stop() {
+                let error = format!("Failed to stop search: {:?}", e);
+                self.errors.report(AppError::Engine(error));
+            }
+            self.live = None;
+            return;
+        }
+        let state = sf.analysis();
+        if state.fen != fen || state.depth <= self.live_depth || board >= self.latest.boards.len() {
+            return;
+        }
+        self.live_depth = state.depth;
+        let white_to_move = fen.split_whitespace().nth(1) == Some("w");
+        let lines = state.lines();
+        let eval = lines
+            .first()
+            .and_then(|l| l.eval)
+            .map(|e| e.for_white(white_to_move));
+        if let (Some(e), Some(tracker)) = (eval, self.boards.get_mut(board)) {
+            if tracker.game.record_eval(&fen, e) {
+                self.latest.games[board] = Some(game_record(&tracker.game));
+            }
+            let best = lines.first().map(|l| l.mv.as_str());
+            let threshold = s.blunder_threshold.unwrap_or(i32::MAX);
+            tracker.evals.record(&fen, e, best, threshold);
+        }
+        let tracker = self.boards.get(board);
+        let previous = tracker.and_then(|b| b.previous.as_ref());
+        let blunder = tracker.and_then(|b| b.evals.alert().cloned());
+        let mut analysis = board_analysis(fen, lines, eval, previous, Vec::new());
+        analysis.nps = state.nps;
+        analysis.min_confidence = tracker.and_then(|b| b.min_confidence);
+        analysis.grid = tracker.and_then(|b| b.grid);
+        analysis.blunder = blunder;
+        self.latest.boards[board] = Some(analysis);
+        self.dirty = true;
+    }
+}